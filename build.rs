@@ -0,0 +1,128 @@
+//! Embeds the translations under `i18n/` into the binary.
+//!
+//! Every `i18n/<locale>/*.ftl` file is included with `include_str!` in a
+//! generated `locales.rs`, so adding a language only takes adding its
+//! directory; `src/i18n.rs` negotiates against whatever was found.
+//!
+//! The message IDs of the `en-US` fallback also become the `MessageKey`
+//! enum in `message_keys.rs`, so code can only ask for messages that exist.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let root = Path::new(&env::var("CARGO_MANIFEST_DIR").expect("set by cargo")).join("i18n");
+    println!("cargo:rerun-if-changed={}", root.display());
+
+    let mut locales: Vec<(String, Vec<PathBuf>)> = fs::read_dir(&root)
+        .expect("i18n/ directory is missing")
+        .map(|entry| entry.expect("unreadable i18n/ entry").path())
+        .filter(|path| path.is_dir())
+        .map(|dir| {
+            let name = dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .expect("locale directory names are UTF-8")
+                .to_string();
+            (name, ftl_files(&dir))
+        })
+        .filter(|(_, files)| !files.is_empty())
+        .collect();
+    locales.sort();
+    assert!(
+        locales.iter().any(|(name, _)| name == "en-US"),
+        "i18n/en-US is required as the fallback locale"
+    );
+
+    let mut out = String::from("/// Embedded translations: locale tag and `.ftl` sources.\n");
+    out.push_str("const EMBEDDED_LOCALES: &[(&str, &[&str])] = &[\n");
+    for (name, files) in &locales {
+        let _ = write!(out, "    ({name:?}, &[");
+        for file in files {
+            let _ = write!(out, "include_str!({:?}), ", file.display().to_string());
+        }
+        out.push_str("]),\n");
+    }
+    out.push_str("];\n");
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("set by cargo"));
+    fs::write(out_dir.join("locales.rs"), out).expect("failed to write locales.rs");
+
+    let (_, en_us) = locales
+        .iter()
+        .find(|(name, _)| name == "en-US")
+        .expect("checked above");
+    fs::write(out_dir.join("message_keys.rs"), message_keys(en_us))
+        .expect("failed to write message_keys.rs");
+}
+
+/// Generate the `MessageKey` enum from the messages defined in `files`.
+fn message_keys(files: &[PathBuf]) -> String {
+    let mut ids: Vec<String> = files
+        .iter()
+        .flat_map(|file| {
+            let source = fs::read_to_string(file).expect("unreadable .ftl file");
+            source.lines().filter_map(message_id).collect::<Vec<_>>()
+        })
+        .collect();
+    ids.sort();
+    ids.dedup();
+
+    let mut out = String::from(
+        "/// A message defined by the `en-US` translation, generated from `i18n/en-US/`.\n",
+    );
+    out.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n");
+    out.push_str("pub enum MessageKey {\n");
+    for id in &ids {
+        let _ = writeln!(out, "    /// `{id}`\n    {},", variant_name(id));
+    }
+    out.push_str("}\n\nimpl MessageKey {\n");
+    out.push_str("    /// The Fluent message ID.\n    #[must_use]\n");
+    // One line per message, however many there are
+    out.push_str("    #[allow(clippy::too_many_lines)]\n");
+    out.push_str("    pub const fn id(self) -> &'static str {\n        match self {\n");
+    for id in &ids {
+        let _ = writeln!(out, "            Self::{} => {id:?},", variant_name(id));
+    }
+    out.push_str("        }\n    }\n}\n");
+    out
+}
+
+/// The ID of the message defined on `line`, if it starts one.
+///
+/// Terms (`-brand = ...`), comments and continuation lines are skipped.
+fn message_id(line: &str) -> Option<String> {
+    let (id, _) = line.split_once('=')?;
+    let id = id.trim_end();
+    let valid = id.starts_with(|c: char| c.is_ascii_alphabetic())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| id.to_string())
+}
+
+/// `err-dest-exists` -> `ErrDestExists`.
+fn variant_name(id: &str) -> String {
+    id.split(['-', '_'])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect()
+}
+
+/// The `.ftl` files in `dir`, in file name order.
+fn ftl_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .expect("unreadable locale directory")
+        .map(|entry| entry.expect("unreadable locale file").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ftl"))
+        .collect();
+    files.sort();
+    files
+}
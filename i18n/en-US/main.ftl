@@ -3,34 +3,227 @@
 # Operation messages
 op-moving = Moving { $src } -> { $dest }
 op-linking = Creating symlink { $link } -> { $target }
-op-complete = Complete: { $files } file(s) moved, { $links } symlink(s) created
+op-dedupe = Hard-linking { $dest } to identical { $existing }
+op-sanitized = Renamed { $src } to { $dest } (--sanitize)
+op-complete = Complete: { $files ->
+        [one] { $files } file
+       *[other] { $files } files
+    } moved, { $links ->
+        [one] { $links } symlink
+       *[other] { $links } symlinks
+    } created{ $skipped ->
+        [0] {""}
+       *[other] , { $skipped } skipped
+    }{ $failed ->
+        [0] {""}
+       *[other] , { $failed } failed
+    }
 op-dry-run = [DRY-RUN] No changes made
+op-deferred = { $count ->
+        [one] { $count } source
+       *[other] { $count } sources
+    } deferred by --max-files/--max-bytes:
+op-estimate = Estimate: { $bytes } in { $renames ->
+        [one] { $renames } rename
+       *[other] { $renames } renames
+    } and { $copies ->
+        [one] { $copies } cross-device copy
+       *[other] { $copies } cross-device copies
+    } ({ $copy_bytes } copied)
+op-estimate-fs = { $path }: { $available } free, { $after } after ({ $delta })
+op-estimate-fs-unknown = { $path }: free space unknown ({ $delta })
+op-estimate-short = Not enough space on { $path } for this plan
+op-cross-device-copy = Copying { $src } to another filesystem: { $files ->
+        [one] { $files } file
+       *[other] { $files } files
+    }, { $size }
+op-copy-progress = Copying { $dir }: { $files }/{ $total } files, { $size }/{ $total_size }
+op-skipped-dangling = Skipping dangling symlink { $path } -> { $target }
+op-skipped-open = Skipping { $path }: { $file } is open for writing by { $process }
+op-adopted = Adopted { $link } -> { $target }
+op-adopt-complete = Complete: { $links ->
+        [one] { $links } symlink
+       *[other] { $links } symlinks
+    } adopted, { $unchanged } already tracked
+op-stow-complete = Complete: { $links ->
+        [one] { $links } symlink
+       *[other] { $links } symlinks
+    } created, { $existing } already linked
+op-converted = Complete: { $links ->
+        [one] { $links } symlink
+       *[other] { $links } symlinks
+    } converted, { $unchanged } already in place
+op-recovered = Finished interrupted move { $src } -> { $dest }
+op-recovery-dropped = Dropped interrupted move { $src } -> { $dest }: neither path exists
+op-resume-complete = Resumed: { $finished } finished, { $failed } failed
+op-resume-none = No interrupted moves to resume
+op-resume-busy = Another mvln run is active; run `mvln resume` again once it has finished
+op-background-started = Started background job { $id } (pid { $pid }); see `mvln status`
+    Its output goes to { $path }
+status-none = No background jobs
+status-job = [{ $id }] { $state ->
+        [running] running (pid { $pid })
+        [ok] finished
+        [failed] failed (exit status { $status })
+       *[stopped] stopped before finishing
+    }, started { $started }
+status-progress = { $moved }/{ $total } moved, { $size }/{ $total_size }, { $failed } failed
+status-current = Moving { $path }
+status-failed = Failed: { $path }
+status-output = Output: { $path }
 
 # Equivalent commands (debug output)
 cmd-mv = mv { $src } { $dest }
 cmd-ln = ln -s { $target } { $link }
 
 # Error messages
-err-source-not-found = Error: Source not found: { $path }
-err-dest-exists = Error: Destination already exists: { $path }
-    .hint = Use -f/--force to overwrite
-err-is-directory = Error: { $path } is a directory
+err-source-not-found = Source not found: { $path }
+    .hint = Check the path for typos, and quote glob patterns to let mvln expand them
+err-dest-exists = Destination already exists: { $path }
+    .hint = Rerun with -f/--force to overwrite it, or --rename-on-conflict to keep both
+err-conflict-skipped = Skipped { $src }: destination already exists: { $dest }
+err-conflict-aborted = Aborted at { $src }: destination already exists: { $dest }
+    .note = The remaining sources were not moved
+err-cross-device-declined = Skipped { $src }: { $dest } is on another filesystem
+err-cross-device = Cannot move { $src } to { $dest }: destination is on another filesystem (--no-cross-device)
+err-skipped-dir-kept = Cannot link { $src }: { $dir } is left out of the copy and stays in place (--skip-vcs); add --no-link to move the rest
+err-stow-conflict = Stow target already exists: { $path }
+    .hint = Move it into the package or remove it, then rerun
+err-is-directory = { $path } is a directory
     .hint = Use -w/--whole-dir to move directories, or use glob pattern (e.g., { $path }/*)
-err-symlink-failed = Error: Failed to create symlink { $link } -> { $target }
-    .reason = Reason: { $reason }
-err-move-failed = Error: Failed to move { $src } -> { $dest }
-    .reason = Reason: { $reason }
-err-copy-failed = Error: Failed to copy { $src } -> { $dest }
-    .reason = Reason: { $reason }
-err-remove-failed = Warning: File copied but failed to remove source: { $src }
-    .reason = Reason: { $reason }
+err-dangling-symlink = { $path } is a dangling symlink
+    .reason = its target { $target } does not exist
+    .hint = Use --dangling move to archive the broken link anyway, or --dangling skip to leave it
+err-open-for-writing = { $path } is open for writing by { $process }
+    .hint = Wait for it to finish, or use --check-open=skip to leave such sources in place
+err-symlink-failed = Failed to create symlink { $link } -> { $target }
+    .reason = { $reason }
+err-move-failed = Failed to move { $src } -> { $dest }
+    .reason = { $reason }
+err-copy-failed = Failed to copy { $src } -> { $dest }
+    .reason = { $reason }
+err-remove-failed = File copied but failed to remove source: { $src }
+    .reason = { $reason }
     .note = File exists in both locations. Manual cleanup may be needed.
+err-too-deep = Directory tree too deep to copy: { $path }
+    .reason = more than { $limit } nested levels
+    .note = The source was left in place; a partial copy may remain at the destination
+err-filesystem-loop = Directory loop while copying: { $path }
+    .reason = same directory as its ancestor { $ancestor }
+    .note = The source was left in place; a partial copy may remain at the destination
+err-name-too-long = File name too long for the destination filesystem: { $name }
+    .reason = { $len } bytes, the filesystem allows { $limit }
+    .note = Nothing was moved
+    .hint = Choose a shorter destination name
+err-path-too-long = Destination path too long: { $path }
+    .reason = { $len } bytes, the limit is { $limit }
+    .note = Nothing was moved
+    .hint = Choose a destination closer to the root, or with shorter names
+err-source-access = Cannot access source { $path }
+    .reason = { $reason }
+err-same-path = Source and destination are the same: { $path }
+    .hint = Choose a different destination
+err-dest-inside-source = Cannot move directory into itself: { $src } -> { $dest }
+    .hint = Choose a destination outside { $src }
+err-type-mismatch = Type mismatch: cannot replace { $dest_type } with { $src_type }: { $src } -> { $dest }
+    .hint = Remove or rename the destination first; -f/--force never replaces across types
+err-chmod-failed = Failed to change the mode of { $path }
+    .reason = { $reason }
+    .note = The move itself completed; only the permissions were not applied
+err-create-dir-failed = Failed to create directory { $path }
+    .reason = { $reason }
+    .hint = Check the permissions of the parent directory
+err-invalid-destination = Invalid destination: { $reason }
+err-invalid-path = Invalid path { $path }: { $reason }
+err-glob-failed = Glob expansion failed: { $reason }
+err-no-matches = No files matched pattern: { $pattern }
+    .hint = Check the pattern and the current directory; quote it to keep the shell from expanding it
+err-too-many-matches = Pattern { $pattern } matched more than { $limit } paths
+    .hint = Narrow the pattern, or raise the limit with --max-matches N (0 for none)
+err-batch-failed = { $count ->
+        [one] { $count } operation
+       *[other] { $count } operations
+    } failed
+err-lock-held = Another mvln run is using { $root }
+    .hint = Use --lock-timeout to wait or --no-lock to skip
+err-lock-failed = Failed to acquire lock { $path }
+    .reason = { $reason }
+err-config = Invalid configuration { $path }: { $reason }
+    .hint = Fix the file, or point MVLN_CONFIG at another one
+err-preset-not-found = Preset not found: { $name }
+    .hint = Use one of { $presets }, or define [preset.{ $name }] in the configuration file
+err-profile-not-found = Profile not found: { $name }
+    .hint = Define [profile.{ $name }] in the configuration file
+err-journal = Journal error { $path }: { $reason }
+err-job = Background job error { $path }: { $reason }
+err-job-not-found = Background job not found: { $id }
+    .hint = Run `mvln status` to list the jobs
+err-script-write-failed = Failed to write script { $path }
+    .reason = { $reason }
+err-sums-failed = Failed to write checksums for { $path }
+    .reason = { $reason }
+err-log-file-failed = Failed to open log file { $path }
+    .reason = { $reason }
+    .hint = Check that the directory exists and is writable
+err-hook-failed = Hook failed: { $command }
+    .reason = { $reason }
+    .note = A failed pre-hook skips its source; after a post-hook the move is kept
+err-remote-failed = Failed to move { $src } to { $dest }
+    .reason = { $reason }
+    .note = The source was left in place
+err-remote-stub-failed = Moved { $src } to { $url }, but failed to leave a placeholder
+    .reason = { $reason }
+    .note = The data is only on the remote host now
+err-io = I/O error: { $reason }
+warn-journal-failed = Warning: operation not recorded in journal: { $reason }
+warn-recovery-failed = Warning: could not check for interrupted moves: { $reason }
+warn-interrupted = Warning: { $count ->
+        [one] a move was
+       *[other] { $count } moves were
+    } interrupted and never finished:
+warn-interrupted-item = { $src } -> { $dest } ({ $state ->
+        [done] linked, not recorded
+        [moved] moved, not linked
+        [copied] copied, source not removed
+        [missing] both paths are gone
+       *[not-moved] not started
+    })
+warn-interrupted-hint = Run `mvln resume` to finish { $count ->
+        [one] it
+       *[other] them
+    }
+warn-staged = Warning: partial copy { $path } was left by an interrupted run; move the same source to { $dest } again to resume it, or delete it
+warn-open-for-writing = Warning: { $file } is open for writing by { $process }; moving { $path } anyway
+warn-network-links = Warning: the links left in { $dir } are stored on a network filesystem ({ $fs }); other clients of the share may not resolve their targets
+
+# Diagnostic labels
+diag-error = Error
+diag-warning = Warning
+diag-reason = reason
+diag-note = note
+diag-help = help
 
 # Recovery messages
 recovery-header = File has been moved to: { $dest }
 recovery-command = Recovery command (to rollback):
 recovery-mv = mv { $dest } { $src }
 
+# Prompts
+prompt-conflict = { $dest } already exists. [o]verwrite, [s]kip, [b]ackup, [r]ename, overwrite [a]ll or [q]uit?
+prompt-cross-device = { $dest } is on another filesystem: moving { $src } copies { $size }. Continue? [y/n]
+
+# Review (--review)
+review-title = Review planned moves
+review-selected = { $selected } of { $total } selected ({ $size })
+review-help = ↑/↓ move · space toggle · a toggle all · enter apply · q cancel
+review-cancelled = Review cancelled, nothing moved
+
+# Selection (--pick)
+pick-title = Sources to move:
+pick-help = Toggle numbers or ranges (e.g. 2 5-7), a = all, n = none, q = cancel, enter = move { $selected } of { $total } ({ $size }):
+pick-cancelled = Selection cancelled, nothing moved
+pick-cross-device = (copied to another filesystem)
+
 # Help text
 help-source = Source file(s) or glob pattern
 help-dest = Destination path
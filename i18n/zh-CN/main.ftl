@@ -3,34 +3,197 @@
 # 操作消息
 op-moving = 移动 { $src } -> { $dest }
 op-linking = 创建软链接 { $link } -> { $target }
-op-complete = 完成: 移动了 { $files } 个文件, 创建了 { $links } 个软链接
+op-dedupe = 硬链接 { $dest } 到相同文件 { $existing }
+op-sanitized = 已将 { $src } 重命名为 { $dest }（--sanitize）
+op-complete = 完成: 移动了 { $files } 个文件, 创建了 { $links } 个软链接{ $skipped ->
+        [0] {""}
+       *[other] , 跳过了 { $skipped } 个
+    }{ $failed ->
+        [0] {""}
+       *[other] , { $failed } 个失败
+    }
 op-dry-run = [预览模式] 未做任何更改
+op-deferred = { $count } 个源因 --max-files/--max-bytes 限制推迟处理:
+op-estimate = 预估：共 { $bytes }，{ $renames } 次重命名，{ $copies } 次跨设备复制（复制 { $copy_bytes }）
+op-estimate-fs = { $path }：可用 { $available }，完成后 { $after }（{ $delta }）
+op-estimate-fs-unknown = { $path }：可用空间未知（{ $delta }）
+op-estimate-short = { $path } 的空间不足以执行此计划
+op-cross-device-copy = 正在将 { $src } 复制到另一个文件系统：{ $files } 个文件，{ $size }
+op-copy-progress = 正在复制 { $dir }：{ $files }/{ $total } 个文件，{ $size }/{ $total_size }
+op-skipped-dangling = 跳过失效的软链接 { $path } -> { $target }
+op-skipped-open = 跳过 { $path }: { $file } 正被 { $process } 以写入方式打开
+op-adopted = 已纳入管理 { $link } -> { $target }
+op-adopt-complete = 完成: 纳入了 { $links } 个软链接, { $unchanged } 个已在记录中
+op-stow-complete = 完成: 创建了 { $links } 个软链接, { $existing } 个已存在
+op-converted = 完成: 转换了 { $links } 个软链接, { $unchanged } 个无需更改
+op-recovered = 已完成中断的移动 { $src } -> { $dest }
+op-recovery-dropped = 已放弃中断的移动 { $src } -> { $dest }: 两个路径都不存在
+op-resume-complete = 恢复: { $finished } 个完成, { $failed } 个失败
+op-resume-none = 没有需要恢复的中断移动
+op-resume-busy = 另一个 mvln 正在运行; 请在其结束后再次运行 `mvln resume`
+op-background-started = 已启动后台任务 { $id } (pid { $pid }); 使用 `mvln status` 查看
+    输出写入 { $path }
+status-none = 没有后台任务
+status-job = [{ $id }] { $state ->
+        [running] 运行中 (pid { $pid })
+        [ok] 已完成
+        [failed] 失败 (退出状态 { $status })
+       *[stopped] 未完成即停止
+    }, 开始于 { $started }
+status-progress = 已移动 { $moved }/{ $total } 个, { $size }/{ $total_size }, 失败 { $failed } 个
+status-current = 正在移动 { $path }
+status-failed = 失败: { $path }
+status-output = 输出: { $path }
 
 # 等效命令（调试输出）
 cmd-mv = mv { $src } { $dest }
 cmd-ln = ln -s { $target } { $link }
 
 # 错误消息
-err-source-not-found = 错误: 源文件不存在: { $path }
-err-dest-exists = 错误: 目标已存在: { $path }
-    .hint = 使用 -f/--force 覆盖
-err-is-directory = 错误: { $path } 是目录
+err-source-not-found = 源文件不存在: { $path }
+    .hint = 检查路径拼写, 并给通配符加引号以交由 mvln 展开
+err-dest-exists = 目标已存在: { $path }
+    .hint = 使用 -f/--force 覆盖, 或使用 --rename-on-conflict 保留两者
+err-conflict-skipped = 已跳过 { $src }: 目标已存在: { $dest }
+err-conflict-aborted = 已在 { $src } 处中止: 目标已存在: { $dest }
+    .note = 其余的源未被移动
+err-cross-device-declined = 已跳过 { $src }: { $dest } 位于另一个文件系统
+err-cross-device = 无法将 { $src } 移动到 { $dest }: 目标位于另一个文件系统 (--no-cross-device)
+err-skipped-dir-kept = 无法链接 { $src }: { $dir } 不会被复制，将保留在原处 (--skip-vcs); 添加 --no-link 以移动其余内容
+err-stow-conflict = Stow 目标已存在: { $path }
+    .hint = 将其移入软件包或删除后重试
+err-is-directory = { $path } 是目录
     .hint = 使用 -w/--whole-dir 移动目录, 或使用通配符 (如 { $path }/*)
-err-symlink-failed = 错误: 创建软链接失败 { $link } -> { $target }
-    .reason = 原因: { $reason }
-err-move-failed = 错误: 移动失败 { $src } -> { $dest }
-    .reason = 原因: { $reason }
-err-copy-failed = 错误: 复制失败 { $src } -> { $dest }
-    .reason = 原因: { $reason }
-err-remove-failed = 警告: 文件已复制但无法删除源文件: { $src }
-    .reason = 原因: { $reason }
+err-dangling-symlink = { $path } 是失效的软链接
+    .reason = 其目标 { $target } 不存在
+    .hint = 使用 --dangling move 仍然归档该链接, 或使用 --dangling skip 跳过它
+err-open-for-writing = { $path } 正被 { $process } 以写入方式打开
+    .hint = 等待其写入完成, 或使用 --check-open=skip 保留此类源不动
+err-symlink-failed = 创建软链接失败 { $link } -> { $target }
+    .reason = { $reason }
+err-move-failed = 移动失败 { $src } -> { $dest }
+    .reason = { $reason }
+err-copy-failed = 复制失败 { $src } -> { $dest }
+    .reason = { $reason }
+err-remove-failed = 文件已复制但无法删除源文件: { $src }
+    .reason = { $reason }
     .note = 文件在两个位置都存在, 可能需要手动清理
+err-too-deep = 目录树层级过深, 无法复制: { $path }
+    .reason = 嵌套超过 { $limit } 层
+    .note = 源文件保持原位, 目标位置可能残留部分副本
+err-filesystem-loop = 复制时发现目录循环: { $path }
+    .reason = 与其上级目录 { $ancestor } 是同一目录
+    .note = 源文件保持原位, 目标位置可能残留部分副本
+err-name-too-long = 文件名超出目标文件系统的长度限制: { $name }
+    .reason = { $len } 字节, 文件系统最多允许 { $limit }
+    .note = 未移动任何文件
+    .hint = 请选择更短的目标名称
+err-path-too-long = 目标路径过长: { $path }
+    .reason = { $len } 字节, 上限为 { $limit }
+    .note = 未移动任何文件
+    .hint = 请选择更靠近根目录或名称更短的目标
+err-source-access = 无法访问源 { $path }
+    .reason = { $reason }
+err-same-path = 源和目标相同: { $path }
+    .hint = 请选择其他目标
+err-dest-inside-source = 不能将目录移动到其自身内部: { $src } -> { $dest }
+    .hint = 请选择 { $src } 之外的目标
+err-type-mismatch = 类型不匹配: 不能用{ $src_type ->
+        [directory] 目录
+       *[other] 文件
+    }替换{ $dest_type ->
+        [directory] 目录
+       *[other] 文件
+    }: { $src } -> { $dest }
+    .hint = 请先删除或重命名目标; -f/--force 不会替换不同类型的文件
+err-chmod-failed = 无法修改 { $path } 的权限
+    .reason = { $reason }
+    .note = 移动本身已完成, 只是权限未能应用
+err-create-dir-failed = 创建目录失败 { $path }
+    .reason = { $reason }
+    .hint = 检查上级目录的权限
+err-invalid-destination = 无效的目标: { $reason }
+err-invalid-path = 无效的路径 { $path }: { $reason }
+err-glob-failed = 通配符展开失败: { $reason }
+err-no-matches = 没有文件匹配模式: { $pattern }
+    .hint = 检查模式和当前目录; 用引号括起来以免被 shell 展开
+err-too-many-matches = 模式 { $pattern } 匹配了超过 { $limit } 个路径
+    .hint = 缩小模式范围, 或用 --max-matches N 提高上限 (0 表示不限)
+err-batch-failed = { $count } 个操作失败
+err-lock-held = 另一个 mvln 进程正在使用 { $root }
+    .hint = 使用 --lock-timeout 等待, 或使用 --no-lock 跳过
+err-lock-failed = 获取锁失败 { $path }
+    .reason = { $reason }
+err-config = 无效的配置 { $path }: { $reason }
+    .hint = 修正该文件, 或通过 MVLN_CONFIG 指定其他配置文件
+err-preset-not-found = 预设不存在: { $name }
+    .hint = 可用的预设: { $presets }；也可在配置文件中定义 [preset.{ $name }]
+err-profile-not-found = 配置方案不存在: { $name }
+    .hint = 在配置文件中定义 [profile.{ $name }]
+err-journal = 日志错误 { $path }: { $reason }
+err-job = 后台任务错误 { $path }: { $reason }
+err-job-not-found = 找不到后台任务: { $id }
+    .hint = 运行 `mvln status` 列出所有任务
+err-script-write-failed = 写入脚本失败 { $path }
+    .reason = { $reason }
+err-sums-failed = 写入校验和失败 { $path }
+    .reason = { $reason }
+err-log-file-failed = 无法打开日志文件 { $path }
+    .reason = { $reason }
+    .hint = 检查目录是否存在且可写
+err-hook-failed = 钩子命令失败: { $command }
+    .reason = { $reason }
+    .note = 前置钩子失败会跳过对应的源; 后置钩子失败时移动会被保留
+err-remote-failed = 无法将 { $src } 移动到 { $dest }
+    .reason = { $reason }
+    .note = 源文件保留在原处
+err-remote-stub-failed = 已将 { $src } 移动到 { $url }, 但无法留下占位文件
+    .reason = { $reason }
+    .note = 数据现在只在远程主机上
+err-io = I/O 错误: { $reason }
+warn-journal-failed = 警告: 操作未记录到日志: { $reason }
+warn-recovery-failed = 警告: 无法检查中断的移动: { $reason }
+warn-interrupted = 警告: { $count } 个移动被中断且未完成:
+warn-interrupted-item = { $src } -> { $dest } ({ $state ->
+        [done] 已链接, 未记录
+        [moved] 已移动, 未链接
+        [copied] 已复制, 源未删除
+        [missing] 两个路径都已不存在
+       *[not-moved] 未开始
+    })
+warn-interrupted-hint = 运行 `mvln resume` 以完成它们
+warn-staged = 警告: 中断的运行留下了部分副本 { $path }; 再次将同一源移动到 { $dest } 可继续复制, 否则请删除它
+warn-open-for-writing = 警告: { $file } 正被 { $process } 以写入方式打开; 仍然移动 { $path }
+warn-network-links = 警告: 留在 { $dir } 中的链接位于网络文件系统 ({ $fs }) 上; 共享的其他客户端可能无法解析其目标
+
+# 诊断标签
+diag-error = 错误
+diag-warning = 警告
+diag-reason = 原因
+diag-note = 注意
+diag-help = 提示
 
 # 恢复消息
 recovery-header = 文件已移动到: { $dest }
 recovery-command = 恢复命令 (回滚用):
 recovery-mv = mv { $dest } { $src }
 
+# 交互提示
+prompt-conflict = { $dest } 已存在。[o] 覆盖, [s] 跳过, [b] 备份, [r] 重命名, [a] 全部覆盖, [q] 退出?
+prompt-cross-device = { $dest } 位于另一个文件系统: 移动 { $src } 需要复制 { $size }。继续? [y/n]
+
+# 审阅 (--review)
+review-title = 审阅计划中的移动
+review-selected = 已选择 { $selected } / { $total } 项（{ $size }）
+review-help = ↑/↓ 移动 · 空格 切换 · a 全部切换 · 回车 执行 · q 取消
+review-cancelled = 已取消审阅，未移动任何文件
+
+# 选择 (--pick)
+pick-title = 要移动的源:
+pick-help = 输入编号或范围切换选择（如 2 5-7），a = 全选，n = 全不选，q = 取消，回车 = 移动 { $total } 项中的 { $selected } 项（{ $size }）:
+pick-cancelled = 已取消选择，未移动任何文件
+pick-cross-device = （复制到另一个文件系统）
+
 # 帮助文本
 help-source = 源文件或通配符模式
 help-dest = 目标路径
@@ -0,0 +1,254 @@
+//! `--background` jobs and `mvln status`.
+//!
+//! `--background` starts the same command line again as a detached worker
+//! with [`JOB_ENV`] set, and returns once the worker has registered. The
+//! worker runs the batch as usual, also recording the size of its batch and
+//! its moves in the job's journal (see [`mvln::jobs`]), from which
+//! `mvln status` shows how far it has got.
+
+use std::fs::File;
+use std::io::{self, IsTerminal};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fluent::FluentArgs;
+use mvln::date::DateTime;
+use mvln::error::{MvlnError, Result};
+use mvln::estimate::Estimate;
+use mvln::i18n::{self, MessageKey};
+use mvln::jobs::{Job, JobState, Jobs, Worker, JOB_ENV};
+use mvln::journal::Entry;
+use mvln::plan::MovePlan;
+use mvln::size::format_size;
+
+use crate::cli::{Cli, ShellDialect, StatusArgs};
+use crate::echo::Echo;
+use crate::logfile::{self, Kind};
+use crate::Bundle;
+
+/// How long to wait for a new worker to register before returning anyway.
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Registration of the job this process is the worker of.
+static WORKER: Mutex<Option<Worker>> = Mutex::new(None);
+
+/// Time between refreshes of `mvln status --watch`.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Ended jobs listed by `mvln status`, besides the running ones.
+const RECENT_JOBS: usize = 10;
+
+/// Whether this process is the worker of a background job.
+pub fn is_worker() -> bool {
+    std::env::var_os(JOB_ENV).is_some()
+}
+
+/// Start this command line as a background job.
+pub fn start(bundle: &Bundle) -> Result<()> {
+    let posix = Echo::new(ShellDialect::Posix, None);
+    let command: Vec<String> = std::env::args_os()
+        .map(|arg| posix.quote(&arg.to_string_lossy()))
+        .collect();
+    let jobs = Jobs::open_default();
+    let job = jobs.create(&command.join(" "))?;
+    let output_path = job.output_path();
+    let job_error = |source| MvlnError::JobError {
+        path: output_path.clone(),
+        source,
+    };
+
+    let output = File::create(&output_path).map_err(job_error)?;
+    let mut worker = Command::new(std::env::current_exe().map_err(job_error)?);
+    worker
+        .args(std::env::args_os().skip(1))
+        .env(JOB_ENV, job.id.to_string())
+        .stdin(Stdio::null())
+        .stdout(output.try_clone().map_err(job_error)?)
+        .stderr(output);
+    // Out of the terminal's process group, so closing it or pressing
+    // Ctrl-C leaves the job running
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut worker, 0);
+    let mut child = worker.spawn().map_err(job_error)?;
+
+    let started = Instant::now();
+    while started.elapsed() < START_TIMEOUT {
+        let registered = jobs.get(job.id)?.is_some_and(|job| job.pid.is_some());
+        if registered || child.try_wait().map_err(job_error)?.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let mut args = FluentArgs::new();
+    args.set("id", job.id);
+    args.set("pid", child.id());
+    args.set("path", output_path.display().to_string());
+    println!(
+        "{}",
+        i18n::msg(bundle, MessageKey::OpBackgroundStarted, Some(&args))
+    );
+    logfile::write(Kind::Run, &format!("started background job {}", job.id));
+    Ok(())
+}
+
+/// If this process is the worker of a background job, register it as such.
+pub fn attach() -> Result<()> {
+    let Some(id) = std::env::var(JOB_ENV).ok().and_then(|id| id.parse().ok()) else {
+        return Ok(());
+    };
+    let worker = Jobs::open_default().attach(id)?;
+    *lock_worker() = Some(worker);
+    Ok(())
+}
+
+/// Record that the job this process is the worker of, if any, ended with
+/// exit status `status`.
+pub fn finish(status: i32) -> Result<()> {
+    match lock_worker().take() {
+        Some(worker) => worker.finish(status),
+        None => Ok(()),
+    }
+}
+
+/// Record the size of `plan` for the job this process is the worker of, if
+/// any. Nothing is recorded in a dry run.
+pub fn plan(plan: &MovePlan, cli: &Cli) {
+    if let Some(worker) = lock_worker().as_ref().filter(|_| !cli.dry_run) {
+        let bytes = Estimate::of(plan).bytes;
+        if let Err(e) = worker.plan(plan.len() as u64, bytes) {
+            logfile::write(Kind::Error, &e.to_string());
+        }
+    }
+}
+
+/// Record `entry` in the journal of the job this process is the worker of,
+/// if any.
+pub fn record(entry: &Entry) {
+    if let Some(worker) = lock_worker().as_ref() {
+        if let Err(e) = worker.journal().append(entry) {
+            logfile::write(Kind::Error, &format!("not recorded in job journal: {e}"));
+        }
+    }
+}
+
+fn lock_worker() -> MutexGuard<'static, Option<Worker>> {
+    WORKER.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Show the progress of job `args.job`, or of the running and most recent
+/// jobs; with `--watch`, again every [`WATCH_INTERVAL`] while one of them
+/// is running.
+pub fn run_status(args: &StatusArgs, bundle: &Bundle) -> Result<()> {
+    let jobs = Jobs::open_default();
+    let terminal = io::stdout().is_terminal();
+    loop {
+        let shown = match args.job {
+            Some(id) => vec![jobs.get(id)?.ok_or(MvlnError::JobNotFound { id })?],
+            None => recent(jobs.list()?),
+        };
+        if args.watch && terminal {
+            // Redraw in place
+            print!("\x1b[H\x1b[2J");
+        }
+        if shown.is_empty() {
+            println!("{}", i18n::msg(bundle, MessageKey::StatusNone, None));
+        }
+        for job in &shown {
+            print_job(job, args.job.is_some(), bundle)?;
+        }
+        if !args.watch || !shown.iter().any(|job| job.state == JobState::Running) {
+            return Ok(());
+        }
+        thread::sleep(WATCH_INTERVAL);
+        if !terminal {
+            println!();
+        }
+    }
+}
+
+/// The running jobs of `jobs` and the [`RECENT_JOBS`] that ended last.
+fn recent(jobs: Vec<Job>) -> Vec<Job> {
+    let ended = jobs
+        .iter()
+        .filter(|job| job.state != JobState::Running)
+        .count();
+    let mut skip = ended.saturating_sub(RECENT_JOBS);
+    jobs.into_iter()
+        .filter(|job| {
+            if job.state == JobState::Running || skip == 0 {
+                return true;
+            }
+            skip -= 1;
+            false
+        })
+        .collect()
+}
+
+/// Print the state, command and progress of `job`; with `details`, also
+/// the sources that failed and where its output is.
+fn print_job(job: &Job, details: bool, bundle: &Bundle) -> Result<()> {
+    let mut args = FluentArgs::new();
+    args.set("id", job.id);
+    let state = match job.state {
+        JobState::Running => "running",
+        JobState::Finished { status: 0, .. } => "ok",
+        JobState::Finished { status, .. } => {
+            args.set("status", status);
+            "failed"
+        }
+        JobState::Stopped => "stopped",
+    };
+    args.set("state", state);
+    args.set(
+        "pid",
+        job.pid.map_or_else(|| "?".to_string(), |p| p.to_string()),
+    );
+    let started = i64::try_from(job.started).unwrap_or(0);
+    args.set(
+        "started",
+        DateTime::from_unix(started).format("%Y-%m-%d %H:%M UTC"),
+    );
+    println!("{}", i18n::msg(bundle, MessageKey::StatusJob, Some(&args)));
+    println!("    {}", job.command);
+
+    let progress = job.progress()?;
+    let (total, total_bytes) = job.planned.unwrap_or_default();
+    let mut args = FluentArgs::new();
+    args.set("moved", progress.moved);
+    args.set("total", total.max(progress.moved));
+    args.set("size", format_size(progress.bytes));
+    args.set("total_size", format_size(total_bytes.max(progress.bytes)));
+    args.set("failed", progress.failed.len());
+    println!(
+        "    {}",
+        i18n::msg(bundle, MessageKey::StatusProgress, Some(&args))
+    );
+    if let Some(current) = &progress.current {
+        let mut args = FluentArgs::new();
+        args.set("path", current.display().to_string());
+        println!(
+            "    {}",
+            i18n::msg(bundle, MessageKey::StatusCurrent, Some(&args))
+        );
+    }
+    if details {
+        for failed in &progress.failed {
+            let mut args = FluentArgs::new();
+            args.set("path", failed.display().to_string());
+            println!(
+                "    {}",
+                i18n::msg(bundle, MessageKey::StatusFailed, Some(&args))
+            );
+        }
+        let mut args = FluentArgs::new();
+        args.set("path", job.output_path().display().to_string());
+        println!(
+            "    {}",
+            i18n::msg(bundle, MessageKey::StatusOutput, Some(&args))
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,295 @@
+//! `--chmod`: rsync-style permission changes applied at the destination.
+//!
+//! A [`Chmod`] is a comma-separated list of clauses, each either an octal
+//! mode (`644`) or a symbolic change as understood by `chmod(1)`
+//! (`g+r`, `u=rwX,go=rX`, `o-w`). A clause prefixed with `D` applies only
+//! to directories and one prefixed with `F` only to files, so
+//! `D2775,F664` makes a tree group-writable with set-group-ID directories.
+//! Symbolic clauses without a `u`, `g`, `o` or `a` apply to everyone, as in
+//! rsync (the umask is not consulted). Symlinks are never changed.
+//!
+//! Modes are a Unix concept; elsewhere [`Chmod::apply_tree`] does nothing.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(unix)]
+use crate::error::MvlnError;
+use crate::error::Result;
+
+/// Permission bits of user, group and others, with their special bits.
+const USER: u32 = 0o4700;
+const GROUP: u32 = 0o2070;
+const OTHER: u32 = 0o1007;
+
+/// A parsed `--chmod` specification.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::chmod::Chmod;
+///
+/// let chmod: Chmod = "D2775,F664".parse().unwrap();
+/// assert_eq!(chmod.apply(0o700, true), 0o2775);
+/// assert_eq!(chmod.apply(0o600, false), 0o664);
+///
+/// let chmod: Chmod = "go+rX".parse().unwrap();
+/// assert_eq!(chmod.apply(0o700, true), 0o755);
+/// assert_eq!(chmod.apply(0o600, false), 0o644);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct Chmod {
+    text: String,
+    clauses: Vec<Clause>,
+}
+
+/// One comma-separated part of a [`Chmod`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Clause {
+    /// `Some(true)` for directories only (`D`), `Some(false)` for files
+    /// only (`F`).
+    dirs: Option<bool>,
+    change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    /// Set the mode to exactly this.
+    Octal(u32),
+    /// Apply each operator in turn to the bits selected by `who`.
+    Symbolic { who: u32, ops: Vec<(char, String)> },
+}
+
+impl Chmod {
+    /// The mode a file (or, with `is_dir`, a directory) with `mode` gets.
+    /// Only the permission bits of `mode` are looked at and returned.
+    #[must_use]
+    pub fn apply(&self, mode: u32, is_dir: bool) -> u32 {
+        let mut mode = mode & 0o7777;
+        for clause in &self.clauses {
+            if clause.dirs.is_some_and(|dirs| dirs != is_dir) {
+                continue;
+            }
+            match &clause.change {
+                Change::Octal(octal) => mode = *octal,
+                Change::Symbolic { who, ops } => {
+                    for (op, perms) in ops {
+                        let bits = perm_bits(perms, mode, is_dir) & who;
+                        mode = match op {
+                            '+' => mode | bits,
+                            '-' => mode & !bits,
+                            _ => (mode & !who) | bits,
+                        };
+                    }
+                }
+            }
+        }
+        mode
+    }
+
+    /// Apply to `path` and, if it is a directory, everything below it.
+    /// Symlinks are skipped; directories are changed after their contents,
+    /// so taking away access to one does not stop the walk.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::ChmodFailed`] for the first entry that cannot
+    /// be read or changed.
+    pub fn apply_tree(&self, path: &Path) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            for entry in walkdir::WalkDir::new(path).contents_first(true) {
+                let entry = entry.map_err(|e| MvlnError::ChmodFailed {
+                    path: e.path().unwrap_or(path).to_path_buf(),
+                    source: e.into(),
+                })?;
+                let file_type = entry.file_type();
+                if file_type.is_symlink() {
+                    continue;
+                }
+                let failed = |e| MvlnError::ChmodFailed {
+                    path: entry.path().to_path_buf(),
+                    source: e,
+                };
+                let metadata = entry.metadata().map_err(|e| failed(e.into()))?;
+                let mode = self.apply(metadata.permissions().mode(), file_type.is_dir());
+                std::fs::set_permissions(entry.path(), std::fs::Permissions::from_mode(mode))
+                    .map_err(failed)?;
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = path;
+        Ok(())
+    }
+}
+
+/// The bits named by the letters of `perms` for every class, given the
+/// current `mode` (`X` depends on it).
+fn perm_bits(perms: &str, mode: u32, is_dir: bool) -> u32 {
+    perms.chars().fold(0, |bits, c| {
+        bits | match c {
+            'r' => 0o444,
+            'w' => 0o222,
+            'x' => 0o111,
+            'X' if is_dir || mode & 0o111 != 0 => 0o111,
+            's' => 0o6000,
+            't' => 0o1000,
+            _ => 0,
+        }
+    })
+}
+
+impl FromStr for Chmod {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let clauses = s
+            .split(',')
+            .map(parse_clause)
+            .collect::<Option<Vec<_>>>()
+            .filter(|clauses| !clauses.is_empty())
+            .ok_or_else(|| format!("invalid chmod {s:?}: expected e.g. `D2775,F664` or `g+rX`"))?;
+        Ok(Self {
+            text: s.to_string(),
+            clauses,
+        })
+    }
+}
+
+/// Parse one clause, such as `D755` or `Fgo-w`.
+fn parse_clause(text: &str) -> Option<Clause> {
+    let (dirs, rest) = match text.as_bytes().first()? {
+        b'D' => (Some(true), &text[1..]),
+        b'F' => (Some(false), &text[1..]),
+        _ => (None, text),
+    };
+    if rest.bytes().all(|b| b.is_ascii_digit()) {
+        let octal = u32::from_str_radix(rest, 8).ok().filter(|&m| m <= 0o7777)?;
+        return Some(Clause {
+            dirs,
+            change: Change::Octal(octal),
+        });
+    }
+
+    let split = rest.find(['+', '-', '='])?;
+    let (classes, mut ops_text) = rest.split_at(split);
+    let mut who = 0;
+    for class in classes.chars() {
+        who |= match class {
+            'u' => USER,
+            'g' => GROUP,
+            'o' => OTHER,
+            'a' => USER | GROUP | OTHER,
+            _ => return None,
+        };
+    }
+    if who == 0 {
+        who = USER | GROUP | OTHER;
+    }
+    let mut ops = Vec::new();
+    while let Some(op) = ops_text.chars().next() {
+        if !matches!(op, '+' | '-' | '=') {
+            return None;
+        }
+        let perms_len = ops_text[1..]
+            .find(['+', '-', '='])
+            .unwrap_or(ops_text.len() - 1);
+        let perms = &ops_text[1..=perms_len];
+        if !perms.chars().all(|c| "rwxXst".contains(c)) {
+            return None;
+        }
+        ops.push((op, perms.to_string()));
+        ops_text = &ops_text[1 + perms_len..];
+    }
+    Some(Clause {
+        dirs,
+        change: Change::Symbolic { who, ops },
+    })
+}
+
+impl fmt::Display for Chmod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl From<Chmod> for String {
+    fn from(chmod: Chmod) -> Self {
+        chmod.text
+    }
+}
+
+impl TryFrom<String> for Chmod {
+    type Error = String;
+
+    fn try_from(text: String) -> std::result::Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chmod(text: &str) -> Chmod {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn octal_clauses_by_kind() {
+        let spec = chmod("D2775,F664");
+        assert_eq!(spec.apply(0o700, true), 0o2775);
+        assert_eq!(spec.apply(0o4755, false), 0o664);
+        assert_eq!(chmod("640").apply(0o777, true), 0o640);
+    }
+
+    #[test]
+    fn symbolic_clauses() {
+        assert_eq!(chmod("g+r").apply(0o600, false), 0o640);
+        assert_eq!(chmod("o-rwx").apply(0o755, true), 0o750);
+        assert_eq!(chmod("u=rwX,go=rX").apply(0o600, false), 0o644);
+        assert_eq!(chmod("u=rwX,go=rX").apply(0o700, false), 0o755);
+        assert_eq!(chmod("u=rwX,go=rX").apply(0o600, true), 0o755);
+        assert_eq!(chmod("Dg+s,Fa-x").apply(0o775, true), 0o2775);
+        assert_eq!(chmod("Dg+s,Fa-x").apply(0o775, false), 0o664);
+        assert_eq!(chmod("+t").apply(0o777, true), 0o1777);
+        assert_eq!(chmod("g=r-r+w").apply(0o070, false), 0o020);
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        for text in ["", "D", "888", "77777", "g", "q+r", "g+z", "u+r,"] {
+            assert!(text.parse::<Chmod>().is_err(), "{text:?}");
+        }
+        assert_eq!(chmod("Dg+s,F644").to_string(), "Dg+s,F644");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn apply_tree_changes_files_and_dirs() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path().join("tree");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/file.txt"), "data").unwrap();
+        std::os::unix::fs::symlink("sub/file.txt", root.join("link")).unwrap();
+
+        chmod("D750,F640").apply_tree(&root).unwrap();
+        let mode = |path: &str| {
+            let metadata = std::fs::symlink_metadata(root.join(path)).unwrap();
+            metadata.permissions().mode() & 0o7777
+        };
+        assert_eq!(mode(""), 0o750);
+        assert_eq!(mode("sub"), 0o750);
+        assert_eq!(mode("sub/file.txt"), 0o640);
+    }
+}
@@ -4,9 +4,23 @@
 //! It handles validation of command-line arguments and converts them into
 //! the internal `MoveOptions` type used by the core logic.
 
-use clap::Parser;
-use mvln::operation::MoveOptions;
-use std::path::PathBuf;
+use crate::echo::Echo;
+use crate::prompt;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use mvln::chmod::Chmod;
+use mvln::config::Profile;
+use mvln::conflict::BackupStyle;
+use mvln::glob_expand::{GlobOptions, DEFAULT_MAX_MATCHES};
+use mvln::normalize::Normalization;
+use mvln::operation::{MoveOptions, SymlinkStyle, VCS_DIRS};
+use mvln::path_utils::DirBehavior;
+#[cfg(feature = "exif")]
+use mvln::plan::DEFAULT_EXIF_DATE_FORMAT;
+use mvln::plan::{ErrorPolicy, Layout, SortKey, DEFAULT_DATE_FORMAT};
+use mvln::preserve::PreserveSet;
+use mvln::sanitize::Sanitizer;
+use mvln::size::{parse_size, SizeFilter};
+use std::path::{Path, PathBuf};
 
 /// Move files with flexible path resolution
 ///
@@ -15,35 +29,47 @@ use std::path::PathBuf;
 #[derive(Parser, Debug)]
 #[command(name = "mvln")]
 #[command(author, version, about, long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct Cli {
-    /// Source file(s) or directory to move
-    ///
-    /// Accepts one or more paths. If multiple sources are provided,
-    /// the destination must be a directory.
-    #[arg(required = true)]
-    pub source: Vec<PathBuf>,
+    /// Optional subcommand; without one, mvln moves SOURCE... to DEST
+    #[command(subcommand)]
+    pub command: Option<Command>,
 
-    /// Destination path (file or directory)
+    /// Source file(s) or directory to move, followed by the destination
     ///
-    /// If moving multiple sources, this must be a directory.
-    #[arg(required = true)]
-    pub dest: PathBuf,
+    /// The last path is the destination (file or directory) unless the
+    /// selected profile defines one, in which case every path is a source.
+    /// If multiple sources are provided, the destination must be a directory.
+    #[arg(required = true, value_name = "SOURCE... DEST")]
+    pub paths: Vec<PathBuf>,
 
     /// Use relative paths from the destination directory
     ///
     /// When creating symbolic links, paths will be relative to the
     /// destination directory. This is the default behavior.
-    #[arg(short = 'r', long, conflicts_with = "absolute")]
+    #[arg(short = 'r', long, global = true, conflicts_with = "absolute")]
     pub relative: bool,
 
     /// Use absolute paths for symbolic links
     ///
     /// When creating symbolic links, use absolute paths instead of
     /// relative paths.
-    #[arg(short = 'a', long, conflicts_with = "relative")]
+    #[arg(
+        short = 'a',
+        long,
+        global = true,
+        conflicts_with_all = ["relative", "relative_to"]
+    )]
     pub absolute: bool,
 
+    /// Compute relative symlink targets from DIR instead of the link's directory
+    ///
+    /// Useful when the links will be read through a different path than the
+    /// one they are created at (e.g. a tree that is later mounted elsewhere).
+    #[arg(long, value_name = "DIR", global = true, conflicts_with = "relative")]
+    pub relative_to: Option<PathBuf>,
+
     /// Move entire directory instead of just contents
     ///
     /// When the source is a directory, move the directory itself
@@ -52,22 +78,867 @@ pub struct Cli {
     #[arg(short = 'w', long)]
     pub whole_dir: bool,
 
+    /// Treat DEST as the new path even if it is a directory
+    ///
+    /// Like `mv -T`: `mvln -T -w photos /mnt/archive` makes `/mnt/archive`
+    /// the moved directory instead of moving into it, which fails if it
+    /// exists (replace it with -f). Without -T, a DEST written with a
+    /// trailing slash (`archive/`) is always a directory to move into, and
+    /// is created if missing.
+    #[arg(short = 'T', long, conflicts_with_all = ["cas", "by_ext", "by_date"])]
+    pub no_target_directory: bool,
+
+    /// Move the target of symlink sources instead of the link itself
+    ///
+    /// The real file (or directory, with -w) the link resolves to is moved
+    /// to DEST and linked back as usual, and the original symlink is
+    /// re-pointed at the new location.
+    #[arg(short = 'L', long, conflicts_with = "cas")]
+    pub dereference: bool,
+
+    /// With -L, also re-point the links a symlink source leads through
+    ///
+    /// When a source is a symlink to a symlink to a file, every link in the
+    /// chain ends up pointing directly at the moved file instead of at the
+    /// next link.
+    #[arg(long, requires = "dereference")]
+    pub flatten_links: bool,
+
     /// Enable verbose output
     ///
     /// Print detailed information about operations being performed.
-    #[arg(short = 'v', long)]
+    #[arg(short = 'v', long, global = true)]
     pub verbose: bool,
 
     /// Force overwrite of existing destination
     ///
     /// Overwrite the destination if it already exists. Only allows replacing
     /// files with files and directories with directories (not cross-type).
-    #[arg(short = 'f', long)]
+    #[arg(short = 'f', long, global = true)]
     pub force: bool,
 
     /// Print commands without executing
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub dry_run: bool,
+
+    /// Replace a moved directory by a tree of links N levels deep
+    ///
+    /// Instead of one symlink for the directory, recreate its structure at
+    /// the source down to depth N and link the individual entries, for tools
+    /// that refuse to traverse a symlinked top-level directory. 0 (the
+    /// default) links the directory itself.
+    #[arg(long, value_name = "N", default_value_t = 0, requires = "whole_dir")]
+    pub link_depth: usize,
+
+    /// Only move, without leaving a symlink at the source
+    ///
+    /// Makes mvln a safer `mv`: moves are still verified, copied across
+    /// filesystems with the same care and recorded in the journal.
+    #[arg(
+        long,
+        conflicts_with_all = ["cas", "link_depth", "dereference"]
+    )]
+    pub no_link: bool,
+
+    /// Leave a symlink into this sshfs mount for `user@host:path` moves
+    ///
+    /// MOUNT is where the destination directory is mounted locally with
+    /// sshfs; the source is replaced by a symlink to its copy there instead
+    /// of a `NAME.url` stub holding its `sftp://` URL.
+    #[cfg(feature = "ssh")]
+    #[arg(long, value_name = "MOUNT", conflicts_with = "no_link")]
+    pub sshfs: Option<PathBuf>,
+
+    /// Hard-link to identical files already at the destination
+    ///
+    /// When a moved file's contents match a file already under the
+    /// destination (or one recorded in the journal), the destination is
+    /// created as a hard link to that copy instead of storing the bytes
+    /// twice. The source is still replaced by a symlink.
+    #[arg(long)]
+    pub dedupe: bool,
+
+    /// Store files content-addressed under DEST/objects/
+    ///
+    /// Each file is kept once at `DEST/objects/<xx>/<sha256>` and the
+    /// original path becomes a symlink to it; files with identical contents
+    /// share one object across runs. Only regular files are supported.
+    #[arg(
+        long,
+        conflicts_with_all = ["by_ext", "by_date", "rename_on_conflict", "dedupe", "link_depth"]
+    )]
+    pub cas: bool,
+
+    /// Move directories entry by entry, down to N levels deep
+    ///
+    /// Instead of moving each -w directory in one piece, its entries are
+    /// moved (and linked) individually, recursing into subdirectories for
+    /// N levels; directories at that depth are moved whole. The source
+    /// directories stay in place. Useful for enormous trees, e.g. with
+    /// --max-files or --review.
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "whole_dir",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        conflicts_with_all = ["cas", "link_depth"]
+    )]
+    pub max_depth: Option<usize>,
+
+    /// With --max-depth, leave directories at the depth limit in place
+    /// instead of moving them whole
+    #[arg(long, requires = "max_depth")]
+    pub skip_deeper: bool,
+
+    /// Do not take the per-destination run lock
+    ///
+    /// By default mvln holds an advisory lock on the destination root so
+    /// that concurrent runs (e.g. cron and an interactive shell) don't race.
+    #[arg(long, conflicts_with = "lock_timeout")]
+    pub no_lock: bool,
+
+    /// Wait up to SECONDS for another run to release the lock
+    ///
+    /// Without this option mvln fails immediately if the destination is
+    /// locked by another run.
+    #[arg(long, value_name = "SECONDS")]
+    pub lock_timeout: Option<u64>,
+
+    /// Copy across filesystems in chunks of SIZE (e.g. `4M`)
+    ///
+    /// By default the OS picks the copy method. A large buffer can help
+    /// when moving huge files to spinning disks.
+    #[arg(long, value_name = "SIZE", value_parser = parse_buffer_size)]
+    pub buffer_size: Option<usize>,
+
+    /// Bypass the page cache when copying across filesystems (Linux only)
+    ///
+    /// Keeps a large move from evicting everything else from the cache.
+    /// Filesystems without direct I/O support fall back to normal writes.
+    #[arg(long)]
+    pub direct_io: bool,
+
+    /// Copy directory trees across filesystems through streamed packs
+    ///
+    /// Small files are read whole and written in batches, cutting the
+    /// per-file overhead of trees with many thousands of tiny files.
+    /// Nothing is written to a temporary archive.
+    #[arg(long)]
+    pub pack: bool,
+
+    /// Show how far directory copies across filesystems have got
+    ///
+    /// Prints the files and bytes copied so far and the directory being
+    /// copied (e.g. `node_modules/react/…`), to see where the time goes in
+    /// large trees. On a terminal the line is updated in place; otherwise a
+    /// line is printed for each directory reached.
+    #[arg(long)]
+    pub progress: bool,
+
+    /// Leave version control and cache directories out of copied trees
+    ///
+    /// `.git`, `.hg`, `node_modules`, `target` and `__pycache__` inside
+    /// directories copied across filesystems are not copied and stay in
+    /// place at the source, so such moves need --no-link. A move within one
+    /// filesystem is a rename and takes them along.
+    #[arg(long)]
+    pub skip_vcs: bool,
+
+    /// With --skip-vcs, also leave out directories named NAME (may be
+    /// repeated)
+    #[arg(long, value_name = "NAME", requires = "skip_vcs")]
+    pub skip_dir: Vec<String>,
+
+    /// With --skip-vcs, copy directories named NAME after all, e.g. `.git`
+    /// (may be repeated)
+    #[arg(long, value_name = "NAME", requires = "skip_vcs")]
+    pub keep_dir: Vec<String>,
+
+    /// Attributes to keep when copying across filesystems
+    ///
+    /// Comma-separated list of mode, ownership, timestamps, xattr, finder,
+    /// links and all, added to the default of mode, timestamps and finder
+    /// (Finder tags and labels on macOS).
+    #[arg(long, value_name = "LIST")]
+    pub preserve: Option<PreserveSet>,
+
+    /// Attributes not to keep when copying across filesystems
+    ///
+    /// Same names as --preserve; wins over it.
+    #[arg(long, value_name = "LIST")]
+    pub no_preserve: Option<PreserveSet>,
+
+    /// Give files copied across filesystems their original owner
+    ///
+    /// Needs root. Without it, copies are owned by the user running mvln.
+    #[arg(long)]
+    pub owner: bool,
+
+    /// Give files copied across filesystems their original group
+    ///
+    /// Needs root, or membership of the group.
+    #[arg(long)]
+    pub group: bool,
+
+    /// With --owner/--group, restore IDs even if no such user or group exists
+    ///
+    /// By default, files of deleted users and groups are left owned by the
+    /// user running mvln.
+    #[arg(long)]
+    pub numeric_ids: bool,
+
+    /// Change the permissions of moved files at the destination
+    ///
+    /// rsync-style MODE: comma-separated octal or symbolic clauses, prefixed
+    /// with D (directories only) or F (files only), e.g. `D2775,F664` or
+    /// `g+rX,o-w`.
+    #[arg(long, value_name = "MODE")]
+    pub chmod: Option<Chmod>,
+
+    /// Remove the macOS quarantine attribute from moved files
+    ///
+    /// Downloads carry `com.apple.quarantine`, which makes Gatekeeper ask
+    /// again when they are opened from the archive. Ignored elsewhere.
+    #[arg(long, overrides_with = "keep_quarantine")]
+    pub strip_quarantine: bool,
+
+    /// Keep the macOS quarantine attribute of moved files (the default)
+    #[arg(long, overrides_with = "strip_quarantine")]
+    pub keep_quarantine: bool,
+
+    /// Unicode normalization of destination names
+    ///
+    /// macOS tools often write names decomposed (nfd), Linux ones composed
+    /// (nfc). Either way, an existing destination that differs only in
+    /// normalization counts as a conflict rather than getting a twin.
+    #[arg(long, value_name = "FORM", default_value = "none")]
+    pub normalize: NormalizeForm,
+
+    /// Rewrite names that FAT, exFAT or NTFS would reject
+    ///
+    /// Each of `"*:<>?\|`, control characters and trailing spaces or dots
+    /// is replaced with REPLACEMENT (default `_`), and device names such as
+    /// `CON` get it appended. Applies inside directories copied across
+    /// filesystems too; every rename is reported.
+    #[arg(
+        long,
+        value_name = "REPLACEMENT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "_",
+        conflicts_with = "link_depth"
+    )]
+    pub sanitize: Option<Sanitizer>,
+
+    /// Sort sources into per-extension subdirectories of DEST
+    ///
+    /// Each source goes to `DEST/<ext>/` (lowercased, `noext` for files
+    /// without an extension); subdirectories are created as needed.
+    #[arg(long, conflicts_with = "by_date")]
+    pub by_ext: bool,
+
+    /// Sort sources into date subdirectories of DEST by modification time
+    ///
+    /// FORMAT is a strftime-style pattern (`%Y`, `%m`, `%d`, `%H`, `%M`,
+    /// `%S`, `%j`, `%F`) evaluated in UTC; the default `%Y/%m` gives
+    /// `DEST/2024/06/`.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = DEFAULT_DATE_FORMAT
+    )]
+    pub by_date: Option<String>,
+
+    /// Sort photos into date subdirectories of DEST by when they were taken
+    ///
+    /// The date comes from the EXIF metadata of JPEG, HEIC and TIFF-based
+    /// raw files (DNG, CR2, NEF, ARW, ...), in the camera's local time;
+    /// other files, and photos without one, use their modification time.
+    /// FORMAT is as for --by-date; the default `%Y/%m/%d` gives
+    /// `DEST/2024/06/01/`.
+    #[cfg(feature = "exif")]
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = DEFAULT_EXIF_DATE_FORMAT,
+        conflicts_with_all = ["by_ext", "by_date", "cas", "no_target_directory"]
+    )]
+    pub by_exif_date: Option<String>,
+
+    /// Ask what to do when a destination exists
+    ///
+    /// Answer o(verwrite), s(kip), b(ackup: rename the existing file to
+    /// NAME~), r(ename the moved file), a(ll: overwrite this and every later
+    /// conflict) or q(uit: leave the remaining sources alone).
+    #[arg(short = 'i', long, conflicts_with_all = ["force", "rename_on_conflict"])]
+    pub interactive: bool,
+
+    /// Answer every prompt without asking, for unattended runs
+    ///
+    /// With --interactive, existing destinations are backed up (renamed to
+    /// NAME~) and the source is moved in their place, so nothing is lost.
+    #[arg(short = 'y', long, global = true)]
+    pub yes: bool,
+
+    /// Pick a unique name instead of failing when the destination exists
+    ///
+    /// Appends ` (1)`, ` (2)`, ... to the file stem when a destination
+    /// already exists or is claimed by another source in the same run.
+    #[arg(long, conflicts_with = "force")]
+    pub rename_on_conflict: bool,
+
+    /// Back up existing destinations instead of failing
+    ///
+    /// `simple` (the default) renames an existing destination to NAME~;
+    /// `timestamp` renames it to NAME.2024-06-01T12-30-00.bak (UTC time of
+    /// the move), so every generation is kept. The source is then moved in
+    /// its place.
+    #[arg(
+        long,
+        value_name = "STYLE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "simple",
+        conflicts_with_all = ["force", "interactive", "rename_on_conflict", "cas"]
+    )]
+    pub backup: Option<BackupMode>,
+
+    /// Take a destination file with the same size and mtime as already moved
+    ///
+    /// The source is removed and linked without copying, which makes
+    /// rerunning an interrupted batch cheap. Modification times are compared
+    /// to the second; contents are not compared at all.
+    #[arg(long, conflicts_with = "cas")]
+    pub skip_same: bool,
+
+    /// Ask before moving across filesystems, optionally only from SIZE up
+    ///
+    /// A move onto another filesystem is a copy followed by a removal, and
+    /// takes as long as copying the data. Answer y to copy or n to skip the
+    /// source; --yes answers y.
+    #[arg(
+        long,
+        value_name = "SIZE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0",
+        value_parser = parse_size,
+        conflicts_with = "no_cross_device"
+    )]
+    pub confirm_cross_device: Option<u64>,
+
+    /// Fail instead of copying when a source is on another filesystem
+    ///
+    /// For moves meant to stay on one disk: a source that can't be renamed
+    /// into place fails with `mvln::cross_device` and is left alone.
+    #[arg(long)]
+    pub no_cross_device: bool,
+
+    /// What to do with sources that are dangling symlinks
+    ///
+    /// `move` archives the broken link like any other file, `skip` leaves
+    /// it in place and `error` fails on it.
+    #[arg(long, value_name = "POLICY", default_value = "move")]
+    pub dangling: Dangling,
+
+    /// Check whether sources are open for writing before moving them
+    ///
+    /// Catches log files still being appended to. Open files are found
+    /// through /proc (Linux only; other users' processes only as root).
+    /// POLICY is `skip` (the default) to leave such sources in place,
+    /// `warn` to move them anyway, or `fail` to fail on them.
+    #[arg(
+        long,
+        value_name = "POLICY",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "skip"
+    )]
+    pub check_open: Option<CheckOpen>,
+
+    /// Let wildcards in sources match hidden files
+    ///
+    /// By default, as in most shells, `*` and `?` don't match a leading
+    /// `.`; spell it out (`.*`) or pass this flag to include dotfiles.
+    #[arg(long, global = true)]
+    pub hidden: bool,
+
+    /// Fail if one source pattern matches more than N paths (0: no limit)
+    ///
+    /// Catches an overly broad pattern like `**/*` before anything is
+    /// queued.
+    #[arg(long, value_name = "N", global = true, default_value_t = DEFAULT_MAX_MATCHES)]
+    pub max_matches: usize,
+
+    /// Skip sources matching PATTERN (may be repeated)
+    ///
+    /// Patterns are matched against both the file name and the full path.
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude: Vec<String>,
+
+    /// Only select sources larger than SIZE (e.g. `1G`, `500M`, `10KB`)
+    ///
+    /// Directories moved with -w are measured by their total size.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub larger_than: Option<u64>,
+
+    /// Only select sources smaller than SIZE
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub smaller_than: Option<u64>,
+
+    /// Move at most N sources in this run
+    ///
+    /// Remaining sources are listed at the end so a later run (e.g. the next
+    /// cron invocation) can pick them up.
+    #[arg(long, value_name = "N")]
+    pub max_files: Option<usize>,
+
+    /// Move at most SIZE bytes in this run (e.g. `50G`)
+    ///
+    /// Sources that would exceed the cap are deferred and listed at the end;
+    /// smaller ones later in the list may still be moved.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_bytes: Option<u64>,
+
+    /// Process sources in this order (default: as given / glob order)
+    #[arg(long, value_name = "KEY")]
+    pub sort: Option<SortBy>,
+
+    /// Reverse the --sort order (e.g. biggest or newest first)
+    #[arg(long, requires = "sort")]
+    pub reverse: bool,
+
+    /// Choose which of the sources to move before running
+    ///
+    /// Lists the sources with their sizes, numbered and all selected. Type
+    /// numbers or ranges (e.g. `2 5-7`) to toggle them, `a` or `n` to select
+    /// all or none, then press enter to move the selection or `q` to
+    /// cancel. Answers are read from stdin line by line.
+    #[arg(long)]
+    pub pick: bool,
+
+    /// Review the planned moves in a terminal UI before running them
+    ///
+    /// Shows the moves grouped by destination directory with their sizes;
+    /// deselect entries with space, then press enter to move the rest or q
+    /// to cancel.
+    #[cfg(feature = "tui")]
+    #[arg(long, conflicts_with = "background")]
+    pub review: bool,
+
+    /// Run in the background, detached from the terminal
+    ///
+    /// Starts the batch as a numbered job and returns at once; the terminal
+    /// can then be closed. The job's moves are recorded in its own journal
+    /// as they go, and its output is kept. See `mvln status`.
+    #[arg(long, conflicts_with_all = ["interactive", "pick"])]
+    pub background: bool,
+
+    /// Run CMD with `sh -c` before each move
+    ///
+    /// The source, destination and symlink paths are passed in
+    /// `MVLN_SOURCE`, `MVLN_DEST` and `MVLN_LINK`. If CMD fails, the source
+    /// is skipped. Not run with --dry-run.
+    #[arg(long, value_name = "CMD")]
+    pub pre_hook: Option<String>,
+
+    /// Run CMD with `sh -c` after each successful move
+    ///
+    /// Gets the same variables as --pre-hook, plus `MVLN_LINK_TARGET`, the
+    /// symlink's stored target. A failure is reported, but the move is kept.
+    #[arg(long, value_name = "CMD")]
+    pub post_hook: Option<String>,
+
+    /// Stop at the first failed source and skip the remaining ones
+    #[arg(long, overrides_with = "keep_going")]
+    pub fail_fast: bool,
+
+    /// Continue with the remaining sources after a failure (the default)
+    #[arg(long, overrides_with = "fail_fast")]
+    pub keep_going: bool,
+
+    /// Print the equivalent commands for this shell
+    ///
+    /// The echoed `mv`/`ln -s` lines use POSIX quoting by default; choose
+    /// fish, powershell or cmd (which uses `mklink`) to get commands that can
+    /// be pasted into that shell.
+    #[arg(
+        long = "shell",
+        value_name = "SHELL",
+        default_value = "posix",
+        global = true
+    )]
+    pub shell_dialect: ShellDialect,
+
+    /// Don't print the equivalent `mv`/`ln -s` commands
+    #[arg(long, global = true, conflicts_with = "commands_to")]
+    pub no_commands: bool,
+
+    /// Stream the equivalent commands are printed to
+    ///
+    /// Use `stderr` to keep stdout free for machine-readable output in
+    /// pipelines.
+    #[arg(long, value_name = "STREAM", default_value = "stdout", global = true)]
+    pub commands_to: CommandsTo,
+
+    /// Also write the commands to FILE as a standalone shell script
+    ///
+    /// The script (`#!/bin/sh`, `set -e`) reproduces the batch with plain
+    /// `mv`, `ln -s` and `mkdir -p`, for auditing or replaying it later on a
+    /// machine without mvln. Combine with --dry-run to only write the script.
+    #[arg(long, value_name = "FILE", global = true)]
+    pub emit_script: Option<PathBuf>,
+
+    /// Write a SHA256SUMS manifest of everything moved to FILE
+    ///
+    /// Each moved file (every file of a moved directory) is hashed at its
+    /// destination and listed relative to the destination root, so the
+    /// archive can be checked later with `sha256sum -c FILE` run there.
+    #[arg(long, value_name = "FILE")]
+    pub write_sums: Option<PathBuf>,
+
+    /// Append a timestamped log of the run to FILE
+    ///
+    /// Records every command run, every path skipped or deferred and every
+    /// error, in English, so unattended (e.g. cron) runs can be audited
+    /// after their output is gone.
+    #[arg(long, value_name = "FILE", global = true)]
+    pub log_file: Option<PathBuf>,
+
+    /// Also send the run log to the system log
+    ///
+    /// Errors are logged with priority `err`, everything else with `info`,
+    /// tagged `mvln`.
+    #[cfg(all(unix, feature = "syslog"))]
+    #[arg(long, value_enum, value_name = "TARGET", global = true)]
+    pub log: Option<LogTarget>,
+
+    /// Language for messages, e.g. `en-US` or `zh-CN`
+    ///
+    /// Overrides the `MVLN_LANG` environment variable and the system locale.
+    #[arg(long, value_name = "LOCALE", global = true)]
+    pub lang: Option<String>,
+
+    /// Apply the named profile from the configuration file
+    ///
+    /// Profiles are defined as `[profile.NAME]` tables in the configuration
+    /// file and may set the destination and default options.
+    #[arg(short = 'p', long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Apply a built-in preset for a common workflow
+    ///
+    /// `downloads-archive`, `media-offload` and `log-archive` bundle exclude
+    /// lists, checks and a destination layout. A `[preset.NAME]` table in
+    /// the configuration file changes a preset or defines a new one. Flags
+    /// and the --profile given win over the preset.
+    #[arg(long, value_name = "NAME")]
+    pub preset: Option<String>,
+
+    /// Read configuration from PATH instead of the default location
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+/// Shells accepted by `--shell`.
+#[allow(clippy::enum_variant_names)]
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShellDialect {
+    /// sh, bash, zsh and other POSIX shells
+    Posix,
+    Fish,
+    #[value(name = "powershell")]
+    PowerShell,
+    /// Windows cmd.exe
+    Cmd,
+}
+
+/// Policies accepted by `--dangling`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dangling {
+    /// Fail on the source
+    Error,
+    /// Leave the source in place
+    Skip,
+    /// Move the broken link like any other file
+    Move,
+}
+
+/// Policies accepted by `--check-open`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckOpen {
+    /// Leave the source in place
+    Skip,
+    /// Warn and move the source anyway
+    Warn,
+    /// Fail on the source
+    Fail,
+}
+
+/// Streams accepted by `--commands-to`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandsTo {
+    Stdout,
+    Stderr,
+}
+
+/// System logs accepted by `--log`.
+#[cfg(all(unix, feature = "syslog"))]
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogTarget {
+    /// The classic syslog socket (`/dev/log`)
+    Syslog,
+    /// systemd-journald's native protocol
+    Journald,
+}
+
+/// Values accepted by `--sort`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    /// File name
+    Name,
+    /// Size (smallest first; directories by total size)
+    Size,
+    /// Modification time (oldest first)
+    Mtime,
+}
+
+impl From<SortBy> for SortKey {
+    fn from(sort: SortBy) -> Self {
+        match sort {
+            SortBy::Name => Self::Name,
+            SortBy::Size => Self::Size,
+            SortBy::Mtime => Self::Mtime,
+        }
+    }
+}
+
+/// Styles accepted by `--backup`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupMode {
+    /// NAME~
+    Simple,
+    /// NAME.<date>T<time>.bak
+    Timestamp,
+}
+
+impl From<BackupMode> for BackupStyle {
+    fn from(mode: BackupMode) -> Self {
+        match mode {
+            BackupMode::Simple => Self::Simple,
+            BackupMode::Timestamp => Self::Timestamp,
+        }
+    }
+}
+
+/// Forms accepted by `--normalize`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalizeForm {
+    /// Composed (é as one character)
+    Nfc,
+    /// Decomposed (e followed by a combining accent)
+    Nfd,
+    /// Keep names as they are
+    None,
+}
+
+impl From<NormalizeForm> for Normalization {
+    fn from(form: NormalizeForm) -> Self {
+        match form {
+            NormalizeForm::Nfc => Self::Nfc,
+            NormalizeForm::Nfd => Self::Nfd,
+            NormalizeForm::None => Self::None,
+        }
+    }
+}
+
+/// Subcommands beyond the default move-and-link operation.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Sort files in a directory into subdirectories using the configured rules
+    ///
+    /// Rules come from the `[rules]` table of the configuration file and map
+    /// glob patterns to destination subdirectories, e.g. `"*.iso" = "images/"`.
+    /// Each matched file is moved and replaced by a symlink.
+    Organize(OrganizeArgs),
+
+    /// Rewrite existing symlinks between absolute and relative targets
+    ///
+    /// Works on any symlink, whether mvln created it or not. Only the way
+    /// the target is written changes; no data is moved.
+    ConvertLinks(ConvertLinksArgs),
+
+    /// Record existing symlinks in the journal so mvln can manage them
+    ///
+    /// Use this for links made by hand (e.g. with `mv` and `ln -s`). Each
+    /// link's target must exist; links already recorded are skipped.
+    Adopt(AdoptArgs),
+
+    /// Create the symlink at SOURCE for data already present at DEST
+    ///
+    /// Skips the move entirely, for repairing setups where files were moved
+    /// by other tools. DEST is used as-is and must exist. Honors -r, -a,
+    /// --relative-to and --dry-run; -f replaces an existing symlink (never a
+    /// regular file or directory).
+    LinkOnly(LinkOnlyArgs),
+
+    /// Link every file of a package directory into a prefix, like GNU stow
+    ///
+    /// For each file `PACKAGE/a/b` a symlink `TARGET/a/b` is created;
+    /// intermediate directories are created as real directories. Nothing is
+    /// changed if any path under TARGET is already occupied.
+    Stow(StowArgs),
+
+    /// Finish moves that an earlier run was interrupted in
+    ///
+    /// Every run already finishes interrupted moves that only lack their
+    /// symlink, and warns about the rest. This also moves sources that were
+    /// left in place, resuming partial copies, and replaces a partial copy
+    /// of a directory. A different file left at the destination is never
+    /// overwritten. Honors -r, -a, --relative-to and --dry-run.
+    Resume,
+
+    /// Show the progress of background jobs started with --background
+    ///
+    /// Lists the running jobs and the most recent ended ones: whether each
+    /// is running, has finished or was stopped (e.g. killed), its command,
+    /// the moves and bytes done out of its batch, the source being moved
+    /// and the moves that failed so far. Given a job number, shows that job
+    /// with the sources that failed and where its output is.
+    Status(StatusArgs),
+
+    /// Print a shell completion script
+    ///
+    /// Load it from your shell's startup file, e.g. for bash:
+    /// `source <(mvln completions bash)`. Profile names are completed from
+    /// the configuration file at completion time.
+    Completions(CompletionsArgs),
+
+    /// Print the manual page (roff) to stdout
+    ///
+    /// For packagers: `mvln man > mvln.1`.
+    Man,
+
+    /// List profile names for shell completion
+    #[command(name = "complete-profiles", hide = true)]
+    CompleteProfiles,
+}
+
+/// Arguments for `mvln status`.
+#[derive(Args, Debug)]
+pub struct StatusArgs {
+    /// Number of the job to show
+    #[arg(value_name = "JOB_ID")]
+    pub job: Option<u64>,
+
+    /// Refresh every two seconds until no shown job is running
+    #[arg(long)]
+    pub watch: bool,
+}
+
+/// Arguments for `mvln completions`.
+#[derive(Args, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to generate the script for
+    pub shell: Shell,
+}
+
+/// Shells supported by `mvln completions`.
+#[allow(clippy::enum_variant_names)]
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    #[value(name = "powershell")]
+    PowerShell,
+}
+
+/// Arguments for `mvln organize`.
+#[derive(Args, Debug)]
+pub struct OrganizeArgs {
+    /// Directory whose files are organized
+    pub dir: PathBuf,
+
+    /// Root under which rule destinations are created (default: DIR itself)
+    pub dest: Option<PathBuf>,
+}
+
+/// Arguments for `mvln convert-links`.
+#[derive(Args, Debug)]
+pub struct ConvertLinksArgs {
+    /// Rewrite targets relative to each link's directory (default)
+    #[arg(long, conflicts_with = "to_absolute")]
+    pub to_relative: bool,
+
+    /// Rewrite targets as absolute paths
+    #[arg(long)]
+    pub to_absolute: bool,
+
+    /// Symlinks to convert (glob patterns are expanded)
+    #[arg(required = true, value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+}
+
+/// Arguments for `mvln adopt`.
+#[derive(Args, Debug)]
+pub struct AdoptArgs {
+    /// Symlinks to record (glob patterns are expanded)
+    #[arg(required = true, value_name = "SYMLINK")]
+    pub links: Vec<PathBuf>,
+}
+
+/// Arguments for `mvln link-only`.
+#[derive(Args, Debug)]
+pub struct LinkOnlyArgs {
+    /// Where the symlink is created (the data's original location)
+    pub source: PathBuf,
+
+    /// Where the data already lives
+    pub dest: PathBuf,
+}
+
+/// Arguments for `mvln stow`.
+#[derive(Args, Debug)]
+pub struct StowArgs {
+    /// Package directory whose tree is linked into the target
+    pub package: PathBuf,
+
+    /// Prefix to link into (default: the parent of PACKAGE's parent)
+    #[arg(short = 't', long, value_name = "DIR")]
+    pub target: Option<PathBuf>,
+}
+
+impl StowArgs {
+    /// The prefix links are created under.
+    ///
+    /// Like stow, this defaults to the parent of the directory containing
+    /// the package, e.g. `~` for `~/dotfiles/vim`.
+    pub fn prefix(&self) -> PathBuf {
+        if let Some(target) = &self.target {
+            return target.clone();
+        }
+        let package = std::path::absolute(&self.package).unwrap_or_else(|_| self.package.clone());
+        package
+            .parent()
+            .and_then(Path::parent)
+            .map_or_else(|| PathBuf::from("/"), Path::to_path_buf)
+    }
+}
+
+impl ConvertLinksArgs {
+    /// Symlink style selected by `--to-relative` / `--to-absolute`.
+    pub fn symlink_style(&self) -> SymlinkStyle {
+        if self.to_absolute {
+            SymlinkStyle::Absolute
+        } else {
+            SymlinkStyle::Relative
+        }
+    }
 }
 
 impl Cli {
@@ -91,86 +962,657 @@ impl Cli {
     /// let options = cli.to_move_options();
     /// ```
     pub fn to_move_options(&self) -> MoveOptions {
-        MoveOptions {
-            absolute: self.absolute,
-            force: self.force,
-            dry_run: self.dry_run,
+        let builder = MoveOptions::builder()
+            .symlink_style(self.symlink_style())
+            .force(self.force)
+            .dry_run(self.dry_run)
+            .link_depth(self.link_depth)
+            .dir_behavior(self.dir_behavior())
+            .no_link(self.no_link)
+            .skip_same(self.skip_same)
+            .no_cross_device(self.no_cross_device)
+            .direct_io(self.direct_io)
+            .pack(self.pack)
+            .skip_dirs(self.skip_dirs())
+            .preserve(self.preserve_set())
+            .numeric_ids(self.numeric_ids)
+            .strip_quarantine(self.strip_quarantine)
+            .normalize(self.normalize.into());
+        let builder = match self.buffer_size {
+            Some(size) => builder.buffer_size(size),
+            None => builder,
+        };
+        let builder = match &self.chmod {
+            Some(chmod) => builder.chmod(chmod.clone()),
+            None => builder,
+        };
+        let builder = match &self.sanitize {
+            Some(sanitizer) => builder.sanitize(sanitizer.clone()),
+            None => builder,
+        };
+        let builder = match self.backup {
+            Some(mode) => builder.backup(mode.into()),
+            None => builder,
+        };
+        let lang = self.lang.clone();
+        let yes = self.yes;
+        let threshold = self.confirm_cross_device;
+        let builder = builder.on_cross_device(move |info| {
+            prompt::announce_copy(
+                lang.as_deref(),
+                info.source,
+                info.dest,
+                info.files,
+                info.bytes,
+            );
+            threshold.is_none_or(|threshold| info.bytes < threshold)
+                || prompt::ask_cross_device(lang.as_deref(), yes, info)
+        });
+        let builder = if self.progress {
+            let lang = self.lang.clone();
+            builder.on_progress(move |progress| prompt::show_progress(lang.as_deref(), progress))
+        } else {
+            builder
+        };
+        if self.interactive {
+            let lang = self.lang.clone();
+            let yes = self.yes;
+            builder
+                .on_conflict(move |conflict| prompt::ask_conflict(lang.as_deref(), yes, conflict))
+                .build()
+        } else {
+            builder.build()
+        }
+    }
+
+    /// Symlink style selected by `-r`, `-a` and `--relative-to`.
+    pub fn symlink_style(&self) -> SymlinkStyle {
+        if self.absolute {
+            SymlinkStyle::Absolute
+        } else if let Some(base) = &self.relative_to {
+            SymlinkStyle::RelativeTo(base.clone())
+        } else {
+            SymlinkStyle::Relative
+        }
+    }
+
+    /// Directories left out of copied trees: the built-in [`VCS_DIRS`]
+    /// with --skip-vcs, plus --skip-dir, minus --keep-dir.
+    pub fn skip_dirs(&self) -> Vec<String> {
+        if !self.skip_vcs {
+            return Vec::new();
+        }
+        let mut names: Vec<String> = Vec::new();
+        let builtin = VCS_DIRS.iter().map(|name| (*name).to_string());
+        for name in builtin.chain(self.skip_dir.iter().cloned()) {
+            if !self.keep_dir.contains(&name) && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Whether DEST is a directory to move into or the new path (`-T`).
+    pub fn dir_behavior(&self) -> DirBehavior {
+        if self.no_target_directory {
+            DirBehavior::NoTargetDirectory
+        } else {
+            DirBehavior::Auto
         }
     }
+
+    /// Destination layout selected by the routing flags.
+    pub fn layout(&self) -> Layout {
+        #[cfg(feature = "exif")]
+        if let Some(format) = &self.by_exif_date {
+            return Layout::ByExifDate(format.clone());
+        }
+        if self.by_ext {
+            Layout::ByExtension
+        } else if let Some(format) = &self.by_date {
+            Layout::ByDate(format.clone())
+        } else {
+            Layout::Flat
+        }
+    }
+
+    /// What the batch does after a failed source, from `--fail-fast` and
+    /// `--keep-going` (the last one given wins).
+    pub fn error_policy(&self) -> ErrorPolicy {
+        if self.fail_fast {
+            ErrorPolicy::FailFast
+        } else {
+            ErrorPolicy::KeepGoing
+        }
+    }
+
+    /// Renderer for the echoed commands, in the `--shell` dialect and
+    /// printing to the `--commands-to` stream.
+    pub fn echo(&self) -> Echo {
+        let output = (!self.no_commands).then_some(self.commands_to);
+        Echo::new(self.shell_dialect, output)
+    }
+
+    /// Attributes kept by copies: the defaults plus `--preserve`,
+    /// `--owner` and `--group`, minus `--no-preserve`.
+    pub fn preserve_set(&self) -> PreserveSet {
+        let mut set = PreserveSet::default() | self.preserve.unwrap_or(PreserveSet::empty());
+        set.set(
+            PreserveSet::OWNER,
+            set.contains(PreserveSet::OWNER) || self.owner,
+        );
+        set.set(
+            PreserveSet::GROUP,
+            set.contains(PreserveSet::GROUP) || self.group,
+        );
+        set - self.no_preserve.unwrap_or(PreserveSet::empty())
+    }
+
+    /// How glob patterns among the sources are expanded.
+    pub fn glob_options(&self) -> GlobOptions {
+        let options = GlobOptions::builder().hidden(self.hidden);
+        match self.max_matches {
+            0 => options.build(),
+            limit => options.max_matches(limit).build(),
+        }
+    }
+
+    /// Size bounds selected by `--larger-than` and `--smaller-than`.
+    pub fn size_filter(&self) -> SizeFilter {
+        SizeFilter {
+            larger_than: self.larger_than,
+            smaller_than: self.smaller_than,
+        }
+    }
+
+    /// Merge the settings of a profile or preset into the parsed arguments.
+    ///
+    /// Flags given on the command line always win: a profile can turn
+    /// options on but never turns off something the user asked for.
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        if profile.absolute == Some(true) && !self.relative && self.relative_to.is_none() {
+            self.absolute = true;
+        }
+        self.force |= profile.force.unwrap_or(false);
+        self.whole_dir |= profile.whole_dir.unwrap_or(false);
+        self.verbose |= profile.verbose.unwrap_or(false);
+        if self.lock_timeout.is_none() && !self.no_lock {
+            self.lock_timeout = profile.lock_timeout;
+        }
+        self.exclude.extend(profile.exclude.iter().cloned());
+        if self.pre_hook.is_none() {
+            self.pre_hook.clone_from(&profile.pre_hook);
+        }
+        if self.post_hook.is_none() {
+            self.post_hook.clone_from(&profile.post_hook);
+        }
+        let routed = self.by_ext || self.by_date.is_some() || self.cas;
+        #[cfg(feature = "exif")]
+        let routed = routed || self.by_exif_date.is_some();
+        if !routed {
+            self.apply_profile_layout(profile);
+        }
+        if self.check_open.is_none() {
+            self.check_open = profile
+                .check_open
+                .as_deref()
+                .and_then(|policy| CheckOpen::from_str(policy, false).ok());
+        }
+        if !self.force && !self.interactive && self.backup.is_none() {
+            self.rename_on_conflict |= profile.rename_on_conflict.unwrap_or(false);
+        }
+        self.skip_same |= profile.skip_same.unwrap_or(false) && !self.cas;
+    }
+
+    /// The destination layout of a profile, for a command line that
+    /// selects none. Without the `exif` feature, `by-exif-date` sorts by
+    /// modification time, as it does for files without EXIF data.
+    fn apply_profile_layout(&mut self, profile: &Profile) {
+        if let Some(format) = &profile.by_exif_date {
+            #[cfg(feature = "exif")]
+            {
+                self.by_exif_date = Some(format.clone());
+            }
+            #[cfg(not(feature = "exif"))]
+            {
+                self.by_date = Some(format.clone());
+            }
+        } else if let Some(format) = &profile.by_date {
+            self.by_date = Some(format.clone());
+        } else {
+            self.by_ext = profile.by_ext.unwrap_or(false);
+        }
+    }
+
+    /// Split the positional paths into sources and destination.
+    ///
+    /// With a `default_dest` (from a profile) every path is a source;
+    /// otherwise the last path is the destination. Returns `None` if no
+    /// source remains.
+    pub fn operands(&self, default_dest: Option<&Path>) -> Option<(Vec<PathBuf>, PathBuf)> {
+        if let Some(dest) = default_dest {
+            return Some((self.paths.clone(), dest.to_path_buf()));
+        }
+        let (dest, sources) = self.paths.split_last()?;
+        if sources.is_empty() {
+            return None;
+        }
+        Some((sources.to_vec(), dest.clone()))
+    }
+}
+
+/// Parse a non-zero `--buffer-size`.
+fn parse_buffer_size(text: &str) -> Result<usize, String> {
+    match usize::try_from(parse_size(text)?) {
+        Ok(0) => Err("buffer size must not be zero".to_string()),
+        Ok(size) => Ok(size),
+        Err(_) => Err(format!("invalid size {text:?}: too large")),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn parse(args: &[&str]) -> Cli {
+        Cli::parse_from(std::iter::once("mvln").chain(args.iter().copied()))
+    }
+
     #[test]
     fn test_default_to_relative() {
-        let cli = Cli {
-            source: vec![PathBuf::from("src")],
-            dest: PathBuf::from("dst"),
-            relative: false,
-            absolute: false,
-            whole_dir: false,
-            verbose: false,
-            force: false,
-            dry_run: false,
-        };
+        let cli = parse(&["src", "dst"]);
 
         let options = cli.to_move_options();
-        assert!(!options.absolute); // Default is relative
+        assert_eq!(options.symlink_style, SymlinkStyle::Relative); // Default is relative
     }
 
     #[test]
     fn test_explicit_relative() {
-        let cli = Cli {
-            source: vec![PathBuf::from("src")],
-            dest: PathBuf::from("dst"),
-            relative: true,
-            absolute: false,
-            whole_dir: false,
-            verbose: false,
-            force: false,
-            dry_run: false,
-        };
+        let cli = parse(&["-r", "src", "dst"]);
 
         let options = cli.to_move_options();
-        assert!(!options.absolute); // Explicit relative
+        assert_eq!(options.symlink_style, SymlinkStyle::Relative); // Explicit relative
     }
 
     #[test]
     fn test_explicit_absolute() {
-        let cli = Cli {
-            source: vec![PathBuf::from("src")],
-            dest: PathBuf::from("dst"),
-            relative: false,
-            absolute: true,
-            whole_dir: false,
-            verbose: false,
-            force: false,
-            dry_run: false,
-        };
+        let cli = parse(&["-a", "src", "dst"]);
+
+        let options = cli.to_move_options();
+        assert_eq!(options.symlink_style, SymlinkStyle::Absolute); // Explicit absolute
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let cli = parse(&["--relative-to", "/mnt", "src", "dst"]);
 
         let options = cli.to_move_options();
-        assert!(options.absolute); // Explicit absolute
+        assert_eq!(
+            options.symlink_style,
+            SymlinkStyle::RelativeTo(PathBuf::from("/mnt"))
+        );
     }
 
     #[test]
     fn test_multiple_sources() {
-        let cli = Cli {
-            source: vec![
-                PathBuf::from("file1.txt"),
-                PathBuf::from("file2.txt"),
-                PathBuf::from("dir"),
-            ],
-            dest: PathBuf::from("target"),
-            relative: false,
-            absolute: false,
-            whole_dir: false,
-            verbose: false,
-            force: false,
-            dry_run: false,
+        let cli = parse(&["file1.txt", "file2.txt", "dir", "target"]);
+
+        let (sources, dest) = cli.operands(None).unwrap();
+        assert_eq!(sources.len(), 3);
+        assert_eq!(dest, PathBuf::from("target"));
+    }
+
+    #[test]
+    fn test_by_ext_layout() {
+        assert_eq!(parse(&["a", "b"]).layout(), Layout::Flat);
+        assert_eq!(parse(&["--by-ext", "a", "b"]).layout(), Layout::ByExtension);
+    }
+
+    #[test]
+    fn test_by_date_layout() {
+        assert_eq!(
+            parse(&["--by-date", "a", "b"]).layout(),
+            Layout::ByDate("%Y/%m".to_string())
+        );
+        assert_eq!(
+            parse(&["--by-date=%Y/%m/%d", "a", "b"]).layout(),
+            Layout::ByDate("%Y/%m/%d".to_string())
+        );
+    }
+
+    #[cfg(feature = "exif")]
+    #[test]
+    fn test_by_exif_date_layout() {
+        assert_eq!(
+            parse(&["--by-exif-date", "a", "b"]).layout(),
+            Layout::ByExifDate("%Y/%m/%d".to_string())
+        );
+        assert!(Cli::try_parse_from(["mvln", "--by-exif-date", "--by-date", "a", "b"]).is_err());
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn test_sshfs_conflicts_with_no_link() {
+        let cli = parse(&["--sshfs", "/mnt/nas", "a", "nas:backups"]);
+        assert_eq!(cli.sshfs.as_deref(), Some(Path::new("/mnt/nas")));
+        assert!(Cli::try_parse_from(["mvln", "--sshfs", "m", "--no-link", "a", "b"]).is_err());
+    }
+
+    #[test]
+    fn test_background_needs_no_terminal() {
+        assert!(parse(&["--background", "-w", "a", "b"]).background);
+        assert!(matches!(
+            parse(&["status", "3", "--watch"]).command,
+            Some(Command::Status(StatusArgs {
+                job: Some(3),
+                watch: true
+            }))
+        ));
+        for prompt in ["-i", "--pick"] {
+            assert!(Cli::try_parse_from(["mvln", "--background", prompt, "a", "b"]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_missing_destination() {
+        let cli = parse(&["only-one"]);
+        assert!(cli.operands(None).is_none());
+    }
+
+    #[test]
+    fn test_profile_dest_makes_all_paths_sources() {
+        let cli = parse(&["a", "b"]);
+
+        let (sources, dest) = cli.operands(Some(Path::new("/archive"))).unwrap();
+        assert_eq!(sources, vec![PathBuf::from("a"), PathBuf::from("b")]);
+        assert_eq!(dest, PathBuf::from("/archive"));
+    }
+
+    #[test]
+    fn test_profile_does_not_override_explicit_flags() {
+        let mut cli = parse(&["-r", "--exclude", "*.tmp", "src", "dst"]);
+        cli.apply_profile(&Profile {
+            absolute: Some(true),
+            force: Some(true),
+            exclude: vec!["*.part".to_string()],
+            ..Profile::default()
+        });
+
+        assert!(!cli.absolute); // -r given explicitly
+        assert!(cli.force);
+        assert_eq!(cli.exclude, vec!["*.tmp", "*.part"]);
+    }
+
+    #[test]
+    fn test_profile_layout_and_checks() {
+        let mut cli = parse(&["src", "dst"]);
+        cli.apply_profile(&Profile {
+            by_date: Some("%Y".to_string()),
+            check_open: Some("warn".to_string()),
+            rename_on_conflict: Some(true),
+            ..Profile::default()
+        });
+        assert_eq!(cli.layout(), Layout::ByDate("%Y".to_string()));
+        assert_eq!(cli.check_open, Some(CheckOpen::Warn));
+        assert!(cli.rename_on_conflict);
+
+        let mut cli = parse(&["--by-ext", "--force", "src", "dst"]);
+        cli.apply_profile(&Profile {
+            by_date: Some("%Y".to_string()),
+            rename_on_conflict: Some(true),
+            ..Profile::default()
+        });
+        assert_eq!(cli.layout(), Layout::ByExtension);
+        assert!(!cli.rename_on_conflict);
+    }
+
+    #[test]
+    fn test_convert_links_subcommand() {
+        let cli = parse(&["convert-links", "--to-absolute", "link", "--dry-run"]);
+        assert!(cli.dry_run);
+        let Some(Command::ConvertLinks(args)) = cli.command else {
+            panic!("expected convert-links");
+        };
+        assert_eq!(args.symlink_style(), SymlinkStyle::Absolute);
+        assert_eq!(args.paths, vec![PathBuf::from("link")]);
+    }
+
+    #[test]
+    fn test_link_only_accepts_style_flags_after_subcommand() {
+        let cli = parse(&["link-only", "-a", "-f", "src", "dst"]);
+        assert_eq!(cli.symlink_style(), SymlinkStyle::Absolute);
+        assert!(cli.force);
+        assert!(matches!(cli.command, Some(Command::LinkOnly(_))));
+    }
+
+    #[test]
+    fn test_resume_subcommand() {
+        let cli = parse(&["--dry-run", "resume"]);
+        assert!(cli.dry_run);
+        assert!(matches!(cli.command, Some(Command::Resume)));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_stow_default_prefix_is_grandparent() {
+        let cli = parse(&["stow", "/home/u/dotfiles/vim"]);
+        let Some(Command::Stow(args)) = cli.command else {
+            panic!("expected stow");
         };
+        assert_eq!(args.prefix(), PathBuf::from("/home/u"));
+    }
 
-        assert_eq!(cli.source.len(), 3);
+    #[test]
+    fn test_size_filter_flags() {
+        let cli = parse(&["--larger-than", "1G", "--smaller-than=2G", "src", "dst"]);
+        assert_eq!(
+            cli.size_filter(),
+            SizeFilter {
+                larger_than: Some(1 << 30),
+                smaller_than: Some(2 << 30),
+            }
+        );
+    }
+
+    #[test]
+    fn test_copy_tuning_flags() {
+        let options =
+            parse(&["--buffer-size", "4M", "--direct-io", "src", "dst"]).to_move_options();
+        assert_eq!(options.buffer_size, Some(4 << 20));
+        assert!(options.direct_io);
+        assert!(parse(&["--pack", "src", "dst"]).to_move_options().pack);
+        assert!(Cli::try_parse_from(["mvln", "--buffer-size", "0", "src", "dst"]).is_err());
+    }
+
+    #[test]
+    fn test_skip_vcs_dirs() {
+        assert!(parse(&["src", "dst"])
+            .to_move_options()
+            .skip_dirs
+            .is_empty());
+        assert_eq!(parse(&["--skip-vcs", "src", "dst"]).skip_dirs(), VCS_DIRS);
+        let cli = parse(&[
+            "--skip-vcs",
+            "--keep-dir",
+            ".git",
+            "--skip-dir",
+            ".venv",
+            "src",
+            "dst",
+        ]);
+        assert_eq!(
+            cli.skip_dirs(),
+            [".hg", "node_modules", "target", "__pycache__", ".venv"]
+        );
+        assert!(Cli::try_parse_from(["mvln", "--skip-dir", ".venv", "src", "dst"]).is_err());
+    }
+
+    #[test]
+    fn test_chmod_flag() {
+        let options = parse(&["--chmod", "D2775,F664", "src", "dst"]).to_move_options();
+        assert_eq!(options.chmod.unwrap().apply(0o700, true), 0o2775);
+        assert!(Cli::try_parse_from(["mvln", "--chmod", "g+z", "src", "dst"]).is_err());
+    }
+
+    #[test]
+    fn test_quarantine_flags() {
+        assert!(!parse(&["src", "dst"]).to_move_options().strip_quarantine);
+        let options = parse(&["--strip-quarantine", "src", "dst"]).to_move_options();
+        assert!(options.strip_quarantine);
+        let cli = parse(&["--strip-quarantine", "--keep-quarantine", "src", "dst"]);
+        assert!(!cli.to_move_options().strip_quarantine);
+    }
+
+    #[test]
+    fn test_normalize_flag() {
+        let options = parse(&["--normalize", "nfd", "src", "dst"]).to_move_options();
+        assert_eq!(options.normalize, Normalization::Nfd);
+        let options = parse(&["src", "dst"]).to_move_options();
+        assert_eq!(options.normalize, Normalization::None);
+        assert!(Cli::try_parse_from(["mvln", "--normalize", "nfkc", "src", "dst"]).is_err());
+    }
+
+    #[test]
+    fn test_no_link_flag() {
+        assert!(
+            parse(&["--no-link", "src", "dst"])
+                .to_move_options()
+                .no_link
+        );
+        assert!(!parse(&["src", "dst"]).to_move_options().no_link);
+        assert!(Cli::try_parse_from(["mvln", "--no-link", "--cas", "src", "dst"]).is_err());
+    }
+
+    #[test]
+    fn test_backup_flag() {
+        let backup = |args: &[&str]| parse(args).to_move_options().backup;
+        assert_eq!(
+            backup(&["--backup=timestamp", "src", "dst"]),
+            Some(BackupStyle::Timestamp)
+        );
+        assert_eq!(
+            backup(&["--backup", "src", "dst"]),
+            Some(BackupStyle::Simple)
+        );
+        assert_eq!(backup(&["src", "dst"]), None);
+        assert!(Cli::try_parse_from(["mvln", "--backup", "-f", "src", "dst"]).is_err());
+    }
+
+    #[test]
+    fn test_skip_same_flag() {
+        assert!(
+            parse(&["--skip-same", "src", "dst"])
+                .to_move_options()
+                .skip_same
+        );
+        assert!(Cli::try_parse_from(["mvln", "--skip-same", "--cas", "src", "dst"]).is_err());
+    }
+
+    #[test]
+    fn test_confirm_cross_device_flag() {
+        let threshold = |args: &[&str]| parse(args).confirm_cross_device;
+        assert_eq!(
+            threshold(&["--confirm-cross-device=1M", "src", "dst"]),
+            Some(1 << 20)
+        );
+        assert_eq!(
+            threshold(&["--confirm-cross-device", "src", "dst"]),
+            Some(0)
+        );
+        assert_eq!(threshold(&["src", "dst"]), None);
+    }
+
+    #[test]
+    fn test_check_open_policy() {
+        let policy = |args: &[&str]| parse(args).check_open;
+        assert_eq!(
+            policy(&["--check-open", "src", "dst"]),
+            Some(CheckOpen::Skip)
+        );
+        assert_eq!(
+            policy(&["--check-open=fail", "src", "dst"]),
+            Some(CheckOpen::Fail)
+        );
+        assert_eq!(policy(&["src", "dst"]), None);
+    }
+
+    #[test]
+    fn test_no_cross_device_flag() {
+        assert!(
+            parse(&["--no-cross-device", "src", "dst"])
+                .to_move_options()
+                .no_cross_device
+        );
+        assert!(Cli::try_parse_from([
+            "mvln",
+            "--no-cross-device",
+            "--confirm-cross-device",
+            "src",
+            "dst"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_sanitize_flag() {
+        let options = parse(&["--sanitize", "src", "dst"]).to_move_options();
+        assert_eq!(options.sanitize.unwrap().replacement(), "_");
+        let options = parse(&["--sanitize=-", "src", "dst"]).to_move_options();
+        assert_eq!(options.sanitize.unwrap().replacement(), "-");
+        assert!(parse(&["src", "dst"]).to_move_options().sanitize.is_none());
+        assert!(Cli::try_parse_from(["mvln", "--sanitize=:", "src", "dst"]).is_err());
+    }
+
+    #[test]
+    fn test_ownership_flags() {
+        let options =
+            parse(&["--owner", "--group", "--numeric-ids", "src", "dst"]).to_move_options();
+        assert!(options.preserve.contains(PreserveSet::OWNERSHIP) && options.numeric_ids);
+        let options = parse(&["src", "dst"]).to_move_options();
+        assert_eq!(options.preserve, PreserveSet::default());
+    }
+
+    #[test]
+    fn test_preserve_lists() {
+        let cli = parse(&["--preserve=xattr,links", "src", "dst"]);
+        assert_eq!(
+            cli.preserve_set(),
+            PreserveSet::default() | PreserveSet::XATTR | PreserveSet::LINKS
+        );
+        let cli = parse(&[
+            "--preserve=all",
+            "--no-preserve=mode,ownership",
+            "src",
+            "dst",
+        ]);
+        assert_eq!(
+            cli.preserve_set(),
+            PreserveSet::TIMESTAMPS | PreserveSet::XATTR | PreserveSet::FINDER | PreserveSet::LINKS
+        );
+        let cli = parse(&["--owner", "--no-preserve", "timestamps", "src", "dst"]);
+        assert_eq!(
+            cli.preserve_set(),
+            PreserveSet::MODE | PreserveSet::FINDER | PreserveSet::OWNER
+        );
+        assert!(Cli::try_parse_from(["mvln", "--preserve=acl", "src", "dst"]).is_err());
+    }
+
+    #[test]
+    fn test_sort_flags() {
+        let cli = parse(&["--sort", "size", "--reverse", "src", "dst"]);
+        assert_eq!(cli.sort.map(SortKey::from), Some(SortKey::Size));
+        assert!(cli.reverse);
+    }
+
+    #[test]
+    fn test_error_policy_last_flag_wins() {
+        assert_eq!(
+            parse(&["src", "dst"]).error_policy(),
+            ErrorPolicy::KeepGoing
+        );
+        let cli = parse(&["--fail-fast", "src", "dst"]);
+        assert_eq!(cli.error_policy(), ErrorPolicy::FailFast);
+        let cli = parse(&["--fail-fast", "--keep-going", "src", "dst"]);
+        assert_eq!(cli.error_policy(), ErrorPolicy::KeepGoing);
     }
 }
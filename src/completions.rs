@@ -0,0 +1,496 @@
+//! Shell completion scripts.
+//!
+//! The scripts are generated from the clap command definition, so new
+//! options and subcommands are picked up automatically. Profile names are
+//! completed dynamically: the scripts call the hidden `mvln complete-profiles`
+//! subcommand, which lists the profiles of the current configuration file.
+
+use std::fmt::Write;
+
+use clap::{Arg, Command};
+
+use crate::cli::Shell;
+
+/// Hidden subcommand printing the configured profile names, one per line.
+pub const PROFILES_COMMAND: &str = "complete-profiles";
+
+/// How the value of an option is completed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ValueKind {
+    /// The option is a flag and takes no value.
+    None,
+    /// Any value; fall back to file names.
+    Path,
+    /// One of a fixed set of values.
+    Choice(Vec<String>),
+    /// A profile name from the configuration file.
+    Profile,
+}
+
+/// An option as seen by the completion scripts.
+#[derive(Debug, Clone)]
+struct Opt {
+    short: Option<char>,
+    long: Option<String>,
+    help: String,
+    value: ValueKind,
+    global: bool,
+}
+
+impl Opt {
+    fn from_arg(arg: &Arg) -> Self {
+        let takes_value = arg.get_num_args().is_some_and(|n| n.takes_values());
+        let value = if !takes_value {
+            ValueKind::None
+        } else if arg.get_id() == "profile" {
+            ValueKind::Profile
+        } else {
+            let values: Vec<String> = arg
+                .get_possible_values()
+                .iter()
+                .filter(|v| !v.is_hide_set())
+                .map(|v| v.get_name().to_string())
+                .collect();
+            if values.is_empty() {
+                ValueKind::Path
+            } else {
+                ValueKind::Choice(values)
+            }
+        };
+        Self {
+            short: arg.get_short(),
+            long: arg.get_long().map(str::to_string),
+            help: first_line(arg.get_help().map(ToString::to_string)),
+            value,
+            global: arg.is_global_set(),
+        }
+    }
+
+    /// All spellings of the option, e.g. `-v` and `--verbose`.
+    fn names(&self) -> Vec<String> {
+        self.short
+            .map(|s| format!("-{s}"))
+            .into_iter()
+            .chain(self.long.iter().map(|l| format!("--{l}")))
+            .collect()
+    }
+}
+
+/// A (sub)command with its visible options.
+#[derive(Debug)]
+struct Cmd {
+    name: String,
+    help: String,
+    opts: Vec<Opt>,
+}
+
+impl Cmd {
+    fn from_command(cmd: &Command) -> Self {
+        Self {
+            name: cmd.get_name().to_string(),
+            help: first_line(cmd.get_about().map(ToString::to_string)),
+            opts: cmd
+                .get_arguments()
+                .filter(|a| !a.is_positional() && !a.is_hide_set())
+                .map(Opt::from_arg)
+                .collect(),
+        }
+    }
+}
+
+/// Generate the completion script for `shell`.
+pub fn generate(shell: Shell, cmd: &mut Command) -> String {
+    cmd.build();
+    let bin = cmd.get_name().to_string();
+    let root = Cmd::from_command(cmd);
+    let subcommands: Vec<Cmd> = cmd
+        .get_subcommands()
+        .filter(|c| !c.is_hide_set())
+        .map(Cmd::from_command)
+        .collect();
+    match shell {
+        Shell::Bash => bash(&bin, &root, &subcommands),
+        Shell::Zsh => zsh(&bin, &root, &subcommands),
+        Shell::Fish => fish(&bin, &root, &subcommands),
+        Shell::PowerShell => powershell(&bin, &root, &subcommands),
+    }
+}
+
+fn first_line(text: Option<String>) -> String {
+    text.and_then(|t| t.lines().next().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn bash(bin: &str, root: &Cmd, subcommands: &[Cmd]) -> String {
+    let func = format!("_{}", bin.replace('-', "_"));
+    let sub_names: Vec<&str> = subcommands.iter().map(|c| c.name.as_str()).collect();
+    let mut out = String::new();
+
+    let _ = writeln!(out, "{func}() {{");
+    let _ = writeln!(out, "    local cur prev cmd i");
+    let _ = writeln!(out, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+    let _ = writeln!(out, "    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"");
+    let _ = writeln!(out, "    cmd=\"\"");
+    let _ = writeln!(out, "    for ((i = 1; i < COMP_CWORD; i++)); do");
+    let _ = writeln!(out, "        case \"${{COMP_WORDS[i]}}\" in");
+    let _ = writeln!(
+        out,
+        "            {}) cmd=\"${{COMP_WORDS[i]}}\"; break ;;",
+        sub_names.join("|")
+    );
+    let _ = writeln!(out, "        esac");
+    let _ = writeln!(out, "    done");
+    let _ = writeln!(out);
+
+    // Option values
+    let _ = writeln!(out, "    case \"$prev\" in");
+    let all_opts = root
+        .opts
+        .iter()
+        .chain(subcommands.iter().flat_map(|c| &c.opts));
+    let mut seen = Vec::new();
+    for opt in all_opts {
+        let names = opt.names().join("|");
+        if opt.value == ValueKind::None || seen.contains(&names) {
+            continue;
+        }
+        let reply = match &opt.value {
+            ValueKind::Profile => {
+                format!("$(compgen -W \"$({bin} {PROFILES_COMMAND} 2>/dev/null)\" -- \"$cur\")")
+            }
+            ValueKind::Choice(values) => {
+                format!("$(compgen -W \"{}\" -- \"$cur\")", values.join(" "))
+            }
+            _ => "$(compgen -f -- \"$cur\")".to_string(),
+        };
+        let _ = writeln!(out, "        {names}) COMPREPLY=({reply}); return ;;");
+        seen.push(names);
+    }
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out);
+
+    // Options of the current command
+    let _ = writeln!(out, "    local opts");
+    let _ = writeln!(out, "    case \"$cmd\" in");
+    for sub in subcommands {
+        let names: Vec<String> = sub.opts.iter().flat_map(Opt::names).collect();
+        let _ = writeln!(out, "        {}) opts=\"{}\" ;;", sub.name, names.join(" "));
+    }
+    let names: Vec<String> = root.opts.iter().flat_map(Opt::names).collect();
+    let _ = writeln!(out, "        *) opts=\"{}\" ;;", names.join(" "));
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out);
+
+    let _ = writeln!(out, "    if [[ \"$cur\" == -* ]]; then");
+    let _ = writeln!(
+        out,
+        "        COMPREPLY=($(compgen -W \"$opts\" -- \"$cur\"))"
+    );
+    let _ = writeln!(out, "    elif [[ -z \"$cmd\" ]]; then");
+    let _ = writeln!(
+        out,
+        "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\") $(compgen -f -- \"$cur\"))",
+        sub_names.join(" ")
+    );
+    let _ = writeln!(out, "    else");
+    let _ = writeln!(out, "        COMPREPLY=($(compgen -f -- \"$cur\"))");
+    let _ = writeln!(out, "    fi");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "complete -F {func} -o filenames {bin}");
+    out
+}
+
+/// Escape text for use inside a single-quoted zsh `_arguments` spec.
+fn zsh_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_specs(bin: &str, opts: &[Opt]) -> Vec<String> {
+    let func = format!("_{}_profiles", bin.replace('-', "_"));
+    opts.iter()
+        .flat_map(|opt| {
+            let help = zsh_escape(&opt.help);
+            let action = match &opt.value {
+                ValueKind::None => String::new(),
+                ValueKind::Path => ":value:_files".to_string(),
+                ValueKind::Choice(values) => format!(":value:({})", values.join(" ")),
+                ValueKind::Profile => format!(":profile:{func}"),
+            };
+            opt.names()
+                .into_iter()
+                .map(move |name| format!("'{name}[{help}]{action}'"))
+        })
+        .collect()
+}
+
+fn zsh(bin: &str, root: &Cmd, subcommands: &[Cmd]) -> String {
+    let func = format!("_{}", bin.replace('-', "_"));
+    let mut out = String::new();
+    let _ = writeln!(out, "#compdef {bin}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{func}_profiles() {{");
+    let _ = writeln!(out, "    local -a profiles");
+    let _ = writeln!(
+        out,
+        "    profiles=(${{(f)\"$({bin} {PROFILES_COMMAND} 2>/dev/null)\"}})"
+    );
+    let _ = writeln!(out, "    _describe 'profile' profiles");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+
+    let _ = writeln!(out, "{func}() {{");
+    let _ = writeln!(out, "    local curcontext=\"$curcontext\" state line");
+    let _ = writeln!(out, "    typeset -A opt_args");
+    let _ = writeln!(out, "    _arguments -C \\");
+    for spec in zsh_specs(bin, &root.opts) {
+        let _ = writeln!(out, "        {spec} \\");
+    }
+    let _ = writeln!(out, "        '*:: :->args'");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    case \"$words[1]\" in");
+    for sub in subcommands {
+        let _ = writeln!(out, "        {})", sub.name);
+        let _ = writeln!(out, "            _arguments \\");
+        for spec in zsh_specs(bin, &sub.opts) {
+            let _ = writeln!(out, "                {spec} \\");
+        }
+        let _ = writeln!(out, "                '*:file:_files'");
+        let _ = writeln!(out, "            ;;");
+    }
+    let _ = writeln!(out, "        *)");
+    let _ = writeln!(out, "            if (( CURRENT == 1 )); then");
+    let _ = writeln!(out, "                local -a commands");
+    let _ = writeln!(out, "                commands=(");
+    for sub in subcommands {
+        let _ = writeln!(
+            out,
+            "                    '{}:{}'",
+            sub.name,
+            sub.help.replace('\'', "'\\''").replace(':', "\\:")
+        );
+    }
+    let _ = writeln!(out, "                )");
+    let _ = writeln!(out, "                _describe 'command' commands");
+    let _ = writeln!(out, "            fi");
+    let _ = writeln!(out, "            _files");
+    let _ = writeln!(out, "            ;;");
+    let _ = writeln!(out, "    esac");
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{func} \"$@\"");
+    out
+}
+
+/// Quote text as a single-quoted fish string.
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn fish_opt(bin: &str, condition: Option<&str>, opt: &Opt) -> String {
+    let mut line = format!("complete -c {bin}");
+    if let Some(condition) = condition {
+        let _ = write!(line, " -n {}", fish_quote(condition));
+    }
+    if let Some(short) = opt.short {
+        let _ = write!(line, " -s {short}");
+    }
+    if let Some(long) = &opt.long {
+        let _ = write!(line, " -l {long}");
+    }
+    match &opt.value {
+        ValueKind::None => {}
+        ValueKind::Path => line.push_str(" -r -F"),
+        ValueKind::Choice(values) => {
+            let _ = write!(line, " -x -a {}", fish_quote(&values.join(" ")));
+        }
+        ValueKind::Profile => {
+            let _ = write!(line, " -x -a '({bin} {PROFILES_COMMAND} 2>/dev/null)'");
+        }
+    }
+    if !opt.help.is_empty() {
+        let _ = write!(line, " -d {}", fish_quote(&opt.help));
+    }
+    line
+}
+
+fn fish(bin: &str, root: &Cmd, subcommands: &[Cmd]) -> String {
+    let sub_names: Vec<&str> = subcommands.iter().map(|c| c.name.as_str()).collect();
+    let no_subcommand = format!("not __fish_seen_subcommand_from {}", sub_names.join(" "));
+    let mut out = String::new();
+
+    for opt in &root.opts {
+        let condition = (!opt.global).then_some(no_subcommand.as_str());
+        let _ = writeln!(out, "{}", fish_opt(bin, condition, opt));
+    }
+    for sub in subcommands {
+        let _ = writeln!(
+            out,
+            "complete -c {bin} -n '__fish_use_subcommand' -a {} -d {}",
+            sub.name,
+            fish_quote(&sub.help)
+        );
+    }
+    for sub in subcommands {
+        let condition = format!("__fish_seen_subcommand_from {}", sub.name);
+        for opt in sub.opts.iter().filter(|o| !o.global) {
+            let _ = writeln!(out, "{}", fish_opt(bin, Some(&condition), opt));
+        }
+    }
+    out
+}
+
+/// Quote text as a single-quoted PowerShell string.
+fn ps_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn ps_list<I: IntoIterator<Item = String>>(items: I) -> String {
+    let items: Vec<String> = items.into_iter().map(|i| ps_quote(&i)).collect();
+    format!("@({})", items.join(", "))
+}
+
+fn powershell(bin: &str, root: &Cmd, subcommands: &[Cmd]) -> String {
+    let sub_names = ps_list(subcommands.iter().map(|c| c.name.clone()));
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Register-ArgumentCompleter -Native -CommandName {} -ScriptBlock {{",
+        ps_quote(bin)
+    );
+    let _ = writeln!(
+        out,
+        "    param($wordToComplete, $commandAst, $cursorPosition)"
+    );
+    let _ = writeln!(
+        out,
+        "    $words = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object {{ $_.ToString() }})"
+    );
+    let _ = writeln!(out, "    if ($wordToComplete -and $words.Count -gt 0) {{");
+    let _ = writeln!(
+        out,
+        "        $words = @($words | Select-Object -SkipLast 1)"
+    );
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "    $subcommands = {sub_names}");
+    let _ = writeln!(out, "    $command = ''");
+    let _ = writeln!(out, "    foreach ($word in $words) {{");
+    let _ = writeln!(
+        out,
+        "        if ($subcommands -contains $word) {{ $command = $word; break }}"
+    );
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(
+        out,
+        "    $prev = if ($words.Count -gt 0) {{ $words[-1] }} else {{ '' }}"
+    );
+    let _ = writeln!(out);
+
+    // Option values
+    let _ = writeln!(out, "    $candidates = switch ($prev) {{");
+    let all_opts = root
+        .opts
+        .iter()
+        .chain(subcommands.iter().flat_map(|c| &c.opts));
+    let mut seen = Vec::new();
+    for opt in all_opts {
+        let values = match &opt.value {
+            ValueKind::Choice(values) => ps_list(values.iter().cloned()),
+            ValueKind::Profile => format!("@(& {bin} {PROFILES_COMMAND} 2>$null)"),
+            _ => continue,
+        };
+        for name in opt.names() {
+            if seen.contains(&name) {
+                continue;
+            }
+            let _ = writeln!(out, "        {} {{ {values}; break }}", ps_quote(&name));
+            seen.push(name);
+        }
+    }
+    let _ = writeln!(out, "        default {{");
+    let _ = writeln!(out, "            if ($wordToComplete -like '-*') {{");
+    let _ = writeln!(out, "                switch ($command) {{");
+    for sub in subcommands {
+        let _ = writeln!(
+            out,
+            "                    {} {{ {} }}",
+            ps_quote(&sub.name),
+            ps_list(sub.opts.iter().flat_map(Opt::names))
+        );
+    }
+    let _ = writeln!(
+        out,
+        "                    default {{ {} }}",
+        ps_list(root.opts.iter().flat_map(Opt::names))
+    );
+    let _ = writeln!(out, "                }}");
+    let _ = writeln!(out, "            }} elseif (-not $command) {{");
+    let _ = writeln!(out, "                $subcommands");
+    let _ = writeln!(out, "            }}");
+    let _ = writeln!(out, "        }}");
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "    @($candidates) | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{"
+    );
+    let _ = writeln!(
+        out,
+        "        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)"
+    );
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "}}");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::CommandFactory;
+
+    fn script(shell: Shell) -> String {
+        generate(shell, &mut Cli::command())
+    }
+
+    #[test]
+    fn bash_lists_subcommands_and_options() {
+        let script = script(Shell::Bash);
+        assert!(script.contains("complete -F _mvln -o filenames mvln"));
+        assert!(script.contains("organize|convert-links"));
+        assert!(script.contains("--dry-run"));
+        assert!(script.contains("--sort) COMPREPLY=($(compgen -W \"name size mtime\""));
+        assert!(!script.contains(&format!("{PROFILES_COMMAND}|")));
+    }
+
+    #[test]
+    fn profiles_are_completed_dynamically() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            assert!(
+                script(shell).contains(&format!("mvln {PROFILES_COMMAND}")),
+                "{shell:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn zsh_escapes_help_text() {
+        assert_eq!(zsh_escape("a [b]: c's"), "a \\[b\\]\\: c'\\''s");
+        let script = script(Shell::Zsh);
+        assert!(script.starts_with("#compdef mvln"));
+        assert!(script.contains("'--sort[Process sources in this order"));
+    }
+
+    #[test]
+    fn fish_scopes_subcommand_options() {
+        let script = script(Shell::Fish);
+        assert!(script
+            .contains("complete -c mvln -n '__fish_seen_subcommand_from stow' -s t -l target"));
+        // Global options are offered everywhere, without a condition
+        assert!(script.contains("complete -c mvln -l dry-run"));
+    }
+}
@@ -0,0 +1,759 @@
+//! Configuration file support.
+//!
+//! mvln reads an optional TOML configuration file from
+//! `$XDG_CONFIG_HOME/mvln/config.toml` (see [`crate::dirs::config_file`]).
+//! The file defines named profiles bundling options for recurring workflows:
+//!
+//! ```toml
+//! [profile.offload-nas]
+//! dest = "/mnt/nas/archive"
+//! absolute = true
+//! exclude = ["*.part", "*.tmp"]
+//! ```
+//!
+//! which `mvln --profile offload-nas big.iso` then applies.
+//!
+//! Presets are profiles for common workflows that ship with mvln, such as
+//! `mvln --preset downloads-archive ~/Downloads/* ~/archive` (see
+//! [`PRESETS`]). A `[preset.NAME]` table in the configuration file changes
+//! or adds keys of the built-in preset of that name, or defines a new one.
+//!
+//! Paths in the configuration may use `~` and `$VAR` (see
+//! [`crate::path_utils::expand_path`]).
+//!
+//! Only the subset of TOML that mvln needs is understood: tables, dotted and
+//! quoted keys, strings, integers, booleans and (possibly multi-line) arrays.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{MvlnError, Result};
+use crate::path_utils::expand_path;
+
+/// The built-in presets, in the format of the configuration file.
+pub const PRESETS: &str = r#"
+# Finished downloads, filed by month. Partial files of browsers and torrent
+# clients, and files still being written, stay where they are.
+[preset.downloads-archive]
+exclude = ["*.part", "*.crdownload", "*.download", "*.partial", "*.!qB", "*.tmp"]
+check-open = "skip"
+by-date = "%Y/%m"
+rename-on-conflict = true
+
+# Photos and videos from a camera card, filed by the day they were taken.
+# Rerunning an interrupted offload links what was already copied.
+[preset.media-offload]
+exclude = [".DS_Store", "Thumbs.db", "._*"]
+by-exif-date = "%Y/%m/%d"
+skip-same = true
+absolute = true
+
+# Rotated logs, filed by month. Logs still being appended to stay.
+[preset.log-archive]
+exclude = ["*.pid", "*.lock"]
+check-open = "skip"
+by-date = "%Y/%m"
+rename-on-conflict = true
+"#;
+
+/// A parsed configuration value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A string value.
+    String(String),
+    /// An integer value.
+    Integer(i64),
+    /// A boolean value.
+    Bool(bool),
+    /// An array of values.
+    Array(Vec<Value>),
+    /// A nested table.
+    Table(Table),
+}
+
+impl Value {
+    /// Short type name used in error messages.
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "string",
+            Self::Integer(_) => "integer",
+            Self::Bool(_) => "boolean",
+            Self::Array(_) => "array",
+            Self::Table(_) => "table",
+        }
+    }
+}
+
+/// A table of key/value pairs, preserving the order of the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    entries: Vec<(String, Value)>,
+}
+
+impl Table {
+    /// Look up a value by key.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Iterate over entries in file order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Whether the table has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Set every key of `other` in this table, replacing existing values.
+    fn overlay(&mut self, other: &Table) {
+        for (key, value) in other.iter() {
+            match self.get_mut(key) {
+                Some(existing) => *existing = value.clone(),
+                None => self.entries.push((key.to_string(), value.clone())),
+            }
+        }
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+/// A named set of options from the configuration file.
+///
+/// Every field is optional; unset fields leave the command-line value alone.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// Destination used when none is given on the command line.
+    pub dest: Option<PathBuf>,
+    /// Use absolute symlinks.
+    pub absolute: Option<bool>,
+    /// Overwrite existing destinations.
+    pub force: Option<bool>,
+    /// Allow moving whole directories.
+    pub whole_dir: Option<bool>,
+    /// Verbose output.
+    pub verbose: Option<bool>,
+    /// Seconds to wait for the run lock.
+    pub lock_timeout: Option<u64>,
+    /// Glob patterns of sources to leave alone.
+    pub exclude: Vec<String>,
+    /// Shell command run before each move.
+    pub pre_hook: Option<String>,
+    /// Shell command run after each move.
+    pub post_hook: Option<String>,
+    /// Sort sources into per-extension subdirectories.
+    pub by_ext: Option<bool>,
+    /// Sort sources into date subdirectories in this format.
+    pub by_date: Option<String>,
+    /// Sort photos by capture date in this format (by modification time
+    /// without the `exif` feature).
+    pub by_exif_date: Option<String>,
+    /// What to do with sources open for writing: `skip`, `warn` or `fail`.
+    pub check_open: Option<String>,
+    /// Pick a unique name when the destination exists.
+    pub rename_on_conflict: Option<bool>,
+    /// Take destinations with the same size and mtime as already moved.
+    pub skip_same: Option<bool>,
+}
+
+/// The parsed configuration file.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Path the configuration was loaded from, if any.
+    pub path: Option<PathBuf>,
+    /// The raw top-level table.
+    pub root: Table,
+}
+
+impl Config {
+    /// Load the configuration from `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::ConfigError`] if the file cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|e| MvlnError::ConfigError {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        let root = parse(&text).map_err(|reason| MvlnError::ConfigError {
+            path: path.to_path_buf(),
+            reason,
+        })?;
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            root,
+        })
+    }
+
+    /// Load the configuration from `path` if it exists, or return an empty one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::ConfigError`] if the file exists but is invalid.
+    pub fn load_optional(path: &Path) -> Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Parse configuration from a string.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::ConfigError`] if the text is not valid.
+    pub fn parse_str(text: &str) -> Result<Self> {
+        let root = parse(text).map_err(|reason| MvlnError::ConfigError {
+            path: PathBuf::new(),
+            reason,
+        })?;
+        Ok(Self { path: None, root })
+    }
+
+    /// Names of all profiles defined in the file.
+    #[must_use]
+    pub fn profile_names(&self) -> Vec<String> {
+        self.names("profile")
+    }
+
+    /// Look up and validate the profile called `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::ProfileNotFound`] if no such profile exists, or
+    /// [`MvlnError::ConfigError`] if it contains unknown keys or bad values.
+    pub fn profile(&self, name: &str) -> Result<Profile> {
+        let Some(table) = self.table("profile", name) else {
+            return Err(MvlnError::ProfileNotFound {
+                name: name.to_string(),
+            });
+        };
+        self.read_profile(table, &format!("profile '{name}'"))
+    }
+
+    /// Names of the built-in presets, followed by those only defined in
+    /// the file.
+    #[must_use]
+    pub fn preset_names(&self) -> Vec<String> {
+        let mut names = builtin_presets().names("preset");
+        for name in self.names("preset") {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Look up and validate the preset called `name`: the built-in preset
+    /// with the keys of a `[preset.NAME]` table in the file set over it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::PresetNotFound`] if no such preset exists, or
+    /// [`MvlnError::ConfigError`] if it contains unknown keys or bad values.
+    pub fn preset(&self, name: &str) -> Result<Profile> {
+        let builtin = builtin_presets();
+        let mut table = match builtin.table("preset", name) {
+            Some(table) => table.clone(),
+            None => Table::default(),
+        };
+        match self.table("preset", name) {
+            Some(overrides) => table.overlay(overrides),
+            None if table.is_empty() => {
+                return Err(MvlnError::PresetNotFound {
+                    name: name.to_string(),
+                    presets: self.preset_names().join(", "),
+                })
+            }
+            None => {}
+        }
+        self.read_profile(&table, &format!("preset '{name}'"))
+    }
+
+    /// Names of the tables under `[kind]`.
+    fn names(&self, kind: &str) -> Vec<String> {
+        match self.root.get(kind) {
+            Some(Value::Table(t)) => t.iter().map(|(k, _)| k.to_string()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The table `[kind.name]`, if defined.
+    fn table(&self, kind: &str, name: &str) -> Option<&Table> {
+        match self.root.get(kind) {
+            Some(Value::Table(tables)) => match tables.get(name) {
+                Some(Value::Table(table)) => Some(table),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Validate the keys of a profile or preset, called `label` in errors.
+    fn read_profile(&self, table: &Table, label: &str) -> Result<Profile> {
+        let error = |reason: String| MvlnError::ConfigError {
+            path: self.path.clone().unwrap_or_default(),
+            reason: format!("{label}: {reason}"),
+        };
+
+        let mut profile = Profile::default();
+        for (key, value) in table.iter() {
+            match key {
+                "dest" => {
+                    let dest = expect_str(key, value).map_err(error)?;
+                    profile.dest = Some(expand_path(dest)?);
+                }
+                "absolute" => profile.absolute = Some(expect_bool(key, value).map_err(error)?),
+                "force" => profile.force = Some(expect_bool(key, value).map_err(error)?),
+                "whole-dir" => profile.whole_dir = Some(expect_bool(key, value).map_err(error)?),
+                "verbose" => profile.verbose = Some(expect_bool(key, value).map_err(error)?),
+                "lock-timeout" => {
+                    let secs = expect_int(key, value).map_err(error)?;
+                    profile.lock_timeout = Some(
+                        u64::try_from(secs)
+                            .map_err(|_| error(format!("'{key}' must not be negative")))?,
+                    );
+                }
+                "exclude" => profile.exclude = expect_str_array(key, value).map_err(error)?,
+                "pre-hook" => {
+                    profile.pre_hook = Some(expect_str(key, value).map_err(error)?.to_string());
+                }
+                "post-hook" => {
+                    profile.post_hook = Some(expect_str(key, value).map_err(error)?.to_string());
+                }
+                "by-ext" => profile.by_ext = Some(expect_bool(key, value).map_err(error)?),
+                "by-date" => {
+                    profile.by_date = Some(expect_str(key, value).map_err(error)?.to_string());
+                }
+                "by-exif-date" => {
+                    let format = expect_str(key, value).map_err(error)?;
+                    profile.by_exif_date = Some(format.to_string());
+                }
+                "check-open" => {
+                    let policy = expect_str(key, value).map_err(error)?;
+                    if !["skip", "warn", "fail"].contains(&policy) {
+                        return Err(error(format!(
+                            "'{key}' must be \"skip\", \"warn\" or \"fail\""
+                        )));
+                    }
+                    profile.check_open = Some(policy.to_string());
+                }
+                "rename-on-conflict" => {
+                    profile.rename_on_conflict = Some(expect_bool(key, value).map_err(error)?);
+                }
+                "skip-same" => profile.skip_same = Some(expect_bool(key, value).map_err(error)?),
+                _ => return Err(error(format!("unknown key '{key}'"))),
+            }
+        }
+        Ok(profile)
+    }
+}
+
+/// The parsed [`PRESETS`].
+fn builtin_presets() -> Config {
+    Config {
+        path: None,
+        root: parse(PRESETS).expect("the built-in presets are valid"),
+    }
+}
+
+/// Expect a string value.
+fn expect_str<'a>(key: &str, value: &'a Value) -> std::result::Result<&'a str, String> {
+    match value {
+        Value::String(s) => Ok(s),
+        other => Err(format!(
+            "'{key}' must be a string, not {}",
+            other.type_name()
+        )),
+    }
+}
+
+/// Expect a boolean value.
+fn expect_bool(key: &str, value: &Value) -> std::result::Result<bool, String> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        other => Err(format!(
+            "'{key}' must be a boolean, not {}",
+            other.type_name()
+        )),
+    }
+}
+
+/// Expect an integer value.
+fn expect_int(key: &str, value: &Value) -> std::result::Result<i64, String> {
+    match value {
+        Value::Integer(i) => Ok(*i),
+        other => Err(format!(
+            "'{key}' must be an integer, not {}",
+            other.type_name()
+        )),
+    }
+}
+
+/// Expect an array of strings (a single string is accepted as a one-element array).
+fn expect_str_array(key: &str, value: &Value) -> std::result::Result<Vec<String>, String> {
+    match value {
+        Value::String(s) => Ok(vec![s.clone()]),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| expect_str(key, v).map(str::to_string))
+            .collect(),
+        other => Err(format!(
+            "'{key}' must be an array of strings, not {}",
+            other.type_name()
+        )),
+    }
+}
+
+/// Parse a TOML document into its root table.
+///
+/// Errors are reported as `line N: reason`.
+fn parse(text: &str) -> std::result::Result<Table, String> {
+    let mut root = Table::default();
+    let mut current: Vec<String> = Vec::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((idx, raw)) = lines.next() {
+        let lineno = idx + 1;
+        let err = |reason: String| format!("line {lineno}: {reason}");
+        let line = strip_comment(raw).trim().to_string();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header
+                .strip_suffix(']')
+                .ok_or_else(|| err("unterminated table header".to_string()))?;
+            current = parse_key(header.trim()).map_err(err)?;
+            table_at(&mut root, &current).map_err(err)?;
+            continue;
+        }
+
+        let (key, rest) =
+            split_key_value(&line).ok_or_else(|| err("expected 'key = value'".to_string()))?;
+        let mut path = current.clone();
+        path.extend(parse_key(key.trim()).map_err(err)?);
+
+        // Arrays may span multiple lines; keep reading until brackets balance.
+        let mut value_text = rest.trim().to_string();
+        while value_text.starts_with('[') && !brackets_balanced(&value_text) {
+            let Some((_, next)) = lines.next() else {
+                return Err(err("unterminated array".to_string()));
+            };
+            value_text.push(' ');
+            value_text.push_str(strip_comment(next).trim());
+        }
+
+        let (value, remainder) = parse_value(&value_text).map_err(err)?;
+        if !remainder.trim().is_empty() {
+            return Err(err(format!(
+                "unexpected trailing characters '{}'",
+                remainder.trim()
+            )));
+        }
+
+        let (last, parents) = path.split_last().expect("key path is never empty");
+        let table = table_at(&mut root, parents).map_err(err)?;
+        if table.get(last).is_some() {
+            return Err(err(format!("duplicate key '{last}'")));
+        }
+        table.entries.push((last.clone(), value));
+    }
+
+    Ok(root)
+}
+
+/// Return the table at `path`, creating intermediate tables as needed.
+fn table_at<'a>(
+    root: &'a mut Table,
+    path: &[String],
+) -> std::result::Result<&'a mut Table, String> {
+    let mut table = root;
+    for part in path {
+        if table.get(part).is_none() {
+            table
+                .entries
+                .push((part.clone(), Value::Table(Table::default())));
+        }
+        table = match table.get_mut(part) {
+            Some(Value::Table(t)) => t,
+            _ => return Err(format!("'{part}' is not a table")),
+        };
+    }
+    Ok(table)
+}
+
+/// Call `f` for each character outside of string literals, stopping at
+/// (and returning) the first byte offset for which it returns `true`.
+fn scan_unquoted(text: &str, mut f: impl FnMut(char) -> bool) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if q == '"' && c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+        } else if c == '"' || c == '\'' {
+            quote = Some(c);
+        } else if f(c) {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Remove a trailing `#` comment, ignoring `#` inside strings.
+fn strip_comment(line: &str) -> &str {
+    scan_unquoted(line, |c| c == '#').map_or(line, |i| &line[..i])
+}
+
+/// Split `key = value` at the first `=` outside quotes.
+fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    scan_unquoted(line, |c| c == '=').map(|i| (&line[..i], &line[i + 1..]))
+}
+
+/// Whether all `[`/`]` outside strings are balanced.
+fn brackets_balanced(text: &str) -> bool {
+    let mut depth = 0i32;
+    scan_unquoted(text, |c| {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => {}
+        }
+        false
+    });
+    depth <= 0
+}
+
+/// Parse a (possibly dotted, possibly quoted) key into its parts.
+fn parse_key(key: &str) -> std::result::Result<Vec<String>, String> {
+    let mut parts = Vec::new();
+    let mut rest = key.trim();
+    loop {
+        let (part, remainder) = if rest.starts_with('"') || rest.starts_with('\'') {
+            parse_string(rest)?
+        } else {
+            let end = rest.find('.').unwrap_or(rest.len());
+            let bare = rest[..end].trim();
+            if bare.is_empty()
+                || !bare
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(format!("invalid key '{key}'"));
+            }
+            (bare.to_string(), &rest[end..])
+        };
+        parts.push(part);
+        let remainder = remainder.trim_start();
+        if remainder.is_empty() {
+            return Ok(parts);
+        }
+        rest = remainder
+            .strip_prefix('.')
+            .ok_or_else(|| format!("invalid key '{key}'"))?
+            .trim_start();
+    }
+}
+
+/// Parse one value, returning it and the unparsed remainder.
+fn parse_value(text: &str) -> std::result::Result<(Value, &str), String> {
+    let text = text.trim_start();
+    if text.starts_with('"') || text.starts_with('\'') {
+        let (s, rest) = parse_string(text)?;
+        return Ok((Value::String(s), rest));
+    }
+    if let Some(mut rest) = text.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                return Ok((Value::Array(items), after));
+            }
+            let (item, after) = parse_value(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.starts_with(']') {
+                return Err("expected ',' or ']' in array".to_string());
+            }
+        }
+    }
+
+    let end = text
+        .find(|c: char| c == ',' || c == ']' || c.is_whitespace())
+        .unwrap_or(text.len());
+    let (word, rest) = text.split_at(end);
+    let value = match word {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::Integer(
+            word.replace('_', "")
+                .parse()
+                .map_err(|_| format!("invalid value '{word}'"))?,
+        ),
+    };
+    Ok((value, rest))
+}
+
+/// Parse a basic (`"..."`) or literal (`'...'`) string.
+fn parse_string(text: &str) -> std::result::Result<(String, &str), String> {
+    let mut chars = text.char_indices();
+    let quote = chars.next().map_or('"', |(_, c)| c);
+    let mut out = String::new();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            return Ok((out, &text[i + c.len_utf8()..]));
+        }
+        if c == '\\' && quote == '"' {
+            let escaped = match chars.next().map(|(_, c)| c) {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('\\') => '\\',
+                Some('"') => '"',
+                other => return Err(format!("invalid escape '\\{}'", other.unwrap_or(' '))),
+            };
+            out.push(escaped);
+        } else {
+            out.push(c);
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_profiles() {
+        let config = Config::parse_str(
+            r#"
+            # Archive big downloads to the NAS
+            [profile.offload-nas]
+            dest = "/mnt/nas/archive"   # trailing comment
+            absolute = true
+            lock-timeout = 30
+            post-hook = "notify-send moved"
+            exclude = [
+                "*.part",
+                '*.tmp',
+            ]
+
+            [profile."home docs"]
+            dest = 'docs/$$literal'
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.profile_names(), vec!["offload-nas", "home docs"]);
+
+        let profile = config.profile("offload-nas").unwrap();
+        assert_eq!(profile.dest, Some(PathBuf::from("/mnt/nas/archive")));
+        assert_eq!(profile.absolute, Some(true));
+        assert_eq!(profile.force, None);
+        assert_eq!(profile.lock_timeout, Some(30));
+        assert_eq!(profile.exclude, vec!["*.part", "*.tmp"]);
+        assert_eq!(profile.post_hook.as_deref(), Some("notify-send moved"));
+        assert_eq!(profile.pre_hook, None);
+
+        let profile = config.profile("home docs").unwrap();
+        assert_eq!(profile.dest, Some(PathBuf::from("docs/$literal")));
+    }
+
+    #[test]
+    fn missing_profile_is_reported() {
+        let config = Config::parse_str("[profile.a]\nforce = true\n").unwrap();
+        assert!(matches!(
+            config.profile("b"),
+            Err(MvlnError::ProfileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn builtin_presets_are_valid() {
+        let config = Config::default();
+        for name in config.preset_names() {
+            config.preset(&name).unwrap();
+        }
+        let preset = config.preset("downloads-archive").unwrap();
+        assert_eq!(preset.check_open.as_deref(), Some("skip"));
+        assert!(preset.exclude.contains(&"*.crdownload".to_string()));
+    }
+
+    #[test]
+    fn presets_are_overridden_by_the_file() {
+        let config = Config::parse_str(
+            r#"
+            [preset.media-offload]
+            by-exif-date = "%Y/%m"
+            dest = "/mnt/photos"
+
+            [preset.scans]
+            by-ext = true
+            "#,
+        )
+        .unwrap();
+
+        let preset = config.preset("media-offload").unwrap();
+        assert_eq!(preset.by_exif_date.as_deref(), Some("%Y/%m"));
+        assert_eq!(preset.dest, Some(PathBuf::from("/mnt/photos")));
+        assert_eq!(preset.skip_same, Some(true));
+        assert_eq!(config.preset("scans").unwrap().by_ext, Some(true));
+        assert!(config.preset_names().ends_with(&["scans".to_string()]));
+        assert!(matches!(
+            config.preset("nope"),
+            Err(MvlnError::PresetNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn unknown_profile_key_is_rejected() {
+        let config = Config::parse_str("[profile.a]\nforse = true\n").unwrap();
+        let err = config.profile("a").unwrap_err();
+        assert!(err.to_string().contains("unknown key 'forse'"));
+    }
+
+    #[test]
+    fn wrong_value_type_is_rejected() {
+        let config = Config::parse_str("[profile.a]\nforce = \"yes\"\n").unwrap();
+        let err = config.profile("a").unwrap_err();
+        assert!(err.to_string().contains("must be a boolean"));
+    }
+
+    #[test]
+    fn syntax_errors_include_line_number() {
+        let err = Config::parse_str("[profile.a]\ndest = \"unterminated\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+
+        let err = Config::parse_str("[profile.a]\nforce = true\nforce = false\n").unwrap_err();
+        assert!(err.to_string().contains("duplicate key"));
+    }
+
+    #[test]
+    fn escapes_in_basic_strings() {
+        let config = Config::parse_str("a = \"x\\\"y\\\\z # not a comment\"\n").unwrap();
+        assert_eq!(
+            config.root.get("a"),
+            Some(&Value::String("x\"y\\z # not a comment".to_string()))
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! Resolving existing destinations.
+//!
+//! By default a move onto an existing destination fails with
+//! [`MvlnError::DestinationExists`](crate::MvlnError::DestinationExists), or
+//! replaces it with [`MoveOptions::force`](crate::MoveOptions::force). A
+//! [`ConflictHandler`] set with
+//! [`MoveOptionsBuilder::on_conflict`](crate::MoveOptionsBuilder::on_conflict)
+//! is asked instead, once per conflict, so a GUI can show its own prompt.
+//! The CLI's `--interactive` mode is built on this hook.
+//!
+//! With [`MoveOptions::backup`](crate::MoveOptions::backup) set, existing
+//! destinations are backed up without asking, named by a [`BackupStyle`].
+//!
+//! # Examples
+//!
+//! ```
+//! use mvln::conflict::Resolution;
+//! use mvln::MoveOptions;
+//!
+//! // Keep both files: the moved one gets a ` (1)` suffix
+//! let options = MoveOptions::builder()
+//!     .on_conflict(|_| Resolution::Rename)
+//!     .build();
+//! ```
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::date::DateTime;
+use crate::path_utils::unique_name;
+
+/// A move whose destination already exists.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct ConflictInfo<'a> {
+    /// The file or directory being moved.
+    pub source: &'a Path,
+    /// The existing destination (with the source's file name appended if
+    /// the move targeted a directory).
+    pub dest: &'a Path,
+    /// Whether the source is a directory (not a symlink to one).
+    pub source_is_dir: bool,
+    /// Whether the destination is a directory (not a symlink to one).
+    pub dest_is_dir: bool,
+}
+
+/// What to do about a [conflict](ConflictInfo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Resolution {
+    /// Replace the destination, as with `force`. Replacing a directory with
+    /// a file or vice versa still fails with a type mismatch.
+    Overwrite,
+    /// Leave both paths alone and fail with
+    /// [`MvlnError::ConflictSkipped`](crate::MvlnError::ConflictSkipped).
+    Skip,
+    /// Rename the destination as the
+    /// [`MoveOptions::backup`](crate::MoveOptions::backup) style says
+    /// (`NAME~` by default) and move the source in its place.
+    Backup,
+    /// Move the source to a free name next to the destination, like
+    /// `--rename-on-conflict`.
+    Rename,
+    /// Leave both paths alone and fail with
+    /// [`MvlnError::ConflictAborted`](crate::MvlnError::ConflictAborted);
+    /// [`MovePlan::execute`](crate::MovePlan::execute) stops the batch.
+    Abort,
+}
+
+/// How an existing destination is renamed when it is backed up.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::conflict::BackupStyle;
+/// use std::path::{Path, PathBuf};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let now = UNIX_EPOCH + Duration::from_secs(1_717_245_001);
+/// assert_eq!(
+///     BackupStyle::Timestamp.backup_path(Path::new("/nonexistent/a.txt"), now),
+///     PathBuf::from("/nonexistent/a.txt.2024-06-01T12-30-01.bak")
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BackupStyle {
+    /// `NAME~`, or `NAME (1)~`, ... if that is taken.
+    #[default]
+    Simple,
+    /// `NAME.2024-06-01T12-30-00.bak`, stamped with the time of the move
+    /// (UTC), so every generation is kept.
+    Timestamp,
+}
+
+impl BackupStyle {
+    /// A free name to move `dest` aside to, stamped with `now` for
+    /// [`Timestamp`](Self::Timestamp). Two backups in the same second get
+    /// ` (1)`, ` (2)`, ... suffixes.
+    #[must_use]
+    pub fn backup_path(self, dest: &Path, now: SystemTime) -> PathBuf {
+        let mut name = dest.as_os_str().to_os_string();
+        match self {
+            Self::Simple => name.push("~"),
+            Self::Timestamp => {
+                name.push(".");
+                name.push(DateTime::from_system_time(now).format("%Y-%m-%dT%H-%M-%S"));
+                name.push(".bak");
+            }
+        }
+        unique_name(Path::new(&name), |p| p.symlink_metadata().is_ok())
+    }
+}
+
+/// A callback deciding how to resolve conflicts, shared between clones of
+/// the [`MoveOptions`](crate::MoveOptions) it is set on.
+#[derive(Clone)]
+pub struct ConflictHandler(Arc<dyn Fn(&ConflictInfo<'_>) -> Resolution + Send + Sync>);
+
+impl ConflictHandler {
+    /// Wrap a callback.
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&ConflictInfo<'_>) -> Resolution + Send + Sync + 'static,
+    {
+        Self(Arc::new(callback))
+    }
+
+    /// Ask the callback how to resolve `conflict`.
+    #[must_use]
+    pub fn resolve(&self, conflict: &ConflictInfo<'_>) -> Resolution {
+        (self.0)(conflict)
+    }
+}
+
+impl fmt::Debug for ConflictHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ConflictHandler(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn timestamped_backups_keep_every_generation() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dest = tmp.path().join("report.pdf");
+        let now = UNIX_EPOCH + Duration::from_secs(1_717_245_001);
+        let first = BackupStyle::Timestamp.backup_path(&dest, now);
+        assert_eq!(first, tmp.path().join("report.pdf.2024-06-01T12-30-01.bak"));
+        std::fs::write(&first, "").unwrap();
+        assert_eq!(
+            BackupStyle::Timestamp.backup_path(&dest, now),
+            tmp.path().join("report.pdf.2024-06-01T12-30-01 (1).bak")
+        );
+        assert_eq!(
+            BackupStyle::Simple.backup_path(&dest, now),
+            tmp.path().join("report.pdf~")
+        );
+    }
+}
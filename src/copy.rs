@@ -0,0 +1,650 @@
+//! Copying file contents across filesystems.
+//!
+//! The destination is preallocated to the size of the source (Linux only),
+//! so a full disk is reported before the copy starts rather than halfway
+//! through it, and then filled with [`io::copy`], which lets the OS pick the
+//! fastest method (e.g. `copy_file_range` on Linux). Setting
+//! [`MoveOptions::buffer_size`] or [`MoveOptions::direct_io`] switches to a
+//! chunked copy instead: the file is read and written in chunks of the given
+//! size, optionally bypassing the page cache.
+//!
+//! Every file is copied into a staged file next to the destination
+//! (`.<name>.mvln-tmp`), which is renamed into place once complete, so the
+//! destination never holds a half-written file. When a run is interrupted,
+//! the next one finds the staged file and continues after the prefix whose
+//! SHA-256 still matches the source, or starts over if nothing matches. For
+//! files of at least [`RESUMABLE_MIN_SIZE`] the number of bytes copied is
+//! also recorded every [`CHECKPOINT_INTERVAL`], which bounds the check.
+//!
+//! Direct I/O (`O_DIRECT`) is only available on Linux, and only for the
+//! writes. Filesystems that don't support it (e.g. tmpfs) fall back to
+//! buffered writes, as does the final chunk when it is not a multiple of
+//! the block size. Elsewhere the option is ignored.
+//!
+//! Onto network filesystems (NFS, SMB, SSHFS) copies default to chunks of
+//! [`NETWORK_BUFFER_SIZE`] and are retried when the server reports a stale
+//! file handle. No reflinks are ever attempted, so none need turning off
+//! there.
+
+use std::ffi::OsString;
+use std::fs::{self, File, Metadata};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::hash::hash_reader;
+use crate::operation::MoveOptions;
+use crate::ownership;
+use crate::preserve::{self, PreserveSet};
+
+/// Chunk size of the chunked copy when only `direct_io` is set.
+pub const DEFAULT_BUFFER_SIZE: usize = 1 << 20;
+
+/// Alignment of buffers, offsets and lengths for direct I/O. 4 KiB covers
+/// the logical block size of practically all disks.
+const DIRECT_ALIGN: usize = 4096;
+
+/// Sources at least this large have their progress saved every
+/// [`CHECKPOINT_INTERVAL`] bytes while they are copied.
+pub const RESUMABLE_MIN_SIZE: u64 = 256 << 20;
+
+/// Bytes copied between saves of a large copy's progress, and the size of
+/// the blocks a staged file is verified in.
+pub const CHECKPOINT_INTERVAL: u64 = 64 << 20;
+
+/// Chunk size of copies onto network filesystems when no
+/// [`MoveOptions::buffer_size`] is set (see
+/// [`network_filesystem`](crate::device::network_filesystem)). Smaller
+/// writes keep each request within what one round trip carries.
+pub const NETWORK_BUFFER_SIZE: usize = 256 << 10;
+
+/// [`MoveOptions::stale_retries`] of copies onto network filesystems, unless
+/// set higher.
+pub const NETWORK_STALE_RETRIES: u32 = 3;
+
+/// Pause before retrying a copy that failed with a stale file handle.
+const STALE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Suffix of the hidden file a copy is staged in (`.<name>.mvln-tmp`).
+pub const STAGING_SUFFIX: &str = ".mvln-tmp";
+
+/// Copy the contents of `source` to `dest`, with the attributes selected
+/// by [`MoveOptions::preserve`], as configured by `options`. Returns the
+/// number of bytes copied.
+///
+/// The data is staged in `.<name>.mvln-tmp` next to `dest` and renamed into
+/// place once complete. A staged file left by an interrupted run is resumed
+/// as far as it matches `source`, and discarded otherwise.
+///
+/// # Errors
+///
+/// Returns any I/O error reading `source` or writing `dest`, including
+/// running out of space when reserving room for the copy.
+pub fn copy_file(source: &Path, dest: &Path, options: &MoveOptions) -> io::Result<u64> {
+    retry_stale(options, || {
+        let mut input = File::open(source)?;
+        let metadata = input.metadata()?;
+        copy_staged(&mut input, &metadata, dest, options, CHECKPOINT_INTERVAL)
+    })
+}
+
+/// Run `attempt`, again after a short pause each time it fails with a
+/// stale file handle (`ESTALE`), up to [`MoveOptions::stale_retries`]
+/// times. A retried copy resumes from its staged file.
+pub(crate) fn retry_stale<T>(
+    options: &MoveOptions,
+    mut attempt: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let mut retries = options.stale_retries;
+    loop {
+        match attempt() {
+            Err(e) if e.kind() == ErrorKind::StaleNetworkFileHandle && retries > 0 => {
+                retries -= 1;
+                thread::sleep(STALE_RETRY_DELAY);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Write `data`, the whole contents of a file with `metadata`, straight to
+/// `dest`, then carry over the attributes selected by
+/// [`MoveOptions::preserve`] except extended attributes.
+///
+/// Unlike [`copy_file`] nothing is staged, so this suits small files whose
+/// contents are already in memory (see [`crate::pack`]): an interrupted
+/// write leaves a short file at `dest` rather than a resumable one.
+pub(crate) fn write_whole(
+    dest: &Path,
+    data: &[u8],
+    metadata: &Metadata,
+    options: &MoveOptions,
+) -> io::Result<()> {
+    let attributes = creation_attributes(metadata, options);
+    let mut output = Output::open(dest, false, false, attributes)?;
+    output.file.write_all(data)?;
+    ownership::restore(metadata, dest, options)?;
+    if options.preserve.contains(PreserveSet::MODE) {
+        fs::set_permissions(dest, metadata.permissions())?;
+    }
+    if options.preserve.contains(PreserveSet::TIMESTAMPS) {
+        let _ = preserve::copy_times(metadata, &output.file);
+    }
+    Ok(())
+}
+
+/// Copy `input` to `dest` through its staged file, resuming what a previous
+/// run left there, with `interval` as the checkpoint and verification
+/// block size.
+fn copy_staged(
+    input: &mut File,
+    metadata: &Metadata,
+    dest: &Path,
+    options: &MoveOptions,
+    interval: u64,
+) -> io::Result<u64> {
+    let staged = sidecar(dest, STAGING_SUFFIX);
+    let state = sidecar(dest, ".mvln-tmp.state");
+    let offset = resume_offset(input, metadata, &staged, &state, interval)?;
+
+    let attributes = creation_attributes(metadata, options);
+    let mut output = Output::open(&staged, options.direct_io, offset > 0, attributes)?;
+    if offset == 0 {
+        preallocate(&output.file, metadata.len())?;
+    }
+    input.seek(SeekFrom::Start(offset))?;
+    output.file.seek(SeekFrom::Start(offset))?;
+
+    let checkpoints = metadata.len() >= RESUMABLE_MIN_SIZE;
+    let mut copied = offset;
+    loop {
+        let chunk = copy_contents(&mut (&mut *input).take(interval), &mut output, options)?;
+        copied += chunk;
+        if chunk < interval {
+            break;
+        }
+        if checkpoints {
+            // Only a hint: a resumed copy verifies the prefix anyway
+            fs::write(&state, progress_line(metadata, copied))?;
+        }
+    }
+    finish(input, &output, &staged, copied, metadata, options)?;
+    drop(output);
+    fs::rename(&staged, dest)?;
+    let _ = fs::remove_file(&state);
+    Ok(copied)
+}
+
+/// Where a copy of `input` into `staged` can pick up: the longest prefix,
+/// in whole `block`s, whose hash matches the source. Zero if there is no
+/// staged file or its first block differs.
+///
+/// If progress was recorded in `state` for this very source, only that
+/// many bytes are checked; otherwise the staged file's whole length is,
+/// since preallocation makes its length meaningless.
+fn resume_offset(
+    input: &mut File,
+    metadata: &Metadata,
+    staged: &Path,
+    state: &Path,
+    block: u64,
+) -> io::Result<u64> {
+    let Ok(mut partial) = File::open(staged) else {
+        return Ok(0);
+    };
+    let recorded = fs::read_to_string(state)
+        .ok()
+        .and_then(|saved| parse_progress(&saved, metadata));
+    let limit = recorded
+        .unwrap_or(u64::MAX)
+        .min(partial.metadata()?.len())
+        .min(metadata.len());
+
+    input.seek(SeekFrom::Start(0))?;
+    let mut verified = 0;
+    while verified < limit {
+        let len = block.min(limit - verified);
+        let copied = hash_reader((&mut *input).take(len))?;
+        let written = hash_reader((&mut partial).take(len))?;
+        if copied != written {
+            break;
+        }
+        verified += len;
+    }
+    Ok(verified)
+}
+
+/// The progress record of a copy: the size and mtime of the source (so a
+/// changed source is re-verified in full), and the bytes copied.
+fn progress_line(metadata: &Metadata, copied: u64) -> String {
+    format!("{}\t{}\t{copied}\n", metadata.len(), mtime_nanos(metadata))
+}
+
+/// The offset in a progress record, if it was written for `metadata`.
+fn parse_progress(line: &str, metadata: &Metadata) -> Option<u64> {
+    let mut fields = line.trim_end().split('\t');
+    let len: u64 = fields.next()?.parse().ok()?;
+    let mtime: u128 = fields.next()?.parse().ok()?;
+    let offset: u64 = fields.next()?.parse().ok()?;
+    (len == metadata.len() && mtime == mtime_nanos(metadata) && offset <= len).then_some(offset)
+}
+
+fn mtime_nanos(metadata: &Metadata) -> u128 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos())
+}
+
+/// `dest` with a leading dot and `suffix` added to its file name.
+fn sidecar(dest: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(dest.file_name().unwrap_or_default());
+    name.push(suffix);
+    dest.with_file_name(name)
+}
+
+/// Copy all of `input` to `output`, with the OS's copy method unless a
+/// buffer size or direct I/O is configured.
+fn copy_contents<R: Read>(
+    input: &mut R,
+    output: &mut Output,
+    options: &MoveOptions,
+) -> io::Result<u64> {
+    if options.buffer_size.is_none() && !options.direct_io {
+        io::copy(input, &mut output.file)
+    } else {
+        let buffer_size = options.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE).max(1);
+        copy_chunked(input, output, buffer_size)
+    }
+}
+
+/// Trim the preallocated `output` (at `path`) to the `copied` bytes, in
+/// case the source shrank while it was copied or a resumed staged file
+/// was longer than the source, and carry over the
+/// attributes of `input` that `options` asks for. Ownership and extended
+/// attributes go first, since changing the owner clears set-user-ID bits
+/// and a read-only mode could rule out setting attributes.
+fn finish(
+    input: &File,
+    output: &Output,
+    path: &Path,
+    copied: u64,
+    metadata: &Metadata,
+    options: &MoveOptions,
+) -> io::Result<()> {
+    if output.file.metadata()?.len() > copied {
+        output.file.set_len(copied)?;
+    }
+    ownership::restore(metadata, path, options)?;
+    if options.preserve.copies_xattrs() {
+        preserve::copy_xattrs(input, &output.file, options.preserve)?;
+    }
+    if options.preserve.contains(PreserveSet::MODE) {
+        fs::set_permissions(path, metadata.permissions())?;
+    }
+    if options.preserve.contains(PreserveSet::TIMESTAMPS) {
+        // Best effort, like the times of directories
+        let _ = preserve::copy_times(metadata, &output.file);
+    }
+    Ok(())
+}
+
+/// Copy `input` to `output` in chunks of (about) `buffer_size` bytes.
+fn copy_chunked<R: Read>(
+    input: &mut R,
+    output: &mut Output,
+    buffer_size: usize,
+) -> io::Result<u64> {
+    let buffer_size = if output.direct {
+        buffer_size.next_multiple_of(DIRECT_ALIGN)
+    } else {
+        buffer_size
+    };
+    // Over-allocate so the chunk can start at an aligned address
+    let mut storage = vec![0u8; buffer_size + DIRECT_ALIGN];
+    let offset = storage.as_ptr().align_offset(DIRECT_ALIGN);
+    let buffer = &mut storage[offset..offset + buffer_size];
+
+    let mut copied = 0u64;
+    loop {
+        let filled = fill(input, buffer)?;
+        if filled == 0 {
+            break;
+        }
+        output.write_chunk(&buffer[..filled])?;
+        copied += filled as u64;
+        if filled < buffer.len() {
+            break;
+        }
+    }
+    Ok(copied)
+}
+
+/// Reserve `len` bytes for `file` up front, so that a full disk fails the
+/// copy before any data is written and the copy is laid out contiguously.
+/// Filesystems that can't preallocate are left to grow the file as usual.
+#[cfg(target_os = "linux")]
+fn preallocate(file: &File, len: u64) -> io::Result<()> {
+    use rustix::fs::{fallocate, FallocateFlags};
+    use rustix::io::Errno;
+
+    if len == 0 {
+        return Ok(());
+    }
+    match fallocate(file, FallocateFlags::empty(), 0, len) {
+        Ok(()) | Err(Errno::OPNOTSUPP | Errno::NOSYS) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn preallocate(_file: &File, _len: u64) -> io::Result<()> {
+    Ok(())
+}
+
+/// Read into `buffer` until it is full or the end of the file is reached.
+fn fill<R: Read>(input: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match input.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// The destination file, and whether it is still open for direct I/O.
+struct Output {
+    file: File,
+    direct: bool,
+}
+
+/// File attributes a copy is created with: on Windows, the Hidden and
+/// System attributes of the source when its mode is preserved. (ReadOnly
+/// is part of the permissions, which are set once the data is written.)
+#[cfg(windows)]
+fn creation_attributes(metadata: &Metadata, options: &MoveOptions) -> u32 {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+    if options.preserve.contains(PreserveSet::MODE) {
+        metadata.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM)
+    } else {
+        0
+    }
+}
+
+#[cfg(not(windows))]
+fn creation_attributes(_metadata: &Metadata, _options: &MoveOptions) -> u32 {
+    0
+}
+
+impl Output {
+    /// Open `path` for writing, truncating it unless `keep` is set. A new
+    /// file gets the Windows file `attributes`.
+    #[cfg(target_os = "linux")]
+    fn open(path: &Path, direct: bool, keep: bool, attributes: u32) -> io::Result<Self> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        if direct {
+            let opened = Self::options(keep, attributes)
+                .custom_flags(libc::O_DIRECT)
+                .open(path);
+            match opened {
+                Ok(file) => return Ok(Self { file, direct: true }),
+                Err(e) if e.raw_os_error() != Some(libc::EINVAL) => return Err(e),
+                // Not supported by this filesystem
+                Err(_) => {}
+            }
+        }
+        Ok(Self {
+            file: Self::options(keep, attributes).open(path)?,
+            direct: false,
+        })
+    }
+
+    /// Open `path` for writing, truncating it unless `keep` is set. A new
+    /// file gets the Windows file `attributes`.
+    #[cfg(not(target_os = "linux"))]
+    fn open(path: &Path, _direct: bool, keep: bool, attributes: u32) -> io::Result<Self> {
+        Ok(Self {
+            file: Self::options(keep, attributes).open(path)?,
+            direct: false,
+        })
+    }
+
+    fn options(keep: bool, attributes: u32) -> fs::OpenOptions {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(!keep);
+        #[cfg(windows)]
+        std::os::windows::fs::OpenOptionsExt::attributes(&mut options, attributes);
+        #[cfg(not(windows))]
+        let _ = attributes;
+        options
+    }
+
+    fn write_chunk(&mut self, chunk: &[u8]) -> io::Result<()> {
+        if self.direct && !chunk.len().is_multiple_of(DIRECT_ALIGN) {
+            self.stop_direct()?;
+        }
+        match self.file.write_all(chunk) {
+            // Some filesystems accept O_DIRECT on open but not on write
+            Err(e) if self.direct && e.raw_os_error() == Some(libc::EINVAL) => {
+                self.stop_direct()?;
+                self.file.write_all(chunk)
+            }
+            result => result,
+        }
+    }
+
+    /// Continue with buffered writes.
+    #[cfg(target_os = "linux")]
+    fn stop_direct(&mut self) -> io::Result<()> {
+        use rustix::fs::{fcntl_getfl, fcntl_setfl, OFlags};
+
+        let flags = fcntl_getfl(&self.file)?;
+        fcntl_setfl(&self.file, flags - OFlags::DIRECT)?;
+        self.direct = false;
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn stop_direct(&mut self) -> io::Result<()> {
+        self.direct = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_stale_file_handles_only() {
+        let options = MoveOptions::builder().stale_retries(2).build();
+        let mut attempts = 0;
+        let result = retry_stale(&options, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(io::Error::from(ErrorKind::StaleNetworkFileHandle))
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        attempts = 0;
+        let result: io::Result<()> = retry_stale(&options, || {
+            attempts += 1;
+            Err(io::Error::from(ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn copy_keeps_hidden_and_read_only_attributes() {
+        use std::os::windows::fs::{MetadataExt, OpenOptionsExt};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("source.txt");
+        fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .attributes(0x2)
+            .open(&source)
+            .unwrap()
+            .write_all(b"data")
+            .unwrap();
+        let mut permissions = fs::metadata(&source).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&source, permissions).unwrap();
+
+        let dest = tmp.path().join("dest.txt");
+        copy_file(&source, &dest, &MoveOptions::default()).unwrap();
+        // Hidden (0x2) and ReadOnly (0x1)
+        assert_eq!(fs::metadata(&dest).unwrap().file_attributes() & 0x3, 0x3);
+    }
+
+    fn copy_with(options: &MoveOptions, len: usize) {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("source.bin");
+        let data: Vec<u8> = (0..len).map(|i| u8::try_from(i % 251).unwrap()).collect();
+        fs::write(&source, &data).unwrap();
+
+        let dest = tmp.path().join("dest.bin");
+        assert_eq!(copy_file(&source, &dest, options).unwrap(), len as u64);
+        assert_eq!(fs::read(&dest).unwrap(), data);
+    }
+
+    #[test]
+    fn chunked_copy_handles_partial_chunks() {
+        let options = MoveOptions::builder().buffer_size(1000).build();
+        for len in [0, 1, 999, 1000, 1001, 12_345] {
+            copy_with(&options, len);
+        }
+    }
+
+    #[test]
+    fn default_copy_keeps_size_and_permissions() {
+        copy_with(&MoveOptions::default(), 0);
+        copy_with(&MoveOptions::default(), 100_000);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let tmp = tempfile::TempDir::new().unwrap();
+            let source = tmp.path().join("script.sh");
+            fs::write(&source, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&source, fs::Permissions::from_mode(0o750)).unwrap();
+            let dest = tmp.path().join("copy.sh");
+            copy_file(&source, &dest, &MoveOptions::default()).unwrap();
+            let mode = fs::metadata(&dest).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o750);
+        }
+    }
+
+    /// A source of `len` bytes, and the staged file of an interrupted copy
+    /// of it to `dest.bin` with `staged` as its contents.
+    fn interrupted(len: usize, staged: &[u8]) -> (tempfile::TempDir, Vec<u8>) {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let data: Vec<u8> = (0..len).map(|i| u8::try_from(i % 251).unwrap()).collect();
+        fs::write(tmp.path().join("source.bin"), &data).unwrap();
+        fs::write(tmp.path().join(".dest.bin.mvln-tmp"), staged).unwrap();
+        (tmp, data)
+    }
+
+    fn record(tmp: &Path, copied: u64) {
+        let metadata = fs::metadata(tmp.join("source.bin")).unwrap();
+        let state = tmp.join(".dest.bin.mvln-tmp.state");
+        fs::write(state, progress_line(&metadata, copied)).unwrap();
+    }
+
+    fn resume(tmp: &Path) -> u64 {
+        let mut input = File::open(tmp.join("source.bin")).unwrap();
+        let metadata = input.metadata().unwrap();
+        let staged = tmp.join(".dest.bin.mvln-tmp");
+        let state = tmp.join(".dest.bin.mvln-tmp.state");
+        resume_offset(&mut input, &metadata, &staged, &state, 1000).unwrap()
+    }
+
+    #[test]
+    fn staged_copy_resumes_the_matching_prefix() {
+        let (tmp, data) = interrupted(10_000, &[]);
+        let mut staged = data[..4500].to_vec();
+        staged.resize(10_000, 0);
+        fs::write(tmp.path().join(".dest.bin.mvln-tmp"), &staged).unwrap();
+        assert_eq!(resume(tmp.path()), 4000);
+        record(tmp.path(), 3000);
+        assert_eq!(resume(tmp.path()), 3000);
+
+        let dest = tmp.path().join("dest.bin");
+        let mut input = File::open(tmp.path().join("source.bin")).unwrap();
+        let metadata = input.metadata().unwrap();
+        let copied =
+            copy_staged(&mut input, &metadata, &dest, &MoveOptions::default(), 1000).unwrap();
+        assert_eq!(copied, 10_000);
+        assert_eq!(fs::read(&dest).unwrap(), data);
+        assert!(!tmp.path().join(".dest.bin.mvln-tmp").exists());
+        assert!(!tmp.path().join(".dest.bin.mvln-tmp.state").exists());
+    }
+
+    #[test]
+    fn staged_copy_discards_a_mismatch() {
+        let (tmp, data) = interrupted(10_000, &[0xff; 4096]);
+        assert_eq!(resume(tmp.path()), 0);
+
+        let dest = tmp.path().join("dest.bin");
+        copy_file(
+            &tmp.path().join("source.bin"),
+            &dest,
+            &MoveOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), data);
+
+        // Progress recorded for another version of the source is ignored
+        let (tmp, data) = interrupted(10_000, &[]);
+        fs::write(tmp.path().join(".dest.bin.mvln-tmp"), &data[..2000]).unwrap();
+        record(tmp.path(), 2000);
+        fs::write(tmp.path().join("source.bin"), [0u8; 9000]).unwrap();
+        assert_eq!(resume(tmp.path()), 0);
+    }
+
+    #[test]
+    fn staged_copy_longer_than_the_source_is_truncated() {
+        // Left by an earlier, longer version of the source
+        let (tmp, data) = interrupted(10_000, &[]);
+        let mut staged = data.clone();
+        staged.extend_from_slice(&[0xff; 2500]);
+        fs::write(tmp.path().join(".dest.bin.mvln-tmp"), &staged).unwrap();
+        assert_eq!(resume(tmp.path()), 10_000);
+
+        let dest = tmp.path().join("dest.bin");
+        let mut input = File::open(tmp.path().join("source.bin")).unwrap();
+        let metadata = input.metadata().unwrap();
+        let copied =
+            copy_staged(&mut input, &metadata, &dest, &MoveOptions::default(), 1000).unwrap();
+        assert_eq!(copied, 10_000);
+        assert_eq!(fs::read(&dest).unwrap(), data);
+    }
+
+    #[test]
+    fn direct_io_copies_aligned_and_unaligned_files() {
+        let options = MoveOptions::builder()
+            .buffer_size(8192)
+            .direct_io(true)
+            .build();
+        for len in [0, 4096, 8192, 20_000] {
+            copy_with(&options, len);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Minimal calendar date handling for destination templates.
+//!
+//! Routing files into date-based folders only needs to turn a file's
+//! timestamp into year/month/day fields and format them. Dates are computed
+//! in UTC, which keeps results identical across machines and avoids platform
+//! time zone APIs.
+
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A broken-down UTC timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    /// Full year, e.g. 2024.
+    pub year: i64,
+    /// Month, 1-12.
+    pub month: u32,
+    /// Day of month, 1-31.
+    pub day: u32,
+    /// Hour, 0-23.
+    pub hour: u32,
+    /// Minute, 0-59.
+    pub minute: u32,
+    /// Second, 0-59.
+    pub second: u32,
+}
+
+impl DateTime {
+    /// Convert a [`SystemTime`] to a UTC date.
+    ///
+    /// Times before the Unix epoch are supported.
+    #[must_use]
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => i64::try_from(d.as_secs()).unwrap_or(i64::MAX),
+            Err(e) => -i64::try_from(e.duration().as_secs()).unwrap_or(i64::MAX),
+        };
+        Self::from_unix(secs)
+    }
+
+    /// Convert seconds since the Unix epoch to a UTC date.
+    #[must_use]
+    pub fn from_unix(secs: i64) -> Self {
+        let days = secs.div_euclid(86_400);
+        let rem = secs.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+        // rem is in 0..86_400, so these conversions cannot fail
+        let rem = u32::try_from(rem).unwrap_or(0);
+        Self {
+            year,
+            month,
+            day,
+            hour: rem / 3600,
+            minute: rem % 3600 / 60,
+            second: rem % 60,
+        }
+    }
+
+    /// Format the date with a `strftime`-style format string.
+    ///
+    /// Supported conversions: `%Y` (year), `%m` (month), `%d` (day),
+    /// `%H`, `%M`, `%S` (time), `%F` (`%Y-%m-%d`), `%j` (day of year)
+    /// and `%%`. Unknown conversions are copied through unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mvln::date::DateTime;
+    ///
+    /// let date = DateTime::from_unix(1_717_245_000);
+    /// assert_eq!(date.format("%Y/%m"), "2024/06");
+    /// assert_eq!(date.format("%F %H:%M"), "2024-06-01 12:30");
+    /// ```
+    #[must_use]
+    pub fn format(&self, fmt: &str) -> String {
+        let mut out = String::with_capacity(fmt.len() + 8);
+        let mut chars = fmt.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+            // Writing to a String cannot fail
+            let _ = match chars.next() {
+                Some('Y') => write!(out, "{:04}", self.year),
+                Some('m') => write!(out, "{:02}", self.month),
+                Some('d') => write!(out, "{:02}", self.day),
+                Some('H') => write!(out, "{:02}", self.hour),
+                Some('M') => write!(out, "{:02}", self.minute),
+                Some('S') => write!(out, "{:02}", self.second),
+                Some('j') => write!(out, "{:03}", self.day_of_year()),
+                Some('F') => write!(out, "{:04}-{:02}-{:02}", self.year, self.month, self.day),
+                Some(other) if other != '%' => write!(out, "%{other}"),
+                _ => write!(out, "%"),
+            };
+        }
+        out
+    }
+
+    /// Day of the year, 1-366.
+    #[must_use]
+    pub fn day_of_year(&self) -> u32 {
+        const CUMULATIVE: [u32; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+        let leap = self.year % 4 == 0 && (self.year % 100 != 0 || self.year % 400 == 0);
+        let idx = usize::try_from(self.month.saturating_sub(1))
+            .unwrap_or(0)
+            .min(11);
+        CUMULATIVE[idx] + self.day + u32::from(leap && self.month > 2)
+    }
+}
+
+/// Convert days since 1970-01-01 to a (year, month, day) triple.
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097); // [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365; // [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11]
+    let day = doy - (153 * mp + 2) / 5 + 1; // [1, 31]
+    let month = if mp < 10 { mp + 3 } else { mp - 9 }; // [1, 12]
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (
+        year,
+        u32::try_from(month).unwrap_or(1),
+        u32::try_from(day).unwrap_or(1),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn epoch_is_1970_01_01() {
+        let date = DateTime::from_unix(0);
+        assert_eq!((date.year, date.month, date.day), (1970, 1, 1));
+        assert_eq!((date.hour, date.minute, date.second), (0, 0, 0));
+    }
+
+    #[test]
+    fn leap_day() {
+        // 2024-02-29T23:59:59Z
+        let date = DateTime::from_unix(1_709_251_199);
+        assert_eq!(date.format("%F %H:%M:%S"), "2024-02-29 23:59:59");
+        assert_eq!(date.day_of_year(), 60);
+    }
+
+    #[test]
+    fn before_epoch() {
+        let date = DateTime::from_unix(-1);
+        assert_eq!(date.format("%F %H:%M:%S"), "1969-12-31 23:59:59");
+    }
+
+    #[test]
+    fn unknown_conversions_are_kept() {
+        let date = DateTime::from_unix(0);
+        assert_eq!(date.format("%Y%q%%"), "1970%q%");
+    }
+}
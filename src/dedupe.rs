@@ -0,0 +1,195 @@
+//! Finding identical files for hard-link deduplication.
+//!
+//! A [`DedupeIndex`] collects candidate files (typically everything under a
+//! destination tree plus the targets recorded in the journal) and answers
+//! whether a given file's contents are already stored somewhere. Only file
+//! sizes are gathered up front; contents are hashed lazily, and only for
+//! candidates whose size matches, so indexing a large tree stays cheap.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{MvlnError, Result};
+use crate::hash::{hash_file, Digest};
+use crate::journal::Journal;
+
+/// Index of candidate files for deduplication.
+#[derive(Debug, Default)]
+pub struct DedupeIndex {
+    /// Candidate files grouped by size.
+    by_size: HashMap<u64, Vec<PathBuf>>,
+    /// Digests computed so far.
+    digests: HashMap<PathBuf, Digest>,
+}
+
+impl DedupeIndex {
+    /// Create an empty index.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add every regular file under `root` (symlinks are not followed).
+    ///
+    /// A missing `root` adds nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::SourceAccessError`] if a directory cannot be read.
+    pub fn add_tree<P: AsRef<Path>>(&mut self, root: P) -> Result<()> {
+        let root = root.as_ref();
+        if !root.is_dir() {
+            return Ok(());
+        }
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let read_err = |e: std::io::Error| MvlnError::SourceAccessError {
+                path: dir.clone(),
+                source: e,
+            };
+            for entry in fs::read_dir(&dir).map_err(read_err)? {
+                let entry = entry.map_err(read_err)?;
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    pending.push(entry.path());
+                } else if file_type.is_file() {
+                    self.add_file(entry.path());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the journal's recorded targets that are still regular files.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JournalError`] if the journal cannot be read.
+    pub fn add_journal(&mut self, journal: &Journal) -> Result<()> {
+        for entry in journal.entries()? {
+            if entry.action.is_progress() {
+                continue;
+            }
+            self.add_file(entry.target);
+        }
+        Ok(())
+    }
+
+    /// Add a single file. Paths that are not regular files are ignored.
+    pub fn add_file<P: Into<PathBuf>>(&mut self, path: P) {
+        let path = path.into();
+        let Ok(meta) = path.symlink_metadata() else {
+            return;
+        };
+        if !meta.is_file() {
+            return;
+        }
+        let candidates = self.by_size.entry(meta.len()).or_default();
+        if !candidates.contains(&path) {
+            candidates.push(path);
+        }
+    }
+
+    /// Number of candidate files in the index.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.by_size.values().map(Vec::len).sum()
+    }
+
+    /// Whether the index has no candidates.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.by_size.is_empty()
+    }
+
+    /// Find an indexed file with the same contents as `file`.
+    ///
+    /// `file` itself is never returned, and candidates that disappeared or
+    /// cannot be read are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::SourceAccessError`] if `file` cannot be read.
+    pub fn find_duplicate<P: AsRef<Path>>(&mut self, file: P) -> Result<Option<PathBuf>> {
+        let file = file.as_ref();
+        let access_err = |e: std::io::Error| MvlnError::SourceAccessError {
+            path: file.to_path_buf(),
+            source: e,
+        };
+        let size = file.symlink_metadata().map_err(access_err)?.len();
+        let Some(candidates) = self.by_size.get(&size) else {
+            return Ok(None);
+        };
+        // Candidates may have changed since they were indexed (e.g. a
+        // source that has since been replaced by a symlink)
+        let candidates: Vec<PathBuf> = candidates
+            .iter()
+            .filter(|c| c.as_path() != file)
+            .filter(|c| c.symlink_metadata().is_ok_and(|m| m.is_file()))
+            .cloned()
+            .collect();
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let digest = hash_file(file).map_err(access_err)?;
+        for candidate in candidates {
+            if self.digest(&candidate) == Some(digest) {
+                return Ok(Some(candidate));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Digest of an indexed file, computed on first use.
+    fn digest(&mut self, path: &Path) -> Option<Digest> {
+        if let Some(digest) = self.digests.get(path) {
+            return Some(*digest);
+        }
+        let digest = hash_file(path).ok()?;
+        self.digests.insert(path.to_path_buf(), digest);
+        Some(digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_identical_file_in_tree() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let tree = tmp.path().join("tree");
+        fs::create_dir_all(tree.join("sub")).unwrap();
+        fs::write(tree.join("sub/same.txt"), "hello").unwrap();
+        fs::write(tree.join("other.txt"), "world").unwrap();
+        let file = tmp.path().join("new.txt");
+        fs::write(&file, "hello").unwrap();
+
+        let mut index = DedupeIndex::new();
+        index.add_tree(&tree).unwrap();
+        assert_eq!(index.len(), 2);
+
+        assert_eq!(
+            index.find_duplicate(&file).unwrap(),
+            Some(tree.join("sub/same.txt"))
+        );
+    }
+
+    #[test]
+    fn different_contents_are_not_duplicates() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let existing = tmp.path().join("a");
+        let file = tmp.path().join("b");
+        fs::write(&existing, "aaaa").unwrap();
+        fs::write(&file, "bbbb").unwrap();
+
+        let mut index = DedupeIndex::new();
+        index.add_file(&existing);
+        index.add_file(&file);
+        assert_eq!(index.find_duplicate(&file).unwrap(), None);
+    }
+}
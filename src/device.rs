@@ -0,0 +1,288 @@
+//! Which filesystem a path is on.
+//!
+//! A rename only works within one filesystem; anywhere else a move falls
+//! back to copying and removing, which takes time in proportion to the
+//! data. Comparing device IDs tells the two apart before anything is
+//! moved, so a [`CrossDeviceHandler`] set with
+//! [`MoveOptionsBuilder::on_cross_device`](crate::operation::MoveOptionsBuilder::on_cross_device)
+//! can be asked first.
+//!
+//! For a whole batch, [`Devices`] looks up each directory once, so a plan
+//! can be classified up front (see
+//! [`MovePlan::crosses_filesystems`](crate::plan::MovePlan::crosses_filesystems)).
+//!
+//! Bind mounts of one filesystem share a device ID although a rename
+//! between them fails; such moves are copied without asking.
+//!
+//! [`network_filesystem`] tells NFS, SMB/CIFS and SSHFS mounts apart from
+//! local ones (Linux only), since copies onto them and links stored on
+//! them need different treatment.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The device ID of the filesystem `path` is on, or would be created on:
+/// that of its closest existing ancestor. Symlinks are not followed.
+///
+/// `None` where the platform has no device IDs or nothing along the path
+/// exists.
+#[must_use]
+pub fn device_id(path: &Path) -> Option<u64> {
+    let path = if path.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        path
+    };
+    path.ancestors()
+        .find_map(|dir| dir.symlink_metadata().ok())
+        .and_then(|metadata| dev(&metadata))
+}
+
+/// Whether moving `source` to `dest` crosses filesystems, so it cannot be
+/// done by a rename. `None` if either device ID is unknown.
+///
+/// `dest` is where `source` would end up; it is judged by its parent
+/// directory, since that is what a rename writes to.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::device::crosses_filesystems;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let file = dir.path().join("a.txt");
+/// std::fs::write(&file, "data").unwrap();
+/// # #[cfg(unix)]
+/// assert_eq!(
+///     crosses_filesystems(&file, &dir.path().join("new/b.txt")),
+///     Some(false)
+/// );
+/// ```
+#[must_use]
+pub fn crosses_filesystems(source: &Path, dest: &Path) -> Option<bool> {
+    let dest_dir = dest.parent().unwrap_or(dest);
+    Some(device_id(source)? != device_id(dest_dir)?)
+}
+
+/// Device IDs of paths, each looked up once, for classifying a batch of
+/// moves without stat'ing the same directories over and over.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::device::Devices;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let mut devices = Devices::new();
+/// # #[cfg(unix)]
+/// assert_eq!(
+///     devices.crosses(&dir.path().join("a.txt"), &dir.path().join("archive/")),
+///     Some(false)
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Devices {
+    ids: HashMap<PathBuf, Option<u64>>,
+}
+
+impl Devices {
+    /// An empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The [`device_id`] of `path`, looked up the first time it is asked
+    /// for.
+    pub fn of(&mut self, path: &Path) -> Option<u64> {
+        *self
+            .ids
+            .entry(path.to_path_buf())
+            .or_insert_with(|| device_id(path))
+    }
+
+    /// Whether moving `source` to `dest` crosses filesystems, as
+    /// [`crosses_filesystems`] but with `dest` given as planned: an existing
+    /// directory receives the source inside it. The source is judged by its
+    /// parent directory, so a batch from one directory to another costs two
+    /// lookups.
+    pub fn crosses(&mut self, source: &Path, dest: &Path) -> Option<bool> {
+        let source_dir = match source.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        Some(self.of(source_dir)? != self.of(dest)?)
+    }
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_wraps)]
+fn dev(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn dev(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// A network filesystem, as told by [`network_filesystem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NetworkFs {
+    /// NFS, any version.
+    Nfs,
+    /// SMB or CIFS (Samba, Windows shares).
+    Smb,
+    /// SSHFS, a FUSE filesystem over SFTP.
+    Sshfs,
+}
+
+impl NetworkFs {
+    /// The usual name of the filesystem, as shown in messages.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nfs => "NFS",
+            Self::Smb => "SMB",
+            Self::Sshfs => "SSHFS",
+        }
+    }
+}
+
+/// The network filesystem `path` is on, or would be created on (that of
+/// its closest existing ancestor), or `None` for a local one.
+///
+/// Told by the `statfs` magic number; FUSE mounts are looked up in
+/// `/proc/self/mounts` to tell SSHFS from other FUSE filesystems. Always
+/// `None` on platforms other than Linux.
+#[must_use]
+pub fn network_filesystem(path: &Path) -> Option<NetworkFs> {
+    let path = if path.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        path
+    };
+    let dir = path
+        .ancestors()
+        .find(|dir| dir.symlink_metadata().is_ok())?;
+    network_magic(dir)
+}
+
+#[cfg(target_os = "linux")]
+fn network_magic(dir: &Path) -> Option<NetworkFs> {
+    const NFS_SUPER_MAGIC: u32 = 0x6969;
+    const SMB_SUPER_MAGIC: u32 = 0x517b;
+    const CIFS_MAGIC_NUMBER: u32 = 0xff53_4d42;
+    const SMB2_MAGIC_NUMBER: u32 = 0xfe53_4d42;
+    const FUSE_SUPER_MAGIC: u32 = 0x6573_5546;
+
+    let stat = rustix::fs::statfs(dir).ok()?;
+    // The field is signed on some targets, but magic numbers are 32 bits
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    match stat.f_type as u32 {
+        NFS_SUPER_MAGIC => Some(NetworkFs::Nfs),
+        SMB_SUPER_MAGIC | CIFS_MAGIC_NUMBER | SMB2_MAGIC_NUMBER => Some(NetworkFs::Smb),
+        FUSE_SUPER_MAGIC => {
+            let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+            (fuse_type(&mounts, &dir.canonicalize().ok()?)? == "fuse.sshfs")
+                .then_some(NetworkFs::Sshfs)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn network_magic(_dir: &Path) -> Option<NetworkFs> {
+    None
+}
+
+/// The type of the innermost mount in the `/proc/self/mounts` listing
+/// `mounts` that holds `dir`.
+#[cfg(target_os = "linux")]
+fn fuse_type<'a>(mounts: &'a str, dir: &Path) -> Option<&'a str> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let point = fields.nth(1)?.replace("\\040", " ");
+            Some((PathBuf::from(point), fields.next()?))
+        })
+        .filter(|(point, _)| dir.starts_with(point))
+        .max_by_key(|(point, _)| point.as_os_str().len())
+        .map(|(_, kind)| kind)
+}
+
+/// A move about to be done by copying, passed to a [`CrossDeviceHandler`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct CrossDeviceInfo<'a> {
+    /// The source being moved.
+    pub source: &'a Path,
+    /// Where it is being moved to.
+    pub dest: &'a Path,
+    /// How much would be copied, in bytes (see
+    /// [`disk_usage`](crate::size::disk_usage)).
+    pub bytes: u64,
+    /// How many files would be copied; 1 if the source is not a directory
+    /// (see [`tree_size`](crate::size::tree_size)).
+    pub files: u64,
+}
+
+/// A callback deciding whether a move across filesystems goes ahead,
+/// shared between clones of the [`MoveOptions`](crate::MoveOptions) it is
+/// set on.
+#[derive(Clone)]
+pub struct CrossDeviceHandler(Arc<dyn Fn(&CrossDeviceInfo<'_>) -> bool + Send + Sync>);
+
+impl CrossDeviceHandler {
+    /// Wrap a callback.
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&CrossDeviceInfo<'_>) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(callback))
+    }
+
+    /// Ask the callback whether to copy as `info` describes.
+    #[must_use]
+    pub fn confirm(&self, info: &CrossDeviceInfo<'_>) -> bool {
+        (self.0)(info)
+    }
+}
+
+impl fmt::Debug for CrossDeviceHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CrossDeviceHandler(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn asks_the_closest_existing_ancestor() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let id = device_id(tmp.path());
+        assert!(id.is_some());
+        assert_eq!(device_id(&tmp.path().join("missing/dir/file")), id);
+        assert_eq!(crosses_filesystems(tmp.path(), tmp.path()), Some(false));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn finds_the_innermost_mount() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n\
+                      me@host:/ /mnt/my\\040box fuse.sshfs rw 0 0\n\
+                      /dev/sdb1 /mnt/my\\040box/usb vfat rw 0 0\n";
+        let kind = |dir: &str| fuse_type(mounts, Path::new(dir));
+        assert_eq!(kind("/mnt/my box/docs"), Some("fuse.sshfs"));
+        assert_eq!(kind("/mnt/my box/usb"), Some("vfat"));
+        assert_eq!(kind("/mnt/my"), Some("ext4"));
+    }
+}
@@ -0,0 +1,114 @@
+//! Locations of mvln's on-disk state and configuration.
+//!
+//! mvln keeps a small amount of per-user state (lock files and its journal)
+//! outside of the directories it operates on, and reads an optional
+//! configuration file and user-provided data such as translations. The
+//! locations follow the XDG base directory conventions on Unix and can be
+//! overridden with the `MVLN_STATE_DIR`, `MVLN_CONFIG` and `MVLN_DATA_DIR`
+//! environment variables, which is mainly useful for tests and for running
+//! several isolated mvln setups side by side.
+
+use std::env;
+use std::path::PathBuf;
+
+/// Environment variable overriding the state directory.
+pub const STATE_DIR_ENV: &str = "MVLN_STATE_DIR";
+
+/// Environment variable overriding the configuration file path.
+pub const CONFIG_FILE_ENV: &str = "MVLN_CONFIG";
+
+/// Environment variable overriding the data directory.
+pub const DATA_DIR_ENV: &str = "MVLN_DATA_DIR";
+
+/// Return the directory used for mvln's runtime state.
+///
+/// Resolution order:
+/// 1. `$MVLN_STATE_DIR`
+/// 2. `$XDG_STATE_HOME/mvln`
+/// 3. `$HOME/.local/state/mvln` (`%LOCALAPPDATA%\mvln` on Windows)
+/// 4. `<temp dir>/mvln` as a last resort
+///
+/// The directory is not created by this function.
+#[must_use]
+pub fn state_dir() -> PathBuf {
+    if let Some(dir) = non_empty_var(STATE_DIR_ENV) {
+        return PathBuf::from(dir);
+    }
+    if let Some(dir) = non_empty_var("XDG_STATE_HOME") {
+        return PathBuf::from(dir).join("mvln");
+    }
+    #[cfg(windows)]
+    if let Some(dir) = non_empty_var("LOCALAPPDATA") {
+        return PathBuf::from(dir).join("mvln");
+    }
+    if let Some(home) = non_empty_var("HOME") {
+        return PathBuf::from(home)
+            .join(".local")
+            .join("state")
+            .join("mvln");
+    }
+    env::temp_dir().join("mvln")
+}
+
+/// Return the path of the configuration file.
+///
+/// Resolution order:
+/// 1. `$MVLN_CONFIG`
+/// 2. `$XDG_CONFIG_HOME/mvln/config.toml`
+/// 3. `$HOME/.config/mvln/config.toml` (`%APPDATA%\mvln\config.toml` on Windows)
+///
+/// Returns `None` if no candidate location can be determined. The file may
+/// not exist.
+#[must_use]
+pub fn config_file() -> Option<PathBuf> {
+    if let Some(file) = non_empty_var(CONFIG_FILE_ENV) {
+        return Some(PathBuf::from(file));
+    }
+    if let Some(dir) = non_empty_var("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("mvln").join("config.toml"));
+    }
+    #[cfg(windows)]
+    if let Some(dir) = non_empty_var("APPDATA") {
+        return Some(PathBuf::from(dir).join("mvln").join("config.toml"));
+    }
+    non_empty_var("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".config")
+            .join("mvln")
+            .join("config.toml")
+    })
+}
+
+/// Return the directory holding user-provided data (e.g. translations).
+///
+/// Resolution order:
+/// 1. `$MVLN_DATA_DIR`
+/// 2. `$XDG_DATA_HOME/mvln`
+/// 3. `$HOME/.local/share/mvln` (`%APPDATA%\mvln` on Windows)
+///
+/// Returns `None` if no candidate location can be determined. The directory
+/// may not exist.
+#[must_use]
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = non_empty_var(DATA_DIR_ENV) {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = non_empty_var("XDG_DATA_HOME") {
+        return Some(PathBuf::from(dir).join("mvln"));
+    }
+    #[cfg(windows)]
+    if let Some(dir) = non_empty_var("APPDATA") {
+        return Some(PathBuf::from(dir).join("mvln"));
+    }
+    non_empty_var("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".local")
+            .join("share")
+            .join("mvln")
+    })
+}
+
+/// Read an environment variable, treating empty values as unset.
+fn non_empty_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
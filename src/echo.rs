@@ -0,0 +1,318 @@
+//! Equivalent shell commands printed for each operation.
+//!
+//! mvln echoes what it does as commands the user could have typed (`mv`,
+//! `ln -s`, ...). [`Echo`] renders those commands for the shell selected
+//! with `--shell`, so they can be pasted back into that shell as-is, and
+//! prints them where `--commands-to` says (or nowhere, with
+//! `--no-commands`).
+//!
+//! With `--emit-script` every command is additionally recorded in POSIX
+//! form, and [`write_script`] turns the recording into a standalone shell
+//! script replaying the batch without mvln. The POSIX form also goes to the
+//! `--log-file` run log.
+
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use mvln::error::{MvlnError, Result};
+
+use crate::cli::{CommandsTo, ShellDialect};
+use crate::logfile::{self, Kind};
+
+thread_local! {
+    /// Commands recorded for `--emit-script`, if recording was started.
+    static SCRIPT: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Start recording commands for `--emit-script`.
+pub fn record_script() {
+    SCRIPT.with(|script| *script.borrow_mut() = Some(Vec::new()));
+}
+
+/// Write the recorded commands to `path` as an executable `/bin/sh` script.
+///
+/// The script changes to the current directory first, so relative paths
+/// resolve as they did for mvln, and stops at the first failing command.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::ScriptWriteFailed`] if the file cannot be written.
+pub fn write_script(path: &Path) -> Result<()> {
+    let commands = SCRIPT
+        .with(|script| script.borrow_mut().take())
+        .unwrap_or_default();
+    let posix = Echo::new(ShellDialect::Posix, None);
+    let cwd = std::env::current_dir().map_or_else(
+        |_| ".".to_string(),
+        |dir| posix.quote(&dir.display().to_string()),
+    );
+
+    let mut text = String::new();
+    let _ = writeln!(text, "#!/bin/sh");
+    let _ = writeln!(
+        text,
+        "# Generated by mvln {}; replays the batch with mv and ln -s.",
+        env!("CARGO_PKG_VERSION")
+    );
+    let _ = writeln!(text, "set -e");
+    let _ = writeln!(text, "cd {cwd}");
+    for command in commands {
+        let _ = writeln!(text, "{command}");
+    }
+
+    let error = |e: std::io::Error| MvlnError::ScriptWriteFailed {
+        path: path.to_path_buf(),
+        source: e,
+    };
+    fs::write(path, text).map_err(error)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).map_err(error)?;
+    }
+    Ok(())
+}
+
+/// Renders equivalent commands in one shell dialect.
+#[derive(Debug, Clone, Copy)]
+pub struct Echo {
+    dialect: ShellDialect,
+    /// Where commands are printed; `None` suppresses them.
+    output: Option<CommandsTo>,
+}
+
+impl Echo {
+    pub fn new(dialect: ShellDialect, output: Option<CommandsTo>) -> Self {
+        Self { dialect, output }
+    }
+
+    /// Print the command built by `render` to the selected stream, and
+    /// record it for `--emit-script` and `--log-file`.
+    pub fn emit(self, render: impl Fn(Self) -> String) {
+        match self.output {
+            Some(CommandsTo::Stdout) => println!("{}", render(self)),
+            Some(CommandsTo::Stderr) => eprintln!("{}", render(self)),
+            None => {}
+        }
+        self.script_only(render);
+    }
+
+    /// Record the command built by `render` for `--emit-script` and
+    /// `--log-file` only.
+    ///
+    /// For steps mvln performs implicitly (such as creating a destination
+    /// directory) that a plain script has to spell out.
+    pub fn script_only(self, render: impl Fn(Self) -> String) {
+        let command = render(Self {
+            dialect: ShellDialect::Posix,
+            ..self
+        });
+        logfile::write(Kind::Action, &command);
+        SCRIPT.with(|script| {
+            if let Some(commands) = script.borrow_mut().as_mut() {
+                commands.push(command);
+            }
+        });
+    }
+
+    /// Quote `s` as a single argument, leaving plain words unquoted.
+    pub fn quote(self, s: &str) -> String {
+        match self.dialect {
+            ShellDialect::Posix => posix_quote(s),
+            ShellDialect::Fish => {
+                // Unquoted backslashes are escapes in fish
+                if is_plain(s) && !s.contains('\\') {
+                    s.to_string()
+                } else {
+                    format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))
+                }
+            }
+            ShellDialect::PowerShell => {
+                if is_plain(s) {
+                    s.to_string()
+                } else {
+                    // Curly quotes are quote characters in PowerShell too
+                    let escaped: String = s
+                        .chars()
+                        .flat_map(|c| match c {
+                            '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' => {
+                                vec![c, c]
+                            }
+                            _ => vec![c],
+                        })
+                        .collect();
+                    format!("'{escaped}'")
+                }
+            }
+            // Windows paths cannot contain double quotes
+            ShellDialect::Cmd => {
+                if is_plain(s) {
+                    s.to_string()
+                } else {
+                    format!("\"{s}\"")
+                }
+            }
+        }
+    }
+
+    fn path(self, path: &Path) -> String {
+        self.quote(&path.display().to_string())
+    }
+
+    /// Move `src` to `dest`.
+    pub fn mv(self, src: &str, dest: &str) -> String {
+        let (src, dest) = (self.quote(src), self.quote(dest));
+        match self.dialect {
+            ShellDialect::Posix | ShellDialect::Fish => format!("mv {src} {dest}"),
+            ShellDialect::PowerShell => format!("Move-Item -LiteralPath {src} -Destination {dest}"),
+            ShellDialect::Cmd => format!("move {src} {dest}"),
+        }
+    }
+
+    /// Create a symlink at `link` pointing to `target`.
+    pub fn symlink(self, target: &Path, link: &Path) -> String {
+        let (quoted_target, quoted_link) = (self.path(target), self.path(link));
+        match self.dialect {
+            ShellDialect::Posix | ShellDialect::Fish => {
+                format!("ln -s {quoted_target} {quoted_link}")
+            }
+            ShellDialect::PowerShell => format!(
+                "New-Item -ItemType SymbolicLink -Path {quoted_link} -Target {quoted_target}"
+            ),
+            ShellDialect::Cmd => {
+                let flag = if link.is_dir() { "/D " } else { "" };
+                format!("mklink {flag}{quoted_link} {quoted_target}")
+            }
+        }
+    }
+
+    /// Replace the symlink at `link` with one pointing to `target`.
+    pub fn replace_symlink(self, target: &Path, link: &Path) -> String {
+        let (quoted_target, quoted_link) = (self.path(target), self.path(link));
+        match self.dialect {
+            ShellDialect::Posix | ShellDialect::Fish => {
+                format!("ln -sfn {quoted_target} {quoted_link}")
+            }
+            ShellDialect::PowerShell => format!(
+                "New-Item -ItemType SymbolicLink -Path {quoted_link} -Target {quoted_target} -Force"
+            ),
+            ShellDialect::Cmd => {
+                if link.is_dir() {
+                    format!("rmdir {quoted_link} && mklink /D {quoted_link} {quoted_target}")
+                } else {
+                    format!("del {quoted_link} && mklink {quoted_link} {quoted_target}")
+                }
+            }
+        }
+    }
+
+    /// Create a hard link at `dest` to `existing`.
+    pub fn hardlink(self, existing: &Path, dest: &Path) -> String {
+        let (existing, dest) = (self.path(existing), self.path(dest));
+        match self.dialect {
+            ShellDialect::Posix | ShellDialect::Fish => format!("ln {existing} {dest}"),
+            ShellDialect::PowerShell => {
+                format!("New-Item -ItemType HardLink -Path {dest} -Target {existing}")
+            }
+            ShellDialect::Cmd => format!("mklink /H {dest} {existing}"),
+        }
+    }
+
+    /// Remove the file `path`.
+    pub fn rm(self, path: &str) -> String {
+        let path = self.quote(path);
+        match self.dialect {
+            ShellDialect::Posix | ShellDialect::Fish => format!("rm {path}"),
+            ShellDialect::PowerShell => format!("Remove-Item -LiteralPath {path}"),
+            ShellDialect::Cmd => format!("del {path}"),
+        }
+    }
+
+    /// Create `dir` and any missing parents.
+    pub fn mkdir(self, dir: &Path) -> String {
+        let dir = self.path(dir);
+        match self.dialect {
+            ShellDialect::Posix | ShellDialect::Fish => format!("mkdir -p {dir}"),
+            ShellDialect::PowerShell => format!("New-Item -ItemType Directory -Force -Path {dir}"),
+            // Command extensions (on by default) create intermediate directories
+            ShellDialect::Cmd => format!("mkdir {dir}"),
+        }
+    }
+}
+
+/// Shell-escape a string by wrapping it in single quotes and escaping embedded quotes.
+///
+/// This ensures paths with spaces or special characters can be safely copied to a shell,
+/// or written to an `--emit-script` script.
+fn posix_quote(s: &str) -> String {
+    // If string contains only characters no POSIX shell treats specially, return as-is
+    if !s.is_empty()
+        && s.chars().all(|c| {
+            c.is_alphanumeric()
+                || matches!(c, '/' | '.' | '_' | '-' | '+' | ':' | ',' | '=' | '@' | '%')
+        })
+    {
+        return s.to_string();
+    }
+
+    // Otherwise, wrap in single quotes and escape embedded single quotes
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Whether `s` needs no quoting in any supported shell.
+fn is_plain(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '/' | '\\' | '.' | '_' | '-' | ':' | '+'))
+        // A leading dash or colon could be read as a parameter / drive label
+        && !s.starts_with(['-', ':'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posix_quoting() {
+        let echo = Echo::new(ShellDialect::Posix, None);
+        assert_eq!(echo.mv("a b", "dest/"), "mv 'a b' dest/");
+        assert_eq!(echo.rm("it's"), r"rm 'it'\''s'");
+        assert_eq!(echo.quote("a;b&c"), "'a;b&c'");
+        assert_eq!(echo.quote("~user"), "'~user'");
+    }
+
+    #[test]
+    fn fish_escapes_backslashes_and_quotes() {
+        let echo = Echo::new(ShellDialect::Fish, None);
+        assert_eq!(echo.quote(r"it's a\b"), r"'it\'s a\\b'");
+        assert_eq!(echo.quote("plain/path.txt"), "plain/path.txt");
+    }
+
+    #[test]
+    fn powershell_commands() {
+        let echo = Echo::new(ShellDialect::PowerShell, None);
+        assert_eq!(
+            echo.mv("it's.txt", "dest"),
+            "Move-Item -LiteralPath 'it''s.txt' -Destination dest"
+        );
+        assert_eq!(
+            echo.symlink(Path::new("../dest/a"), Path::new("a")),
+            "New-Item -ItemType SymbolicLink -Path a -Target ../dest/a"
+        );
+    }
+
+    #[test]
+    fn cmd_uses_mklink() {
+        let echo = Echo::new(ShellDialect::Cmd, None);
+        assert_eq!(
+            echo.symlink(Path::new(r"..\dest\a b"), Path::new("missing-link")),
+            r#"mklink missing-link "..\dest\a b""#
+        );
+        assert_eq!(
+            echo.hardlink(Path::new("x"), Path::new("y")),
+            "mklink /H y x"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Error types for mvln operations.
 
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 /// Errors that can occur during mvln operations.
@@ -11,17 +12,58 @@ pub enum MvlnError {
     SourceNotFound { path: PathBuf },
 
     /// Cannot access source file or directory (permission denied, etc.).
-    #[error("cannot access source {path}: {reason}")]
-    SourceAccessError { path: PathBuf, reason: String },
+    #[error("cannot access source {path}: {source}")]
+    SourceAccessError { path: PathBuf, source: io::Error },
 
     /// Destination already exists and force flag not set.
     #[error("destination already exists: {path}")]
     DestinationExists { path: PathBuf },
 
+    /// The conflict handler chose to skip a move onto an existing
+    /// destination (see [`crate::conflict`]).
+    #[error("skipped {src}: destination already exists: {dest}")]
+    ConflictSkipped { src: PathBuf, dest: PathBuf },
+
+    /// The conflict handler chose to abort at an existing destination.
+    #[error("aborted at {src}: destination already exists: {dest}")]
+    ConflictAborted { src: PathBuf, dest: PathBuf },
+
+    /// The cross-device handler declined a move that would have been
+    /// done by copying (see [`crate::device`]). Nothing was moved.
+    #[error("skipped {src}: {dest} is on another filesystem")]
+    CrossDeviceDeclined { src: PathBuf, dest: PathBuf },
+
+    /// The move crosses filesystems and `--no-cross-device` forbids
+    /// copying (see [`crate::device`]). Nothing was moved.
+    #[error("cannot move {src} to {dest}: destination is on another filesystem")]
+    CrossDevice { src: PathBuf, dest: PathBuf },
+
+    /// A directory tree copied across filesystems holds a directory that
+    /// [`MoveOptions::skip_dirs`](crate::MoveOptions::skip_dirs) leaves in
+    /// place, so the source cannot be replaced by a symlink. Nothing was
+    /// moved.
+    #[error("cannot link {src}: {dir} is left out of the copy and stays in place")]
+    SkippedDirKept { src: PathBuf, dir: PathBuf },
+
+    /// A stow target path is occupied by something other than the
+    /// expected symlink.
+    #[error("stow target already exists: {path}")]
+    StowConflict { path: PathBuf },
+
     /// Source is a directory but --whole-dir flag not set.
     #[error("source is a directory: {path}")]
     IsDirectory { path: PathBuf },
 
+    /// Source is a symlink to a path that doesn't exist, and `--dangling`
+    /// asks not to move those.
+    #[error("source is a dangling symlink: {path} -> {target}")]
+    DanglingSymlink { path: PathBuf, target: PathBuf },
+
+    /// A file being moved is open for writing by another process, and
+    /// `--check-open` asks not to move those (see [`crate::open_files`]).
+    #[error("{path} is open for writing by {process}")]
+    OpenForWriting { path: PathBuf, process: String },
+
     /// Source and destination are the same path.
     #[error("source and destination are the same: {path}")]
     SameSourceAndDest { path: PathBuf },
@@ -40,41 +82,76 @@ pub enum MvlnError {
     },
 
     /// Failed to move file.
-    #[error("failed to move {src} to {dest}: {reason}")]
+    #[error("failed to move {src} to {dest}: {source}")]
     MoveFailed {
         src: PathBuf,
         dest: PathBuf,
-        reason: String,
+        source: io::Error,
     },
 
     /// Failed to copy file (cross-filesystem).
-    #[error("failed to copy {src} to {dest}: {reason}")]
+    #[error("failed to copy {src} to {dest}: {source}")]
     CopyFailed {
         src: PathBuf,
         dest: PathBuf,
-        reason: String,
+        source: io::Error,
     },
 
     /// File copied but failed to remove source.
     /// This is a warning state - file exists in both locations.
-    #[error("copied but failed to remove source {src}: {reason}")]
+    #[error("copied but failed to remove source {src}: {source}")]
     RemoveFailed {
         src: PathBuf,
         dest: PathBuf,
-        reason: String,
+        source: io::Error,
+    },
+
+    /// A directory tree nests deeper than
+    /// [`MAX_COPY_DEPTH`](crate::operation::MAX_COPY_DEPTH) levels and
+    /// cannot be copied across filesystems.
+    #[error("directory tree too deep to copy: {path} (more than {limit} levels)")]
+    TooDeep { path: PathBuf, limit: usize },
+
+    /// A directory met while copying a tree is one of its own ancestors
+    /// (e.g. through a bind mount), so copying it would never end.
+    #[error("directory loop while copying: {path} is {ancestor}")]
+    FilesystemLoop { path: PathBuf, ancestor: PathBuf },
+
+    /// A component of a destination path is longer than the destination
+    /// filesystem allows (see [`crate::limits`]). Nothing was moved.
+    #[error("file name too long for the destination filesystem: {name} ({len} > {limit} bytes) in {path}")]
+    NameTooLong {
+        path: PathBuf,
+        name: String,
+        len: usize,
+        limit: usize,
+    },
+
+    /// A destination path is longer than the OS accepts (see
+    /// [`crate::limits`]). Nothing was moved.
+    #[error("destination path too long: {path} ({len} > {limit} bytes)")]
+    PathTooLong {
+        path: PathBuf,
+        len: usize,
+        limit: usize,
     },
 
+    /// Failed to apply `--chmod` (see [`crate::chmod`]) to a moved file.
+    /// The move itself is complete.
+    #[error("failed to change mode of {path}: {source}")]
+    ChmodFailed { path: PathBuf, source: io::Error },
+
     /// Failed to create symlink.
-    #[error("failed to create symlink {link} -> {target}: {reason}")]
+    #[error("failed to create symlink {link} -> {target}: {source}")]
     SymlinkFailed {
         link: PathBuf,
         target: PathBuf,
-        reason: String,
+        source: io::Error,
     },
 
     /// Failed to create destination directory.
-    #[error("failed to create directory {path}: {reason}")]
-    CreateDirFailed { path: PathBuf, reason: String },
+    #[error("failed to create directory {path}: {source}")]
+    CreateDirFailed { path: PathBuf, source: io::Error },
 
     /// Invalid destination path.
     #[error("invalid destination: {reason}")]
@@ -88,13 +165,398 @@ pub enum MvlnError {
     #[error("glob expansion failed: {reason}")]
     GlobExpansionFailed { reason: String },
 
+    /// A glob pattern matched no files.
+    #[error("no files matched pattern: {pattern}")]
+    NoMatches { pattern: String },
+
+    /// A glob pattern matched more paths than `--max-matches` allows.
+    #[error("pattern {pattern} matched more than {limit} paths")]
+    TooManyMatches { pattern: String, limit: usize },
+
     /// Batch operation failed with multiple errors.
+    ///
+    /// `data_at_risk` counts the failures that left a file split (see
+    /// [`MvlnError::is_data_at_risk`]).
     #[error("{count} operation(s) failed")]
-    BatchOperationFailed { count: usize },
+    BatchOperationFailed { count: usize, data_at_risk: usize },
+
+    /// Another mvln run holds the lock for this destination root.
+    #[error("another mvln run is using {root} (use --lock-timeout to wait or --no-lock to skip)")]
+    LockHeld { root: PathBuf },
+
+    /// Failed to create or lock the lock file.
+    #[error("failed to acquire lock {path}: {source}")]
+    LockFailed { path: PathBuf, source: io::Error },
+
+    /// Configuration file could not be read or is invalid.
+    #[error("invalid configuration {path}: {reason}")]
+    ConfigError { path: PathBuf, reason: String },
+
+    /// The requested profile is not defined in the configuration file.
+    #[error("profile not found: {name}")]
+    ProfileNotFound { name: String },
+
+    /// The requested preset is neither built in nor defined in the
+    /// configuration file. `presets` lists the ones that exist.
+    #[error("preset not found: {name}")]
+    PresetNotFound { name: String, presets: String },
+
+    /// The journal could not be read or written.
+    #[error("journal error {path}: {source}")]
+    JournalError { path: PathBuf, source: io::Error },
+
+    /// The record of a background job could not be read or written.
+    #[error("background job error {path}: {source}")]
+    JobError { path: PathBuf, source: io::Error },
+
+    /// No background job has this number.
+    #[error("background job not found: {id}")]
+    JobNotFound { id: u64 },
+
+    /// The `--emit-script` replay script could not be written.
+    #[error("failed to write script {path}: {source}")]
+    ScriptWriteFailed { path: PathBuf, source: io::Error },
+
+    /// A moved file could not be hashed for the `--write-sums` manifest, or
+    /// the manifest could not be written (see [`crate::sums`]).
+    #[error("failed to write checksums for {path}: {source}")]
+    SumsFailed { path: PathBuf, source: io::Error },
+
+    /// The `--log-file` run log could not be opened.
+    #[error("failed to open log file {path}: {source}")]
+    LogFileFailed { path: PathBuf, source: io::Error },
+
+    /// A `--pre-hook` or `--post-hook` command failed.
+    #[error("hook failed: {command}: {reason}")]
+    HookFailed { command: String, reason: String },
+
+    /// Moving to an SSH destination failed before the source was removed:
+    /// the upload failed or did not match, or the destination exists.
+    #[error("failed to move {src} to {dest}: {reason}")]
+    RemoteFailed {
+        src: PathBuf,
+        dest: String,
+        reason: String,
+    },
+
+    /// The source was moved to an SSH destination, but the stub or symlink
+    /// meant to take its place could not be created.
+    #[error("moved {src} to {url} but failed to leave a placeholder: {source}")]
+    RemoteStubFailed {
+        src: PathBuf,
+        url: String,
+        source: io::Error,
+    },
 
     /// I/O error wrapper.
     #[error("I/O error: {0}")]
-    Io(#[from] std::io::Error),
+    Io(#[from] io::Error),
+}
+
+impl MvlnError {
+    /// A stable identifier for the kind of error, e.g. `mvln::dest_exists`.
+    ///
+    /// Shown with the CLI's diagnostics so errors can be looked up and
+    /// matched in scripts independently of the message language.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::SourceNotFound { .. } => "mvln::source_not_found",
+            Self::SourceAccessError { .. } => "mvln::source_access",
+            Self::DestinationExists { .. } => "mvln::dest_exists",
+            Self::ConflictSkipped { .. } => "mvln::conflict_skipped",
+            Self::ConflictAborted { .. } => "mvln::conflict_aborted",
+            Self::CrossDeviceDeclined { .. } => "mvln::cross_device_declined",
+            Self::CrossDevice { .. } => "mvln::cross_device",
+            Self::SkippedDirKept { .. } => "mvln::skipped_dir_kept",
+            Self::StowConflict { .. } => "mvln::stow_conflict",
+            Self::IsDirectory { .. } => "mvln::is_directory",
+            Self::DanglingSymlink { .. } => "mvln::dangling_symlink",
+            Self::OpenForWriting { .. } => "mvln::open_for_writing",
+            Self::SameSourceAndDest { .. } => "mvln::same_path",
+            Self::DestinationInsideSource { .. } => "mvln::dest_inside_source",
+            Self::TypeMismatch { .. } => "mvln::type_mismatch",
+            Self::MoveFailed { .. } => "mvln::move_failed",
+            Self::CopyFailed { .. } => "mvln::copy_failed",
+            Self::RemoveFailed { .. } => "mvln::remove_failed",
+            Self::TooDeep { .. } => "mvln::too_deep",
+            Self::FilesystemLoop { .. } => "mvln::filesystem_loop",
+            Self::NameTooLong { .. } => "mvln::name_too_long",
+            Self::PathTooLong { .. } => "mvln::path_too_long",
+            Self::ChmodFailed { .. } => "mvln::chmod_failed",
+            Self::SymlinkFailed { .. } => "mvln::symlink_failed",
+            Self::CreateDirFailed { .. } => "mvln::create_dir_failed",
+            Self::InvalidDestination { .. } => "mvln::invalid_destination",
+            Self::InvalidPath { .. } => "mvln::invalid_path",
+            Self::GlobExpansionFailed { .. } => "mvln::glob_failed",
+            Self::NoMatches { .. } => "mvln::no_matches",
+            Self::TooManyMatches { .. } => "mvln::too_many_matches",
+            Self::BatchOperationFailed { .. } => "mvln::batch_failed",
+            Self::LockHeld { .. } => "mvln::lock_held",
+            Self::LockFailed { .. } => "mvln::lock_failed",
+            Self::ConfigError { .. } => "mvln::config",
+            Self::ProfileNotFound { .. } => "mvln::profile_not_found",
+            Self::PresetNotFound { .. } => "mvln::preset_not_found",
+            Self::JournalError { .. } => "mvln::journal",
+            Self::JobError { .. } => "mvln::job",
+            Self::JobNotFound { .. } => "mvln::job_not_found",
+            Self::ScriptWriteFailed { .. } => "mvln::script_write_failed",
+            Self::SumsFailed { .. } => "mvln::sums_failed",
+            Self::LogFileFailed { .. } => "mvln::log_file_failed",
+            Self::HookFailed { .. } => "mvln::hook_failed",
+            Self::RemoteFailed { .. } => "mvln::remote_failed",
+            Self::RemoteStubFailed { .. } => "mvln::remote_stub_failed",
+            Self::Io(_) => "mvln::io",
+        }
+    }
+
+    /// The paths the error is about, in the order they appear in the message.
+    #[must_use]
+    pub fn paths(&self) -> Vec<&Path> {
+        match self {
+            Self::SourceNotFound { path }
+            | Self::SourceAccessError { path, .. }
+            | Self::DestinationExists { path }
+            | Self::StowConflict { path }
+            | Self::IsDirectory { path }
+            | Self::OpenForWriting { path, .. }
+            | Self::SameSourceAndDest { path }
+            | Self::CreateDirFailed { path, .. }
+            | Self::ChmodFailed { path, .. }
+            | Self::TooDeep { path, .. }
+            | Self::NameTooLong { path, .. }
+            | Self::PathTooLong { path, .. }
+            | Self::InvalidPath { path, .. }
+            | Self::LockFailed { path, .. }
+            | Self::ConfigError { path, .. }
+            | Self::JournalError { path, .. }
+            | Self::JobError { path, .. }
+            | Self::ScriptWriteFailed { path, .. }
+            | Self::SumsFailed { path, .. }
+            | Self::LogFileFailed { path, .. } => vec![path],
+            Self::RemoteFailed { src, .. } | Self::RemoteStubFailed { src, .. } => vec![src],
+            Self::DestinationInsideSource { src, dest }
+            | Self::ConflictSkipped { src, dest }
+            | Self::ConflictAborted { src, dest }
+            | Self::CrossDeviceDeclined { src, dest }
+            | Self::CrossDevice { src, dest }
+            | Self::TypeMismatch { src, dest, .. }
+            | Self::MoveFailed { src, dest, .. }
+            | Self::CopyFailed { src, dest, .. }
+            | Self::RemoveFailed { src, dest, .. } => vec![src, dest],
+            Self::SymlinkFailed { link, target, .. } => vec![link, target],
+            Self::FilesystemLoop { path, ancestor } => vec![path, ancestor],
+            Self::SkippedDirKept { src, dir } => vec![src, dir],
+            Self::DanglingSymlink { path, target } => vec![path, target],
+            Self::LockHeld { root } => vec![root],
+            Self::InvalidDestination { .. }
+            | Self::GlobExpansionFailed { .. }
+            | Self::NoMatches { .. }
+            | Self::TooManyMatches { .. }
+            | Self::BatchOperationFailed { .. }
+            | Self::ProfileNotFound { .. }
+            | Self::PresetNotFound { .. }
+            | Self::JobNotFound { .. }
+            | Self::HookFailed { .. }
+            | Self::Io(_) => Vec::new(),
+        }
+    }
+
+    /// Whether this is a warning rather than a failure: the operation
+    /// completed, but left something for the user to clean up.
+    #[must_use]
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Self::RemoveFailed { .. })
+    }
+
+    /// How much of the operation happened before the error, and so whether
+    /// the user needs to act on what is left on disk.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mvln::error::{MvlnError, Severity};
+    ///
+    /// let error = MvlnError::DestinationExists { path: "b.txt".into() };
+    /// assert_eq!(error.severity(), Severity::Clean);
+    /// assert!(!error.is_data_at_risk());
+    ///
+    /// // The file was moved, but no symlink points to it from the source
+    /// let error = MvlnError::SymlinkFailed {
+    ///     link: "a.txt".into(),
+    ///     target: "/archive/a.txt".into(),
+    ///     source: std::io::ErrorKind::PermissionDenied.into(),
+    /// };
+    /// assert_eq!(error.severity(), Severity::Split);
+    /// assert!(error.is_data_at_risk());
+    /// ```
+    #[must_use]
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::SymlinkFailed { .. }
+            | Self::RemoveFailed { .. }
+            | Self::RemoteStubFailed { .. } => Severity::Split,
+            Self::BatchOperationFailed { data_at_risk, .. } if *data_at_risk > 0 => Severity::Split,
+            Self::MoveFailed { .. }
+            | Self::CopyFailed { .. }
+            | Self::TooDeep { .. }
+            | Self::FilesystemLoop { .. }
+            | Self::ChmodFailed { .. }
+            | Self::BatchOperationFailed { .. }
+            | Self::Io(_) => Severity::Partial,
+            Self::SourceNotFound { .. }
+            | Self::SourceAccessError { .. }
+            | Self::DestinationExists { .. }
+            | Self::ConflictSkipped { .. }
+            | Self::ConflictAborted { .. }
+            | Self::CrossDeviceDeclined { .. }
+            | Self::CrossDevice { .. }
+            | Self::SkippedDirKept { .. }
+            | Self::StowConflict { .. }
+            | Self::IsDirectory { .. }
+            | Self::DanglingSymlink { .. }
+            | Self::OpenForWriting { .. }
+            | Self::SameSourceAndDest { .. }
+            | Self::DestinationInsideSource { .. }
+            | Self::TypeMismatch { .. }
+            | Self::NameTooLong { .. }
+            | Self::PathTooLong { .. }
+            | Self::CreateDirFailed { .. }
+            | Self::InvalidDestination { .. }
+            | Self::InvalidPath { .. }
+            | Self::GlobExpansionFailed { .. }
+            | Self::NoMatches { .. }
+            | Self::TooManyMatches { .. }
+            | Self::LockHeld { .. }
+            | Self::LockFailed { .. }
+            | Self::ConfigError { .. }
+            | Self::ProfileNotFound { .. }
+            | Self::PresetNotFound { .. }
+            | Self::JournalError { .. }
+            | Self::JobError { .. }
+            | Self::JobNotFound { .. }
+            | Self::ScriptWriteFailed { .. }
+            | Self::SumsFailed { .. }
+            | Self::LogFileFailed { .. }
+            | Self::HookFailed { .. }
+            | Self::RemoteFailed { .. } => Severity::Clean,
+        }
+    }
+
+    /// Whether a file was left split between its source and destination,
+    /// so recovery instructions should be shown (see [`Severity::Split`]).
+    #[must_use]
+    pub fn is_data_at_risk(&self) -> bool {
+        self.severity() == Severity::Split
+    }
+
+    /// The underlying I/O error, if the error was caused by one.
+    ///
+    /// This is also the error's [`source`](std::error::Error::source).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::ErrorKind;
+    /// use mvln::error::MvlnError;
+    ///
+    /// let error = MvlnError::CreateDirFailed {
+    ///     path: "/archive".into(),
+    ///     source: ErrorKind::PermissionDenied.into(),
+    /// };
+    /// let kind = error.io_error().map(std::io::Error::kind);
+    /// assert_eq!(kind, Some(ErrorKind::PermissionDenied));
+    /// ```
+    #[must_use]
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Self::SourceAccessError { source, .. }
+            | Self::MoveFailed { source, .. }
+            | Self::CopyFailed { source, .. }
+            | Self::RemoveFailed { source, .. }
+            | Self::SymlinkFailed { source, .. }
+            | Self::CreateDirFailed { source, .. }
+            | Self::ChmodFailed { source, .. }
+            | Self::LockFailed { source, .. }
+            | Self::JournalError { source, .. }
+            | Self::JobError { source, .. }
+            | Self::ScriptWriteFailed { source, .. }
+            | Self::SumsFailed { source, .. }
+            | Self::LogFileFailed { source, .. }
+            | Self::RemoteStubFailed { source, .. }
+            | Self::Io(source) => Some(source),
+            _ => None,
+        }
+    }
+
+    /// The OS error code (`errno` on Unix) of the underlying I/O error, if
+    /// there is one.
+    #[must_use]
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.io_error().and_then(io::Error::raw_os_error)
+    }
+}
+
+/// What an error left behind on disk, from [`MvlnError::severity`].
+///
+/// Variants are ordered from least to most serious.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Severity {
+    /// Nothing was moved: the error was found before touching any file,
+    /// or concerned only mvln's own bookkeeping (journal, lock, logs).
+    /// Fix the cause and rerun.
+    Clean,
+    /// A file operation failed part-way, e.g. an interrupted cross-device
+    /// copy that left a partial destination next to the intact source.
+    /// The source is safe, but the destination may need cleaning up.
+    Partial,
+    /// The file is split between two places: moved but not linked back
+    /// ([`MvlnError::SymlinkFailed`]), or copied but the source not removed
+    /// ([`MvlnError::RemoveFailed`]). Recovery is needed to restore a
+    /// consistent state.
+    Split,
+}
+
+/// A plain-data summary of an [`MvlnError`], for reporting errors to other
+/// tools (with the `serde` feature, as JSON or any other serde format).
+///
+/// # Examples
+///
+/// ```
+/// use mvln::error::{ErrorSummary, MvlnError};
+///
+/// let error = MvlnError::DestinationExists { path: "b.txt".into() };
+/// let summary = ErrorSummary::from(&error);
+/// assert_eq!(summary.code, "mvln::dest_exists");
+/// assert_eq!(summary.paths, vec![std::path::PathBuf::from("b.txt")]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorSummary {
+    /// The error's [code](MvlnError::code).
+    pub code: String,
+    /// The English message (the error's `Display` output).
+    pub message: String,
+    /// The paths involved (see [`MvlnError::paths`]).
+    pub paths: Vec<PathBuf>,
+    /// Whether the error is only a [warning](MvlnError::is_warning).
+    pub warning: bool,
+    /// What the error left behind (see [`MvlnError::severity`]).
+    pub severity: Severity,
+}
+
+impl From<&MvlnError> for ErrorSummary {
+    fn from(error: &MvlnError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.to_string(),
+            paths: error.paths().into_iter().map(Path::to_path_buf).collect(),
+            warning: error.is_warning(),
+            severity: error.severity(),
+        }
+    }
 }
 
 /// Result type alias for mvln operations.
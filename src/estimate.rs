@@ -0,0 +1,214 @@
+//! Size and free-space estimates for a [`MovePlan`].
+//!
+//! A dry run can't know for sure how each move will go, but it can tell
+//! which moves stay on one filesystem (an instant `rename`) and which cross
+//! to another (a full copy followed by a delete), how many bytes that is,
+//! and what it does to the free space of every filesystem involved.
+//!
+//! Filesystems are told apart by device ID (see [`crate::device`]), which
+//! is only available on Unix; elsewhere every move is assumed to be a rename and free space is
+//! unknown.
+
+use std::path::{Path, PathBuf};
+
+use crate::device::Devices;
+use crate::plan::MovePlan;
+use crate::size::disk_usage;
+
+/// The expected cost of executing a plan.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Estimate {
+    /// Total size of all sources, in bytes.
+    pub bytes: u64,
+    /// Moves within one filesystem, done with a `rename`.
+    pub renames: usize,
+    /// Moves across filesystems, done by copying and deleting.
+    pub copies: usize,
+    /// Bytes to be copied across filesystems.
+    pub copy_bytes: u64,
+    /// Every filesystem a source or destination is on, in order of first
+    /// appearance.
+    pub filesystems: Vec<FilesystemDelta>,
+}
+
+/// The effect of a plan on one filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FilesystemDelta {
+    /// The first source or destination directory seen on the filesystem.
+    pub path: PathBuf,
+    /// Bytes currently available to unprivileged users, if known.
+    pub available: Option<u64>,
+    /// Change in free bytes once the plan has run: positive where sources
+    /// are copied away, negative where they are copied to.
+    pub delta: i64,
+}
+
+impl FilesystemDelta {
+    /// Bytes available once the plan has run, if known. Negative results
+    /// (the plan does not fit) are clamped to zero.
+    #[must_use]
+    pub fn available_after(&self) -> Option<u64> {
+        self.available
+            .map(|available| available.saturating_add_signed(self.delta))
+    }
+
+    /// Whether the plan needs more space than is available.
+    #[must_use]
+    pub fn is_short(&self) -> bool {
+        self.available
+            .is_some_and(|available| self.delta < 0 && available < self.delta.unsigned_abs())
+    }
+}
+
+impl Estimate {
+    /// Estimate `plan` by looking at the sources and destinations on disk.
+    ///
+    /// Sizes that cannot be read count as zero; a source whose filesystem
+    /// cannot be determined counts as a rename.
+    #[must_use]
+    pub fn of(plan: &MovePlan) -> Self {
+        let mut estimate = Self::default();
+        let mut cache = Devices::new();
+        let mut devices: Vec<u64> = Vec::new();
+        for planned in plan {
+            let size = disk_usage(&planned.source).unwrap_or(0);
+            estimate.bytes = estimate.bytes.saturating_add(size);
+
+            let source_dir = parent_dir(&planned.source);
+            let dest_dir = if planned.dest.is_dir() {
+                planned.dest.as_path()
+            } else {
+                parent_dir(&planned.dest)
+            };
+            let from = cache
+                .of(source_dir)
+                .map(|device| estimate.filesystem(&mut devices, device, source_dir));
+            let to = existing_ancestor(dest_dir).and_then(|dir| {
+                let device = cache.of(&dir)?;
+                Some(estimate.filesystem(&mut devices, device, &dir))
+            });
+
+            match (from, to) {
+                (Some(from), Some(to)) if from != to => {
+                    estimate.copies += 1;
+                    estimate.copy_bytes = estimate.copy_bytes.saturating_add(size);
+                    let size = i64::try_from(size).unwrap_or(i64::MAX);
+                    let delta = &mut estimate.filesystems[from].delta;
+                    *delta = delta.saturating_add(size);
+                    let delta = &mut estimate.filesystems[to].delta;
+                    *delta = delta.saturating_sub(size);
+                }
+                _ => estimate.renames += 1,
+            }
+        }
+        estimate
+    }
+
+    /// Index of the filesystem with ID `device`, recording it (as `dir`)
+    /// the first time it is seen.
+    fn filesystem(&mut self, devices: &mut Vec<u64>, device: u64, dir: &Path) -> usize {
+        if let Some(index) = devices.iter().position(|&d| d == device) {
+            return index;
+        }
+        devices.push(device);
+        self.filesystems.push(FilesystemDelta {
+            path: dir.to_path_buf(),
+            available: available_space(dir),
+            delta: 0,
+        });
+        self.filesystems.len() - 1
+    }
+}
+
+/// The directory containing `path`; `.` for a bare file name, whose parent
+/// is empty.
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// The closest ancestor of `path` (or `path` itself) that exists; that is
+/// where missing destination directories would be created.
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    let path = if path.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        path
+    };
+    path.ancestors()
+        .find(|dir| dir.symlink_metadata().is_ok())
+        .map(Path::to_path_buf)
+}
+
+#[cfg(unix)]
+fn available_space(dir: &Path) -> Option<u64> {
+    let stat = rustix::fs::statvfs(dir).ok()?;
+    Some(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
+
+#[cfg(not(unix))]
+fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_within_one_filesystem_are_renames() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut plan = MovePlan::new();
+        for (name, size) in [("a", 100), ("b", 50)] {
+            let source = tmp.path().join(name);
+            std::fs::write(&source, vec![0u8; size]).unwrap();
+            plan.push(source, tmp.path().join("missing/dest"));
+        }
+
+        let estimate = Estimate::of(&plan);
+        assert_eq!(estimate.bytes, 150);
+        assert_eq!(estimate.renames, 2);
+        assert_eq!(estimate.copies, 0);
+        assert_eq!(estimate.copy_bytes, 0);
+        if cfg!(unix) {
+            assert_eq!(estimate.filesystems.len(), 1);
+            assert_eq!(estimate.filesystems[0].delta, 0);
+            assert!(estimate.filesystems[0].available.is_some());
+        }
+    }
+
+    #[test]
+    fn bare_file_names_are_in_the_current_directory() {
+        let mut plan = MovePlan::new();
+        plan.push("Cargo.toml", "Cargo.toml.moved");
+
+        let estimate = Estimate::of(&plan);
+        assert!(estimate.bytes > 0);
+        assert_eq!(estimate.renames, 1);
+        if cfg!(unix) {
+            assert_eq!(estimate.filesystems.len(), 1);
+            assert_eq!(estimate.filesystems[0].path, Path::new("."));
+            assert!(estimate.filesystems[0].available.is_some());
+        }
+    }
+
+    #[test]
+    fn available_after_applies_the_delta() {
+        let mut fs = FilesystemDelta {
+            path: PathBuf::from("/"),
+            available: Some(100),
+            delta: -30,
+        };
+        assert_eq!(fs.available_after(), Some(70));
+        assert!(!fs.is_short());
+        fs.delta = -130;
+        assert_eq!(fs.available_after(), Some(0));
+        assert!(fs.is_short());
+        fs.delta = 20;
+        assert_eq!(fs.available_after(), Some(120));
+    }
+}
@@ -0,0 +1,490 @@
+//! Capture dates of photos, for `--by-exif-date`.
+//!
+//! [`capture_date`] reads the EXIF `DateTimeOriginal` tag (or, failing
+//! that, `CreateDate` or the `DateTime` the file was last edited) from
+//! JPEG files, HEIF containers (HEIC, AVIF) and the TIFF-based raw
+//! formats most cameras write (DNG, CR2, NEF, ARW, ORF, RW2, PEF and
+//! others). Only the structures leading to the date are parsed, reading a
+//! few small blocks of the file, so routing a card full of raw files does
+//! not read their image data.
+//!
+//! EXIF dates carry no time zone: they are the camera clock's local time,
+//! and are used as they are, unlike modification times, which are taken
+//! in UTC (see [`crate::date`]).
+//!
+//! Requires the `exif` feature.
+
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::date::DateTime;
+
+/// `DateTimeOriginal`, when the picture was taken.
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+/// `CreateDate` (`DateTimeDigitized`), when it was stored.
+const TAG_CREATE_DATE: u16 = 0x9004;
+/// `DateTime`, when the file was last changed.
+const TAG_DATE_TIME: u16 = 0x0132;
+/// Offset of the EXIF sub-IFD.
+const TAG_EXIF_IFD: u16 = 0x8769;
+
+/// Most entries read from one IFD; real ones have a few dozen.
+const MAX_IFD_ENTRIES: u16 = 512;
+
+/// Largest HEIF `meta` box read; real ones are a few KiB.
+const MAX_META_SIZE: u64 = 1 << 20;
+
+/// When the photo at `path` was taken, according to its EXIF metadata.
+///
+/// `None` if the file is not a JPEG, HEIF or TIFF-based raw file, has no
+/// EXIF date, or cannot be read.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::exif::capture_date;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("notes.txt");
+/// std::fs::write(&path, "no photo").unwrap();
+/// assert_eq!(capture_date(&path), None);
+/// ```
+#[must_use]
+pub fn capture_date(path: &Path) -> Option<DateTime> {
+    let mut file = File::open(path).ok()?;
+    let mut magic = [0u8; 12];
+    file.read_exact(&mut magic).ok()?;
+    match magic {
+        [0xff, 0xd8, ..] => {
+            let exif = jpeg_exif(&mut file)?;
+            Tiff::open(Cursor::new(exif), 0)?.date()
+        }
+        [b'I', b'I', ..] | [b'M', b'M', ..] => Tiff::open(file, 0)?.date(),
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => {
+            let base = heif_exif(&mut file)?;
+            Tiff::open(file, base)?.date()
+        }
+        _ => None,
+    }
+}
+
+/// The TIFF structure in the EXIF segment (APP1) of a JPEG file, read
+/// after its start-of-image marker.
+fn jpeg_exif(file: &mut File) -> Option<Vec<u8>> {
+    file.seek(SeekFrom::Start(2)).ok()?;
+    loop {
+        let mut header = [0u8; 4];
+        file.read_exact(&mut header).ok()?;
+        let [0xff, marker, high, low] = header else {
+            return None;
+        };
+        // Metadata comes before the image data (start of scan)
+        if marker == 0xda || marker == 0xd9 {
+            return None;
+        }
+        let len = u16::from_be_bytes([high, low]).checked_sub(2)?;
+        if marker == 0xe1 {
+            let mut segment = vec![0u8; usize::from(len)];
+            file.read_exact(&mut segment).ok()?;
+            if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+                return Some(tiff.to_vec());
+            }
+        } else {
+            file.seek(SeekFrom::Current(i64::from(len))).ok()?;
+        }
+    }
+}
+
+/// File offset of the TIFF structure in the `Exif` item of a HEIF file.
+fn heif_exif(file: &mut File) -> Option<u64> {
+    // Find the top-level `meta` box
+    let mut offset = 0;
+    let (meta_start, meta_len) = loop {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let (kind, header, size) = box_header(file)?;
+        if &kind == b"meta" {
+            break (offset + header, size.checked_sub(header)?);
+        }
+        offset = offset.checked_add(size)?;
+    };
+    if meta_len > MAX_META_SIZE {
+        return None;
+    }
+    let mut meta = vec![0u8; usize::try_from(meta_len).ok()?];
+    file.seek(SeekFrom::Start(meta_start)).ok()?;
+    file.read_exact(&mut meta).ok()?;
+
+    // `meta` is a full box: skip its version and flags
+    let children = meta.get(4..)?;
+    let item = exif_item_id(find_box(children, *b"iinf")?)?;
+    let location = item_location(find_box(children, *b"iloc")?, item)?;
+
+    // The item starts with the offset of the TIFF header within it
+    file.seek(SeekFrom::Start(location)).ok()?;
+    let mut skip = [0u8; 4];
+    file.read_exact(&mut skip).ok()?;
+    location.checked_add(4 + u64::from(u32::from_be_bytes(skip)))
+}
+
+/// Type, header length and total size of the ISO BMFF box at the current
+/// position of `file`.
+fn box_header(file: &mut File) -> Option<([u8; 4], u64, u64)> {
+    let mut header = [0u8; 8];
+    file.read_exact(&mut header).ok()?;
+    let size = u64::from(u32::from_be_bytes(header[..4].try_into().ok()?));
+    let kind = header[4..].try_into().ok()?;
+    match size {
+        1 => {
+            let mut large = [0u8; 8];
+            file.read_exact(&mut large).ok()?;
+            Some((kind, 16, u64::from_be_bytes(large)))
+        }
+        // Extends to the end of the file, so nothing follows it
+        0 => Some((kind, 8, u64::MAX / 2)),
+        size if size >= 8 => Some((kind, 8, size)),
+        _ => None,
+    }
+}
+
+/// The contents of the first box of type `kind` among the boxes in `data`.
+fn find_box(mut data: &[u8], kind: [u8; 4]) -> Option<&[u8]> {
+    while data.len() >= 8 {
+        let size = usize::try_from(be(data, 0, 4)?).ok()?;
+        if size < 8 || size > data.len() {
+            return None;
+        }
+        if data[4..8] == kind {
+            return Some(&data[8..size]);
+        }
+        data = &data[size..];
+    }
+    None
+}
+
+/// The ID of the `Exif` item listed in the contents of an `iinf` box.
+fn exif_item_id(iinf: &[u8]) -> Option<u64> {
+    let version = *iinf.first()?;
+    let mut entries = iinf.get(if version == 0 { 6 } else { 8 }..)?;
+    while entries.len() >= 8 {
+        let size = usize::try_from(be(entries, 0, 4)?).ok()?;
+        if size < 8 || size > entries.len() {
+            return None;
+        }
+        let infe = &entries[8..size];
+        // Item info entries before version 2 have no item type
+        match infe.first()? {
+            2 if infe.get(8..12)? == b"Exif" => return be(infe, 4, 2),
+            3 if infe.get(10..14)? == b"Exif" => return be(infe, 4, 4),
+            _ => {}
+        }
+        entries = &entries[size..];
+    }
+    None
+}
+
+/// File offset of the first extent of `item`, from the contents of an
+/// `iloc` box.
+fn item_location(iloc: &[u8], item: u64) -> Option<u64> {
+    let version = *iloc.first()?;
+    let sizes = *iloc.get(4)?;
+    let (offset_size, length_size) = (usize::from(sizes >> 4), usize::from(sizes & 0xf));
+    let sizes = *iloc.get(5)?;
+    let base_offset_size = usize::from(sizes >> 4);
+    let index_size = if version == 0 {
+        0
+    } else {
+        usize::from(sizes & 0xf)
+    };
+    let id_size = if version < 2 { 2 } else { 4 };
+    let count = be(iloc, 6, id_size)?;
+    let mut at = 6 + id_size;
+    for _ in 0..count {
+        let id = be(iloc, at, id_size)?;
+        at += id_size;
+        if version > 0 {
+            // Only items stored at an offset in the file are supported
+            if be(iloc, at, 2)? & 0xf != 0 {
+                return None;
+            }
+            at += 2;
+        }
+        let base = be(iloc, at + 2, base_offset_size)?;
+        at += 2 + base_offset_size;
+        let extents = be(iloc, at, 2)?;
+        at += 2;
+        if id == item && extents > 0 {
+            return base.checked_add(be(iloc, at + index_size, offset_size)?);
+        }
+        let extent_size = index_size + offset_size + length_size;
+        at += usize::try_from(extents).ok()? * extent_size;
+    }
+    None
+}
+
+/// The big-endian number of `len` bytes (at most 8) at `at` in `data`;
+/// zero for a length of 0.
+fn be(data: &[u8], at: usize, len: usize) -> Option<u64> {
+    let bytes = data.get(at..at.checked_add(len)?)?;
+    if len > 8 {
+        return None;
+    }
+    Some(bytes.iter().fold(0, |n, &b| n << 8 | u64::from(b)))
+}
+
+/// A TIFF structure (the layout of EXIF data) starting at `base` in
+/// `reader`.
+struct Tiff<R> {
+    reader: R,
+    base: u64,
+    big_endian: bool,
+    ifd0: u32,
+}
+
+/// An IFD entry: tag, count, and the value or the offset of the value.
+type IfdEntry = (u16, u32, [u8; 4]);
+
+impl<R: Read + Seek> Tiff<R> {
+    fn open(mut reader: R, base: u64) -> Option<Self> {
+        reader.seek(SeekFrom::Start(base)).ok()?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header).ok()?;
+        let big_endian = match &header[..2] {
+            b"MM" => true,
+            b"II" => false,
+            _ => return None,
+        };
+        let mut tiff = Self {
+            reader,
+            base,
+            big_endian,
+            ifd0: 0,
+        };
+        tiff.ifd0 = tiff.u32(header[4..].try_into().ok()?);
+        Some(tiff)
+    }
+
+    /// The best date in the file: the capture date, the digitized date,
+    /// or the date the file was changed.
+    fn date(mut self) -> Option<DateTime> {
+        let ifd0 = self.entries(self.ifd0)?;
+        let changed = ifd0.iter().find(|(tag, ..)| *tag == TAG_DATE_TIME);
+        let changed = changed.and_then(|&(_, count, value)| self.date_value(count, value));
+        let exif = ifd0
+            .iter()
+            .find(|(tag, ..)| *tag == TAG_EXIF_IFD)
+            .map(|&(_, _, value)| self.u32(value));
+        let exif = exif
+            .and_then(|offset| self.entries(offset))
+            .unwrap_or_default();
+        [TAG_DATE_TIME_ORIGINAL, TAG_CREATE_DATE]
+            .into_iter()
+            .filter_map(|wanted| exif.iter().find(|(tag, ..)| *tag == wanted))
+            .find_map(|&(_, count, value)| self.date_value(count, value))
+            .or(changed)
+    }
+
+    /// The entries of the IFD at `offset`.
+    fn entries(&mut self, offset: u32) -> Option<Vec<IfdEntry>> {
+        self.seek(offset)?;
+        let mut count = [0u8; 2];
+        self.reader.read_exact(&mut count).ok()?;
+        let count = self.u16(count).min(MAX_IFD_ENTRIES);
+        let mut entries = vec![0u8; usize::from(count) * 12];
+        self.reader.read_exact(&mut entries).ok()?;
+        entries
+            .chunks_exact(12)
+            .map(|entry| {
+                let tag = self.u16(entry[..2].try_into().ok()?);
+                let count = self.u32(entry[4..8].try_into().ok()?);
+                Some((tag, count, entry[8..].try_into().ok()?))
+            })
+            .collect()
+    }
+
+    /// The date in an ASCII value of `count` bytes (`2024:06:01 12:30:00`
+    /// and a terminating NUL).
+    fn date_value(&mut self, count: u32, value: [u8; 4]) -> Option<DateTime> {
+        if !(19..=64).contains(&count) {
+            return None;
+        }
+        self.seek(self.u32(value))?;
+        let mut text = vec![0u8; usize::try_from(count).ok()?];
+        self.reader.read_exact(&mut text).ok()?;
+        parse_date(text.get(..19)?)
+    }
+
+    fn seek(&mut self, offset: u32) -> Option<()> {
+        let at = self.base.checked_add(u64::from(offset))?;
+        self.reader.seek(SeekFrom::Start(at)).ok().map(drop)
+    }
+
+    fn u16(&self, bytes: [u8; 2]) -> u16 {
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+
+    fn u32(&self, bytes: [u8; 4]) -> u32 {
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+}
+
+/// Parse an EXIF date, `YYYY:MM:DD HH:MM:SS`. Blank or zeroed dates,
+/// which cameras write when their clock is not set, are `None`.
+fn parse_date(text: &[u8]) -> Option<DateTime> {
+    let text = std::str::from_utf8(text).ok()?;
+    let field = |range: std::ops::Range<usize>| text.get(range)?.parse::<u32>().ok();
+    let date = DateTime {
+        year: i64::from(field(0..4)?),
+        month: field(5..7)?,
+        day: field(8..10)?,
+        hour: field(11..13)?,
+        minute: field(14..16)?,
+        second: field(17..19)?,
+    };
+    let valid = date.year > 0
+        && (1..=12).contains(&date.month)
+        && (1..=31).contains(&date.day)
+        && date.hour < 24
+        && date.minute < 60
+        && date.second < 61;
+    valid.then_some(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A TIFF structure with `DateTimeOriginal` in its EXIF IFD.
+    fn tiff(big_endian: bool, date: &str) -> Vec<u8> {
+        let u16 = |n: u16| {
+            if big_endian {
+                n.to_be_bytes()
+            } else {
+                n.to_le_bytes()
+            }
+        };
+        let u32 = |n: u32| {
+            if big_endian {
+                n.to_be_bytes()
+            } else {
+                n.to_le_bytes()
+            }
+        };
+        let mut bytes = Vec::new();
+        bytes.extend(if big_endian { b"MM" } else { b"II" });
+        bytes.extend(u16(42));
+        bytes.extend(u32(8));
+        // IFD0 at 8: one entry pointing at the EXIF IFD at 26
+        bytes.extend(u16(1));
+        bytes.extend(u16(TAG_EXIF_IFD));
+        bytes.extend(u16(4));
+        bytes.extend(u32(1));
+        bytes.extend(u32(26));
+        bytes.extend(u32(0));
+        // EXIF IFD at 26: the date, stored at 44
+        bytes.extend(u16(1));
+        bytes.extend(u16(TAG_DATE_TIME_ORIGINAL));
+        bytes.extend(u16(2));
+        bytes.extend(u32(20));
+        bytes.extend(u32(44));
+        bytes.extend(u32(0));
+        bytes.extend(date.as_bytes());
+        bytes.push(0);
+        bytes
+    }
+
+    fn expected() -> DateTime {
+        DateTime {
+            year: 2023,
+            month: 7,
+            day: 14,
+            hour: 18,
+            minute: 5,
+            second: 9,
+        }
+    }
+
+    #[test]
+    fn reads_jpeg_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let exif = [b"Exif\0\0".as_slice(), &tiff(false, "2023:07:14 18:05:09")].concat();
+        let mut jpeg = vec![0xff, 0xd8];
+        // An APP0 segment before the EXIF one
+        jpeg.extend([0xff, 0xe0, 0x00, 0x04, 0x00, 0x00]);
+        jpeg.extend([0xff, 0xe1]);
+        jpeg.extend(u16::try_from(exif.len() + 2).unwrap().to_be_bytes());
+        jpeg.extend(&exif);
+        jpeg.extend([0xff, 0xda, 0x00, 0x02, 0xff, 0xd9]);
+        let path = tmp.path().join("a.jpg");
+        fs::write(&path, jpeg).unwrap();
+        assert_eq!(capture_date(&path), Some(expected()));
+    }
+
+    #[test]
+    fn reads_tiff_based_raw_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("a.nef");
+        fs::write(&path, tiff(true, "2023:07:14 18:05:09")).unwrap();
+        assert_eq!(capture_date(&path), Some(expected()));
+
+        fs::write(&path, tiff(false, "0000:00:00 00:00:00")).unwrap();
+        assert_eq!(capture_date(&path), None);
+    }
+
+    #[test]
+    fn reads_heif_files() {
+        let full_box = |kind: &[u8; 4], body: &[u8]| {
+            let len = u32::try_from(body.len() + 12).unwrap();
+            [&len.to_be_bytes(), kind.as_slice(), &[0; 4], body].concat()
+        };
+        let ftyp = [&20u32.to_be_bytes(), b"ftypheic".as_slice(), &[0; 8]].concat();
+        let infe = |id: u8, kind: &[u8; 4]| {
+            let mut entry = full_box(b"infe", &[0, id, 0, 0]);
+            entry.extend(kind);
+            entry[3] += 4;
+            entry[8] = 2;
+            entry
+        };
+        let infos = [infe(1, b"hvc1"), infe(2, b"Exif")].concat();
+        let iinf = full_box(b"iinf", &[[0, 2].as_slice(), &infos].concat());
+        let exif_at = |meta_len: usize| ftyp.len() + meta_len + 8;
+        let iloc = |at: usize| {
+            let mut body = vec![0x44, 0x00, 0, 2];
+            for (id, offset) in [(1u16, 0u32), (2, u32::try_from(at).unwrap())] {
+                body.extend(id.to_be_bytes());
+                body.extend([0, 0, 0, 1]);
+                body.extend(offset.to_be_bytes());
+                body.extend(100u32.to_be_bytes());
+            }
+            full_box(b"iloc", &body)
+        };
+        let meta_len = full_box(b"meta", &[iinf.clone(), iloc(0)].concat()).len();
+        let meta = full_box(b"meta", &[iinf, iloc(exif_at(meta_len))].concat());
+        let item = [
+            &6u32.to_be_bytes(),
+            b"Exif\0\0".as_slice(),
+            &tiff(false, "2023:07:14 18:05:09"),
+        ]
+        .concat();
+        let mdat = [
+            &u32::try_from(item.len() + 8).unwrap().to_be_bytes(),
+            b"mdat".as_slice(),
+            &item,
+        ]
+        .concat();
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("a.heic");
+        fs::write(&path, [ftyp.as_slice(), &meta, &mdat].concat()).unwrap();
+        assert_eq!(capture_date(&path), Some(expected()));
+    }
+}
@@ -19,7 +19,7 @@
 //! assert!(!paths.is_empty());
 //! ```
 
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use thiserror::Error;
 
 /// Errors that can occur during glob expansion.
@@ -42,6 +42,149 @@ pub enum GlobError {
     /// No files matched the glob pattern.
     #[error("no files matched pattern: {pattern}")]
     NoMatches { pattern: String },
+
+    /// The glob pattern matched more paths than
+    /// [`GlobOptions::max_matches`] allows.
+    #[error("pattern '{pattern}' matched more than {limit} paths")]
+    TooManyMatches { pattern: String, limit: usize },
+}
+
+/// How many paths the `mvln` command lets one pattern expand to, unless
+/// `--max-matches` says otherwise.
+pub const DEFAULT_MAX_MATCHES: usize = 100_000;
+
+/// Options for [`expand_globs_with`].
+///
+/// # Examples
+///
+/// ```
+/// use mvln::glob_expand::GlobOptions;
+///
+/// let options = GlobOptions::builder().hidden(true).build();
+/// assert!(options.hidden);
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct GlobOptions {
+    /// Let wildcards match names starting with `.`. By default, as in most
+    /// shells, hidden files and directories only match a pattern that
+    /// spells out the leading dot, like `.*`. `.` and `..` themselves are
+    /// never matched.
+    pub hidden: bool,
+    /// Fail with [`GlobError::TooManyMatches`] rather than expand one
+    /// pattern to more paths than this, so an overly broad `**/*` is caught
+    /// before anything is queued. No limit by default.
+    pub max_matches: Option<usize>,
+}
+
+impl GlobOptions {
+    /// Start building options from the defaults.
+    pub fn builder() -> GlobOptionsBuilder {
+        GlobOptionsBuilder::default()
+    }
+
+    /// Whether `path`, found by globbing the pattern split into
+    /// `segments` (see [`segments`]), is kept. Each name of the path is
+    /// checked against the segment that matched it: `.` and `..` only
+    /// match when spelled out, hidden names only with
+    /// [`hidden`](Self::hidden) or a segment that spells out their dot.
+    ///
+    /// The glob crate's own `require_literal_leading_dot` also drops hidden
+    /// names the pattern spells out, so paths are filtered afterwards.
+    fn keeps(&self, segments: &[Option<glob::Pattern>], path: &Path) -> bool {
+        let names: Vec<_> = path
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        // Segments before the first `**` match one name each, and so do
+        // those after the last; the names in between are `**`'s
+        let (head, tail) = match (
+            segments.iter().position(Option::is_none),
+            segments.iter().rposition(Option::is_none),
+        ) {
+            (Some(first), Some(last)) => (first, segments.len() - last - 1),
+            _ => (segments.len(), 0),
+        };
+        // Fewer names than segments means a wildcard matched `.`, which
+        // paths leave out
+        let fits = if head == segments.len() {
+            names.len() == head
+        } else {
+            names.len() >= head + tail
+        };
+        if !fits {
+            return false;
+        }
+        let (head_names, rest) = names.split_at(head);
+        let (middle_names, tail_names) = rest.split_at(rest.len() - tail);
+        let middle = &segments[head..segments.len() - tail];
+        head_names
+            .iter()
+            .zip(&segments[..head])
+            .chain(tail_names.iter().zip(&segments[segments.len() - tail..]))
+            .all(|(name, segment)| self.shows(segment.as_ref(), name))
+            && middle_names
+                .iter()
+                .all(|name| middle.iter().any(|s| self.shows(s.as_ref(), name)))
+    }
+
+    /// Whether `name` is matched by `segment` of a pattern, or by `**` if
+    /// `None`, with the rules of [`keeps`](Self::keeps).
+    fn shows(&self, segment: Option<&glob::Pattern>, name: &str) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_leading_dot: !self.hidden,
+            ..glob::MatchOptions::new()
+        };
+        match segment {
+            Some(pattern) if pattern.as_str() == name => true,
+            _ if name == "." || name == ".." => false,
+            Some(pattern) => pattern.matches_with(name, options),
+            None => self.hidden || !name.starts_with('.'),
+        }
+    }
+}
+
+/// The `/`-separated segments of the glob `pattern`, without the `.` ones
+/// that the paths it finds leave out; `None` stands for `**`, which matches
+/// any number of names.
+fn segments(pattern: &str) -> Result<Vec<Option<glob::Pattern>>, glob::PatternError> {
+    Path::new(pattern)
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .map(|c| match c.as_os_str().to_string_lossy() {
+            name if name == "**" => Ok(None),
+            name => glob::Pattern::new(&name).map(Some),
+        })
+        .collect()
+}
+
+/// Builder for [`GlobOptions`], created by [`GlobOptions::builder`].
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct GlobOptionsBuilder {
+    options: GlobOptions,
+}
+
+impl GlobOptionsBuilder {
+    /// Let wildcards match hidden files.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.options.hidden = hidden;
+        self
+    }
+
+    /// Fail if one pattern matches more than `limit` paths.
+    pub fn max_matches(mut self, limit: usize) -> Self {
+        self.options.max_matches = Some(limit);
+        self
+    }
+
+    /// Finish building.
+    #[must_use]
+    pub fn build(self) -> GlobOptions {
+        self.options
+    }
 }
 
 /// Check if a string contains glob metacharacters.
@@ -69,7 +212,8 @@ pub fn is_glob_pattern(s: &str) -> bool {
 /// If a pattern contains glob metacharacters (`*`, `?`, `[`, `]`), it will be expanded
 /// to all matching paths. Otherwise, the path is returned as-is (even if it doesn't exist).
 ///
-/// Results are sorted alphabetically for consistent output.
+/// Results are sorted alphabetically for consistent output. Wildcards
+/// don't match hidden files (see [`GlobOptions::hidden`]).
 ///
 /// # Errors
 ///
@@ -77,6 +221,7 @@ pub fn is_glob_pattern(s: &str) -> bool {
 /// - The glob pattern syntax is invalid
 /// - Glob expansion fails due to I/O errors
 /// - A glob pattern matches no files
+/// - A glob pattern matches more paths than [`GlobOptions::max_matches`]
 ///
 /// # Examples
 ///
@@ -97,46 +242,247 @@ pub fn is_glob_pattern(s: &str) -> bool {
 /// # Ok::<(), mvln::glob_expand::GlobError>(())
 /// ```
 pub fn expand_globs(patterns: &[String]) -> Result<Vec<PathBuf>, GlobError> {
-    let mut all_paths = Vec::new();
+    expand_globs_with(Path::new(""), patterns, &GlobOptions::default())
+}
+
+/// Expand glob patterns relative to `base` instead of the current directory.
+///
+/// Relative patterns and paths are taken inside `base`, and the results are
+/// `base` joined with what they matched, so they stay valid whatever the
+/// current directory is; absolute patterns are expanded as they are.
+/// Glob metacharacters in `base` itself match literally. Otherwise this is
+/// [`expand_globs`].
+///
+/// # Errors
+///
+/// Returns [`GlobError`] as [`expand_globs`] does.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mvln::glob_expand::expand_globs_in;
+/// use std::path::Path;
+///
+/// let paths = expand_globs_in(Path::new("/var/log"), &["*.log".to_string()])?;
+/// // paths are like /var/log/syslog.log
+/// # Ok::<(), mvln::glob_expand::GlobError>(())
+/// ```
+pub fn expand_globs_in(base: &Path, patterns: &[String]) -> Result<Vec<PathBuf>, GlobError> {
+    expand_globs_with(base, patterns, &GlobOptions::default())
+}
+
+/// Expand glob patterns relative to `base`, as [`expand_globs_in`] does,
+/// with `options`.
+///
+/// # Errors
+///
+/// Returns [`GlobError`] as [`expand_globs`] does.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mvln::glob_expand::{expand_globs_with, GlobOptions};
+/// use std::path::Path;
+///
+/// // Dotfiles too
+/// let options = GlobOptions::builder().hidden(true).build();
+/// let paths = expand_globs_with(Path::new("/home/user"), &["*".to_string()], &options)?;
+/// # Ok::<(), mvln::glob_expand::GlobError>(())
+/// ```
+pub fn expand_globs_with(
+    base: &Path,
+    patterns: &[String],
+    options: &GlobOptions,
+) -> Result<Vec<PathBuf>, GlobError> {
+    let expansion = expand_globs_matches(base, patterns, options)?;
+    // Error if a glob pattern matched nothing
+    if let Some(index) = expansion.unmatched().next() {
+        return Err(GlobError::NoMatches {
+            pattern: patterns[index].clone(),
+        });
+    }
+    Ok(expansion.into_paths())
+}
+
+/// A path found by [`expand_globs_matches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Match {
+    /// The path, as [`expand_globs_in`] returns it.
+    pub path: PathBuf,
+    /// Index of the pattern that produced it; of the first one, if several
+    /// did.
+    pub pattern: usize,
+}
+
+/// The paths that patterns expanded to, with where each came from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Expansion {
+    /// The paths, sorted and without duplicates.
+    pub matches: Vec<Match>,
+    /// How many paths each pattern matched, by index, counting those other
+    /// patterns matched too. A regular path always counts as one.
+    pub counts: Vec<usize>,
+}
+
+impl Expansion {
+    /// Index of the pattern that produced `path`, if any did.
+    #[must_use]
+    pub fn pattern_of(&self, path: &Path) -> Option<usize> {
+        self.matches
+            .binary_search_by(|m| m.path.as_path().cmp(path))
+            .ok()
+            .map(|i| self.matches[i].pattern)
+    }
+
+    /// Indexes of the patterns that matched nothing.
+    pub fn unmatched(&self) -> impl Iterator<Item = usize> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count == 0)
+            .map(|(index, _)| index)
+    }
 
-    for pattern in patterns {
+    /// The paths alone, as [`expand_globs_with`] returns them.
+    #[must_use]
+    pub fn into_paths(self) -> Vec<PathBuf> {
+        self.matches.into_iter().map(|m| m.path).collect()
+    }
+}
+
+/// Expand glob patterns relative to `base` with `options`, keeping track of
+/// which pattern produced each path.
+///
+/// Unlike [`expand_globs_with`], a pattern that matches nothing is not an
+/// error: it has a count of zero in [`Expansion::counts`], so every such
+/// pattern can be reported.
+///
+/// # Errors
+///
+/// Returns [`GlobError`] if a pattern is invalid, cannot be expanded or
+/// matches more paths than [`GlobOptions::max_matches`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use mvln::glob_expand::{expand_globs_matches, GlobOptions};
+/// use std::path::Path;
+///
+/// let patterns = vec!["*.log".to_string(), "*.tmp".to_string()];
+/// let expansion = expand_globs_matches(Path::new("/var/log"), &patterns, &GlobOptions::default())?;
+/// for index in expansion.unmatched() {
+///     println!("pattern '{}' matched 0 files", patterns[index]);
+/// }
+/// # Ok::<(), mvln::glob_expand::GlobError>(())
+/// ```
+pub fn expand_globs_matches(
+    base: &Path,
+    patterns: &[String],
+    options: &GlobOptions,
+) -> Result<Expansion, GlobError> {
+    let mut matches = Vec::new();
+    let mut counts = Vec::with_capacity(patterns.len());
+    let escaped_base = PathBuf::from(glob::Pattern::escape(&base.to_string_lossy()));
+
+    for (index, pattern) in patterns.iter().enumerate() {
         if is_glob_pattern(pattern) {
             // Expand glob pattern
-            let glob_iter = glob::glob(pattern).map_err(|e| GlobError::InvalidPattern {
+            let rooted = escaped_base.join(pattern).to_string_lossy().into_owned();
+            let invalid = |e| GlobError::InvalidPattern {
                 pattern: pattern.clone(),
                 source: e,
-            })?;
+            };
+            let glob_iter = glob::glob(&rooted).map_err(invalid)?;
+            let segments = segments(&rooted).map_err(invalid)?;
 
-            let mut matched_paths = Vec::new();
+            let mut count = 0;
             for entry in glob_iter {
                 let path = entry.map_err(|e| GlobError::ExpansionFailed {
                     pattern: pattern.clone(),
                     source: e,
                 })?;
-                matched_paths.push(path);
-            }
-
-            // Error if glob pattern matched nothing
-            if matched_paths.is_empty() {
-                return Err(GlobError::NoMatches {
-                    pattern: pattern.clone(),
-                });
+                if options.keeps(&segments, &path) {
+                    matches.push(Match {
+                        path,
+                        pattern: index,
+                    });
+                    count += 1;
+                }
+                // Stop walking as soon as the limit is passed
+                if let Some(limit) = options.max_matches.filter(|&l| count > l) {
+                    return Err(GlobError::TooManyMatches {
+                        pattern: pattern.clone(),
+                        limit,
+                    });
+                }
             }
-
-            all_paths.extend(matched_paths);
+            counts.push(count);
         } else {
             // Regular path, add as-is (even if it doesn't exist)
             // Existence check will be done by the caller
-            all_paths.push(PathBuf::from(pattern));
+            matches.push(Match {
+                path: base.join(pattern),
+                pattern: index,
+            });
+            counts.push(1);
         }
     }
 
     // Sort for consistent output and deduplicate
     // (overlapping globs or duplicate explicit sources would cause issues)
-    all_paths.sort();
-    all_paths.dedup();
+    matches.sort_by(|a, b| a.path.cmp(&b.path).then(a.pattern.cmp(&b.pattern)));
+    matches.dedup_by(|later, first| later.path == first.path);
+
+    Ok(Expansion { matches, counts })
+}
+
+/// Remove paths matching any of the `exclude` glob patterns.
+///
+/// Each pattern is matched against the path's file name and against the
+/// whole path, so both `*.tmp` and `build/*` work as expected.
+///
+/// # Errors
+///
+/// Returns [`GlobError::InvalidPattern`] if an exclude pattern is invalid.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::glob_expand::filter_excluded;
+/// use std::path::PathBuf;
+///
+/// let paths = vec![PathBuf::from("a.txt"), PathBuf::from("a.tmp")];
+/// let kept = filter_excluded(paths, &["*.tmp".to_string()]).unwrap();
+/// assert_eq!(kept, vec![PathBuf::from("a.txt")]);
+/// ```
+pub fn filter_excluded(paths: Vec<PathBuf>, exclude: &[String]) -> Result<Vec<PathBuf>, GlobError> {
+    if exclude.is_empty() {
+        return Ok(paths);
+    }
 
-    Ok(all_paths)
+    let patterns = exclude
+        .iter()
+        .map(|p| {
+            glob::Pattern::new(p).map_err(|e| GlobError::InvalidPattern {
+                pattern: p.clone(),
+                source: e,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(paths
+        .into_iter()
+        .filter(|path| {
+            !patterns.iter().any(|pattern| {
+                pattern.matches_path(path)
+                    || path
+                        .file_name()
+                        .is_some_and(|name| pattern.matches(&name.to_string_lossy()))
+            })
+        })
+        .collect())
 }
 
 #[cfg(test)]
@@ -227,6 +573,144 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_expand_in_base_directory() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        // Metacharacters in the base match literally
+        let base = tmp.path().join("[2024]");
+        std::fs::create_dir(&base).unwrap();
+        std::fs::write(base.join("a.log"), "").unwrap();
+        std::fs::write(base.join("b.log"), "").unwrap();
+        std::fs::write(base.join("c.txt"), "").unwrap();
+
+        let patterns = vec!["*.log".to_string(), "missing.txt".to_string()];
+        let result = expand_globs_in(&base, &patterns).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                base.join("a.log"),
+                base.join("b.log"),
+                base.join("missing.txt")
+            ]
+        );
+        assert!(matches!(
+            expand_globs_in(&base, &["*.rs".to_string()]),
+            Err(GlobError::NoMatches { pattern }) if pattern == "*.rs"
+        ));
+    }
+
+    #[test]
+    fn test_hidden_files_need_option_or_leading_dot() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let base = tmp.path();
+        std::fs::write(base.join(".hidden"), "").unwrap();
+        std::fs::write(base.join("shown"), "").unwrap();
+        let star = vec!["*".to_string()];
+
+        let result = expand_globs_in(base, &star).unwrap();
+        assert_eq!(result, vec![base.join("shown")]);
+
+        // Never . or ..
+        let result = expand_globs_in(base, &[".*".to_string()]).unwrap();
+        assert_eq!(result, vec![base.join(".hidden")]);
+
+        let options = GlobOptions::builder().hidden(true).build();
+        let result = expand_globs_with(base, &star, &options).unwrap();
+        assert_eq!(result, vec![base.join(".hidden"), base.join("shown")]);
+    }
+
+    #[test]
+    fn test_hidden_files_are_checked_per_wildcard_segment() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let base = tmp.path();
+        std::fs::create_dir_all(base.join("sub/.cache")).unwrap();
+        std::fs::create_dir_all(base.join(".config")).unwrap();
+        for name in [
+            "a.txt",
+            ".b.txt",
+            "sub/c.txt",
+            "sub/.cache/d.txt",
+            ".config/e.txt",
+        ] {
+            std::fs::write(base.join(name), "").unwrap();
+        }
+        let expand = |pattern: &str| expand_globs_in(base, &[pattern.to_string()]).unwrap();
+
+        assert_eq!(expand("./*.txt"), vec![base.join("./a.txt")]);
+        assert_eq!(expand("sub/./*.txt"), vec![base.join("sub/./c.txt")]);
+        assert_eq!(
+            expand("**/*.txt"),
+            vec![base.join("a.txt"), base.join("sub/c.txt")]
+        );
+        // A spelled-out dot lets its own segment match hidden names only
+        assert_eq!(expand(".config/*.txt"), vec![base.join(".config/e.txt")]);
+        assert_eq!(expand("sub/.*/*.txt"), vec![base.join("sub/.cache/d.txt")]);
+        assert_eq!(
+            expand(".*"),
+            vec![base.join(".b.txt"), base.join(".config")]
+        );
+    }
+
+    #[test]
+    fn test_max_matches_limits_each_pattern() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let base = tmp.path();
+        for name in ["a.log", "b.log", "c.txt"] {
+            std::fs::write(base.join(name), "").unwrap();
+        }
+        let options = GlobOptions::builder().max_matches(2).build();
+
+        // Two patterns of two matches each are fine
+        let patterns = vec!["*.log".to_string(), "[ab].*".to_string()];
+        assert_eq!(
+            expand_globs_with(base, &patterns, &options).unwrap().len(),
+            2
+        );
+
+        let result = expand_globs_with(base, &["*".to_string()], &options);
+        assert!(matches!(
+            result,
+            Err(GlobError::TooManyMatches { pattern, limit: 2 }) if pattern == "*"
+        ));
+    }
+
+    #[test]
+    fn test_matches_record_their_pattern() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let base = tmp.path();
+        for name in ["a.log", "b.log", "c.txt"] {
+            std::fs::write(base.join(name), "").unwrap();
+        }
+        let patterns = vec![
+            "*.txt".to_string(),
+            "*.tmp".to_string(),
+            "a.log".to_string(),
+            "*.log".to_string(),
+        ];
+
+        let expansion = expand_globs_matches(base, &patterns, &GlobOptions::default()).unwrap();
+        assert_eq!(expansion.counts, vec![1, 0, 1, 2]);
+        assert_eq!(expansion.unmatched().collect::<Vec<_>>(), vec![1]);
+        // a.log is attributed to the first pattern that produced it
+        assert_eq!(expansion.pattern_of(&base.join("a.log")), Some(2));
+        assert_eq!(expansion.pattern_of(&base.join("b.log")), Some(3));
+        assert_eq!(expansion.pattern_of(&base.join("c.txt")), Some(0));
+        assert_eq!(expansion.pattern_of(&base.join("d.txt")), None);
+        assert_eq!(expansion.into_paths().len(), 3);
+    }
+
+    #[test]
+    fn test_filter_excluded_by_name_and_path() {
+        let paths = vec![
+            PathBuf::from("dir/keep.txt"),
+            PathBuf::from("dir/drop.tmp"),
+            PathBuf::from("build/out.txt"),
+        ];
+        let exclude = vec!["*.tmp".to_string(), "build/*".to_string()];
+        let result = filter_excluded(paths, &exclude).unwrap();
+        assert_eq!(result, vec![PathBuf::from("dir/keep.txt")]);
+    }
+
     #[test]
     fn test_invalid_glob_pattern() {
         // Unclosed bracket is invalid glob syntax
@@ -0,0 +1,110 @@
+//! `--pre-hook` and `--post-hook` commands.
+//!
+//! Hooks are shell commands run around each move, e.g. to stop a service
+//! using the file, trigger an indexer or send a notification. They run with
+//! `sh -c` (`cmd /C` on Windows) and get the paths of the move in the
+//! environment:
+//!
+//! | Variable | Value |
+//! |----------|-------|
+//! | `MVLN_HOOK` | `pre` or `post` |
+//! | `MVLN_SOURCE` | The source path |
+//! | `MVLN_DEST` | The destination path (including the file name) |
+//! | `MVLN_LINK` | The symlink left at the source location |
+//! | `MVLN_LINK_TARGET` | The symlink's stored target (post-hook only) |
+//!
+//! Hooks don't read stdin, so they can't steal answers meant for
+//! `--interactive`.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use mvln::error::{MvlnError, Result};
+use mvln::operation::MoveResult;
+
+use crate::cli::Cli;
+use crate::logfile::{self, Kind};
+
+/// Run the `--pre-hook` for moving `source` to `dest`, if there is one.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::HookFailed`] if the hook cannot be started or exits
+/// unsuccessfully; the move should then be skipped.
+pub fn pre(cli: &Cli, source: &Path, dest: &Path) -> Result<()> {
+    let Some(command) = &cli.pre_hook else {
+        return Ok(());
+    };
+    // Hooks see the final destination, as move_and_link resolves it
+    let dest = crate::final_dest(source, dest, cli);
+    run(
+        cli,
+        command,
+        &[
+            ("MVLN_HOOK", "pre".as_ref()),
+            ("MVLN_SOURCE", source.as_os_str()),
+            ("MVLN_DEST", dest.as_os_str()),
+            ("MVLN_LINK", source.as_os_str()),
+        ],
+    )
+}
+
+/// Run the `--post-hook` for a completed move, if there is one.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::HookFailed`] if the hook cannot be started or exits
+/// unsuccessfully. The move itself stands.
+pub fn post(cli: &Cli, result: &MoveResult) -> Result<()> {
+    let Some(command) = &cli.post_hook else {
+        return Ok(());
+    };
+    run(
+        cli,
+        command,
+        &[
+            ("MVLN_HOOK", "post".as_ref()),
+            ("MVLN_SOURCE", result.source.as_os_str()),
+            ("MVLN_DEST", result.dest.as_os_str()),
+            ("MVLN_LINK", result.source.as_os_str()),
+            ("MVLN_LINK_TARGET", result.symlink_target.as_os_str()),
+        ],
+    )
+}
+
+fn run(cli: &Cli, command: &str, env: &[(&str, &std::ffi::OsStr)]) -> Result<()> {
+    if cli.dry_run {
+        logfile::write(Kind::Decision, &format!("dry run, hook not run: {command}"));
+        return Ok(());
+    }
+    logfile::write(Kind::Action, &format!("hook: {command}"));
+
+    let failed = |reason: String| MvlnError::HookFailed {
+        command: command.to_string(),
+        reason,
+    };
+    let status = shell(command)
+        .envs(env.iter().copied())
+        .stdin(Stdio::null())
+        .status()
+        .map_err(|e| failed(e.to_string()))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(failed(status.to_string()))
+    }
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
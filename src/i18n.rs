@@ -5,30 +5,48 @@
 //!
 //! # Supported Locales
 //!
+//! Every `i18n/<locale>/` directory is embedded by the build script, so
+//! adding a language only takes adding its `.ftl` files. The tree currently
+//! ships:
+//!
 //! - `en-US`: English (United States) - Default fallback
 //! - `zh-CN`: Simplified Chinese
 //!
+//! # User Translations
+//!
+//! Additional `.ftl` files are loaded at startup from
+//! `<data dir>/i18n/<locale>/` (see [`crate::dirs::data_dir`], usually
+//! `~/.local/share/mvln/i18n/<locale>/`). Their messages override the
+//! embedded ones, and messages they don't define fall back to the embedded
+//! resources, so a directory can both fix a few strings of an existing
+//! translation and add a new language.
+//!
 //! # Examples
 //!
 //! ```no_run
-//! use mvln::i18n;
+//! use mvln::i18n::{self, MessageKey};
 //! use fluent::FluentArgs;
 //!
 //! let bundle = i18n::init();
 //!
 //! // Simple message without arguments
-//! let msg = i18n::simple_msg(&bundle, "op-dry-run");
+//! let msg = i18n::simple_msg(&bundle, MessageKey::OpDryRun);
 //! println!("{}", msg);
 //!
 //! // Message with arguments
 //! let mut args = FluentArgs::new();
 //! args.set("src", "file.txt");
 //! args.set("dest", "/backup/file.txt");
-//! let msg = i18n::msg(&bundle, "op-moving", Some(&args));
+//! let msg = i18n::msg(&bundle, MessageKey::OpMoving, Some(&args));
 //! println!("{}", msg);
 //! ```
 
+use std::fs;
+use std::path::{Path, PathBuf};
+
 use fluent::{FluentArgs, FluentBundle, FluentResource};
+
+use crate::error::MvlnError;
 use fluent_langneg::{negotiate_languages, NegotiationStrategy};
 use unic_langid::{langid, LanguageIdentifier};
 
@@ -38,29 +56,33 @@ use fluent_langneg::LanguageIdentifier as NegLangId;
 /// English (US) locale - default fallback.
 static EN_US: LanguageIdentifier = langid!("en-US");
 
-/// English translations (embedded at compile time).
-const EN_US_FTL: &str = include_str!("../i18n/en-US/main.ftl");
+// Generated by build.rs from the locale directories under i18n/
+include!(concat!(env!("OUT_DIR"), "/locales.rs"));
+include!(concat!(env!("OUT_DIR"), "/message_keys.rs"));
+
+/// Environment variable that overrides the detected system locale.
+pub const LANG_ENV: &str = "MVLN_LANG";
 
-/// Simplified Chinese translations (embedded at compile time).
-const ZH_CN_FTL: &str = include_str!("../i18n/zh-CN/main.ftl");
+/// Subdirectory of the data directory holding user translations.
+pub const USER_I18N_DIR: &str = "i18n";
 
 /// Initialize internationalization with system locale detection.
 ///
 /// This function:
-/// 1. Detects the system locale using [`sys_locale`]
-/// 2. Negotiates the best matching locale from available translations
-/// 3. Loads the appropriate `.ftl` resource
-/// 4. Falls back to `en-US` if the system locale is not supported
+/// 1. Uses the `MVLN_LANG` environment variable if it is set, otherwise
+///    detects the preferred system locales using [`sys_locale`]
+/// 2. Negotiates the matching locales from available translations
+/// 3. Builds a bundle with the fallback chain described in
+///    [`init_with_locale`], ending in `en-US`
 ///
 /// # Returns
 ///
-/// A [`FluentBundle`] configured with the negotiated locale and loaded messages.
+/// A [`FluentBundle`] configured with the negotiated locales and loaded messages.
 ///
 /// # Panics
 ///
-/// Panics if the embedded FTL resources are invalid or if locale parsing fails
-/// for hardcoded locale strings. This should never happen with valid embedded
-/// resources.
+/// Panics if the embedded FTL resources are invalid. This should never happen
+/// with valid embedded resources.
 ///
 /// # Examples
 ///
@@ -69,81 +91,209 @@ const ZH_CN_FTL: &str = include_str!("../i18n/zh-CN/main.ftl");
 /// ```
 #[must_use]
 pub fn init() -> FluentBundle<FluentResource> {
-    // Detect system locale
-    let system_locale = sys_locale::get_locale()
-        .and_then(|locale_str| locale_str.parse::<LanguageIdentifier>().ok())
-        .unwrap_or_else(|| EN_US.clone());
+    let requested = std::env::var(LANG_ENV)
+        .ok()
+        .filter(|lang| !lang.is_empty())
+        .unwrap_or_else(|| sys_locale::get_locales().collect::<Vec<_>>().join(":"));
+    init_with_locale(&requested)
+}
 
-    // Convert unic-langid to fluent-langneg format
-    let system_locale_str = system_locale.to_string();
-    let en_us_neg: NegLangId = "en-US".parse().expect("en-US locale is always valid");
+/// Initialize internationalization for an explicitly requested locale.
+///
+/// `locale` may be a BCP 47 tag (`zh-CN`) or a POSIX locale name
+/// (`zh_CN.UTF-8`), or a colon-separated list of them in order of
+/// preference (`de:zh-CN`, as in GNU `LANGUAGE`).
+///
+/// Messages are resolved one at a time along a fallback chain: the matching
+/// requested locales in order, then `en-US`. A partially translated locale
+/// therefore shows its own messages where it has them and the next locale's
+/// elsewhere. Unsupported or unparsable locales are skipped.
+///
+/// # Panics
+///
+/// Panics if the embedded FTL resources are invalid. This should never happen
+/// with valid embedded resources.
+///
+/// # Examples
+///
+/// ```
+/// let bundle = mvln::i18n::init_with_locale("zh_CN.UTF-8");
+/// assert_eq!(bundle.locales[0].to_string(), "zh-CN");
+/// assert_eq!(bundle.locales.last().unwrap().to_string(), "en-US");
+/// ```
+#[must_use]
+pub fn init_with_locale(locale: &str) -> FluentBundle<FluentResource> {
+    let user_root = crate::dirs::data_dir().map(|dir| dir.join(USER_I18N_DIR));
+    init_with_translations(locale, user_root.as_deref())
+}
 
-    let requested_neg: Vec<NegLangId> = vec![system_locale_str
-        .parse()
-        .unwrap_or_else(|_| en_us_neg.clone())];
+/// Initialize internationalization for `locale`, loading user translations
+/// from `user_root/<locale>/*.ftl` instead of the default location.
+///
+/// Passing `None` uses only the embedded resources.
+///
+/// # Panics
+///
+/// Panics if the embedded FTL resources are invalid. This should never happen
+/// with valid embedded resources.
+#[must_use]
+pub fn init_with_translations(
+    locale: &str,
+    user_root: Option<&Path>,
+) -> FluentBundle<FluentResource> {
+    let en_us_neg: NegLangId = "en-US".parse().expect("en-US locale is always valid");
 
-    let zh_cn_neg: NegLangId = "zh-CN".parse().expect("zh-CN locale is always valid");
-    let available_neg = vec![en_us_neg.clone(), zh_cn_neg.clone()];
+    let requested_neg: Vec<NegLangId> = locale
+        .split(':')
+        .filter_map(|tag| normalize_tag(tag).parse().ok())
+        .collect();
+
+    let user_locales = user_root.map(user_locales).unwrap_or_default();
+    let embedded = embedded_locales();
+    let mut available_neg: Vec<NegLangId> = embedded.iter().map(|(id, _)| id.clone()).collect();
+    for (id, _) in &user_locales {
+        if !available_neg.contains(id) {
+            available_neg.push(id.clone());
+        }
+    }
 
-    // Negotiate best matching locale
-    let negotiated = negotiate_languages(
+    // Negotiate matching locales, most preferred first; en-US always ends
+    // the chain
+    let mut chain: Vec<NegLangId> = negotiate_languages(
         &requested_neg,
         &available_neg,
         Some(&en_us_neg),
         NegotiationStrategy::Filtering,
-    );
-
-    // Use first negotiated locale or fallback to en-US
-    let selected_locale_str = negotiated
-        .first()
-        .map_or_else(|| "en-US".to_string(), std::string::ToString::to_string);
+    )
+    .into_iter()
+    .cloned()
+    .collect();
+    chain.retain(|id| *id != en_us_neg);
+    chain.push(en_us_neg.clone());
+
+    // Convert to unic-langid for FluentBundle (also used for plural rules)
+    let locales: Vec<LanguageIdentifier> = chain
+        .iter()
+        .filter_map(|id| id.to_string().parse().ok())
+        .collect();
+    let mut bundle = FluentBundle::new(if locales.is_empty() {
+        vec![EN_US.clone()]
+    } else {
+        locales
+    });
+
+    // Load the least preferred locale first so that each more preferred one
+    // overrides it message by message; user translations override the
+    // embedded resources of their locale
+    for id in chain.iter().rev() {
+        for (_, sources) in embedded.iter().filter(|(embedded_id, _)| embedded_id == id) {
+            for ftl_source in *sources {
+                let resource = FluentResource::try_new((*ftl_source).to_string())
+                    .expect("Failed to parse embedded FTL resource");
+                bundle.add_resource_overriding(resource);
+            }
+        }
+        for (_, dir) in user_locales.iter().filter(|(user_id, _)| user_id == id) {
+            for resource in load_user_resources(dir) {
+                bundle.add_resource_overriding(resource);
+            }
+        }
+    }
 
-    // Convert back to unic-langid for FluentBundle
-    let selected_locale: LanguageIdentifier = selected_locale_str
-        .parse()
-        .unwrap_or_else(|_| EN_US.clone());
+    bundle
+}
 
-    // Load appropriate FTL resource
-    let zh_cn_locale: LanguageIdentifier = "zh-CN".parse().expect("zh-CN locale is always valid");
-    let ftl_source = if selected_locale == zh_cn_locale {
-        ZH_CN_FTL
-    } else {
-        EN_US_FTL
-    };
+/// Locales embedded at build time with their `.ftl` sources.
+fn embedded_locales() -> Vec<(NegLangId, &'static [&'static str])> {
+    EMBEDDED_LOCALES
+        .iter()
+        .map(|(tag, sources)| {
+            let id = tag
+                .parse()
+                .expect("embedded locale directories are valid tags");
+            (id, *sources)
+        })
+        .collect()
+}
 
-    // Parse FTL resource
-    let resource = FluentResource::try_new(ftl_source.to_string())
-        .expect("Failed to parse embedded FTL resource");
+/// Turn a POSIX locale name into a language tag (`zh_CN.UTF-8` -> `zh-CN`).
+fn normalize_tag(locale: &str) -> String {
+    // Strip encoding/modifier suffixes ("zh_CN.UTF-8@pinyin")
+    locale
+        .trim()
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-")
+}
 
-    // Create bundle with selected locale
-    let mut bundle = FluentBundle::new(vec![selected_locale]);
-    bundle
-        .add_resource(resource)
-        .expect("Failed to add resource to bundle");
+/// Locale directories under `root`, e.g. `root/zh-CN` or `root/fr`.
+fn user_locales(root: &Path) -> Vec<(NegLangId, PathBuf)> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut locales: Vec<(NegLangId, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.replace('_', "-");
+            Some((name.parse().ok()?, entry.path()))
+        })
+        .collect();
+    locales.sort_by(|a, b| a.1.cmp(&b.1));
+    locales
+}
 
-    bundle
+/// Parse the `.ftl` files in `dir`, in file name order.
+///
+/// Unreadable files are skipped; syntax errors only drop the broken entries,
+/// so a typo cannot hide the rest of a translation.
+fn load_user_resources(dir: &Path) -> Vec<FluentResource> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ftl"))
+        .collect();
+    files.sort();
+    files
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|source| FluentResource::try_new(source).unwrap_or_else(|(partial, _)| partial))
+        .collect()
 }
 
-/// Get a localized message by ID with optional arguments.
+/// Get a localized message by key with optional arguments.
 ///
 /// This function retrieves a message from the Fluent bundle and formats it
-/// with the provided arguments (if any). If the message is not found,
-/// returns the message ID itself as a fallback.
+/// with the provided arguments (if any). Every [`MessageKey`] exists in the
+/// embedded `en-US` fallback, so a typo'd key is a compile error:
+///
+/// ```compile_fail
+/// use mvln::i18n::{self, MessageKey};
+///
+/// let msg = i18n::simple_msg(&i18n::init(), MessageKey::OpDryRnu);
+/// ```
+///
+/// If the message has no value (e.g. only attributes), returns the message
+/// ID itself as a fallback.
 ///
 /// # Parameters
 ///
 /// - `bundle`: The Fluent bundle containing loaded messages
-/// - `id`: The message identifier (e.g., "op-moving", "err-source-not-found")
+/// - `key`: The message key (e.g., [`MessageKey::OpMoving`])
 /// - `args`: Optional arguments for message interpolation
 ///
 /// # Returns
 ///
-/// The formatted localized message, or the message ID if not found.
+/// The formatted localized message, or the message ID if it has no value.
 ///
 /// # Examples
 ///
 /// ```no_run
-/// use mvln::i18n;
+/// use mvln::i18n::{self, MessageKey};
 /// use fluent::FluentArgs;
 ///
 /// let bundle = i18n::init();
@@ -151,13 +301,18 @@ pub fn init() -> FluentBundle<FluentResource> {
 /// // With arguments
 /// let mut args = FluentArgs::new();
 /// args.set("path", "/tmp/file.txt");
-/// let msg = i18n::msg(&bundle, "err-source-not-found", Some(&args));
+/// let msg = i18n::msg(&bundle, MessageKey::ErrSourceNotFound, Some(&args));
 ///
 /// // Without arguments
-/// let msg = i18n::msg(&bundle, "op-dry-run", None);
+/// let msg = i18n::msg(&bundle, MessageKey::OpDryRun, None);
 /// ```
 #[must_use]
-pub fn msg(bundle: &FluentBundle<FluentResource>, id: &str, args: Option<&FluentArgs>) -> String {
+pub fn msg(
+    bundle: &FluentBundle<FluentResource>,
+    key: MessageKey,
+    args: Option<&FluentArgs>,
+) -> String {
+    let id = key.id();
     let Some(message) = bundle.get_message(id) else {
         // Fallback: return message ID if not found
         return id.to_string();
@@ -188,24 +343,229 @@ pub fn msg(bundle: &FluentBundle<FluentResource>, id: &str, args: Option<&Fluent
 /// # Parameters
 ///
 /// - `bundle`: The Fluent bundle containing loaded messages
-/// - `id`: The message identifier
+/// - `key`: The message key
 ///
 /// # Returns
 ///
-/// The formatted localized message, or the message ID if not found.
+/// The formatted localized message.
 ///
 /// # Examples
 ///
 /// ```no_run
-/// use mvln::i18n;
+/// use mvln::i18n::{self, MessageKey};
 ///
 /// let bundle = i18n::init();
-/// let msg = i18n::simple_msg(&bundle, "op-dry-run");
+/// let msg = i18n::simple_msg(&bundle, MessageKey::OpDryRun);
 /// println!("{}", msg);
 /// ```
 #[must_use]
-pub fn simple_msg(bundle: &FluentBundle<FluentResource>, id: &str) -> String {
-    msg(bundle, id, None)
+pub fn simple_msg(bundle: &FluentBundle<FluentResource>, key: MessageKey) -> String {
+    msg(bundle, key, None)
+}
+
+/// Render an error as a diagnostic in the bundle's language.
+///
+/// The CLI uses this instead of the English [`Display`](std::fmt::Display)
+/// implementation of [`MvlnError`], which stays as-is for library users.
+/// The first line carries the severity, the error's [code](MvlnError::code)
+/// and the message; the offending paths follow, then the message's
+/// `.reason`, `.note` and `.hint` attributes where defined:
+///
+/// ```text
+/// Error[mvln::dest_exists]: Destination already exists: b.txt
+///   --> b.txt
+///   = help: Rerun with -f/--force to overwrite it, or --rename-on-conflict to keep both
+/// ```
+///
+/// Errors without a translation fall back to their `Display` text.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::error::MvlnError;
+/// use mvln::i18n;
+///
+/// let bundle = i18n::init_with_translations("en-US", None);
+/// let error = MvlnError::ProfileNotFound { name: "nas".into() };
+/// let text = i18n::error_msg(&bundle, &error);
+/// assert!(text.starts_with("Error[mvln::profile_not_found]: Profile not found"));
+/// assert!(text.contains("= help: Define"));
+/// ```
+#[must_use]
+pub fn error_msg(bundle: &FluentBundle<FluentResource>, error: &MvlnError) -> String {
+    let (key, args) = error_args(error);
+    let Some(message) = bundle.get_message(key.id()) else {
+        return error.to_string();
+    };
+
+    let severity = if error.is_warning() {
+        MessageKey::DiagWarning
+    } else {
+        MessageKey::DiagError
+    };
+    let mut text = format!(
+        "{}[{}]: {}",
+        simple_msg(bundle, severity),
+        error.code(),
+        msg(bundle, key, Some(&args))
+    );
+    for path in error.paths() {
+        text.push_str("\n  --> ");
+        text.push_str(&path.display().to_string());
+    }
+    for (name, label) in [
+        ("reason", MessageKey::DiagReason),
+        ("note", MessageKey::DiagNote),
+        ("hint", MessageKey::DiagHelp),
+    ] {
+        if let Some(attribute) = message.get_attribute(name) {
+            let mut errors = vec![];
+            let line = bundle.format_pattern(attribute.value(), Some(&args), &mut errors);
+            text.push_str("\n  = ");
+            text.push_str(&simple_msg(bundle, label));
+            text.push_str(": ");
+            text.push_str(&line);
+        }
+    }
+    text
+}
+
+/// Message key and arguments for an error.
+fn error_args(error: &MvlnError) -> (MessageKey, FluentArgs<'static>) {
+    let mut args = FluentArgs::new();
+    let id = error_id(error, &mut args);
+
+    // Non-path arguments
+    match error {
+        MvlnError::SourceAccessError { source, .. }
+        | MvlnError::MoveFailed { source, .. }
+        | MvlnError::CopyFailed { source, .. }
+        | MvlnError::RemoveFailed { source, .. }
+        | MvlnError::SymlinkFailed { source, .. }
+        | MvlnError::CreateDirFailed { source, .. }
+        | MvlnError::ChmodFailed { source, .. }
+        | MvlnError::LockFailed { source, .. }
+        | MvlnError::JournalError { source, .. }
+        | MvlnError::JobError { source, .. }
+        | MvlnError::ScriptWriteFailed { source, .. }
+        | MvlnError::SumsFailed { source, .. }
+        | MvlnError::LogFileFailed { source, .. } => args.set("reason", source.to_string()),
+        MvlnError::InvalidDestination { reason }
+        | MvlnError::InvalidPath { reason, .. }
+        | MvlnError::GlobExpansionFailed { reason }
+        | MvlnError::ConfigError { reason, .. } => args.set("reason", reason.clone()),
+        MvlnError::TypeMismatch {
+            src_type,
+            dest_type,
+            ..
+        } => {
+            args.set("src_type", *src_type);
+            args.set("dest_type", *dest_type);
+        }
+        MvlnError::NoMatches { pattern } => args.set("pattern", pattern.clone()),
+        MvlnError::TooManyMatches { pattern, limit } => {
+            args.set("pattern", pattern.clone());
+            args.set("limit", *limit);
+        }
+        MvlnError::HookFailed { command, reason } => {
+            args.set("command", command.clone());
+            args.set("reason", reason.clone());
+        }
+        MvlnError::BatchOperationFailed { count, .. } => args.set("count", *count),
+        MvlnError::TooDeep { limit, .. } => args.set("limit", *limit),
+        MvlnError::NameTooLong {
+            name, len, limit, ..
+        } => {
+            args.set("name", name.clone());
+            args.set("len", *len);
+            args.set("limit", *limit);
+        }
+        MvlnError::PathTooLong { len, limit, .. } => {
+            args.set("len", *len);
+            args.set("limit", *limit);
+        }
+        MvlnError::ProfileNotFound { name } => args.set("name", name.clone()),
+        MvlnError::JobNotFound { id } => args.set("id", *id),
+        MvlnError::PresetNotFound { name, presets } => {
+            args.set("name", name.clone());
+            args.set("presets", presets.clone());
+        }
+        MvlnError::OpenForWriting { process, .. } => args.set("process", process.clone()),
+        MvlnError::RemoteFailed { dest, reason, .. } => {
+            args.set("dest", dest.clone());
+            args.set("reason", reason.clone());
+        }
+        MvlnError::RemoteStubFailed { url, source, .. } => {
+            args.set("url", url.clone());
+            args.set("reason", source.to_string());
+        }
+        MvlnError::Io(e) => args.set("reason", e.to_string()),
+        _ => {}
+    }
+    (id, args)
+}
+
+/// Message key for an error, setting its path arguments on `args`.
+fn error_id(error: &MvlnError, args: &mut FluentArgs<'static>) -> MessageKey {
+    const PATH: &[&str] = &["path"];
+    const SRC_DEST: &[&str] = &["src", "dest"];
+    // Argument names for the paths, in the order of `MvlnError::paths`
+    let (key, names) = match error {
+        MvlnError::SourceNotFound { .. } => (MessageKey::ErrSourceNotFound, PATH),
+        MvlnError::SourceAccessError { .. } => (MessageKey::ErrSourceAccess, PATH),
+        MvlnError::DestinationExists { .. } => (MessageKey::ErrDestExists, PATH),
+        MvlnError::ConflictSkipped { .. } => (MessageKey::ErrConflictSkipped, SRC_DEST),
+        MvlnError::ConflictAborted { .. } => (MessageKey::ErrConflictAborted, SRC_DEST),
+        MvlnError::CrossDeviceDeclined { .. } => (MessageKey::ErrCrossDeviceDeclined, SRC_DEST),
+        MvlnError::CrossDevice { .. } => (MessageKey::ErrCrossDevice, SRC_DEST),
+        MvlnError::SkippedDirKept { .. } => (MessageKey::ErrSkippedDirKept, &["src", "dir"][..]),
+        MvlnError::StowConflict { .. } => (MessageKey::ErrStowConflict, PATH),
+        MvlnError::IsDirectory { .. } => (MessageKey::ErrIsDirectory, PATH),
+        MvlnError::DanglingSymlink { .. } => {
+            (MessageKey::ErrDanglingSymlink, &["path", "target"][..])
+        }
+        MvlnError::OpenForWriting { .. } => (MessageKey::ErrOpenForWriting, PATH),
+        MvlnError::SameSourceAndDest { .. } => (MessageKey::ErrSamePath, PATH),
+        MvlnError::DestinationInsideSource { .. } => (MessageKey::ErrDestInsideSource, SRC_DEST),
+        MvlnError::TypeMismatch { .. } => (MessageKey::ErrTypeMismatch, SRC_DEST),
+        MvlnError::MoveFailed { .. } => (MessageKey::ErrMoveFailed, SRC_DEST),
+        MvlnError::CopyFailed { .. } => (MessageKey::ErrCopyFailed, SRC_DEST),
+        MvlnError::RemoveFailed { .. } => (MessageKey::ErrRemoveFailed, SRC_DEST),
+        MvlnError::TooDeep { .. } => (MessageKey::ErrTooDeep, PATH),
+        MvlnError::FilesystemLoop { .. } => {
+            (MessageKey::ErrFilesystemLoop, &["path", "ancestor"][..])
+        }
+        MvlnError::NameTooLong { .. } => (MessageKey::ErrNameTooLong, PATH),
+        MvlnError::PathTooLong { .. } => (MessageKey::ErrPathTooLong, PATH),
+        MvlnError::SymlinkFailed { .. } => (MessageKey::ErrSymlinkFailed, &["link", "target"][..]),
+        MvlnError::CreateDirFailed { .. } => (MessageKey::ErrCreateDirFailed, PATH),
+        MvlnError::ChmodFailed { .. } => (MessageKey::ErrChmodFailed, PATH),
+        MvlnError::InvalidDestination { .. } => (MessageKey::ErrInvalidDestination, &[][..]),
+        MvlnError::InvalidPath { .. } => (MessageKey::ErrInvalidPath, PATH),
+        MvlnError::GlobExpansionFailed { .. } => (MessageKey::ErrGlobFailed, &[][..]),
+        MvlnError::NoMatches { .. } => (MessageKey::ErrNoMatches, &[][..]),
+        MvlnError::TooManyMatches { .. } => (MessageKey::ErrTooManyMatches, &[][..]),
+        MvlnError::BatchOperationFailed { .. } => (MessageKey::ErrBatchFailed, &[][..]),
+        MvlnError::LockHeld { .. } => (MessageKey::ErrLockHeld, &["root"][..]),
+        MvlnError::LockFailed { .. } => (MessageKey::ErrLockFailed, PATH),
+        MvlnError::ConfigError { .. } => (MessageKey::ErrConfig, PATH),
+        MvlnError::ProfileNotFound { .. } => (MessageKey::ErrProfileNotFound, &[][..]),
+        MvlnError::PresetNotFound { .. } => (MessageKey::ErrPresetNotFound, &[][..]),
+        MvlnError::JournalError { .. } => (MessageKey::ErrJournal, PATH),
+        MvlnError::JobError { .. } => (MessageKey::ErrJob, PATH),
+        MvlnError::JobNotFound { .. } => (MessageKey::ErrJobNotFound, &[][..]),
+        MvlnError::ScriptWriteFailed { .. } => (MessageKey::ErrScriptWriteFailed, PATH),
+        MvlnError::SumsFailed { .. } => (MessageKey::ErrSumsFailed, PATH),
+        MvlnError::LogFileFailed { .. } => (MessageKey::ErrLogFileFailed, PATH),
+        MvlnError::HookFailed { .. } => (MessageKey::ErrHookFailed, &[][..]),
+        MvlnError::RemoteFailed { .. } => (MessageKey::ErrRemoteFailed, &["src"][..]),
+        MvlnError::RemoteStubFailed { .. } => (MessageKey::ErrRemoteStubFailed, &["src"][..]),
+        MvlnError::Io(_) => (MessageKey::ErrIo, &[][..]),
+    };
+    for (name, path) in names.iter().zip(error.paths()) {
+        args.set(*name, path.display().to_string());
+    }
+    key
 }
 
 #[cfg(test)]
@@ -222,7 +582,7 @@ mod tests {
     #[test]
     fn test_simple_msg_en_us() {
         let bundle = init();
-        let msg = simple_msg(&bundle, "op-dry-run");
+        let msg = simple_msg(&bundle, MessageKey::OpDryRun);
         // Should contain expected English text
         assert!(msg.contains("DRY-RUN") || msg.contains("预览模式"));
     }
@@ -234,24 +594,22 @@ mod tests {
         args.set("src", "test.txt");
         args.set("dest", "/backup/test.txt");
 
-        let message = msg(&bundle, "op-moving", Some(&args));
+        let message = msg(&bundle, MessageKey::OpMoving, Some(&args));
         // Should contain the interpolated values
         assert!(message.contains("test.txt"));
         assert!(message.contains("/backup/test.txt"));
     }
 
     #[test]
-    fn test_missing_message_returns_id() {
-        let bundle = init();
-        let msg = simple_msg(&bundle, "nonexistent-message-id");
-        // Should return the ID itself as fallback
-        assert_eq!(msg, "nonexistent-message-id");
+    fn test_message_keys_match_ftl_ids() {
+        assert_eq!(MessageKey::OpDryRun.id(), "op-dry-run");
+        assert_eq!(MessageKey::ErrDestExists.id(), "err-dest-exists");
     }
 
     #[test]
     fn test_msg_without_args() {
         let bundle = init();
-        let msg = msg(&bundle, "op-dry-run", None);
+        let msg = msg(&bundle, MessageKey::OpDryRun, None);
         // Should work the same as simple_msg
         assert!(msg.contains("DRY-RUN") || msg.contains("预览模式"));
     }
@@ -263,10 +621,146 @@ mod tests {
         args.set("path", "/tmp/test.txt");
 
         // Test main message
-        let message = msg(&bundle, "err-dest-exists", Some(&args));
+        let message = msg(&bundle, MessageKey::ErrDestExists, Some(&args));
         assert!(message.contains("/tmp/test.txt"));
 
         // Note: Attributes (.hint) need to be retrieved separately in Fluent
         // The msg() function only retrieves the main message value
     }
+
+    #[test]
+    fn test_init_with_locale_overrides() {
+        let bundle = init_with_locale("zh_CN.UTF-8");
+        assert!(simple_msg(&bundle, MessageKey::OpDryRun).contains("预览模式"));
+
+        let bundle = init_with_locale("en");
+        assert!(simple_msg(&bundle, MessageKey::OpDryRun).contains("DRY-RUN"));
+
+        // Unsupported and malformed locales fall back to English
+        for locale in ["fr-FR", "not a locale!", ""] {
+            let bundle = init_with_locale(locale);
+            assert_eq!(bundle.locales[0].to_string(), "en-US", "{locale}");
+        }
+    }
+
+    #[test]
+    fn test_user_translations_override_and_add_locales() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("zh-CN")).unwrap();
+        fs::write(tmp.path().join("zh-CN/fix.ftl"), "op-dry-run = 自定义\n").unwrap();
+        fs::create_dir_all(tmp.path().join("fr")).unwrap();
+        fs::write(
+            tmp.path().join("fr/main.ftl"),
+            "op-dry-run = [SIMULATION] Aucun changement\nbroken = {\n",
+        )
+        .unwrap();
+
+        // Overridden message, other messages still embedded
+        let bundle = init_with_translations("zh-CN", Some(tmp.path()));
+        assert_eq!(simple_msg(&bundle, MessageKey::OpDryRun), "自定义");
+        assert_eq!(
+            simple_msg(&init_with_locale("zh-CN"), MessageKey::RecoveryCommand),
+            simple_msg(&bundle, MessageKey::RecoveryCommand)
+        );
+
+        // New locale, falling back to English for missing messages
+        let bundle = init_with_translations("fr", Some(tmp.path()));
+        assert_eq!(bundle.locales[0].to_string(), "fr");
+        assert!(simple_msg(&bundle, MessageKey::OpDryRun).contains("SIMULATION"));
+        assert!(simple_msg(&bundle, MessageKey::RecoveryCommand).is_ascii());
+    }
+
+    #[test]
+    fn test_fallback_chain_resolves_per_message() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("de")).unwrap();
+        fs::write(tmp.path().join("de/main.ftl"), "op-dry-run = Probelauf\n").unwrap();
+
+        let bundle = init_with_translations("de_DE.UTF-8:zh-CN", Some(tmp.path()));
+        let chain: Vec<String> = bundle.locales.iter().map(ToString::to_string).collect();
+        assert_eq!(chain, ["de", "zh-CN", "en-US"]);
+
+        // German where translated, then Chinese, not all-or-nothing
+        assert_eq!(simple_msg(&bundle, MessageKey::OpDryRun), "Probelauf");
+        assert_eq!(
+            simple_msg(&bundle, MessageKey::RecoveryCommand),
+            simple_msg(
+                &init_with_translations("zh-CN", None),
+                MessageKey::RecoveryCommand
+            )
+        );
+    }
+
+    #[test]
+    fn test_error_msg_is_localized_with_attributes() {
+        let error = MvlnError::LockHeld {
+            root: PathBuf::from("/data"),
+        };
+        let en = error_msg(&init_with_translations("en-US", None), &error);
+        assert!(en.starts_with("Error[mvln::lock_held]: Another mvln run is using"));
+        assert!(en.contains("\n  --> /data\n"));
+        assert!(en.contains("\n  = help: Use --lock-timeout"));
+
+        let zh = error_msg(&init_with_translations("zh-CN", None), &error);
+        assert!(zh.starts_with("错误[mvln::lock_held]: 另一个 mvln 进程"));
+        assert!(zh.contains("= 提示: "));
+
+        let mismatch = MvlnError::TypeMismatch {
+            src: PathBuf::from("a"),
+            dest: PathBuf::from("b"),
+            src_type: "file",
+            dest_type: "directory",
+        };
+        let zh = error_msg(&init_with_translations("zh-CN", None), &mismatch);
+        assert!(zh.contains("文件") && zh.contains("目录"), "{zh}");
+
+        let warning = MvlnError::RemoveFailed {
+            src: PathBuf::from("a"),
+            dest: PathBuf::from("b"),
+            source: std::io::Error::other("denied"),
+        };
+        let en = error_msg(&init_with_translations("en-US", None), &warning);
+        assert!(en.starts_with("Warning[mvln::remove_failed]: "));
+        assert!(en.contains("\n  = reason: denied\n  = note: "));
+    }
+
+    #[test]
+    fn test_summary_plurals() {
+        let strip = |s: String| s.replace(['\u{2068}', '\u{2069}'], "");
+        let summary =
+            |bundle: &FluentBundle<FluentResource>, files: usize, skipped: usize, failed: usize| {
+                let mut args = FluentArgs::new();
+                args.set("files", files);
+                args.set("links", files);
+                args.set("skipped", skipped);
+                args.set("failed", failed);
+                strip(msg(bundle, MessageKey::OpComplete, Some(&args)))
+            };
+
+        let en = init_with_translations("en-US", None);
+        assert_eq!(
+            summary(&en, 1, 0, 0),
+            "Complete: 1 file moved, 1 symlink created"
+        );
+        assert_eq!(
+            summary(&en, 5, 2, 1),
+            "Complete: 5 files moved, 5 symlinks created, 2 skipped, 1 failed"
+        );
+
+        let zh = init_with_translations("zh-CN", None);
+        assert_eq!(
+            summary(&zh, 5, 0, 1),
+            "完成: 移动了 5 个文件, 创建了 5 个软链接, 1 个失败"
+        );
+    }
+
+    #[test]
+    fn test_locales_are_discovered_from_i18n_dir() {
+        let tags: Vec<String> = embedded_locales()
+            .iter()
+            .map(|(id, _)| id.to_string())
+            .collect();
+        assert!(tags.contains(&"en-US".to_string()));
+        assert!(tags.contains(&"zh-CN".to_string()));
+    }
 }
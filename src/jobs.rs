@@ -0,0 +1,460 @@
+//! Batches running in the background, started with `--background`.
+//!
+//! A background job is a detached mvln process working through a batch
+//! while the terminal that started it is gone. Each job has a numbered
+//! directory under `jobs/` in the state directory (see
+//! [`crate::dirs::state_dir`]) holding:
+//!
+//! - `job`: what the job is, as `key<TAB>value` lines appended over its
+//!   life: `started` (Unix time), `command`, `pid` once the worker runs,
+//!   `planned` (`<moves> <bytes>`) once it knows its batch and `exit`
+//!   (`<unix time> <status>`) once it ends. Later lines win.
+//! - `journal`: a [`Journal`] of the job's moves, in the format of the main
+//!   journal, from which [`Job::progress`] tells how far it has got.
+//! - `output`: what the worker printed.
+//! - `lock`: held by the worker for as long as it runs, so a job whose
+//!   worker was killed is told from one still running.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mvln::jobs::{JobState, Jobs};
+//!
+//! for job in Jobs::open_default().list()? {
+//!     if job.state == JobState::Running {
+//!         println!("{}: {}", job.id, job.command);
+//!     }
+//! }
+//! # Ok::<(), mvln::MvlnError>(())
+//! ```
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::dirs::state_dir;
+use crate::error::{MvlnError, Result};
+use crate::journal::{Action, Journal};
+use crate::size::tree_size;
+
+/// Environment variable telling an mvln process that it is the worker of
+/// this job, rather than a command to start in the background.
+pub const JOB_ENV: &str = "MVLN_JOB";
+
+/// Handle to the directory of background jobs.
+#[derive(Debug, Clone)]
+pub struct Jobs {
+    dir: PathBuf,
+}
+
+/// Where a background job stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    /// The worker is running.
+    Running,
+    /// The worker ended with this exit status, at this Unix time.
+    Finished { time: u64, status: i32 },
+    /// The worker is gone without recording an exit status: it was killed
+    /// or the machine went down. The main journal lists its unfinished
+    /// moves for `mvln resume`.
+    Stopped,
+}
+
+/// A background job, as recorded in its directory.
+#[derive(Debug, Clone)]
+pub struct Job {
+    /// Number of the job, counting up from 1.
+    pub id: u64,
+    /// Unix time the job was started.
+    pub started: u64,
+    /// The command line of the job.
+    pub command: String,
+    /// Process ID of the worker, once it has started.
+    pub pid: Option<u32>,
+    /// Whether the job is running or has ended.
+    pub state: JobState,
+    /// Moves in the job's batch and their total size in bytes, once the
+    /// worker has planned it.
+    pub planned: Option<(u64, u64)>,
+    dir: PathBuf,
+}
+
+/// How far a job has got, from its journal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobProgress {
+    /// Moves done.
+    pub moved: u64,
+    /// Bytes now at the destinations of the moves done.
+    pub bytes: u64,
+    /// The source being moved, if a move has begun and not ended.
+    pub current: Option<PathBuf>,
+    /// The sources whose moves failed, in order.
+    pub failed: Vec<PathBuf>,
+}
+
+/// Registration of the worker of a job, held for as long as it runs.
+///
+/// Dropping it without calling [`Worker::finish`] leaves the job
+/// [`Stopped`](JobState::Stopped).
+#[derive(Debug)]
+pub struct Worker {
+    /// Open handle carrying the lock.
+    _lock: File,
+    dir: PathBuf,
+}
+
+impl Jobs {
+    /// Open the jobs kept in `dir`. The directory is created when the first
+    /// job is.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Open the per-user jobs in the state directory.
+    #[must_use]
+    pub fn open_default() -> Self {
+        Self::open(state_dir().join("jobs"))
+    }
+
+    /// Record a new job running `command`, numbered one past the highest
+    /// job so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JobError`] if the job's directory or record
+    /// can't be written.
+    pub fn create(&self, command: &str) -> Result<Job> {
+        fs::create_dir_all(&self.dir).map_err(|e| job_error(&self.dir, e))?;
+        let mut id = self.ids()?.last().map_or(1, |id| id + 1);
+        // Another run may take the same number first
+        let dir = loop {
+            let dir = self.dir.join(id.to_string());
+            match fs::create_dir(&dir) {
+                Ok(()) => break dir,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => id += 1,
+                Err(e) => return Err(job_error(&dir, e)),
+            }
+        };
+        let started = now();
+        append(&dir, "started", &started.to_string())?;
+        append(&dir, "command", command)?;
+        Ok(Job {
+            id,
+            started,
+            command: command.to_string(),
+            pid: None,
+            state: JobState::Running,
+            planned: None,
+            dir,
+        })
+    }
+
+    /// The job numbered `id`, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JobError`] if its record can't be read.
+    pub fn get(&self, id: u64) -> Result<Option<Job>> {
+        let dir = self.dir.join(id.to_string());
+        let text = match fs::read_to_string(dir.join("job")) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(job_error(&dir, e)),
+        };
+        let mut job = Job {
+            id,
+            started: 0,
+            command: String::new(),
+            pid: None,
+            state: JobState::Stopped,
+            planned: None,
+            dir,
+        };
+        let mut exit = None;
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('\t') else {
+                continue;
+            };
+            match key {
+                "started" => job.started = value.parse().unwrap_or(0),
+                "command" => job.command = unescape(value),
+                "pid" => job.pid = value.parse().ok(),
+                "planned" => {
+                    job.planned = value.split_once(' ').and_then(|(moves, bytes)| {
+                        Some((moves.parse().ok()?, bytes.parse().ok()?))
+                    });
+                }
+                "exit" => {
+                    exit = value.split_once(' ').and_then(|(time, status)| {
+                        Some(JobState::Finished {
+                            time: time.parse().ok()?,
+                            status: status.parse().ok()?,
+                        })
+                    });
+                }
+                _ => {}
+            }
+        }
+        job.state = if job.is_locked()? {
+            JobState::Running
+        } else {
+            exit.unwrap_or(JobState::Stopped)
+        };
+        Ok(Some(job))
+    }
+
+    /// All jobs, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JobError`] if the directory or a record can't
+    /// be read.
+    pub fn list(&self) -> Result<Vec<Job>> {
+        let mut jobs = Vec::new();
+        for id in self.ids()? {
+            jobs.extend(self.get(id)?);
+        }
+        Ok(jobs)
+    }
+
+    /// Register the calling process as the worker of job `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JobError`] if the job's lock or record can't be
+    /// written.
+    pub fn attach(&self, id: u64) -> Result<Worker> {
+        let dir = self.dir.join(id.to_string());
+        let lock = lock_file(&dir)?;
+        lock.lock().map_err(|e| job_error(&dir, e))?;
+        append(&dir, "pid", &std::process::id().to_string())?;
+        Ok(Worker { _lock: lock, dir })
+    }
+
+    /// The numbers of the jobs, in ascending order.
+    fn ids(&self) -> Result<Vec<u64>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(job_error(&self.dir, e)),
+        };
+        let mut ids: Vec<u64> = entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .collect();
+        ids.sort_unstable();
+        Ok(ids)
+    }
+}
+
+impl Job {
+    /// The journal of the job's moves.
+    #[must_use]
+    pub fn journal(&self) -> Journal {
+        Journal::open(self.dir.join("journal"))
+    }
+
+    /// How far the job has got. Moves begun by a job that isn't running
+    /// are not [current](JobProgress::current).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JournalError`] if the job's journal can't be
+    /// read.
+    pub fn progress(&self) -> Result<JobProgress> {
+        let mut progress = JobProgress::default();
+        for entry in self.journal().entries()? {
+            match entry.action {
+                Action::Begin | Action::BeginMoveOnly => progress.current = Some(entry.link),
+                Action::Move | Action::MoveOnly => {
+                    progress.moved += 1;
+                    // Gone already if moved on since; then it no longer counts
+                    progress.bytes += tree_size(&entry.target).map_or(0, |size| size.bytes);
+                    progress.current = None;
+                }
+                Action::Failed => {
+                    progress.failed.push(entry.link);
+                    progress.current = None;
+                }
+                Action::Adopt | Action::Link => {}
+            }
+        }
+        if self.state != JobState::Running {
+            progress.current = None;
+        }
+        Ok(progress)
+    }
+
+    /// The file the worker's output goes to.
+    #[must_use]
+    pub fn output_path(&self) -> PathBuf {
+        self.dir.join("output")
+    }
+
+    /// Whether a worker holds the job's lock.
+    fn is_locked(&self) -> Result<bool> {
+        let lock = match lock_file(&self.dir) {
+            Ok(lock) => lock,
+            Err(_) if !self.dir.exists() => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        match lock.try_lock_shared() {
+            Ok(()) => Ok(false),
+            Err(TryLockError::WouldBlock) => Ok(true),
+            Err(TryLockError::Error(e)) => Err(job_error(&self.dir, e)),
+        }
+    }
+}
+
+impl Worker {
+    /// The journal of the job's moves.
+    #[must_use]
+    pub fn journal(&self) -> Journal {
+        Journal::open(self.dir.join("journal"))
+    }
+
+    /// Record that the job's batch has `moves` moves of `bytes` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JobError`] if the record can't be written.
+    pub fn plan(&self, moves: u64, bytes: u64) -> Result<()> {
+        append(&self.dir, "planned", &format!("{moves} {bytes}"))
+    }
+
+    /// Record that the job ended with exit status `status`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JobError`] if the record can't be written.
+    pub fn finish(self, status: i32) -> Result<()> {
+        append(&self.dir, "exit", &format!("{} {status}", now()))
+    }
+}
+
+/// Open (creating it) the lock file of the job in `dir`.
+fn lock_file(dir: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join("lock"))
+        .map_err(|e| job_error(dir, e))
+}
+
+/// Append `key<TAB>value` to the record of the job in `dir`.
+fn append(dir: &Path, key: &str, value: &str) -> Result<()> {
+    let line = format!("{key}\t{}\n", escape(value));
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join("job"))
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| job_error(dir, e))
+}
+
+fn job_error(path: &Path, source: io::Error) -> MvlnError {
+    MvlnError::JobError {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                out.push('\\');
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::Entry;
+
+    #[test]
+    fn jobs_are_numbered_and_tracked_until_they_finish() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let jobs = Jobs::open(tmp.path().join("jobs"));
+        assert!(jobs.list().unwrap().is_empty());
+
+        let first = jobs.create("mvln -w a\\b 'c\nd' /mnt").unwrap();
+        let second = jobs.create("mvln x /mnt").unwrap();
+        assert_eq!((first.id, second.id), (1, 2));
+
+        // Created but never picked up by a worker
+        let job = jobs.get(1).unwrap().unwrap();
+        assert_eq!(job.command, "mvln -w a\\b 'c\nd' /mnt");
+        assert_eq!(job.state, JobState::Stopped);
+
+        let worker = jobs.attach(2).unwrap();
+        worker.plan(3, 4096).unwrap();
+        let job = jobs.get(2).unwrap().unwrap();
+        assert_eq!(job.state, JobState::Running);
+        assert_eq!(job.pid, Some(std::process::id()));
+        assert_eq!(job.planned, Some((3, 4096)));
+        worker.finish(1).unwrap();
+        assert!(matches!(
+            jobs.get(2).unwrap().unwrap().state,
+            JobState::Finished { status: 1, .. }
+        ));
+
+        assert_eq!(jobs.create("mvln y /mnt").unwrap().id, 3);
+        assert!(jobs.get(4).unwrap().is_none());
+    }
+
+    #[test]
+    fn progress_comes_from_the_job_journal() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let jobs = Jobs::open(tmp.path().join("jobs"));
+        jobs.create("mvln a b c /mnt").unwrap();
+        let worker = jobs.attach(1).unwrap();
+        let moved = tmp.path().join("a");
+        fs::write(&moved, "abc").unwrap();
+
+        let journal = worker.journal();
+        let record = |action, link: &str, target: &Path| {
+            let entry = Entry::new(action, &tmp.path().join(link), target);
+            journal.append(&entry).unwrap();
+        };
+        record(Action::Begin, "src/a", &moved);
+        record(Action::Move, "src/a", &moved);
+        record(Action::Begin, "src/b", Path::new("/mnt/b"));
+        record(Action::Failed, "src/b", Path::new("/mnt/b"));
+        record(Action::Begin, "src/c", Path::new("/mnt/c"));
+
+        let progress = jobs.get(1).unwrap().unwrap().progress().unwrap();
+        assert_eq!((progress.moved, progress.bytes), (1, 3));
+        assert_eq!(progress.current, Some(tmp.path().join("src/c")));
+        assert_eq!(progress.failed, [tmp.path().join("src/b")]);
+
+        // A job that was stopped isn't moving anything
+        drop(worker);
+        let progress = jobs.get(1).unwrap().unwrap().progress().unwrap();
+        assert_eq!(progress.current, None);
+    }
+}
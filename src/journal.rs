@@ -0,0 +1,316 @@
+//! Persistent record of the symlinks mvln manages.
+//!
+//! Every successful move appends an entry mapping the symlink left behind to
+//! the location the data now lives at. Symlinks created by other means can be
+//! imported with `mvln adopt`. Moves also record when they begin and when they
+//! fail, so moves cut short by a crash can be found later (see
+//! [`crate::recovery`]). The journal is a plain append-only text file
+//! in the state directory (see [`crate::dirs::state_dir`]) with one
+//! tab-separated record per line:
+//!
+//! ```text
+//! <unix time>\t<action>\t<link>\t<target>
+//! ```
+//!
+//! Backslashes, tabs and newlines inside paths are escaped as `\\`, `\t` and
+//! `\n`. Paths are always stored in absolute form.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::dirs::state_dir;
+use crate::error::{MvlnError, Result};
+
+/// What created a journal entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// mvln moved the data and created the symlink.
+    Move,
+    /// A pre-existing symlink was imported with `mvln adopt`.
+    Adopt,
+    /// mvln created a symlink to data moved by another tool (`link-only`).
+    Link,
+    /// mvln moved the data without leaving a symlink (`--no-link`); the
+    /// link path is where the data used to be.
+    MoveOnly,
+    /// A move is about to start; the link path is the source and the target
+    /// its planned destination. Followed by a [`Move`](Self::Move) or
+    /// [`Failed`](Self::Failed) entry once it ends.
+    Begin,
+    /// Like [`Begin`](Self::Begin), for a move with `--no-link`.
+    BeginMoveOnly,
+    /// A begun move failed and left the source in place, or was given up.
+    Failed,
+}
+
+impl Action {
+    /// Name used in the journal file.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Move => "move",
+            Self::Adopt => "adopt",
+            Self::Link => "link",
+            Self::MoveOnly => "move-only",
+            Self::Begin => "begin",
+            Self::BeginMoveOnly => "begin-move-only",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Whether the entry tracks the progress of a move rather than recording
+    /// a link: [`Begin`](Self::Begin), [`BeginMoveOnly`](Self::BeginMoveOnly)
+    /// or [`Failed`](Self::Failed).
+    #[must_use]
+    pub fn is_progress(self) -> bool {
+        matches!(self, Self::Begin | Self::BeginMoveOnly | Self::Failed)
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "move" => Some(Self::Move),
+            "adopt" => Some(Self::Adopt),
+            "link" => Some(Self::Link),
+            "move-only" => Some(Self::MoveOnly),
+            "begin" => Some(Self::Begin),
+            "begin-move-only" => Some(Self::BeginMoveOnly),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// A single symlink/target pair recorded in the journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Seconds since the Unix epoch when the entry was recorded.
+    pub time: u64,
+    /// What created the entry.
+    pub action: Action,
+    /// Absolute path of the symlink (the original location).
+    pub link: PathBuf,
+    /// Absolute path the data lives at.
+    pub target: PathBuf,
+}
+
+impl Entry {
+    /// Create an entry stamped with the current time.
+    ///
+    /// Relative paths are made absolute against the current directory.
+    #[must_use]
+    pub fn new(action: Action, link: &Path, target: &Path) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self {
+            time,
+            action,
+            link: absolute(link),
+            target: absolute(target),
+        }
+    }
+}
+
+/// Handle to a journal file.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Open the journal at `path`. The file is created on first write.
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Open the per-user journal in the state directory.
+    #[must_use]
+    pub fn open_default() -> Self {
+        Self::open(state_dir().join("journal"))
+    }
+
+    /// Path of the journal file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry to the journal.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JournalError`] if the journal cannot be written.
+    pub fn append(&self, entry: &Entry) -> Result<()> {
+        let line = format!(
+            "{}\t{}\t{}\t{}\n",
+            entry.time,
+            entry.action.as_str(),
+            escape(&entry.link),
+            escape(&entry.target)
+        );
+        self.write_line(&line).map_err(|e| self.error(e))
+    }
+
+    /// Read all entries in the order they were recorded.
+    ///
+    /// A missing journal file yields no entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::JournalError`] if the file cannot be read or
+    /// contains a malformed line.
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(self.error(e)),
+        };
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(idx, line)| {
+                parse_line(line).ok_or_else(|| {
+                    self.error(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: malformed", idx + 1),
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Find the most recent entry for the symlink at `link`, ignoring
+    /// [progress](Action::is_progress) entries.
+    ///
+    /// # Errors
+    ///
+    /// See [`Journal::entries`].
+    pub fn find_link(&self, link: &Path) -> Result<Option<Entry>> {
+        let link = absolute(link);
+        Ok(self
+            .entries()?
+            .into_iter()
+            .rev()
+            .find(|e| e.link == link && !e.action.is_progress()))
+    }
+
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())
+    }
+
+    fn error(&self, source: io::Error) -> MvlnError {
+        MvlnError::JournalError {
+            path: self.path.clone(),
+            source,
+        }
+    }
+}
+
+/// Make `path` absolute without resolving symlinks.
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn parse_line(record: &str) -> Option<Entry> {
+    let mut fields = record.split('\t');
+    let time = fields.next()?.parse().ok()?;
+    let action = Action::parse(fields.next()?)?;
+    let link = unescape(fields.next()?)?;
+    let target = unescape(fields.next()?)?;
+    if fields.next().is_some() {
+        return None;
+    }
+    Some(Entry {
+        time,
+        action,
+        link,
+        target,
+    })
+}
+
+fn escape(path: &Path) -> String {
+    let text = path.to_string_lossy();
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn unescape(field: &str) -> Option<PathBuf> {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => out.push('\\'),
+            't' => out.push('\t'),
+            'n' => out.push('\n'),
+            _ => return None,
+        }
+    }
+    Some(PathBuf::from(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_and_read_back() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let journal = Journal::open(tmp.path().join("state/journal"));
+        assert!(journal.entries().unwrap().is_empty());
+
+        let first = Entry::new(Action::Move, Path::new("/a/x"), Path::new("/b/x"));
+        let second = Entry::new(Action::Adopt, Path::new("/a/x"), Path::new("/c/x"));
+        let third = Entry::new(Action::MoveOnly, Path::new("/a/z"), Path::new("/b/z"));
+        let fourth = Entry::new(Action::Begin, Path::new("/a/x"), Path::new("/d/x"));
+        journal.append(&first).unwrap();
+        journal.append(&second).unwrap();
+        journal.append(&third).unwrap();
+        journal.append(&fourth).unwrap();
+
+        assert_eq!(
+            journal.entries().unwrap(),
+            vec![first, second.clone(), third, fourth]
+        );
+        assert_eq!(journal.find_link(Path::new("/a/x")).unwrap(), Some(second));
+        assert_eq!(journal.find_link(Path::new("/a/y")).unwrap(), None);
+    }
+
+    #[test]
+    fn special_characters_round_trip() {
+        let path = Path::new("/tmp/we\tird\\na\nme");
+        assert_eq!(unescape(&escape(path)).unwrap(), path);
+    }
+
+    #[test]
+    fn malformed_line_is_an_error() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("journal");
+        fs::write(&path, "garbage\n").unwrap();
+
+        let err = Journal::open(&path).entries().unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+}
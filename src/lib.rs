@@ -3,13 +3,54 @@
 //! This library provides the core functionality for moving files
 //! while preserving access through symlinks.
 
+pub mod chmod;
+pub mod config;
+pub mod conflict;
+pub mod copy;
+pub mod date;
+pub mod dedupe;
+pub mod device;
+pub mod dirs;
 pub mod error;
+pub mod estimate;
+#[cfg(feature = "exif")]
+pub mod exif;
 pub mod glob_expand;
+pub mod hash;
 pub mod i18n;
+pub mod jobs;
+pub mod journal;
+pub mod limits;
+pub mod links;
+pub mod lock;
+pub mod normalize;
+pub mod observer;
+pub mod open_files;
 pub mod operation;
+pub mod ownership;
+pub mod pack;
 pub mod path_utils;
+pub mod plan;
+pub mod preserve;
+pub mod progress;
+pub mod recovery;
+pub mod rules;
+pub mod sanitize;
+pub mod size;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+pub mod store;
+pub mod sums;
 
-pub use error::{MvlnError, Result};
-pub use glob_expand::{expand_globs, is_glob_pattern, GlobError};
-pub use operation::{move_and_link, MoveOptions};
-pub use path_utils::compute_symlink_target;
+pub use error::{ErrorSummary, MvlnError, Result, Severity};
+pub use glob_expand::{
+    expand_globs, expand_globs_in, expand_globs_matches, expand_globs_with, filter_excluded,
+    is_glob_pattern, Expansion, GlobError, GlobOptions, DEFAULT_MAX_MATCHES,
+};
+pub use observer::MvlnObserver;
+pub use operation::{
+    link_duplicate, link_only, move_and_link, restore, MoveOptions, MoveOptionsBuilder,
+    RestoreOptions, RestoreOptionsBuilder, RestoreResult, SymlinkPolicy,
+};
+pub use path_utils::{compute_symlink_target, compute_symlink_target_in, SymlinkStyle};
+pub use plan::{execute_each, BatchOutcome, ErrorPolicy, MovePlan, PlannedMove};
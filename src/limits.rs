@@ -0,0 +1,208 @@
+//! Path length limits of the destination filesystem.
+//!
+//! A destination name longer than the filesystem allows (255 bytes on ext4,
+//! XFS and Btrfs, 143 on eCryptfs, 255 UTF-16 units on NTFS and exFAT) or a
+//! path longer than the OS accepts (`PATH_MAX`) would otherwise fail with a
+//! bare `ENAMETOOLONG` halfway through a move, possibly after a large copy.
+//! [`PathLimits::check`] finds such paths up front and names the offending
+//! component, so nothing is moved.
+
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{MvlnError, Result};
+
+/// Longest file name most filesystems accept, in bytes (UTF-16 units on
+/// Windows). Used when the filesystem cannot be asked.
+pub const DEFAULT_NAME_MAX: usize = 255;
+
+/// Longest path the OS accepts.
+///
+/// On Windows the standard library passes long paths in their `\\?\` form,
+/// so only the limit of that form applies.
+#[cfg(unix)]
+#[allow(clippy::cast_sign_loss)]
+pub const DEFAULT_PATH_MAX: usize = libc::PATH_MAX as usize - 1;
+#[cfg(windows)]
+pub const DEFAULT_PATH_MAX: usize = 32_767;
+#[cfg(not(any(unix, windows)))]
+pub const DEFAULT_PATH_MAX: usize = 4095;
+
+/// How long names and paths may be on one filesystem.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::limits::PathLimits;
+/// use std::path::Path;
+///
+/// let limits = PathLimits { name_max: 8, path_max: 20 };
+/// assert!(limits.check(Path::new("dir/short")).is_ok());
+/// assert!(limits.check(Path::new("dir/much-too-long")).is_err());
+/// assert!(limits.check(Path::new("a/b/c/d/e/f/g/h/i/j/k")).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathLimits {
+    /// Longest file name, in bytes (UTF-16 units on Windows).
+    pub name_max: usize,
+    /// Longest path, in the same units.
+    pub path_max: usize,
+}
+
+impl Default for PathLimits {
+    /// [`DEFAULT_NAME_MAX`] and [`DEFAULT_PATH_MAX`].
+    fn default() -> Self {
+        Self {
+            name_max: DEFAULT_NAME_MAX,
+            path_max: DEFAULT_PATH_MAX,
+        }
+    }
+}
+
+impl PathLimits {
+    /// The limits of the filesystem `path` is on, or would be created on:
+    /// that of its closest existing ancestor. Falls back to the
+    /// [defaults](Self::default) where the filesystem cannot be asked.
+    #[must_use]
+    pub fn of(path: &Path) -> Self {
+        let path = if path.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            path
+        };
+        path.ancestors()
+            .find(|dir| dir.symlink_metadata().is_ok())
+            .and_then(name_max)
+            .map_or_else(Self::default, |name_max| Self {
+                name_max,
+                ..Self::default()
+            })
+    }
+
+    /// Check every component of `path`, then `path` as a whole.
+    ///
+    /// # Errors
+    ///
+    /// [`MvlnError::NameTooLong`] naming the first component longer than
+    /// `name_max`, or [`MvlnError::PathTooLong`] if the path is longer than
+    /// `path_max`.
+    pub fn check(&self, path: &Path) -> Result<()> {
+        for component in path.components() {
+            if let Component::Normal(name) = component {
+                self.check_name(path, name)?;
+            }
+        }
+        self.check_path(path)
+    }
+
+    /// `dir` joined with `name`, checking `name` and the joined path. `dir`
+    /// itself is assumed to have been checked already, as when walking a
+    /// tree.
+    pub(crate) fn join(&self, dir: &Path, name: &OsStr) -> Result<PathBuf> {
+        let path = dir.join(name);
+        self.check_name(&path, name)?;
+        self.check_path(&path)?;
+        Ok(path)
+    }
+
+    fn check_name(&self, path: &Path, name: &OsStr) -> Result<()> {
+        let len = units(name);
+        if len > self.name_max {
+            return Err(MvlnError::NameTooLong {
+                path: path.to_path_buf(),
+                name: name.to_string_lossy().into_owned(),
+                len,
+                limit: self.name_max,
+            });
+        }
+        Ok(())
+    }
+
+    fn check_path(&self, path: &Path) -> Result<()> {
+        let len = units(path.as_os_str());
+        if len > self.path_max {
+            return Err(MvlnError::PathTooLong {
+                path: path.to_path_buf(),
+                len,
+                limit: self.path_max,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Length of `s` in the units the OS limits are expressed in.
+#[cfg(windows)]
+fn units(s: &OsStr) -> usize {
+    use std::os::windows::ffi::OsStrExt;
+    s.encode_wide().count()
+}
+
+#[cfg(not(windows))]
+fn units(s: &OsStr) -> usize {
+    s.len()
+}
+
+#[cfg(unix)]
+fn name_max(dir: &Path) -> Option<usize> {
+    let stat = rustix::fs::statvfs(dir).ok()?;
+    usize::try_from(stat.f_namemax).ok().filter(|&max| max > 0)
+}
+
+#[cfg(not(unix))]
+fn name_max(_dir: &Path) -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_the_offending_component() {
+        let limits = PathLimits::default();
+        let long = "x".repeat(256);
+        let path = Path::new("archive").join(&long).join("file.txt");
+        let error = limits.check(&path).unwrap_err();
+        let MvlnError::NameTooLong {
+            name, len, limit, ..
+        } = &error
+        else {
+            panic!("unexpected error: {error}");
+        };
+        assert_eq!((name, *len, *limit), (&long, 256, 255));
+        assert_eq!(error.paths(), [path.as_path()]);
+        assert!(limits.check(&Path::new("archive").join(&long[1..])).is_ok());
+    }
+
+    #[test]
+    fn rejects_paths_beyond_path_max() {
+        let limits = PathLimits::default();
+        let component = "d".repeat(200);
+        let mut path = PathBuf::from("/");
+        while path.as_os_str().len() <= DEFAULT_PATH_MAX {
+            path.push(&component);
+        }
+        let error = limits.check(&path).unwrap_err();
+        assert!(matches!(
+            error,
+            MvlnError::PathTooLong {
+                limit: DEFAULT_PATH_MAX,
+                ..
+            }
+        ));
+        assert!(matches!(
+            limits.join(path.parent().unwrap(), OsStr::new(&component)),
+            Err(MvlnError::PathTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn asks_the_closest_existing_ancestor() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let limits = PathLimits::of(&tmp.path().join("missing/dir/file"));
+        assert_eq!(limits, PathLimits::of(tmp.path()));
+        assert!(limits.name_max > 0);
+        assert_eq!(limits.path_max, DEFAULT_PATH_MAX);
+    }
+}
@@ -0,0 +1,510 @@
+//! Operations on existing symlinks.
+//!
+//! These functions work on symlinks that already exist, whether mvln created
+//! them or not, without moving any data.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{MvlnError, Result};
+use crate::journal::{Action, Entry, Journal};
+use crate::path_utils::{compute_symlink_target, normalize_path, SymlinkStyle};
+
+/// Result of converting a symlink's target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConvertResult {
+    /// The symlink that was examined.
+    pub link: PathBuf,
+    /// The target stored in the link before conversion.
+    pub old_target: PathBuf,
+    /// The target stored in the link after conversion.
+    pub new_target: PathBuf,
+}
+
+impl ConvertResult {
+    /// Whether the link's target changed.
+    #[must_use]
+    pub fn changed(&self) -> bool {
+        self.old_target != self.new_target
+    }
+}
+
+/// Rewrite a symlink's target in the given style without moving any data.
+///
+/// The link keeps pointing at the same location; only the way the target is
+/// written changes (e.g. `/home/u/archive/f` becomes `../archive/f`). Links
+/// already in the requested form are left untouched. The replacement is
+/// atomic: a new link is created next to the old one and renamed over it.
+///
+/// Relative targets are resolved lexically against the link's directory
+/// before conversion.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::InvalidPath`] if `link` is not a symlink, or
+/// [`MvlnError::SymlinkFailed`] if the replacement link cannot be created.
+pub fn convert_link<P: AsRef<Path>>(
+    link: P,
+    style: &SymlinkStyle,
+    dry_run: bool,
+) -> Result<ConvertResult> {
+    let link = link.as_ref();
+    let old_target = read_symlink(link)?;
+
+    let absolute_target = resolve_target(link, &old_target);
+    let new_target = compute_symlink_target(link, &absolute_target, style);
+
+    let result = ConvertResult {
+        link: link.to_path_buf(),
+        old_target,
+        new_target,
+    };
+
+    if result.changed() && !dry_run {
+        replace_symlink(link, &result.new_target)?;
+    }
+    Ok(result)
+}
+
+/// Point the existing symlink `link` at `dest`, with the target written in
+/// `style`. Returns the stored target.
+///
+/// Like [`convert_link`], the replacement is atomic. With `dry_run` the
+/// target is computed but the link is left alone.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::InvalidPath`] if `link` is not a symlink, or
+/// [`MvlnError::SymlinkFailed`] if the replacement link cannot be created.
+pub fn retarget_link<P: AsRef<Path>>(
+    link: P,
+    dest: &Path,
+    style: &SymlinkStyle,
+    dry_run: bool,
+) -> Result<PathBuf> {
+    let link = link.as_ref();
+    read_symlink(link)?;
+    let target = compute_symlink_target(link, dest, style);
+    if !dry_run {
+        replace_symlink(link, &target)?;
+    }
+    Ok(target)
+}
+
+/// Record a pre-existing symlink in the journal.
+///
+/// This imports links made outside mvln (e.g. by hand with `mv` and `ln -s`)
+/// so later operations can manage them. Returns the recorded entry, or
+/// `None` if the journal already maps this link to the same target. With
+/// `dry_run` the entry is computed but not written.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::InvalidPath`] if `link` is not a symlink or its
+/// target does not exist, or [`MvlnError::JournalError`] if the journal
+/// cannot be read or written.
+pub fn adopt<P: AsRef<Path>>(link: P, journal: &Journal, dry_run: bool) -> Result<Option<Entry>> {
+    let link = link.as_ref();
+    let stored = read_symlink(link)?;
+    let target = resolve_target(link, &stored);
+
+    if let Err(e) = target.symlink_metadata() {
+        return Err(MvlnError::InvalidPath {
+            path: link.to_path_buf(),
+            reason: format!("target {} does not exist: {e}", target.display()),
+        });
+    }
+
+    let link = normalize_path(&std::path::absolute(link)?);
+    if journal
+        .find_link(&link)?
+        .is_some_and(|entry| entry.target == target)
+    {
+        return Ok(None);
+    }
+
+    let entry = Entry::new(Action::Adopt, &link, &target);
+    if !dry_run {
+        journal.append(&entry)?;
+    }
+    Ok(Some(entry))
+}
+
+/// A symlink to be created when linking a package into a prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StowLink {
+    /// Where the symlink goes, under the prefix.
+    pub link: PathBuf,
+    /// Absolute path of the package file the link points at.
+    pub file: PathBuf,
+    /// Target written into the symlink.
+    pub target: PathBuf,
+}
+
+/// The links needed to mirror a package tree into a prefix.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StowPlan {
+    /// Links to create, in tree order.
+    pub links: Vec<StowLink>,
+    /// Paths under the prefix that are already correctly linked.
+    pub existing: Vec<PathBuf>,
+    /// Paths under the prefix that are occupied by something else.
+    pub conflicts: Vec<PathBuf>,
+}
+
+/// Plan linking every file under `package` into `prefix`, like GNU stow.
+///
+/// For each file `package/a/b` a symlink `prefix/a/b` is planned.
+/// Directories are never linked themselves; they become real directories
+/// under the prefix (existing ones are reused). Paths that already link to
+/// the right file are reported as `existing`, anything else in the way as a
+/// conflict.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::InvalidPath`] if `package` is not a directory or
+/// cannot be read.
+pub fn plan_stow<P: AsRef<Path>, Q: AsRef<Path>>(
+    package: P,
+    prefix: Q,
+    style: &SymlinkStyle,
+) -> Result<StowPlan> {
+    let package = normalize_path(&std::path::absolute(package.as_ref())?);
+    if !package.is_dir() {
+        return Err(MvlnError::InvalidPath {
+            path: package,
+            reason: "not a directory".to_string(),
+        });
+    }
+    let prefix = normalize_path(&std::path::absolute(prefix.as_ref())?);
+    let mut plan = StowPlan::default();
+    plan_stow_dir(&package, &prefix, style, &mut plan)?;
+    Ok(plan)
+}
+
+/// Create the links of a conflict-free [`StowPlan`].
+///
+/// Intermediate directories under the prefix are created as real
+/// directories.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::DestinationExists`] if the plan has conflicts
+/// (nothing is created in that case), or an error for the first directory
+/// or symlink that cannot be created.
+pub fn apply_stow(plan: &StowPlan) -> Result<()> {
+    if let Some(path) = plan.conflicts.first() {
+        return Err(MvlnError::DestinationExists { path: path.clone() });
+    }
+    for entry in &plan.links {
+        if let Some(parent) = entry.link.parent() {
+            fs::create_dir_all(parent).map_err(|e| MvlnError::CreateDirFailed {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
+        }
+        create_link(&entry.link, &entry.target, entry.file.is_dir())?;
+    }
+    Ok(())
+}
+
+fn plan_stow_dir(
+    dir: &Path,
+    prefix: &Path,
+    style: &SymlinkStyle,
+    plan: &mut StowPlan,
+) -> Result<()> {
+    let read_err = |e: std::io::Error| MvlnError::InvalidPath {
+        path: dir.to_path_buf(),
+        reason: e.to_string(),
+    };
+    let mut entries = fs::read_dir(dir)
+        .map_err(read_err)?
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(read_err)?;
+    entries.sort_by_key(fs::DirEntry::file_name);
+
+    for entry in entries {
+        let file = entry.path();
+        let link = prefix.join(entry.file_name());
+        let is_real_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        let existing = link.symlink_metadata().ok();
+
+        if is_real_dir {
+            // Descend into missing or real directories; a symlink to the
+            // whole package directory also counts as already linked.
+            match existing {
+                None => plan_stow_dir(&file, &link, style, plan)?,
+                Some(meta) if meta.is_dir() => plan_stow_dir(&file, &link, style, plan)?,
+                Some(_) if link_points_to(&link, &file) => plan.existing.push(link),
+                Some(_) => plan.conflicts.push(link),
+            }
+            continue;
+        }
+
+        match existing {
+            Some(_) if link_points_to(&link, &file) => plan.existing.push(link),
+            Some(_) => plan.conflicts.push(link),
+            None => {
+                let target = compute_symlink_target(&link, &file, style);
+                plan.links.push(StowLink { link, file, target });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether `link` is a symlink whose target resolves to `file`.
+fn link_points_to(link: &Path, file: &Path) -> bool {
+    fs::read_link(link).is_ok_and(|stored| resolve_target(link, &stored) == file)
+}
+
+fn create_link(link: &Path, target: &Path, is_dir: bool) -> Result<()> {
+    let failed = |e: std::io::Error| MvlnError::SymlinkFailed {
+        link: link.to_path_buf(),
+        target: target.to_path_buf(),
+        source: e,
+    };
+
+    #[cfg(unix)]
+    {
+        let _ = is_dir;
+        std::os::unix::fs::symlink(target, link).map_err(failed)
+    }
+
+    #[cfg(windows)]
+    {
+        if is_dir {
+            std::os::windows::fs::symlink_dir(target, link)
+        } else {
+            std::os::windows::fs::symlink_file(target, link)
+        }
+        .map_err(failed)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (is_dir, failed);
+        Err(MvlnError::SymlinkFailed {
+            link: link.to_path_buf(),
+            target: target.to_path_buf(),
+            source: std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "symlinks not supported on this platform",
+            ),
+        })
+    }
+}
+
+/// Resolve a stored symlink target to an absolute, normalized path.
+pub(crate) fn resolve_target(link: &Path, stored: &Path) -> PathBuf {
+    let link = std::path::absolute(link).unwrap_or_else(|_| link.to_path_buf());
+    let link_dir = link.parent().unwrap_or(Path::new("."));
+    normalize_path(link_dir.join(stored))
+}
+
+/// Read a symlink's target, reporting non-symlinks as invalid paths.
+pub(crate) fn read_symlink(link: &Path) -> Result<PathBuf> {
+    let meta = link
+        .symlink_metadata()
+        .map_err(|e| MvlnError::InvalidPath {
+            path: link.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+    if !meta.is_symlink() {
+        return Err(MvlnError::InvalidPath {
+            path: link.to_path_buf(),
+            reason: "not a symlink".to_string(),
+        });
+    }
+    fs::read_link(link).map_err(|e| MvlnError::InvalidPath {
+        path: link.to_path_buf(),
+        reason: e.to_string(),
+    })
+}
+
+/// Atomically replace the symlink at `link` with one pointing to `target`.
+fn replace_symlink(link: &Path, target: &Path) -> Result<()> {
+    let failed = |source: std::io::Error| MvlnError::SymlinkFailed {
+        link: link.to_path_buf(),
+        target: target.to_path_buf(),
+        source,
+    };
+
+    let mut tmp_name = link.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".mvln-tmp");
+    let tmp = link.with_file_name(tmp_name);
+    let _ = fs::remove_file(&tmp);
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, &tmp).map_err(failed)?;
+
+    #[cfg(windows)]
+    {
+        let is_dir = link.metadata().is_ok_and(|m| m.is_dir());
+        if is_dir {
+            std::os::windows::fs::symlink_dir(target, &tmp)
+        } else {
+            std::os::windows::fs::symlink_file(target, &tmp)
+        }
+        .map_err(failed)?;
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    return Err(failed(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "symlinks not supported on this platform",
+    )));
+
+    fs::rename(&tmp, link).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        failed(e)
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn retarget_link_points_at_the_new_location() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let link = tmp.path().join("link");
+        symlink("old", &link).unwrap();
+        let dest = tmp.path().join("archive/file");
+
+        let target = retarget_link(&link, &dest, &SymlinkStyle::Relative, true).unwrap();
+        assert_eq!(target, Path::new("archive/file"));
+        assert_eq!(fs::read_link(&link).unwrap(), Path::new("old"));
+
+        retarget_link(&link, &dest, &SymlinkStyle::Relative, false).unwrap();
+        assert_eq!(fs::read_link(&link).unwrap(), Path::new("archive/file"));
+        assert!(retarget_link(&dest, &link, &SymlinkStyle::Relative, false).is_err());
+    }
+
+    #[test]
+    fn converts_absolute_to_relative_and_back() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let target = tmp.path().join("archive/file.txt");
+        fs::create_dir(tmp.path().join("archive")).unwrap();
+        fs::create_dir(tmp.path().join("work")).unwrap();
+        fs::write(&target, "data").unwrap();
+        let link = tmp.path().join("work/file.txt");
+        symlink(&target, &link).unwrap();
+
+        let result = convert_link(&link, &SymlinkStyle::Relative, false).unwrap();
+        assert!(result.changed());
+        assert_eq!(
+            fs::read_link(&link).unwrap(),
+            PathBuf::from("../archive/file.txt")
+        );
+        assert_eq!(fs::read_to_string(&link).unwrap(), "data");
+
+        convert_link(&link, &SymlinkStyle::Absolute, false).unwrap();
+        assert_eq!(fs::read_link(&link).unwrap(), target);
+    }
+
+    #[test]
+    fn already_converted_link_is_unchanged() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let link = tmp.path().join("link");
+        symlink("target", &link).unwrap();
+
+        let result = convert_link(&link, &SymlinkStyle::Relative, false).unwrap();
+        assert!(!result.changed());
+    }
+
+    #[test]
+    fn dry_run_does_not_rewrite() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let link = tmp.path().join("link");
+        symlink("target", &link).unwrap();
+
+        let result = convert_link(&link, &SymlinkStyle::Absolute, true).unwrap();
+        assert!(result.changed());
+        assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("target"));
+    }
+
+    #[test]
+    fn regular_file_is_rejected() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("file");
+        fs::write(&file, "").unwrap();
+
+        let err = convert_link(&file, &SymlinkStyle::Relative, false).unwrap_err();
+        assert!(matches!(err, MvlnError::InvalidPath { .. }));
+    }
+
+    #[test]
+    fn adopt_records_link_once() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let journal = Journal::open(tmp.path().join("journal"));
+        fs::write(tmp.path().join("data"), "").unwrap();
+        let link = tmp.path().join("link");
+        symlink("data", &link).unwrap();
+
+        let entry = adopt(&link, &journal, false).unwrap().unwrap();
+        assert_eq!(entry.action, Action::Adopt);
+        assert_eq!(entry.target, tmp.path().join("data"));
+        assert!(adopt(&link, &journal, false).unwrap().is_none());
+        assert_eq!(journal.entries().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn adopt_rejects_dangling_link() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let journal = Journal::open(tmp.path().join("journal"));
+        let link = tmp.path().join("link");
+        symlink("missing", &link).unwrap();
+
+        assert!(adopt(&link, &journal, false).is_err());
+        assert!(journal.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn stow_links_files_and_creates_real_directories() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let pkg = tmp.path().join("dotfiles/vim");
+        let prefix = tmp.path().join("home");
+        fs::create_dir_all(pkg.join(".vim/colors")).unwrap();
+        fs::write(pkg.join(".vimrc"), "set nu").unwrap();
+        fs::write(pkg.join(".vim/colors/dark.vim"), "").unwrap();
+        fs::create_dir(&prefix).unwrap();
+
+        let plan = plan_stow(&pkg, &prefix, &SymlinkStyle::Relative).unwrap();
+        assert_eq!(plan.links.len(), 2);
+        assert!(plan.conflicts.is_empty());
+        apply_stow(&plan).unwrap();
+
+        assert!(!prefix.join(".vim").is_symlink());
+        assert!(prefix.join(".vim/colors").is_dir());
+        assert_eq!(
+            fs::read_link(prefix.join(".vimrc")).unwrap(),
+            PathBuf::from("../dotfiles/vim/.vimrc")
+        );
+        assert!(prefix.join(".vim/colors/dark.vim").is_file());
+
+        // Running again finds everything in place
+        let again = plan_stow(&pkg, &prefix, &SymlinkStyle::Relative).unwrap();
+        assert!(again.links.is_empty());
+        assert_eq!(again.existing.len(), 2);
+    }
+
+    #[test]
+    fn stow_reports_conflicts_without_changes() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let pkg = tmp.path().join("pkg");
+        let prefix = tmp.path().join("prefix");
+        fs::create_dir_all(&pkg).unwrap();
+        fs::create_dir_all(&prefix).unwrap();
+        fs::write(pkg.join("a"), "").unwrap();
+        fs::write(pkg.join("b"), "").unwrap();
+        fs::write(prefix.join("b"), "mine").unwrap();
+
+        let plan = plan_stow(&pkg, &prefix, &SymlinkStyle::Relative).unwrap();
+        assert_eq!(plan.conflicts, vec![prefix.join("b")]);
+        assert!(apply_stow(&plan).is_err());
+        assert!(!prefix.join("a").exists());
+    }
+}
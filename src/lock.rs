@@ -0,0 +1,190 @@
+//! Advisory locking between concurrent mvln runs.
+//!
+//! Two mvln invocations working on the same destination (for example one
+//! started by cron and one started by hand) can otherwise race on the same
+//! files. Before touching anything, a run takes an exclusive advisory lock
+//! keyed by its destination root. The lock files live in the state directory
+//! (see [`crate::dirs::state_dir`]) so nothing is written next to the user's
+//! files, and the lock is released automatically when the process exits.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mvln::lock::RunLock;
+//! use std::time::Duration;
+//!
+//! let _lock = RunLock::acquire("/backup", Some(Duration::from_secs(30)))?;
+//! // ... move files into /backup ...
+//! # Ok::<(), mvln::MvlnError>(())
+//! ```
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::dirs::state_dir;
+use crate::error::{MvlnError, Result};
+use crate::path_utils::absolute_path_no_follow;
+
+/// Interval between lock attempts while waiting for a timeout.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An exclusive advisory lock held for the duration of a run.
+///
+/// The lock is released when the value is dropped.
+#[derive(Debug)]
+pub struct RunLock {
+    /// Open handle carrying the lock.
+    _file: File,
+    /// Path of the lock file.
+    path: PathBuf,
+}
+
+impl RunLock {
+    /// Acquire the lock for the given destination root.
+    ///
+    /// With `timeout` set to `None` the call fails immediately if another run
+    /// holds the lock; otherwise it retries until the timeout elapses.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::LockHeld`] if the lock is still held by another
+    /// run when giving up, or [`MvlnError::LockFailed`] if the lock file
+    /// cannot be created.
+    pub fn acquire<P: AsRef<Path>>(root: P, timeout: Option<Duration>) -> Result<Self> {
+        Self::acquire_in(state_dir().join("locks"), root, timeout)
+    }
+
+    /// Acquire the lock for `root`, keeping the lock file in `lock_dir`.
+    ///
+    /// This is the same as [`RunLock::acquire`] but lets embedders choose
+    /// where lock files are stored.
+    ///
+    /// # Errors
+    ///
+    /// See [`RunLock::acquire`].
+    pub fn acquire_in<D: AsRef<Path>, P: AsRef<Path>>(
+        lock_dir: D,
+        root: P,
+        timeout: Option<Duration>,
+    ) -> Result<Self> {
+        let root = root.as_ref();
+        let path = lock_path(lock_dir.as_ref(), root);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| MvlnError::LockFailed {
+                path: path.clone(),
+                source: e,
+            })?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| MvlnError::LockFailed {
+                path: path.clone(),
+                source: e,
+            })?;
+
+        let deadline = timeout.map(|t| Instant::now() + t);
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) => {
+                    if deadline.is_none_or(|d| Instant::now() >= d) {
+                        return Err(MvlnError::LockHeld {
+                            root: root.to_path_buf(),
+                        });
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(MvlnError::LockFailed { path, source: e });
+                }
+            }
+        }
+
+        // Record the holder for anyone inspecting the lock file by hand.
+        // This is informational only, so failures are ignored.
+        let _ = file.set_len(0);
+        let _ = writeln!(file, "{} {}", std::process::id(), root.display());
+
+        Ok(Self { _file: file, path })
+    }
+
+    /// Path of the underlying lock file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Compute the lock file path for a destination root inside `lock_dir`.
+///
+/// The root is made absolute (resolving symlinks where possible) so that
+/// different spellings of the same directory share one lock.
+#[must_use]
+pub fn lock_path(lock_dir: &Path, root: &Path) -> PathBuf {
+    let key = root
+        .canonicalize()
+        .unwrap_or_else(|_| absolute_path_no_follow(root));
+
+    lock_dir.join(format!(
+        "{:016x}.lock",
+        fnv1a(key.as_os_str().as_encoded_bytes())
+    ))
+}
+
+/// 64-bit FNV-1a hash, used to derive stable lock file names.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn lock_path_is_same_for_equivalent_roots() {
+        let cwd = std::env::current_dir().unwrap();
+        let dir = Path::new("/locks");
+        assert_eq!(lock_path(dir, Path::new(".")), lock_path(dir, &cwd));
+    }
+
+    #[test]
+    fn different_roots_use_different_lock_files() {
+        let dir = Path::new("/locks");
+        assert_ne!(
+            lock_path(dir, Path::new("/nonexistent/a")),
+            lock_path(dir, Path::new("/nonexistent/b"))
+        );
+    }
+
+    #[test]
+    fn second_acquire_fails_while_held() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let locks = tmp.path().join("locks");
+
+        let held = RunLock::acquire_in(&locks, tmp.path(), None).unwrap();
+        let err = RunLock::acquire_in(&locks, tmp.path(), None).unwrap_err();
+        assert!(matches!(err, MvlnError::LockHeld { .. }));
+
+        drop(held);
+        assert!(RunLock::acquire_in(&locks, tmp.path(), None).is_ok());
+    }
+}
@@ -0,0 +1,148 @@
+//! The `--log-file` run log.
+//!
+//! Unattended runs (e.g. from cron) usually discard stdout and stderr, so
+//! `--log-file PATH` appends a plain-text record of the run to PATH: one
+//! line per event, each starting with a UTC timestamp and a kind.
+//!
+//! ```text
+//! 2026-10-15T03:00:01Z RUN      mvln --log-file /var/log/mvln.log inbox/a.pdf /archive
+//! 2026-10-15T03:00:01Z ACTION   mv inbox/a.pdf /archive
+//! 2026-10-15T03:00:01Z ACTION   ln -s ../archive/a.pdf inbox/a.pdf
+//! 2026-10-15T03:00:01Z RUN      complete: 1 moved, 1 symlinked, 0 skipped, 0 failed
+//! 2026-10-15T03:00:01Z RUN      exit status 0
+//! ```
+//!
+//! Actions are the echoed commands in POSIX form, logged even with
+//! `--no-commands`. Log lines are always in English so they can be searched
+//! and parsed regardless of `--lang`. Errors carry their code (see
+//! [`MvlnError::code`]).
+//!
+//! With `--log syslog|journald` (Unix, `syslog` feature) the same events
+//! also go to the system log; see [`crate::syslog`].
+
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+
+use mvln::date::DateTime;
+use mvln::error::{MvlnError, Result};
+
+#[cfg(all(unix, feature = "syslog"))]
+use crate::syslog::SystemLog;
+
+thread_local! {
+    /// The open log file, if `--log-file` was given.
+    static LOG: RefCell<Option<File>> = const { RefCell::new(None) };
+
+    /// The system log, if `--log` was given.
+    #[cfg(all(unix, feature = "syslog"))]
+    static SYSTEM: RefCell<Option<SystemLog>> = const { RefCell::new(None) };
+}
+
+/// What a log line records.
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    /// Start and end of the run.
+    Run,
+    /// A change made (or, with `--dry-run`, planned) on disk.
+    Action,
+    /// A choice not to act, e.g. a deferred or already linked path.
+    Decision,
+    /// A failure; the run continues with the next path where possible.
+    Error,
+}
+
+impl Kind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Run => "RUN",
+            Self::Action => "ACTION",
+            Self::Decision => "DECISION",
+            Self::Error => "ERROR",
+        }
+    }
+}
+
+/// Open `path` for appending and log subsequent events to it.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::LogFileFailed`] if the file cannot be opened.
+pub fn open(path: &Path) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| MvlnError::LogFileFailed {
+            path: path.to_path_buf(),
+            source: e,
+        })?;
+    LOG.with(|log| *log.borrow_mut() = Some(file));
+    Ok(())
+}
+
+/// Also send subsequent events to the system log selected by `--log`.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::LogFileFailed`] naming the socket if the system
+/// log is not available.
+#[cfg(all(unix, feature = "syslog"))]
+pub fn open_system(target: crate::cli::LogTarget) -> Result<()> {
+    let log = SystemLog::connect(target)?;
+    SYSTEM.with(|system| *system.borrow_mut() = Some(log));
+    Ok(())
+}
+
+/// Append a line to the log, if one is open.
+///
+/// Write failures are ignored: losing a log line must not abort a batch
+/// that is halfway through moving files.
+pub fn write(kind: Kind, text: &str) {
+    #[cfg(all(unix, feature = "syslog"))]
+    SYSTEM.with(|system| {
+        if let Some(log) = system.borrow().as_ref() {
+            log.send(kind, text);
+        }
+    });
+
+    LOG.with(|log| {
+        if let Some(file) = log.borrow_mut().as_mut() {
+            let time = DateTime::from_system_time(SystemTime::now()).format("%FT%H:%M:%SZ");
+            // One line per event, even for multi-line text
+            let text = text.replace('\n', " ");
+            let _ = writeln!(file, "{time} {:<8} {text}", kind.label());
+        }
+    });
+}
+
+/// Log `error` with its code.
+pub fn error(error: &MvlnError) {
+    write(Kind::Error, &format!("[{}] {error}", error.code()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn appends_timestamped_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("run.log");
+        fs::write(&path, "earlier run\n").unwrap();
+
+        open(&path).unwrap();
+        write(Kind::Action, "mv a b");
+        error(&MvlnError::SourceNotFound { path: "x".into() });
+        LOG.with(|log| log.borrow_mut().take());
+
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "earlier run");
+        assert!(lines[1].ends_with("Z ACTION   mv a b"), "{}", lines[1]);
+        assert!(lines[2].contains(" ERROR    [mvln::source_not_found] source not found: x"));
+    }
+}
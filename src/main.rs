@@ -3,260 +3,1673 @@
 //! This binary provides a command-line interface to the mvln library,
 //! allowing users to move files while preserving access through symlinks.
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use fluent::FluentArgs;
+use mvln::config::{Config, Profile};
+use mvln::dedupe::DedupeIndex;
+use mvln::device;
+use mvln::dirs;
 use mvln::error::{MvlnError, Result};
-use mvln::glob_expand::expand_globs;
-use mvln::i18n;
-use mvln::operation::move_and_link;
+use mvln::estimate::Estimate;
+use mvln::glob_expand::{
+    expand_globs_matches, filter_excluded, is_glob_pattern, Expansion, GlobError,
+};
+use mvln::i18n::{self, MessageKey};
+use mvln::journal::{Action, Entry, Journal};
+use mvln::links::{self, convert_link};
+use mvln::lock::RunLock;
+use mvln::open_files::OpenFiles;
+use mvln::operation::{link_duplicate, link_only, move_and_link, MoveResult};
+use mvln::path_utils::{self, expand_path, has_trailing_separator};
+use mvln::plan::{ErrorPolicy, Layout, MovePlan, PlannedMove};
+use mvln::recovery::{self, ActiveRun, Pending, Resolution, State};
+use mvln::rules::{self, RuleSet};
+use mvln::size::{format_size, tree_size};
+use mvln::store::Store;
+use mvln::sums::Manifest;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process;
+use std::time::Duration;
 
+mod background;
 mod cli;
-use cli::Cli;
+mod completions;
+mod echo;
+mod hooks;
+mod logfile;
+mod man;
+mod pick;
+mod prompt;
+#[cfg(feature = "ssh")]
+mod remote;
+#[cfg(feature = "tui")]
+mod review;
+#[cfg(all(unix, feature = "syslog"))]
+mod syslog;
+use cli::{
+    AdoptArgs, CheckOpen, Cli, Command, ConvertLinksArgs, Dangling, LinkOnlyArgs, OrganizeArgs,
+    StowArgs,
+};
+use echo::Echo;
+use logfile::Kind;
 
-/// Shell-escape a string by wrapping it in single quotes and escaping embedded quotes.
-///
-/// This ensures paths with spaces or special characters can be safely copied to a shell.
-fn shell_escape(s: &str) -> String {
-    // If string contains no special chars, return as-is
-    if !s.contains(|c: char| {
-        c.is_whitespace() || matches!(c, '\'' | '"' | '\\' | '$' | '`' | '!' | '*' | '?' | '[')
-    }) {
-        return s.to_string();
-    }
-
-    // Otherwise, wrap in single quotes and escape embedded single quotes
-    format!("'{}'", s.replace('\'', r"'\''"))
-}
+/// Localized message bundle used throughout the CLI.
+type Bundle = fluent::FluentBundle<fluent::FluentResource>;
 
 /// Print equivalent shell command for mv operation.
 ///
 /// # Arguments
 ///
+/// * `echo` - Shell dialect to print the command in
 /// * `src_display` - Source path as entered by user (preserved for display)
 /// * `dest_display` - Destination path as entered by user (preserved for display)
-fn print_mv_command(src_display: &str, dest_display: &str) {
-    println!(
-        "mv {} {}",
-        shell_escape(src_display),
-        shell_escape(dest_display)
-    );
+fn print_mv_command(echo: Echo, src_display: &str, dest_display: &str) {
+    echo.emit(|e| e.mv(src_display, dest_display));
 }
 
 /// Print equivalent shell command for ln -s operation.
 ///
 /// # Arguments
 ///
+/// * `echo` - Shell dialect to print the command in
 /// * `target` - The symlink target (relative or absolute based on options)
 /// * `link` - The symlink location
-fn print_ln_command(target: &Path, link: &Path) {
+fn print_ln_command(echo: Echo, target: &Path, link: &Path) {
+    echo.emit(|e| e.symlink(target, link));
+}
+
+/// Print the equivalent commands for a move that may have been deduplicated.
+///
+/// A hard-linked destination is shown as `ln EXISTING DEST` plus `rm SRC`;
+/// if the hard link fell back to a normal move, the usual `mv` is shown.
+fn print_dedupe_commands(
+    echo: Echo,
+    result: &MoveResult,
+    src_display: &str,
+    bundle: &Bundle,
+    verbose: bool,
+) {
+    let Some(existing) = &result.hardlinked_from else {
+        print_mv_command(echo, src_display, &result.dest.display().to_string());
+        return;
+    };
+    echo.emit(|e| e.hardlink(existing, &result.dest));
+    echo.emit(|e| e.rm(src_display));
+    if verbose {
+        let mut args = FluentArgs::new();
+        args.set("dest", result.dest.display().to_string());
+        args.set("existing", existing.display().to_string());
+        println!("{}", i18n::msg(bundle, MessageKey::OpDedupe, Some(&args)));
+    }
+}
+
+/// Move a single source, printing the equivalent shell commands.
+///
+/// Depending on the options the file goes into the content-addressed
+/// `store`, is hard-linked to an identical copy found in `dedupe_index`, or
+/// is moved normally.
+fn transfer(
+    source: &Path,
+    dest: &Path,
+    src_display: &str,
+    store: Option<&Store>,
+    dedupe_index: Option<&mut DedupeIndex>,
+    cli: &Cli,
+    bundle: &Bundle,
+) -> Result<MoveResult> {
+    let options = cli.to_move_options();
+    let echo = cli.echo();
+
+    if let Some(store) = store {
+        let stored = store.insert(source, &options)?;
+        if stored.reused {
+            echo.emit(|e| e.rm(src_display));
+        } else {
+            if let Some(objects) = stored.result.dest.parent() {
+                echo.script_only(|e| e.mkdir(objects));
+            }
+            print_mv_command(echo, src_display, &stored.result.dest.display().to_string());
+        }
+        return Ok(stored.result);
+    }
+
+    // move_and_link creates missing parents itself; a replay script must too
+    let target = options.resolve_destination(source, dest);
+    if let Some(parent) = target
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty() && !p.exists())
+    {
+        echo.script_only(|e| e.mkdir(parent));
+    }
+
+    // Look for an identical copy to hard-link to (regular files only)
+    let duplicate = match dedupe_index {
+        Some(index) if source.symlink_metadata().is_ok_and(|m| m.is_file()) => {
+            index.find_duplicate(source).ok().flatten()
+        }
+        _ => None,
+    };
+    if let Some(existing) = duplicate {
+        let result = link_duplicate(source, dest, existing, &options)?;
+        print_dedupe_commands(echo, &result, src_display, bundle, cli.verbose);
+        return Ok(result);
+    }
+
+    if cli.interactive || cli.backup.is_some() {
+        // The final destination is only known once a conflict is resolved
+        let result = move_and_link(source, dest, &options)?;
+        if let Some(backup) = &result.backup {
+            print_mv_command(
+                echo,
+                &result.dest.display().to_string(),
+                &backup.display().to_string(),
+            );
+        }
+        print_move_command(
+            echo,
+            &result,
+            src_display,
+            &result.dest.display().to_string(),
+        );
+        return Ok(result);
+    }
+
+    // Note: move_and_link handles destination resolution (appending filename if dest is dir)
+    let result = move_and_link(source, dest, &options)?;
+    // Print equivalent command (using user's original dest for display)
+    print_move_command(echo, &result, src_display, &dest.display().to_string());
+    Ok(result)
+}
+
+/// Print the equivalent command for a completed move: `mv SRC DEST`, or
+/// just `rm SRC` if the destination already matched and nothing was copied
+/// (`--skip-same`).
+fn print_move_command(echo: Echo, result: &MoveResult, src_display: &str, dest_display: &str) {
+    if result.skipped_same {
+        echo.emit(|e| e.rm(src_display));
+    } else {
+        print_mv_command(echo, src_display, dest_display);
+    }
+}
+
+/// Report a directory source given without `-w` and return its error.
+fn reject_directory(source: &Path, bundle: &Bundle) -> MvlnError {
+    let error = MvlnError::IsDirectory {
+        path: source.to_path_buf(),
+    };
+    report(bundle, &error);
+
+    MvlnError::InvalidPath {
+        path: source.to_path_buf(),
+        reason: "is a directory, use -w/--whole-dir flag".to_string(),
+    }
+}
+
+/// Apply the `--dangling` policy to `source`. Returns whether to skip it,
+/// or the (already reported) error to fail it with.
+fn screen_dangling(source: &Path, cli: &Cli, bundle: &Bundle) -> Result<bool> {
+    if cli.dangling == Dangling::Move {
+        return Ok(false);
+    }
+    let Ok(target) = std::fs::read_link(source) else {
+        return Ok(false);
+    };
+    // metadata() follows the link; symlink loops count as dangling too
+    if source.metadata().is_ok() {
+        return Ok(false);
+    }
+    if cli.dangling == Dangling::Error {
+        let error = MvlnError::DanglingSymlink {
+            path: source.to_path_buf(),
+            target,
+        };
+        eprintln!();
+        report(bundle, &error);
+        return Err(error);
+    }
+
+    let mut args = FluentArgs::new();
+    args.set("path", source.display().to_string());
+    args.set("target", target.display().to_string());
+    eprintln!(
+        "{}",
+        i18n::msg(bundle, MessageKey::OpSkippedDangling, Some(&args))
+    );
+    logfile::write(
+        Kind::Decision,
+        &format!(
+            "skipped, dangling symlink: {} -> {}",
+            source.display(),
+            target.display()
+        ),
+    );
+    Ok(true)
+}
+
+/// Apply the `--check-open` policy to `source`, looked up in `open`.
+/// Returns whether to skip it, or the (already reported) error to fail it
+/// with.
+fn screen_open(
+    source: &Path,
+    open: Option<&OpenFiles>,
+    cli: &Cli,
+    bundle: &Bundle,
+) -> Result<bool> {
+    let (Some(open), Some(policy)) = (open, cli.check_open) else {
+        return Ok(false);
+    };
+    let Some(&(file, writer)) = open.writers(source).first() else {
+        return Ok(false);
+    };
+    if policy == CheckOpen::Fail {
+        let error = MvlnError::OpenForWriting {
+            path: file.to_path_buf(),
+            process: writer.to_string(),
+        };
+        eprintln!();
+        report(bundle, &error);
+        return Err(error);
+    }
+
+    let mut args = FluentArgs::new();
+    args.set("path", source.display().to_string());
+    args.set("file", file.display().to_string());
+    args.set("process", writer.to_string());
+    let (key, decision) = if policy == CheckOpen::Skip {
+        (MessageKey::OpSkippedOpen, "skipped")
+    } else {
+        (MessageKey::WarnOpenForWriting, "moved anyway")
+    };
+    eprintln!("{}", i18n::msg(bundle, key, Some(&args)));
+    logfile::write(
+        Kind::Decision,
+        &format!(
+            "{decision}, open for writing by {writer}: {}",
+            source.display()
+        ),
+    );
+    Ok(policy == CheckOpen::Skip)
+}
+
+/// Print the verbose description of a completed move.
+fn print_verbose_result(result: &MoveResult, linked: bool, bundle: &Bundle) {
+    let mut args = FluentArgs::new();
+    args.set("src", result.source.display().to_string());
+    args.set("dest", result.dest.display().to_string());
+    println!("{}", i18n::msg(bundle, MessageKey::OpMoving, Some(&args)));
+    if !linked {
+        return;
+    }
+
+    let mut link_args = FluentArgs::new();
+    link_args.set("link", result.source.display().to_string());
+    link_args.set("target", result.symlink_target.display().to_string());
     println!(
-        "ln -s {} {}",
-        shell_escape(&target.display().to_string()),
-        shell_escape(&link.display().to_string())
+        "{}",
+        i18n::msg(bundle, MessageKey::OpLinking, Some(&link_args))
     );
 }
 
+/// Print equivalent shell commands for a link farm.
+///
+/// Each directory is created (`mkdir -p`) before the first link in it.
+fn print_farm_commands(echo: Echo, farm: &[(PathBuf, PathBuf)]) {
+    let mut last_dir: Option<&Path> = None;
+    for (link, target) in farm {
+        let dir = link.parent();
+        if dir != last_dir {
+            if let Some(dir) = dir {
+                echo.emit(|e| e.mkdir(dir));
+            }
+            last_dir = dir;
+        }
+        print_ln_command(echo, target, link);
+    }
+}
+
 /// Print recovery command when symlink creation fails.
 ///
 /// # Arguments
 ///
+/// * `echo` - Shell dialect to print the command in
 /// * `bundle` - Fluent bundle for i18n messages
 /// * `dest` - Where the file was moved to
 /// * `src` - Original source location
-fn print_recovery_command(
-    bundle: &fluent::FluentBundle<fluent::FluentResource>,
-    dest: &Path,
-    src: &Path,
-) {
+fn print_recovery_command(echo: Echo, bundle: &Bundle, dest: &Path, src: &Path) {
     let mut args = FluentArgs::new();
     args.set("dest", dest.display().to_string());
-    println!("\n{}", i18n::msg(bundle, "recovery-header", Some(&args)));
-    println!("{}", i18n::simple_msg(bundle, "recovery-command"));
+    println!(
+        "\n{}",
+        i18n::msg(bundle, MessageKey::RecoveryHeader, Some(&args))
+    );
+    println!("{}", i18n::simple_msg(bundle, MessageKey::RecoveryCommand));
 
-    // Use shell-escaped paths for the command
     println!(
-        "  mv {} {}",
-        shell_escape(&dest.display().to_string()),
-        shell_escape(&src.display().to_string())
+        "  {}",
+        echo.mv(&dest.display().to_string(), &src.display().to_string())
     );
 }
 
 /// Main entry point for mvln CLI.
 fn main() {
-    if let Err(e) = run() {
-        eprintln!("{e}");
-        process::exit(1);
+    // Parse CLI arguments
+    let cli = Cli::parse();
+
+    // Initialize i18n (--lang, then MVLN_LANG, then the system locale)
+    let bundle = cli
+        .lang
+        .as_deref()
+        .map_or_else(i18n::init, i18n::init_with_locale);
+
+    // The worker of a --background job records how it ended
+    if let Err(e) = background::attach() {
+        report(&bundle, &e);
+        process::exit(exit_code(&e));
+    }
+
+    let code = match run(cli, &bundle) {
+        Ok(()) => 0,
+        Err(e) => {
+            report(&bundle, &e);
+            exit_code(&e)
+        }
+    };
+    logfile::write(Kind::Run, &format!("exit status {code}"));
+    if let Err(e) = background::finish(code) {
+        report(&bundle, &e);
+    }
+    if code != 0 {
+        process::exit(code);
+    }
+}
+
+/// Some operations failed; the rest succeeded.
+const EXIT_FAILED: i32 = 1;
+/// The command line is invalid. clap uses the same status for parse errors.
+const EXIT_USAGE: i32 = 2;
+/// A source pattern matched no files.
+const EXIT_NO_MATCHES: i32 = 3;
+/// A file was left split between source and destination and needs recovery.
+const EXIT_DATA_AT_RISK: i32 = 4;
+
+/// The process exit status for a run that failed with `error`.
+fn exit_code(error: &MvlnError) -> i32 {
+    if error.is_data_at_risk() {
+        return EXIT_DATA_AT_RISK;
+    }
+    match error {
+        MvlnError::NoMatches { .. } => EXIT_NO_MATCHES,
+        MvlnError::InvalidDestination { .. }
+        | MvlnError::GlobExpansionFailed { .. }
+        | MvlnError::TooManyMatches { .. }
+        | MvlnError::ProfileNotFound { .. } => EXIT_USAGE,
+        _ => EXIT_FAILED,
+    }
+}
+
+/// Fold the errors of a batch into its result.
+fn batch_result(errors: &[MvlnError]) -> Result<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(MvlnError::BatchOperationFailed {
+            count: errors.len(),
+            data_at_risk: errors.iter().filter(|e| e.is_data_at_risk()).count(),
+        })
+    }
+}
+
+/// Print `error` as a localized diagnostic and record it in the run log.
+fn report(bundle: &Bundle, error: &MvlnError) {
+    eprintln!("{}", i18n::error_msg(bundle, error));
+    logfile::error(error);
+}
+
+/// Log the command line that started the run.
+fn log_start(cli: &Cli) {
+    let posix = Echo::new(cli::ShellDialect::Posix, None);
+    let args: Vec<String> = std::env::args_os()
+        .map(|arg| posix.quote(&arg.to_string_lossy()))
+        .collect();
+    logfile::write(Kind::Run, &args.join(" "));
+    if cli.dry_run {
+        logfile::write(Kind::Decision, "dry run: nothing is changed on disk");
     }
 }
 
 /// Core application logic.
-fn run() -> Result<()> {
-    // Parse CLI arguments
-    let cli = Cli::parse();
+fn run(mut cli: Cli, bundle: &Bundle) -> Result<()> {
+    if let Some(path) = &cli.log_file {
+        logfile::open(path)?;
+    }
+    #[cfg(all(unix, feature = "syslog"))]
+    if let Some(target) = cli.log {
+        logfile::open_system(target)?;
+    }
+    log_start(&cli);
+    if cli.background && !background::is_worker() {
+        return background::start(bundle);
+    }
+    let script = cli.emit_script.clone();
+    if script.is_some() {
+        echo::record_script();
+    }
 
-    // Initialize i18n
-    let bundle = i18n::init();
+    let command = cli.command.take();
+    let resume = matches!(command, Some(Command::Resume));
+    let _run = match command {
+        Some(
+            Command::Completions(_) | Command::Man | Command::CompleteProfiles | Command::Status(_),
+        ) => None,
+        _ => recover(&cli, resume, bundle)?,
+    };
+
+    let result = match command {
+        Some(Command::Resume) => Ok(()),
+        Some(Command::Status(args)) => background::run_status(&args, bundle),
+        Some(Command::Organize(args)) => run_organize(&cli, &args, bundle),
+        Some(Command::ConvertLinks(args)) => run_convert_links(&cli, &args, bundle),
+        Some(Command::Adopt(args)) => run_adopt(&cli, &args, bundle),
+        Some(Command::LinkOnly(args)) => run_link_only(&cli, &args, bundle),
+        Some(Command::Stow(args)) => run_stow(&cli, &args, bundle),
+        Some(Command::Completions(args)) => {
+            print!("{}", completions::generate(args.shell, &mut Cli::command()));
+            Ok(())
+        }
+        Some(Command::Man) => {
+            print!("{}", man::render(&mut Cli::command()));
+            Ok(())
+        }
+        Some(Command::CompleteProfiles) => {
+            // Completion must not print errors into the user's prompt
+            for name in load_config(&cli)
+                .map(|c| c.profile_names())
+                .unwrap_or_default()
+            {
+                println!("{name}");
+            }
+            Ok(())
+        }
+        None => run_move(cli, bundle),
+    };
+
+    // Write the script even after a failure, covering what was done
+    match script {
+        Some(path) => result.and(echo::write_script(&path)),
+        None => result,
+    }
+}
+
+/// Register the run and deal with moves an earlier run was interrupted in.
+///
+/// Moves that only lack their symlink or journal entry are finished; the
+/// others are listed with a pointer to `mvln resume`. With `resume`, all of
+/// them are finished. Interrupted moves cannot be told from those of another
+/// live run, so nothing is done while one is active.
+fn recover(cli: &Cli, resume: bool, bundle: &Bundle) -> Result<Option<ActiveRun>> {
+    let journal = Journal::open_default();
+    let started = ActiveRun::start(&journal).and_then(|run| {
+        let pending = if run.is_exclusive() {
+            recovery::pending(&journal)?
+        } else {
+            Vec::new()
+        };
+        Ok((run, pending))
+    });
+    let (mut run, pending) = match started {
+        Ok(started) => started,
+        Err(e) if resume => return Err(e),
+        Err(e) => {
+            // Recovery is a courtesy; it must not stop the run itself
+            let mut args = FluentArgs::new();
+            args.set("reason", e.to_string());
+            eprintln!(
+                "{}",
+                i18n::msg(bundle, MessageKey::WarnRecoveryFailed, Some(&args))
+            );
+            logfile::error(&e);
+            return Ok(None);
+        }
+    };
+    if resume && !run.is_exclusive() {
+        println!("{}", i18n::msg(bundle, MessageKey::OpResumeBusy, None));
+        return Ok(Some(run));
+    }
 
-    // Convert CLI arguments to library options
     let options = cli.to_move_options();
+    let mut errors = Vec::new();
+    let mut left = Vec::new();
+    for pending in &pending {
+        if !resume && (cli.dry_run || !pending.state.is_automatic()) {
+            left.push(pending);
+            continue;
+        }
+        let mut args = FluentArgs::new();
+        args.set("src", pending.entry.link.display().to_string());
+        args.set("dest", pending.entry.target.display().to_string());
+        match recovery::finish(pending, &journal, &options) {
+            Ok(resolution) => {
+                let key = if resolution == Resolution::Dropped {
+                    MessageKey::OpRecoveryDropped
+                } else {
+                    MessageKey::OpRecovered
+                };
+                println!("{}", i18n::msg(bundle, key, Some(&args)));
+                logfile::write(
+                    Kind::Decision,
+                    &format!(
+                        "interrupted move {} -> {}: {resolution:?}",
+                        pending.entry.link.display(),
+                        pending.entry.target.display()
+                    ),
+                );
+            }
+            Err(e) => {
+                report(bundle, &e);
+                errors.push(e);
+                left.push(pending);
+            }
+        }
+    }
+    if !resume {
+        warn_interrupted(&left, bundle);
+    }
+    run.share()?;
+
+    if resume {
+        if pending.is_empty() {
+            println!("{}", i18n::msg(bundle, MessageKey::OpResumeNone, None));
+        } else {
+            let mut args = FluentArgs::new();
+            args.set("finished", pending.len() - errors.len());
+            args.set("failed", errors.len());
+            println!(
+                "{}",
+                i18n::msg(bundle, MessageKey::OpResumeComplete, Some(&args))
+            );
+        }
+        batch_result(&errors)?;
+    }
+    Ok(Some(run))
+}
+
+/// List the interrupted moves left for `mvln resume`.
+fn warn_interrupted(left: &[&Pending], bundle: &Bundle) {
+    if left.is_empty() {
+        return;
+    }
+    let mut args = FluentArgs::new();
+    args.set("count", left.len());
+    eprintln!();
+    eprintln!(
+        "{}",
+        i18n::msg(bundle, MessageKey::WarnInterrupted, Some(&args))
+    );
+    for pending in left {
+        let mut args = FluentArgs::new();
+        args.set("src", pending.entry.link.display().to_string());
+        args.set("dest", pending.entry.target.display().to_string());
+        args.set(
+            "state",
+            match pending.state {
+                State::Done => "done",
+                State::Moved => "moved",
+                State::NotMoved => "not-moved",
+                State::Copied => "copied",
+                State::Missing => "missing",
+            },
+        );
+        eprintln!(
+            "  {}",
+            i18n::msg(bundle, MessageKey::WarnInterruptedItem, Some(&args))
+        );
+    }
+    eprintln!(
+        "{}",
+        i18n::msg(bundle, MessageKey::WarnInterruptedHint, Some(&args))
+    );
+    eprintln!();
+    logfile::write(
+        Kind::Error,
+        &format!("{} interrupted move(s) left for `mvln resume`", left.len()),
+    );
+}
+
+/// Warn about partial copies in `dir` that no planned move resumes.
+fn warn_staged(dir: &Path, plan: &MovePlan, cli: &Cli, bundle: &Bundle) {
+    let planned: Vec<PathBuf> = plan
+        .iter()
+        .map(|planned| final_dest(&planned.source, &planned.dest, cli))
+        .collect();
+    for staged in recovery::staged_copies(dir) {
+        if planned.contains(&staged.dest) {
+            continue;
+        }
+        let mut args = FluentArgs::new();
+        args.set("path", staged.path.display().to_string());
+        args.set("dest", staged.dest.display().to_string());
+        eprintln!("{}", i18n::msg(bundle, MessageKey::WarnStaged, Some(&args)));
+    }
+}
+
+/// Move the sources given on the command line to a single destination.
+fn run_move(mut cli: Cli, bundle: &Bundle) -> Result<()> {
+    // Apply the selected profile and then the preset, if any, so the
+    // profile's settings win over the preset's
+    let profile = load_profile(&cli)?;
+    let preset = load_preset(&cli)?;
+    for selected in profile.iter().chain(&preset) {
+        cli.apply_profile(selected);
+    }
 
-    // Expand glob patterns in source paths
-    let source_paths = expand_sources(&cli.source)?;
+    let default_dest = profile
+        .iter()
+        .chain(&preset)
+        .find_map(|p| p.dest.as_deref());
+    let Some((sources, dest)) = cli.operands(default_dest) else {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::MissingRequiredArgument,
+                "a destination is required (give it as the last path or use a profile with 'dest')",
+            )
+            .exit();
+    };
+    #[cfg(feature = "ssh")]
+    let remote = dest
+        .to_str()
+        .and_then(mvln::ssh::Remote::parse)
+        .filter(|_| !dest.exists());
+    let dest = expand_path(&dest)?;
+
+    // Expand glob patterns in source paths, then drop excluded ones
+    let expansion = expand_sources(&sources, &cli, bundle)?;
+    let typed = typed_sources(&sources, &expansion);
+    let source_paths = filter_excluded(expansion.into_paths(), &cli.exclude).map_err(|e| {
+        MvlnError::GlobExpansionFailed {
+            reason: e.to_string(),
+        }
+    })?;
+    let size_filter = cli.size_filter();
+    let source_paths: Vec<PathBuf> = source_paths
+        .into_iter()
+        .filter(|path| size_filter.matches_path(path))
+        .collect();
+    #[cfg(feature = "ssh")]
+    if let Some(remote) = remote {
+        return remote::run_remote(&cli, &source_paths, &remote, bundle);
+    }
 
-    // Validate: if multiple sources, destination must be a directory
-    if source_paths.len() > 1 && !cli.dest.is_dir() {
+    let layout = cli.layout();
+    if layout == Layout::Flat && !cli.cas {
+        // Validate: if multiple sources, destination must be a directory
+        // (or, written with a trailing slash, one to create)
+        let into_dir = dest.is_dir() || has_trailing_separator(&dest);
+        if source_paths.len() > 1 && (!into_dir || cli.no_target_directory) {
+            return Err(MvlnError::InvalidDestination {
+                reason: "destination must be a directory when moving multiple files".to_string(),
+            });
+        }
+    } else if dest.exists() && !dest.is_dir() {
+        // Routed layouts and the store create subdirectories under the destination
         return Err(MvlnError::InvalidDestination {
-            reason: "destination must be a directory when moving multiple files".to_string(),
+            reason: "destination must be a directory when sorting into subdirectories".to_string(),
         });
     }
 
-    // Track statistics
-    let mut files_moved = 0;
-    let mut symlinks_created = 0;
-    let mut errors = Vec::new();
+    let mut plan = MovePlan::build(&source_paths, &dest, &layout);
+    let dereferenced = if cli.dereference {
+        dereference_sources(&mut plan, cli.flatten_links)
+    } else {
+        Vec::new()
+    };
+    if let Some(max_depth) = cli.max_depth {
+        for skipped in plan.split_dirs(max_depth, cli.skip_deeper) {
+            logfile::write(
+                Kind::Decision,
+                &format!(
+                    "{}: below --max-depth, left in place",
+                    skipped.source.display()
+                ),
+            );
+        }
+    }
+    if let Some(sort) = cli.sort {
+        plan.sort(sort.into(), cli.reverse);
+    }
+    if cli.rename_on_conflict {
+        plan.rename_conflicts();
+    }
+    let refused = refuse_cross_device(&cli, &mut plan, bundle);
+    warn_network_links(&cli, &plan, bundle);
 
-    // Process each source file
-    for source in &source_paths {
-        // Check if source is a directory (don't follow symlinks)
-        let is_dir = source
-            .symlink_metadata()
-            .map(|m| m.is_dir())
-            .unwrap_or(false);
+    if !pick_sources(&cli, &mut plan, bundle) {
+        return Ok(());
+    }
 
-        if is_dir && !cli.whole_dir {
-            // Error: directory requires -w flag
-            let mut args = FluentArgs::new();
-            args.set("path", source.display().to_string());
-            eprintln!("{}", i18n::msg(&bundle, "err-is-directory", Some(&args)));
+    let deferred = plan.limit(cli.max_files, cli.max_bytes);
+    if !review_plan(&cli, &mut plan, bundle)? {
+        return Ok(());
+    }
 
-            // Print hint about using -w or glob
-            if let Some(attr) = bundle
-                .get_message("err-is-directory")
-                .and_then(|m| m.get_attribute("hint"))
-            {
-                let mut errors = vec![];
-                let hint = bundle.format_pattern(attr.value(), Some(&args), &mut errors);
-                eprintln!("  {hint}");
+    // Hold the run lock for the destination root until we're done
+    let root = if cli.cas {
+        dest.clone()
+    } else {
+        lock_root(&dest)
+    };
+    let _lock = acquire_lock(&cli, &root)?;
+    if !cli.cas {
+        warn_staged(&root, &plan, &cli, bundle);
+    }
+
+    background::plan(&plan, &cli);
+    let result = execute_plan(&plan, refused, &typed, &root, &cli, bundle);
+    let repointed = repoint_links(&dereferenced, &plan, &cli, bundle);
+    report_deferred(&deferred, bundle);
+    result.and(repointed)
+}
+
+/// With `-L`, replace symlink sources in `plan` by the files they resolve
+/// to. Returns the `(link, resolved)` pairs for [`repoint_links`], which
+/// with `--flatten-links` also include the intermediate links of chains.
+/// Links to files already in the plan are not planned twice; dangling links
+/// are left to the `--dangling` policy.
+fn dereference_sources(plan: &mut MovePlan, flatten: bool) -> Vec<(PathBuf, PathBuf)> {
+    let mut dereferenced: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let mut moves = Vec::with_capacity(plan.len());
+    for mut planned in plan.moves.drain(..) {
+        if planned.source.is_symlink() {
+            if let Ok(resolved) = planned.source.canonicalize() {
+                let mut chain = vec![planned.source.clone()];
+                if flatten {
+                    chain.extend(link_chain(&planned.source));
+                }
+                for link in chain {
+                    if !dereferenced.iter().any(|(l, _)| *l == link) {
+                        dereferenced.push((link, resolved.clone()));
+                    }
+                }
+                planned.source = resolved;
             }
+        }
+        if !moves
+            .iter()
+            .any(|m: &PlannedMove| m.source == planned.source)
+        {
+            moves.push(planned);
+        }
+    }
+    plan.moves = moves;
+    dereferenced
+}
 
-            errors.push(MvlnError::InvalidPath {
-                path: source.clone(),
-                reason: "is a directory, use -w/--whole-dir flag".to_string(),
-            });
-            continue; // Skip this source
+/// The symlinks `link` leads through before reaching a file, not counting
+/// `link` itself, with their directories resolved.
+fn link_chain(link: &Path) -> Vec<PathBuf> {
+    // As many hops as Linux follows before giving up with ELOOP
+    const MAX_HOPS: usize = 40;
+    let mut chain = Vec::new();
+    let mut current = link.to_path_buf();
+    while chain.len() < MAX_HOPS {
+        let Ok(target) = std::fs::read_link(&current) else {
+            break;
+        };
+        let next = current.parent().unwrap_or(Path::new(".")).join(target);
+        let (Some(dir), Some(name)) = (next.parent(), next.file_name()) else {
+            break;
+        };
+        let Ok(dir) = dir.canonicalize() else {
+            break;
+        };
+        let next = dir.join(name);
+        if !next.is_symlink() {
+            break;
         }
-        // Preserve user input format for display (important for mv command output)
-        let src_display = find_original_input(&cli.source, source);
+        chain.push(next.clone());
+        current = next;
+    }
+    chain
+}
 
-        // Print equivalent mv command (using user's original dest for display)
-        print_mv_command(&src_display, &cli.dest.display().to_string());
+/// Point the links dereferenced by `-L` at where their targets were moved.
+/// Targets that were not moved (failed, skipped or deferred) keep their
+/// links as they are.
+fn repoint_links(
+    dereferenced: &[(PathBuf, PathBuf)],
+    plan: &MovePlan,
+    cli: &Cli,
+    bundle: &Bundle,
+) -> Result<()> {
+    let journal = Journal::open_default();
+    let mut errors = Vec::new();
+    for (link, resolved) in dereferenced {
+        let moved_to = if cli.dry_run {
+            plan.iter()
+                .find(|planned| planned.source == *resolved)
+                .map(|planned| final_dest(&planned.source, &planned.dest, cli))
+        } else if resolved.is_symlink() {
+            // The target became a symlink to its new location
+            resolved.canonicalize().ok()
+        } else {
+            None
+        };
+        let Some(moved_to) = moved_to else {
+            continue;
+        };
+        match links::retarget_link(link, &moved_to, &cli.symlink_style(), cli.dry_run) {
+            Ok(target) => {
+                cli.echo().emit(|e| e.replace_symlink(&target, link));
+                if !cli.dry_run {
+                    record(&journal, Action::Move, link, &moved_to, bundle);
+                }
+            }
+            Err(e) => {
+                report(bundle, &e);
+                errors.push(e);
+            }
+        }
+    }
+    batch_result(&errors)
+}
 
-        // Execute move-and-link operation
-        // Note: move_and_link handles destination resolution (appending filename if dest is dir)
-        match move_and_link(source, &cli.dest, &options) {
-            Ok(result) => {
-                // Print equivalent ln -s command
-                print_ln_command(&result.symlink_target, &result.source);
+/// Organize a directory according to the configured rules.
+fn run_organize(cli: &Cli, args: &OrganizeArgs, bundle: &Bundle) -> Result<()> {
+    let config = load_config(cli)?;
+    let rules = RuleSet::from_config(&config)?;
+    if rules.is_empty() {
+        return Err(MvlnError::ConfigError {
+            path: config
+                .path
+                .or_else(|| cli.config.clone().or_else(dirs::config_file))
+                .unwrap_or_default(),
+            reason: "no [rules] defined".to_string(),
+        });
+    }
 
-                files_moved += 1;
-                symlinks_created += 1;
+    let dest_root = expand_path(args.dest.as_deref().unwrap_or(&args.dir))?;
+    let mut plan = rules::organize(&args.dir, &dest_root, &rules)?;
+    let size_filter = cli.size_filter();
+    plan.moves
+        .retain(|planned| size_filter.matches_path(&planned.source));
+    if let Some(sort) = cli.sort {
+        plan.sort(sort.into(), cli.reverse);
+    }
+    let refused = refuse_cross_device(cli, &mut plan, bundle);
+    warn_network_links(cli, &plan, bundle);
 
-                if cli.verbose {
-                    let mut args = FluentArgs::new();
-                    args.set("src", result.source.display().to_string());
-                    args.set("dest", result.dest.display().to_string());
-                    println!("{}", i18n::msg(&bundle, "op-moving", Some(&args)));
+    let deferred = plan.limit(cli.max_files, cli.max_bytes);
+    if !review_plan(cli, &mut plan, bundle)? {
+        return Ok(());
+    }
 
+    let _lock = acquire_lock(cli, &dest_root)?;
+
+    let result = execute_plan(&plan, refused, &HashMap::new(), &dest_root, cli, bundle);
+    report_deferred(&deferred, bundle);
+    result
+}
+
+/// Rewrite existing symlinks to the requested target style.
+fn run_convert_links(cli: &Cli, args: &ConvertLinksArgs, bundle: &Bundle) -> Result<()> {
+    let links = expand_sources(&args.paths, cli, bundle)?.into_paths();
+    let style = args.symlink_style();
+
+    let mut converted = 0;
+    let mut unchanged = 0;
+    let mut errors = Vec::new();
+
+    for link in &links {
+        match convert_link(link, &style, cli.dry_run) {
+            Ok(result) if result.changed() => {
+                let echo = cli.echo();
+                echo.emit(|e| e.replace_symlink(&result.new_target, &result.link));
+                if cli.verbose {
                     let mut link_args = FluentArgs::new();
-                    link_args.set("link", result.source.display().to_string());
-                    link_args.set("target", result.symlink_target.display().to_string());
-                    println!("{}", i18n::msg(&bundle, "op-linking", Some(&link_args)));
+                    link_args.set("link", result.link.display().to_string());
+                    link_args.set("target", result.new_target.display().to_string());
+                    println!(
+                        "{}",
+                        i18n::msg(bundle, MessageKey::OpLinking, Some(&link_args))
+                    );
                 }
+                converted += 1;
+            }
+            Ok(_) => {
+                logfile::write(
+                    Kind::Decision,
+                    &format!("already in the requested style: {}", link.display()),
+                );
+                unchanged += 1;
             }
             Err(e) => {
-                // Handle symlink failure specially (file is preserved)
-                if let MvlnError::SymlinkFailed { target, .. } = &e {
-                    eprintln!("\n{e}");
-                    print_recovery_command(&bundle, target, source);
-                    files_moved += 1; // File was moved successfully
-                } else {
-                    eprintln!("\n{e}");
-                }
+                report(bundle, &e);
                 errors.push(e);
             }
         }
     }
 
-    // Print completion summary
     println!();
     let mut summary_args = FluentArgs::new();
-    summary_args.set("files", files_moved);
-    summary_args.set("links", symlinks_created);
-    println!("{}", i18n::msg(&bundle, "op-complete", Some(&summary_args)));
+    summary_args.set("links", converted);
+    summary_args.set("unchanged", unchanged);
+    println!(
+        "{}",
+        i18n::msg(bundle, MessageKey::OpConverted, Some(&summary_args))
+    );
 
-    // Return error if any operation failed
-    if errors.is_empty() {
-        Ok(())
-    } else {
-        Err(MvlnError::BatchOperationFailed {
-            count: errors.len(),
+    batch_result(&errors)
+}
+
+/// Record pre-existing symlinks in the journal.
+fn run_adopt(cli: &Cli, args: &AdoptArgs, bundle: &Bundle) -> Result<()> {
+    let paths = expand_sources(&args.links, cli, bundle)?.into_paths();
+    let journal = Journal::open_default();
+
+    let mut adopted = 0;
+    let mut unchanged = 0;
+    let mut errors = Vec::new();
+
+    for link in &paths {
+        match links::adopt(link, &journal, cli.dry_run) {
+            Ok(Some(entry)) => {
+                let mut link_args = FluentArgs::new();
+                link_args.set("link", entry.link.display().to_string());
+                link_args.set("target", entry.target.display().to_string());
+                println!(
+                    "{}",
+                    i18n::msg(bundle, MessageKey::OpAdopted, Some(&link_args))
+                );
+                logfile::write(
+                    Kind::Action,
+                    &format!(
+                        "adopt {} -> {}",
+                        entry.link.display(),
+                        entry.target.display()
+                    ),
+                );
+                adopted += 1;
+            }
+            Ok(None) => {
+                logfile::write(
+                    Kind::Decision,
+                    &format!("already tracked: {}", link.display()),
+                );
+                unchanged += 1;
+            }
+            Err(e) => {
+                report(bundle, &e);
+                errors.push(e);
+            }
+        }
+    }
+
+    println!();
+    let mut summary_args = FluentArgs::new();
+    summary_args.set("links", adopted);
+    summary_args.set("unchanged", unchanged);
+    println!(
+        "{}",
+        i18n::msg(bundle, MessageKey::OpAdoptComplete, Some(&summary_args))
+    );
+
+    batch_result(&errors)
+}
+
+/// Create a symlink to data that was already moved by another tool.
+fn run_link_only(cli: &Cli, args: &LinkOnlyArgs, bundle: &Bundle) -> Result<()> {
+    let dest = expand_path(&args.dest)?;
+    let result = link_only(&args.source, &dest, &cli.to_move_options())?;
+
+    print_ln_command(cli.echo(), &result.symlink_target, &result.source);
+    if cli.verbose {
+        let mut link_args = FluentArgs::new();
+        link_args.set("link", result.source.display().to_string());
+        link_args.set("target", result.symlink_target.display().to_string());
+        println!(
+            "{}",
+            i18n::msg(bundle, MessageKey::OpLinking, Some(&link_args))
+        );
+    }
+
+    if !cli.dry_run {
+        let journal = Journal::open_default();
+        record(&journal, Action::Link, &result.source, &result.dest, bundle);
+    }
+    Ok(())
+}
+
+/// Link a package tree into a prefix.
+fn run_stow(cli: &Cli, args: &StowArgs, bundle: &Bundle) -> Result<()> {
+    let prefix = expand_path(args.prefix())?;
+    let plan = links::plan_stow(&args.package, &prefix, &cli.symlink_style())?;
+
+    if !plan.conflicts.is_empty() {
+        for path in &plan.conflicts {
+            let error = MvlnError::StowConflict { path: path.clone() };
+            report(bundle, &error);
+        }
+        return Err(MvlnError::BatchOperationFailed {
+            count: plan.conflicts.len(),
+            data_at_risk: 0,
+        });
+    }
+
+    for entry in &plan.links {
+        print_ln_command(cli.echo(), &entry.target, &entry.link);
+    }
+    for path in &plan.existing {
+        logfile::write(
+            Kind::Decision,
+            &format!("already linked: {}", path.display()),
+        );
+    }
+
+    if !cli.dry_run {
+        let _lock = acquire_lock(cli, &prefix)?;
+        links::apply_stow(&plan)?;
+        let journal = Journal::open_default();
+        for entry in &plan.links {
+            record(&journal, Action::Link, &entry.link, &entry.file, bundle);
+        }
+    }
+
+    println!();
+    let mut summary_args = FluentArgs::new();
+    summary_args.set("links", plan.links.len());
+    summary_args.set("existing", plan.existing.len());
+    println!(
+        "{}",
+        i18n::msg(bundle, MessageKey::OpStowComplete, Some(&summary_args))
+    );
+    Ok(())
+}
+
+/// List the sources left for a later run by `--max-files`/`--max-bytes`.
+fn report_deferred(deferred: &[PlannedMove], bundle: &Bundle) {
+    if deferred.is_empty() {
+        return;
+    }
+    let mut args = FluentArgs::new();
+    args.set("count", deferred.len());
+    println!("{}", i18n::msg(bundle, MessageKey::OpDeferred, Some(&args)));
+    for planned in deferred {
+        println!("  {}", planned.source.display());
+        logfile::write(
+            Kind::Decision,
+            &format!(
+                "deferred by --max-files/--max-bytes: {}",
+                planned.source.display()
+            ),
+        );
+    }
+}
+
+/// With `--no-cross-device`, take the moves that would cross filesystems
+/// out of `plan` before anything is moved, reporting each. Returns their
+/// errors.
+fn refuse_cross_device(cli: &Cli, plan: &mut MovePlan, bundle: &Bundle) -> Vec<MvlnError> {
+    if !cli.no_cross_device {
+        return Vec::new();
+    }
+    plan.remove_cross_device()
+        .into_iter()
+        .map(|planned| {
+            let error = MvlnError::CrossDevice {
+                dest: final_dest(&planned.source, &planned.dest, cli),
+                src: planned.source,
+            };
+            report(bundle, &error);
+            error
         })
+        .collect()
+}
+
+/// Warn, once per directory, when the links of `plan` would be stored on a
+/// network filesystem while their targets are elsewhere: other clients of
+/// the share mostly can't resolve a path that is only mounted here.
+fn warn_network_links(cli: &Cli, plan: &MovePlan, bundle: &Bundle) {
+    if cli.no_link {
+        return;
+    }
+    let mut devices = device::Devices::new();
+    let mut checked = Vec::new();
+    for planned in plan {
+        let Some(dir) = planned.source.parent() else {
+            continue;
+        };
+        if checked.iter().any(|seen| seen == dir)
+            || devices.crosses(&planned.source, &planned.dest) != Some(true)
+        {
+            continue;
+        }
+        checked.push(dir.to_path_buf());
+        let Some(network) = device::network_filesystem(dir) else {
+            continue;
+        };
+        let mut args = FluentArgs::new();
+        args.set("dir", dir.display().to_string());
+        args.set("fs", network.name());
+        eprintln!(
+            "{}",
+            i18n::msg(bundle, MessageKey::WarnNetworkLinks, Some(&args))
+        );
     }
 }
 
-/// Expand glob patterns in source arguments.
-///
-/// Regular paths are passed through as-is (existence check happens in `move_and_link`).
-fn expand_sources(sources: &[PathBuf]) -> Result<Vec<PathBuf>> {
-    let patterns: Vec<String> = sources.iter().map(|p| p.display().to_string()).collect();
+/// Let the user deselect sources with `--pick`. Returns `false` if they
+/// cancelled the run. `--yes` keeps every source without asking.
+fn pick_sources(cli: &Cli, plan: &mut MovePlan, bundle: &Bundle) -> bool {
+    if !cli.pick || cli.yes || plan.is_empty() {
+        return true;
+    }
+    let planned = plan.moves.clone();
+    if !pick::pick(plan, bundle) {
+        logfile::write(Kind::Decision, "selection cancelled, nothing moved");
+        eprintln!("{}", i18n::simple_msg(bundle, MessageKey::PickCancelled));
+        return false;
+    }
+    for dropped in planned.iter().filter(|m| !plan.moves.contains(m)) {
+        logfile::write(
+            Kind::Decision,
+            &format!("deselected with --pick: {}", dropped.source.display()),
+        );
+    }
+    true
+}
+
+/// Let the user deselect moves with `--review`. Returns `false` if they
+/// cancelled the run.
+#[cfg(feature = "tui")]
+fn review_plan(cli: &Cli, plan: &mut MovePlan, bundle: &Bundle) -> Result<bool> {
+    use std::io::IsTerminal;
+
+    if !cli.review || plan.is_empty() {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--review needs a terminal on stdin and stderr",
+            )
+            .exit();
+    }
+
+    let planned = plan.moves.clone();
+    if !review::review(plan, bundle)? {
+        logfile::write(Kind::Decision, "review cancelled, nothing moved");
+        eprintln!("{}", i18n::simple_msg(bundle, MessageKey::ReviewCancelled));
+        return Ok(false);
+    }
+    for dropped in planned.iter().filter(|m| !plan.moves.contains(m)) {
+        logfile::write(
+            Kind::Decision,
+            &format!("deselected in review: {}", dropped.source.display()),
+        );
+    }
+    Ok(true)
+}
+
+#[cfg(not(feature = "tui"))]
+#[allow(clippy::unnecessary_wraps)]
+fn review_plan(_cli: &Cli, _plan: &mut MovePlan, _bundle: &Bundle) -> Result<bool> {
+    Ok(true)
+}
 
-    expand_globs(&patterns).map_err(|e| MvlnError::GlobExpansionFailed {
-        reason: e.to_string(),
-    })
+/// Take the run lock for `root` unless disabled or in dry-run mode.
+fn acquire_lock(cli: &Cli, root: &Path) -> Result<Option<RunLock>> {
+    if cli.no_lock || cli.dry_run {
+        return Ok(None);
+    }
+    RunLock::acquire(root, cli.lock_timeout.map(Duration::from_secs)).map(Some)
 }
 
-/// Find the original user input that corresponds to an expanded path.
+/// Execute every move in `plan`, printing progress and a summary.
 ///
-/// This is used to preserve the user's input format in mv command output.
-/// For example, if user typed `./file.txt`, we should print `mv ./file.txt ...`
-/// not `mv file.txt ...`.
-fn find_original_input(original_args: &[PathBuf], expanded_path: &Path) -> String {
-    for arg in original_args {
-        let arg_str = arg.display().to_string();
-
-        // Exact match
-        if arg == expanded_path {
-            return arg_str;
-        }
-
-        // Normalized match: handle ./file vs file, trailing slashes, etc.
-        // Use canonicalize where possible for accurate comparison, otherwise
-        // fall back to component-based normalization.
-        let arg_canonical = arg.canonicalize().ok();
-        let expanded_canonical = expanded_path.canonicalize().ok();
-
-        match (&arg_canonical, &expanded_canonical) {
-            (Some(a), Some(e)) if a == e => return arg_str,
-            _ => {
-                // Fallback: component-based normalization (strips . and redundant separators)
-                let arg_normalized = arg.components().collect::<std::path::PathBuf>();
-                let expanded_normalized =
-                    expanded_path.components().collect::<std::path::PathBuf>();
-                if arg_normalized == expanded_normalized {
-                    return arg_str;
+/// `refused` are moves that already failed while planning; they count
+/// towards the summary and the result. `typed` maps sources to the
+/// arguments the user typed for them (see [`typed_sources`]) and is only
+/// used to echo commands in the user's own spelling. `dest_root` is
+/// the destination tree searched for duplicates with `--dedupe`.
+fn execute_plan(
+    plan: &MovePlan,
+    refused: Vec<MvlnError>,
+    typed: &HashMap<PathBuf, String>,
+    dest_root: &Path,
+    cli: &Cli,
+    bundle: &Bundle,
+) -> Result<()> {
+    let journal = Journal::open_default();
+    let store = cli.cas.then(|| Store::new(dest_root));
+
+    let mut dedupe_index = dedupe_index(cli, dest_root, &journal)?;
+    let open = cli.check_open.map(|_| OpenFiles::scan());
+    let mut sums = cli.write_sums.as_ref().map(|_| Manifest::new(dest_root));
+
+    // Track statistics
+    let mut files_moved = 0;
+    let mut symlinks_created = 0;
+    let mut skipped = 0;
+    let mut rejected = 0;
+    let mut errors = refused;
+    let fail_fast = cli.error_policy() == ErrorPolicy::FailFast;
+
+    // Process each source file
+    for (index, planned) in plan.iter().enumerate() {
+        let aborted = matches!(errors.last(), Some(MvlnError::ConflictAborted { .. }));
+        if aborted || (fail_fast && !errors.is_empty()) {
+            skipped += plan.len() - index;
+            logfile::write(
+                Kind::Decision,
+                &format!(
+                    "{}: skipping {} remaining source(s)",
+                    if aborted { "aborted" } else { "fail-fast" },
+                    plan.len() - index
+                ),
+            );
+            break;
+        }
+        let source = &planned.source;
+
+        // Check if source is a directory (don't follow symlinks)
+        if !cli.whole_dir && source.symlink_metadata().is_ok_and(|m| m.is_dir()) {
+            // Error: directory requires -w flag
+            errors.push(reject_directory(source, bundle));
+            skipped += 1;
+            rejected += 1;
+            continue; // Skip this source
+        }
+        match screen_dangling(source, cli, bundle)
+            .and_then(|skip| Ok(skip || screen_open(source, open.as_ref(), cli, bundle)?))
+        {
+            Ok(false) => {}
+            Ok(true) => {
+                skipped += 1;
+                continue;
+            }
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        }
+        // Preserve user input format for display (important for mv command output)
+        let src_display = typed
+            .get(source)
+            .cloned()
+            .unwrap_or_else(|| source.display().to_string());
+
+        let outcome = journaled_transfer(
+            planned,
+            &src_display,
+            store.as_ref(),
+            dedupe_index.as_mut(),
+            &journal,
+            cli,
+            bundle,
+        );
+        match outcome {
+            Ok(result) => {
+                if let Some(index) = &mut dedupe_index {
+                    index.add_file(&result.dest);
                 }
+                symlinks_created += finish_move(&result, &journal, cli, bundle);
+                files_moved += usize::from(!result.skipped_same);
+                skipped += usize::from(result.skipped_same);
+                errors.extend(reported(add_sums(sums.as_mut(), &result, cli), bundle));
+                errors.extend(reported(hooks::post(cli, &result), bundle));
+            }
+            Err(
+                e @ (MvlnError::ConflictSkipped { .. } | MvlnError::CrossDeviceDeclined { .. }),
+            ) => {
+                skipped += 1;
+                logfile::write(Kind::Decision, &e.to_string());
+            }
+            Err(e) => {
+                eprintln!();
+                report(bundle, &e);
+                // The file reached its destination but is split from its
+                // source; tell the user how to put it back
+                if e.is_data_at_risk() {
+                    if let MvlnError::SymlinkFailed { target, .. } = &e {
+                        print_recovery_command(cli.echo(), bundle, target, source);
+                    }
+                    files_moved += 1;
+                }
+                errors.push(e);
+            }
+        }
+    }
+
+    print_summary(
+        files_moved,
+        symlinks_created,
+        skipped,
+        errors.len() - rejected,
+        bundle,
+    );
+    if cli.dry_run {
+        print_estimate(&Estimate::of(plan), bundle);
+    }
+    errors.extend(reported(write_sums(sums.as_ref(), cli), bundle));
+
+    // Return error if any operation failed
+    batch_result(&errors)
+}
+
+/// Report the error of `result`, if any, and return it.
+fn reported(result: Result<()>, bundle: &Bundle) -> Option<MvlnError> {
+    let error = result.err()?;
+    eprintln!();
+    report(bundle, &error);
+    Some(error)
+}
+
+/// With `--write-sums`, hash what `result` moved into `sums`. Nothing is
+/// hashed in a dry run.
+fn add_sums(sums: Option<&mut Manifest>, result: &MoveResult, cli: &Cli) -> Result<()> {
+    match sums {
+        Some(sums) if !cli.dry_run => {
+            sums.add(&result.dest)
+                .map_err(|source| MvlnError::SumsFailed {
+                    path: result.dest.clone(),
+                    source,
+                })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// With `--write-sums`, write the manifest of everything moved, even if
+/// some moves failed.
+fn write_sums(sums: Option<&Manifest>, cli: &Cli) -> Result<()> {
+    match (sums, &cli.write_sums) {
+        (Some(sums), Some(path)) if !cli.dry_run => {
+            sums.write(path).map_err(|source| MvlnError::SumsFailed {
+                path: path.clone(),
+                source,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Run the pre-hook and [`transfer`] for one planned move, recording in the
+/// journal that it begins and, if it fails cleanly, that it failed. A crash
+/// midway thus leaves a begun move for the next run to find. The store
+/// picks its own destination, so moves into it are not recorded.
+fn journaled_transfer(
+    planned: &PlannedMove,
+    src_display: &str,
+    store: Option<&Store>,
+    dedupe_index: Option<&mut DedupeIndex>,
+    journal: &Journal,
+    cli: &Cli,
+    bundle: &Bundle,
+) -> Result<MoveResult> {
+    let (source, dest) = (&planned.source, &planned.dest);
+    let begun = !cli.dry_run && store.is_none();
+    if begun {
+        let action = if cli.no_link {
+            Action::BeginMoveOnly
+        } else {
+            Action::Begin
+        };
+        record(
+            journal,
+            action,
+            source,
+            &final_dest(source, dest, cli),
+            bundle,
+        );
+    }
+    let outcome = hooks::pre(cli, source, dest)
+        .and_then(|()| transfer(source, dest, src_display, store, dedupe_index, cli, bundle));
+    if begun && outcome.as_ref().is_err_and(|e| !e.is_data_at_risk()) {
+        record(journal, Action::Failed, source, dest, bundle);
+    }
+    outcome
+}
+
+/// With `--dedupe`, index the files already under `dest_root` and in the
+/// journal.
+fn dedupe_index(cli: &Cli, dest_root: &Path, journal: &Journal) -> Result<Option<DedupeIndex>> {
+    if !cli.dedupe {
+        return Ok(None);
+    }
+    let mut index = DedupeIndex::new();
+    index.add_tree(dest_root)?;
+    index.add_journal(journal)?;
+    Ok(Some(index))
+}
+
+/// Show the notice a real run gives before copying a directory across
+/// filesystems (see [`prompt::announce_copy`]), counted by the same scan.
+fn announce_dry_run_copy(result: &MoveResult, cli: &Cli) {
+    if result.hardlinked_from.is_some()
+        || result.skipped_same
+        || device::crosses_filesystems(&result.source, &result.dest) != Some(true)
+    {
+        return;
+    }
+    if let Ok(size) = tree_size(&result.source) {
+        prompt::announce_copy(
+            cli.lang.as_deref(),
+            &result.source,
+            &result.dest,
+            size.files,
+            size.bytes,
+        );
+    }
+}
+
+/// Echo the symlink command(s) of a completed move and record it in the
+/// journal. Returns the number of symlinks created.
+fn finish_move(result: &MoveResult, journal: &Journal, cli: &Cli, bundle: &Bundle) -> usize {
+    if cli.dry_run {
+        announce_dry_run_copy(result, cli);
+    }
+    let links = if cli.no_link {
+        0
+    } else if result.farm.is_empty() {
+        print_ln_command(cli.echo(), &result.symlink_target, &result.source);
+        1
+    } else {
+        print_farm_commands(cli.echo(), &result.farm);
+        result.farm.len()
+    };
+
+    if result.skipped_same {
+        logfile::write(
+            Kind::Decision,
+            &format!(
+                "{}: same size and mtime at destination, not copied",
+                result.dest.display()
+            ),
+        );
+    }
+    if !cli.dry_run {
+        let action = if cli.no_link {
+            Action::MoveOnly
+        } else {
+            Action::Move
+        };
+        record(journal, action, &result.source, &result.dest, bundle);
+    }
+
+    for (src, dest) in &result.sanitized {
+        let mut args = FluentArgs::new();
+        args.set("src", src.display().to_string());
+        args.set("dest", dest.display().to_string());
+        println!(
+            "{}",
+            i18n::msg(bundle, MessageKey::OpSanitized, Some(&args))
+        );
+    }
+
+    if cli.verbose {
+        print_verbose_result(result, !cli.no_link, bundle);
+    }
+    links
+}
+
+/// Print what a dry-run plan would transfer and do to free space.
+fn print_estimate(estimate: &Estimate, bundle: &Bundle) {
+    let mut args = FluentArgs::new();
+    args.set("bytes", format_size(estimate.bytes));
+    args.set("renames", estimate.renames);
+    args.set("copies", estimate.copies);
+    args.set("copy_bytes", format_size(estimate.copy_bytes));
+    println!("{}", i18n::msg(bundle, MessageKey::OpEstimate, Some(&args)));
+
+    for fs in &estimate.filesystems {
+        let mut args = FluentArgs::new();
+        args.set("path", fs.path.display().to_string());
+        args.set("delta", format_delta(fs.delta));
+        let line = match (fs.available, fs.available_after()) {
+            (Some(available), Some(after)) => {
+                args.set("available", format_size(available));
+                args.set("after", format_size(after));
+                i18n::msg(bundle, MessageKey::OpEstimateFs, Some(&args))
             }
+            _ => i18n::msg(bundle, MessageKey::OpEstimateFsUnknown, Some(&args)),
+        };
+        println!("  {line}");
+        if fs.is_short() {
+            eprintln!(
+                "{}",
+                i18n::msg(bundle, MessageKey::OpEstimateShort, Some(&args))
+            );
         }
+    }
+}
+
+/// Format a change in free space, e.g. `+1.5 GiB`.
+fn format_delta(delta: i64) -> String {
+    let sign = if delta < 0 { '-' } else { '+' };
+    format!("{sign}{}", format_size(delta.unsigned_abs()))
+}
+
+/// Print the completion summary of a batch and log it.
+fn print_summary(files: usize, links: usize, skipped: usize, failed: usize, bundle: &Bundle) {
+    println!();
+    let mut args = FluentArgs::new();
+    args.set("files", files);
+    args.set("links", links);
+    args.set("skipped", skipped);
+    args.set("failed", failed);
+    println!("{}", i18n::msg(bundle, MessageKey::OpComplete, Some(&args)));
+    logfile::write(
+        Kind::Run,
+        &format!("complete: {files} moved, {links} symlinked, {skipped} skipped, {failed} failed"),
+    );
+}
 
-        // If arg is a glob pattern that could have expanded to this path
-        if mvln::glob_expand::is_glob_pattern(&arg_str) {
-            // Return the expanded path display
-            return expanded_path.display().to_string();
+/// Record an operation in the journal.
+///
+/// A journal failure is reported as a warning rather than failing the
+/// operation, whose files have already been changed or are about to be.
+fn record(journal: &Journal, action: Action, link: &Path, target: &Path, bundle: &Bundle) {
+    let entry = Entry::new(action, link, target);
+    background::record(&entry);
+    if let Err(e) = journal.append(&entry) {
+        let mut args = FluentArgs::new();
+        args.set("reason", e.to_string());
+        eprintln!(
+            "{}",
+            i18n::msg(bundle, MessageKey::WarnJournalFailed, Some(&args))
+        );
+        logfile::write(Kind::Error, &format!("not recorded in journal: {e}"));
+    }
+}
+
+/// Load the configuration file (`--config` or the default location).
+///
+/// A missing file yields an empty configuration.
+fn load_config(cli: &Cli) -> Result<Config> {
+    match cli.config.clone().or_else(dirs::config_file) {
+        Some(path) => Config::load_optional(&path),
+        None => Ok(Config::default()),
+    }
+}
+
+/// Load the profile selected with `--profile`, if any.
+fn load_profile(cli: &Cli) -> Result<Option<Profile>> {
+    let Some(name) = &cli.profile else {
+        return Ok(None);
+    };
+    load_config(cli)?.profile(name).map(Some)
+}
+
+/// Load the preset selected with `--preset`, if any.
+fn load_preset(cli: &Cli) -> Result<Option<Profile>> {
+    let Some(name) = &cli.preset else {
+        return Ok(None);
+    };
+    load_config(cli)?.preset(name).map(Some)
+}
+
+/// Where `source` ends up when moved to `dest`, as `move_and_link`
+/// resolves it.
+fn final_dest(source: &Path, dest: &Path, cli: &Cli) -> PathBuf {
+    path_utils::resolve_destination(source, dest, cli.dir_behavior())
+}
+
+/// Determine the directory whose lock guards a run into `dest`.
+///
+/// A directory destination is locked itself; for a file destination the
+/// containing directory is used.
+fn lock_root(dest: &Path) -> PathBuf {
+    if dest.is_dir() {
+        return dest.to_path_buf();
+    }
+    match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Expand glob patterns in source arguments.
+///
+/// Regular paths are passed through as-is (existence check happens in `move_and_link`).
+/// Every pattern that matches nothing is reported; the run then fails with
+/// the last of them.
+fn expand_sources(sources: &[PathBuf], cli: &Cli, bundle: &Bundle) -> Result<Expansion> {
+    let patterns: Vec<String> = sources.iter().map(|p| p.display().to_string()).collect();
+
+    let expansion = expand_globs_matches(Path::new(""), &patterns, &cli.glob_options()).map_err(
+        |e| match e {
+            GlobError::TooManyMatches { pattern, limit } => {
+                MvlnError::TooManyMatches { pattern, limit }
+            }
+            e => MvlnError::GlobExpansionFailed {
+                reason: e.to_string(),
+            },
+        },
+    )?;
+    let mut unmatched: Vec<MvlnError> = expansion
+        .unmatched()
+        .map(|index| MvlnError::NoMatches {
+            pattern: patterns[index].clone(),
+        })
+        .collect();
+    if let Some(last) = unmatched.pop() {
+        for error in &unmatched {
+            report(bundle, error);
         }
+        return Err(last);
     }
+    Ok(expansion)
+}
 
-    // Fallback: return the expanded path
-    expanded_path.display().to_string()
+/// The sources given as regular paths rather than patterns, mapped to the
+/// argument as typed, so commands are echoed in the user's own spelling.
+fn typed_sources(sources: &[PathBuf], expansion: &Expansion) -> HashMap<PathBuf, String> {
+    expansion
+        .matches
+        .iter()
+        .map(|m| (m, sources[m.pattern].display().to_string()))
+        .filter(|(_, arg)| !is_glob_pattern(arg))
+        .map(|(m, arg)| (m.path.clone(), arg))
+        .collect()
 }
@@ -0,0 +1,213 @@
+//! Manual page generation.
+//!
+//! `mvln man` renders a roff manual page from the clap command definition,
+//! so the page always documents the options and subcommands of the binary
+//! that printed it. Packagers can ship it with e.g.
+//! `mvln man > /usr/share/man/man1/mvln.1`.
+
+use std::fmt::Write;
+
+use clap::{Arg, Command};
+
+/// Render the manual page for `cmd` (section 1).
+pub fn render(cmd: &mut Command) -> String {
+    cmd.build();
+    let name = cmd.get_name().to_string();
+    let version = cmd.get_version().unwrap_or_default().to_string();
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        ".TH {} 1 \"\" \"{name} {version}\" \"User Commands\"",
+        name.to_uppercase()
+    );
+    let _ = writeln!(out, ".SH NAME");
+    let about = cmd.get_about().map(ToString::to_string).unwrap_or_default();
+    let _ = writeln!(out, "{} \\- {}", escape(&name), escape(&about));
+
+    let _ = writeln!(out, ".SH SYNOPSIS");
+    let _ = writeln!(out, "{}", synopsis(&name, cmd));
+    for sub in visible_subcommands(cmd) {
+        let _ = writeln!(out, ".br");
+        let _ = writeln!(
+            out,
+            "{}",
+            synopsis(&format!("{name} {}", sub.get_name()), sub)
+        );
+    }
+
+    if let Some(long_about) = cmd.get_long_about() {
+        let _ = writeln!(out, ".SH DESCRIPTION");
+        paragraphs(&mut out, &long_about.to_string(), ".PP");
+    }
+
+    let _ = writeln!(out, ".SH OPTIONS");
+    for arg in visible_args(cmd) {
+        describe_arg(&mut out, arg);
+    }
+
+    let subcommands: Vec<&Command> = visible_subcommands(cmd).collect();
+    if !subcommands.is_empty() {
+        let _ = writeln!(out, ".SH SUBCOMMANDS");
+        for sub in subcommands {
+            let _ = writeln!(out, ".SS \"{} {}\"", escape(&name), escape(sub.get_name()));
+            let help = sub
+                .get_long_about()
+                .or_else(|| sub.get_about())
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            paragraphs(&mut out, &help, ".PP");
+            // Global options are already listed under OPTIONS
+            for arg in visible_args(sub).filter(|a| !a.is_global_set()) {
+                describe_arg(&mut out, arg);
+            }
+        }
+    }
+
+    let _ = writeln!(out, ".SH EXIT STATUS");
+    for (code, meaning) in [
+        ("0", "All operations succeeded."),
+        ("1", "Some operations failed; the rest succeeded."),
+        ("2", "The command line is invalid."),
+        ("3", "A source pattern matched no files."),
+        (
+            "4",
+            "A file was left split between source and destination; see the recovery instructions printed with the error.",
+        ),
+    ] {
+        let _ = writeln!(out, ".TP\n.B {code}\n{}", escape(meaning));
+    }
+    let _ = writeln!(out, ".SH VERSION");
+    let _ = writeln!(out, "v{}", escape(&version));
+    out
+}
+
+fn visible_args(cmd: &Command) -> impl Iterator<Item = &Arg> {
+    cmd.get_arguments().filter(|a| !a.is_hide_set())
+}
+
+fn visible_subcommands(cmd: &Command) -> impl Iterator<Item = &Command> {
+    cmd.get_subcommands()
+        .filter(|c| !c.is_hide_set() && c.get_name() != "help")
+}
+
+/// Escape text for roff, including a leading control character.
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{escaped}")
+    } else {
+        escaped
+    }
+}
+
+/// Write blank-line separated paragraphs, separated by the `break` macro.
+fn paragraphs(out: &mut String, text: &str, separator: &str) {
+    for (i, paragraph) in text.split("\n\n").enumerate() {
+        if i > 0 {
+            let _ = writeln!(out, "{separator}");
+        }
+        for line in paragraph.lines() {
+            let _ = writeln!(out, "{}", escape(line.trim()));
+        }
+    }
+}
+
+/// Placeholder for an argument's value, e.g. `<SIZE>`.
+fn value_name(arg: &Arg) -> String {
+    arg.get_value_names()
+        .and_then(|names| names.first())
+        .map_or_else(
+            || arg.get_id().to_string().to_uppercase(),
+            ToString::to_string,
+        )
+}
+
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_num_args().is_some_and(|n| n.takes_values())
+}
+
+/// Bold option spellings followed by the italic value placeholder.
+fn arg_label(arg: &Arg) -> String {
+    if arg.is_positional() {
+        return format!("\\fI{}\\fR", escape(&value_name(arg)));
+    }
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("\\fB\\-{short}\\fR"));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+    }
+    let mut label = names.join(", ");
+    if takes_value(arg) {
+        let _ = write!(label, " \\fI{}\\fR", escape(&value_name(arg)));
+    }
+    label
+}
+
+fn describe_arg(out: &mut String, arg: &Arg) {
+    let _ = writeln!(out, ".TP");
+    let _ = writeln!(out, "{}", arg_label(arg));
+    let help = arg
+        .get_long_help()
+        .or_else(|| arg.get_help())
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    // `.IP` keeps follow-up paragraphs indented under the `.TP` tag
+    paragraphs(out, &help, ".IP");
+    let values: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_string())
+        .collect();
+    if takes_value(arg) && !values.is_empty() {
+        let _ = writeln!(out, ".IP");
+        let _ = writeln!(out, "Possible values: {}", escape(&values.join(", ")));
+    }
+}
+
+/// One synopsis line: the command, `[OPTIONS]` and its positionals.
+fn synopsis(name: &str, cmd: &Command) -> String {
+    let mut line = format!("\\fB{}\\fR", escape(name));
+    if visible_args(cmd).any(|a| !a.is_positional()) {
+        line.push_str(" [\\fIOPTIONS\\fR]");
+    }
+    for arg in visible_args(cmd).filter(|a| a.is_positional()) {
+        let mut label = arg_label(arg);
+        if arg.get_num_args().is_some_and(|n| n.max_values() > 1) && !label.contains("...") {
+            label.push_str("...");
+        }
+        if arg.is_required_set() {
+            let _ = write!(line, " {label}");
+        } else {
+            let _ = write!(line, " [{label}]");
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::CommandFactory;
+
+    #[test]
+    fn page_documents_options_and_subcommands() {
+        let page = render(&mut Cli::command());
+        assert!(page.starts_with(".TH MVLN 1"));
+        assert!(page.contains(".SH SYNOPSIS"));
+        assert!(page.contains("\\fB\\-\\-dry\\-run\\fR"));
+        assert!(page.contains(".SS \"mvln stow\""));
+        assert!(page.contains("Possible values: name, size, mtime"));
+        assert!(!page.contains("complete\\-profiles"));
+    }
+
+    #[test]
+    fn escapes_roff_control_characters() {
+        assert_eq!(escape(".hidden"), "\\&.hidden");
+        assert_eq!(escape("a-b\\c"), "a\\-b\\ec");
+    }
+}
@@ -0,0 +1,133 @@
+//! Unicode normalization of destination names.
+//!
+//! The same name can be spelled with different bytes: "café" is either
+//! `e` followed by a combining accent (NFD, what HFS+ and many macOS tools
+//! produce) or a single precomposed `é` (NFC, what Linux and most input
+//! methods produce). Moved between the two worlds, such names either
+//! duplicate silently or fail to match what is already there.
+//!
+//! [`MoveOptions::normalize`](crate::MoveOptions::normalize) picks a form
+//! for the name of each moved item at the destination, and
+//! [`resolve`] makes an existing entry that differs from it only in
+//! normalization count as the destination, so it conflicts (or is
+//! replaced) rather than getting a twin. Names inside moved directories
+//! keep their spelling.
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// The form destination names are normalized to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Normalization {
+    /// Keep names as they are (the default).
+    #[default]
+    None,
+    /// Composed form, as usual on Linux and Windows.
+    Nfc,
+    /// Decomposed form, as on HFS+.
+    Nfd,
+}
+
+impl Normalization {
+    /// `name` in this form. Names that are not valid UTF-8 are returned as
+    /// they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mvln::normalize::Normalization;
+    /// use std::ffi::OsStr;
+    ///
+    /// let nfd = OsStr::new("cafe\u{301}.txt");
+    /// assert_eq!(Normalization::Nfc.apply(nfd), OsStr::new("caf\u{e9}.txt"));
+    /// assert_eq!(Normalization::None.apply(nfd), nfd);
+    /// ```
+    #[must_use]
+    pub fn apply(self, name: &OsStr) -> OsString {
+        match (self, name.to_str()) {
+            (Self::Nfc, Some(text)) => text.nfc().collect::<String>().into(),
+            (Self::Nfd, Some(text)) => text.nfd().collect::<String>().into(),
+            _ => name.to_os_string(),
+        }
+    }
+}
+
+/// Whether `a` and `b` are the same name up to Unicode normalization.
+#[must_use]
+pub fn equivalent(a: &OsStr, b: &OsStr) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a.to_str(), b.to_str()) {
+        (Some(a), Some(b)) if !a.is_ascii() && !b.is_ascii() => nfc(a) == nfc(b),
+        _ => false,
+    }
+}
+
+fn nfc(text: &str) -> String {
+    if is_nfc(text) {
+        text.to_string()
+    } else {
+        text.nfc().collect()
+    }
+}
+
+/// The path to move to for the destination `dest`: its file name in
+/// `form`, or the name of an existing entry of the same directory that is
+/// [`equivalent`] to it.
+#[must_use]
+pub fn resolve(dest: &Path, form: Normalization) -> PathBuf {
+    let Some(name) = dest.file_name() else {
+        return dest.to_path_buf();
+    };
+    let name = form.apply(name);
+    let dest = dest.with_file_name(&name);
+    if name.is_ascii() || dest.symlink_metadata().is_ok() {
+        return dest;
+    }
+    let parent = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::read_dir(parent)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name())
+        .find(|existing| equivalent(existing, &name))
+        .map_or(dest.clone(), |existing| dest.with_file_name(existing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NFC: &str = "caf\u{e9}.txt";
+    const NFD: &str = "cafe\u{301}.txt";
+
+    #[test]
+    fn applies_the_form() {
+        let (nfc, nfd) = (OsStr::new(NFC), OsStr::new(NFD));
+        assert_eq!(Normalization::Nfc.apply(nfd), nfc);
+        assert_eq!(Normalization::Nfd.apply(nfc), nfd);
+        assert_eq!(Normalization::None.apply(nfc), nfc);
+        assert!(equivalent(nfc, nfd));
+        assert!(!equivalent(nfc, OsStr::new("cafe.txt")));
+    }
+
+    // APFS finds differently normalized names by itself
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn resolve_finds_differently_normalized_entries() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join(NFD), "existing").unwrap();
+
+        let resolved = resolve(&tmp.path().join(NFC), Normalization::None);
+        assert_eq!(resolved.file_name().unwrap(), OsStr::new(NFD));
+        let resolved = resolve(&tmp.path().join("other\u{e9}"), Normalization::Nfd);
+        assert_eq!(resolved.file_name().unwrap(), OsStr::new("othere\u{301}"));
+    }
+}
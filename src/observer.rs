@@ -0,0 +1,67 @@
+//! Lifecycle notifications for embedders.
+//!
+//! [`MovePlan::execute_with`](crate::MovePlan::execute_with) reports each
+//! step of a batch to an [`MvlnObserver`], so a TUI, GUI or daemon can show
+//! progress without parsing output. Every callback has an empty default, so
+//! an observer only implements the events it cares about.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mvln::observer::MvlnObserver;
+//! use mvln::plan::{ErrorPolicy, Layout, MovePlan, PlannedMove};
+//! use mvln::MoveOptions;
+//! use std::path::{Path, PathBuf};
+//!
+//! struct Progress;
+//!
+//! impl MvlnObserver for Progress {
+//!     fn on_started(&self, planned: &PlannedMove) {
+//!         eprintln!("moving {}", planned.source.display());
+//!     }
+//!     fn on_linked(&self, link: &Path, target: &Path) {
+//!         eprintln!("{} -> {}", link.display(), target.display());
+//!     }
+//! }
+//!
+//! let plan = MovePlan::build(&[PathBuf::from("a.txt")], "/archive", &Layout::Flat);
+//! plan.execute_with(&MoveOptions::default(), ErrorPolicy::KeepGoing, &Progress);
+//! ```
+
+use std::path::Path;
+
+use crate::error::MvlnError;
+use crate::plan::{MovePlan, PlannedMove};
+
+/// Callbacks for the events of a batch, in the order they happen.
+///
+/// For each move, [`on_started`](Self::on_started) is followed by either
+/// [`on_moved`](Self::on_moved) and [`on_linked`](Self::on_linked), or
+/// [`on_skipped`](Self::on_skipped), or [`on_failed`](Self::on_failed).
+/// Moves not attempted because the batch stopped early only get
+/// `on_skipped`.
+pub trait MvlnObserver {
+    /// The batch is about to start.
+    fn on_planned(&self, _plan: &MovePlan) {}
+
+    /// A move is about to be attempted.
+    fn on_started(&self, _planned: &PlannedMove) {}
+
+    /// The data now lives at `dest` (the final path, with the file name).
+    fn on_moved(&self, _source: &Path, _dest: &Path) {}
+
+    /// A symlink was created at `link`, storing `target`. Called once per
+    /// link, so several times when a directory became a link farm.
+    fn on_linked(&self, _link: &Path, _target: &Path) {}
+
+    /// A move was skipped by the conflict handler, or not attempted because
+    /// the batch stopped early.
+    fn on_skipped(&self, _planned: &PlannedMove) {}
+
+    /// A move failed. If [`MvlnError::is_data_at_risk`] is set the data was
+    /// moved but the move did not complete, and `on_moved` was not called.
+    fn on_failed(&self, _planned: &PlannedMove, _error: &MvlnError) {}
+}
+
+/// The observer that ignores every event.
+impl MvlnObserver for () {}
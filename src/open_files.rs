@@ -0,0 +1,183 @@
+//! Which files running processes have open for writing.
+//!
+//! Moving a file that is still being written to splits it: a process
+//! appending to a log keeps writing to the original, which is either gone
+//! or, across filesystems, copied without its latest lines. With
+//! `--check-open` the sources of a batch are looked up in an [`OpenFiles`]
+//! snapshot taken before anything is moved, and skipped, warned about or
+//! refused (see [`MvlnError::OpenForWriting`](crate::MvlnError::OpenForWriting)).
+//!
+//! On Linux the snapshot lists `/proc/<pid>/fd` of every process, with the
+//! access mode from `/proc/<pid>/fdinfo`, like `lsof` does. Only processes
+//! the user may inspect are seen, which for anyone but root means their
+//! own. Elsewhere nothing is ever found open.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A process holding a file open for writing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Writer {
+    /// Its process ID.
+    pub pid: u32,
+    /// Its command name, as shown by `ps`.
+    pub command: String,
+}
+
+impl fmt::Display for Writer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (pid {})", self.command, self.pid)
+    }
+}
+
+/// The files open for writing at one moment, with the processes holding
+/// them.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::open_files::OpenFiles;
+/// use std::io::Write;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let mut log = std::fs::File::create(dir.path().join("app.log")).unwrap();
+/// log.write_all(b"started\n").unwrap();
+///
+/// let open = OpenFiles::scan();
+/// # #[cfg(target_os = "linux")]
+/// assert_eq!(open.writers(dir.path())[0].1.pid, std::process::id());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OpenFiles {
+    files: Vec<(PathBuf, Writer)>,
+}
+
+impl OpenFiles {
+    /// Take a snapshot of the files open for writing now.
+    #[must_use]
+    pub fn scan() -> Self {
+        Self {
+            files: scan_proc(Path::new("/proc")),
+        }
+    }
+
+    /// The files open for writing at `path` or, for a directory, anywhere
+    /// beneath it, each with a process writing to it.
+    #[must_use]
+    pub fn writers(&self, path: &Path) -> Vec<(&Path, &Writer)> {
+        let path = resolve(path);
+        self.files
+            .iter()
+            .filter(|(file, _)| file.starts_with(&path))
+            .map(|(file, writer)| (file.as_path(), writer))
+            .collect()
+    }
+}
+
+/// `path` as the kernel names an open file: absolute, with the symlinks of
+/// its parent resolved. A source that is itself a symlink stays one, since
+/// moving it leaves its target alone.
+fn resolve(path: &Path) -> PathBuf {
+    let resolved = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            parent.canonicalize().map(|parent| parent.join(name))
+        }
+        _ => path.canonicalize(),
+    };
+    resolved.unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The files open for writing by the processes listed under `proc`.
+#[cfg(target_os = "linux")]
+fn scan_proc(proc: &Path) -> Vec<(PathBuf, Writer)> {
+    let Ok(entries) = std::fs::read_dir(proc) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+            continue;
+        };
+        // Processes come and go, and most can't be inspected; skip those
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let command = std::fs::read_to_string(entry.path().join("comm"))
+            .map(|comm| comm.trim_end().to_string())
+            .unwrap_or_default();
+        for fd in fds.flatten() {
+            let Ok(file) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            // Sockets, pipes and the like aren't paths, and deleted files
+            // can't be moved
+            if !file.is_absolute() || file.to_string_lossy().ends_with(" (deleted)") {
+                continue;
+            }
+            let info = entry.path().join("fdinfo").join(fd.file_name());
+            if std::fs::read_to_string(info).is_ok_and(|info| opened_for_writing(&info)) {
+                let command = command.clone();
+                files.push((file, Writer { pid, command }));
+            }
+        }
+    }
+    files
+}
+
+#[cfg(not(target_os = "linux"))]
+fn scan_proc(_proc: &Path) -> Vec<(PathBuf, Writer)> {
+    Vec::new()
+}
+
+/// Whether the `fdinfo` of a file descriptor shows it open for writing.
+#[cfg(target_os = "linux")]
+fn opened_for_writing(fdinfo: &str) -> bool {
+    fdinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|flags| i32::from_str_radix(flags.trim(), 8).ok())
+        .is_some_and(|flags| flags & libc::O_ACCMODE != libc::O_RDONLY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn finds_files_open_for_writing_only() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let log = tmp.path().join("app.log");
+        let data = tmp.path().join("data.txt");
+        std::fs::write(&data, "data").unwrap();
+        let _writing = std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&log)
+            .unwrap();
+        let _reading = std::fs::File::open(&data).unwrap();
+
+        let open = OpenFiles::scan();
+        let writers = open.writers(&log);
+        assert_eq!(writers.len(), 1);
+        assert_eq!(writers[0].1.pid, std::process::id());
+        assert!(open.writers(&data).is_empty());
+        assert_eq!(open.writers(tmp.path()).len(), 1);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn reads_the_access_mode() {
+        assert!(opened_for_writing(
+            "pos:\t0\nflags:\t0102001\nmnt_id:\t25\n"
+        ));
+        assert!(opened_for_writing("flags:\t02\n"));
+        assert!(!opened_for_writing("pos:\t0\nflags:\t0100000\n"));
+        assert!(!opened_for_writing(""));
+    }
+}
@@ -1,34 +1,465 @@
 //! Core move-and-link operations.
+//!
+//! With the `tracing` feature, each operation runs in a span naming its
+//! source and destination, and the individual steps (rename, copy, symlink,
+//! ...) are emitted as `DEBUG` events; failures are recorded as `ERROR`
+//! events on the span.
 
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::fs;
 use std::io::ErrorKind;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::SystemTime;
 
+use crate::chmod::Chmod;
+use crate::conflict::{BackupStyle, ConflictHandler, ConflictInfo, Resolution};
+use crate::copy;
+use crate::device::{self, CrossDeviceHandler, CrossDeviceInfo};
 use crate::error::{MvlnError, Result};
-use crate::path_utils::compute_symlink_target;
+use crate::limits::PathLimits;
+use crate::links;
+use crate::normalize::{self, Normalization};
+use crate::ownership;
+use crate::pack;
+pub use crate::path_utils::SymlinkStyle;
+use crate::path_utils::{self, compute_symlink_target, unique_name, DirBehavior};
+use crate::preserve::{self, PreserveSet};
+use crate::progress::{CopyProgress, ProgressHandler, Tracker};
+use crate::sanitize::Sanitizer;
+use walkdir::WalkDir;
+
+/// Emit a `tracing` event at `$level` when the `tracing` feature is enabled.
+macro_rules! event {
+    ($level:ident, $($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!(tracing::Level::$level, $($arg)*);
+    };
+}
 
 /// Options for `move_and_link` operation.
+///
+/// New options are added over time, so the struct cannot be built with a
+/// struct literal outside this crate; use [`MoveOptions::builder`] (or
+/// [`Default`] and assign the fields).
+///
+/// # Examples
+///
+/// ```
+/// use mvln::{MoveOptions, SymlinkStyle};
+///
+/// let options = MoveOptions::builder()
+///     .symlink_style(SymlinkStyle::Absolute)
+///     .force(true)
+///     .build();
+/// assert!(options.force && !options.dry_run);
+/// ```
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+#[allow(clippy::struct_excessive_bools)]
 pub struct MoveOptions {
-    /// Use absolute paths for symlinks instead of relative.
-    pub absolute: bool,
+    /// How symlink targets are expressed (relative by default).
+    pub symlink_style: SymlinkStyle,
+    /// Which symlinks among the sources are followed (none by default).
+    pub symlink_policy: SymlinkPolicy,
     /// Overwrite existing destination.
     pub force: bool,
     /// Only print commands, don't execute.
     pub dry_run: bool,
+    /// For directories, recreate the tree down to this depth at the source
+    /// and link individual entries instead of the directory itself
+    /// (0 links the directory as a whole).
+    pub link_depth: usize,
+    /// Only move, without leaving a symlink at the source, like a plain
+    /// `mv` (`--no-link`).
+    pub no_link: bool,
+    /// Copy files across filesystems in chunks of this many bytes instead
+    /// of letting the OS choose (see [`crate::copy`]).
+    pub buffer_size: Option<usize>,
+    /// Write files copied across filesystems with direct I/O, bypassing the
+    /// page cache (Linux only; see [`crate::copy`]).
+    pub direct_io: bool,
+    /// Attributes carried over to copies made across filesystems (mode
+    /// and timestamps by default; see [`crate::preserve`]).
+    pub preserve: PreserveSet,
+    /// Restore owners and groups by ID even if no user or group with that
+    /// ID exists on this system.
+    pub numeric_ids: bool,
+    /// Permission changes applied to whatever was moved, at the
+    /// destination (see [`crate::chmod`]).
+    pub chmod: Option<Chmod>,
+    /// Remove the macOS `com.apple.quarantine` attribute from whatever was
+    /// moved, so Gatekeeper does not ask again about files on the archive
+    /// (see [`preserve::strip_quarantine`]).
+    pub strip_quarantine: bool,
+    /// Unicode normalization of the names of moved items at the
+    /// destination (see [`crate::normalize`]).
+    pub normalize: Normalization,
+    /// Rewrite names the destination filesystem would reject (see
+    /// [`crate::sanitize`]).
+    pub sanitize: Option<Sanitizer>,
+    /// Asked how to resolve an existing destination when `force` is not
+    /// set (see [`crate::conflict`]). Without one, the move fails with
+    /// [`MvlnError::DestinationExists`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub on_conflict: Option<ConflictHandler>,
+    /// Back up existing destinations in this style instead of failing or
+    /// asking (`--backup`); also the style of [`Resolution::Backup`].
+    pub backup: Option<BackupStyle>,
+    /// Take an existing destination file with the same size and
+    /// modification time (to the second) as the source file for an earlier
+    /// copy of it (`--skip-same`): the source is removed and linked without
+    /// copying anything. Much cheaper than comparing contents when rerunning
+    /// an interrupted batch.
+    pub skip_same: bool,
+    /// Asked before a move that crosses filesystems, and so would be done
+    /// by copying and removing rather than by a rename (see
+    /// [`crate::device`]). If it declines, the move fails with
+    /// [`MvlnError::CrossDeviceDeclined`] and nothing is changed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub on_cross_device: Option<CrossDeviceHandler>,
+    /// Never copy: a move that cannot be done by a rename fails with
+    /// [`MvlnError::CrossDevice`] (`--no-cross-device`). Detected up front
+    /// where device IDs tell (see [`crate::device`]), otherwise when the
+    /// rename fails.
+    pub no_cross_device: bool,
+    /// Copy the files of directory trees moved across filesystems through
+    /// streamed packs rather than one by one (`--pack`), which is faster
+    /// for trees of many small files (see [`crate::pack`]).
+    pub pack: bool,
+    /// Times a file copied across filesystems is retried after failing with
+    /// a stale file handle (`ESTALE`), which NFS servers report when a file
+    /// is replaced under a client. Raised to
+    /// [`NETWORK_STALE_RETRIES`](crate::copy::NETWORK_STALE_RETRIES) for
+    /// copies onto network filesystems.
+    pub stale_retries: u32,
+    /// Names of directories left out of directory trees copied across
+    /// filesystems (`--skip-vcs`, see [`VCS_DIRS`]). They are not copied
+    /// and stay in place at the source, so such a tree can only be moved
+    /// with [`no_link`](Self::no_link); otherwise the move fails with
+    /// [`MvlnError::SkippedDirKept`] before anything is copied. A rename
+    /// within one filesystem costs nothing and takes them along.
+    pub skip_dirs: Vec<String>,
+    /// Told how far the copy of a directory tree across filesystems has got
+    /// (see [`crate::progress`]).
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub on_progress: Option<ProgressHandler>,
+    /// Whether the destination is a directory to move into or the new path
+    /// itself (`-T`; see [`DirBehavior`]).
+    pub dir_behavior: DirBehavior,
+}
+
+impl MoveOptions {
+    /// Start building options from the defaults.
+    pub fn builder() -> MoveOptionsBuilder {
+        MoveOptionsBuilder::default()
+    }
+
+    /// The exact path [`move_and_link`] moves `source` to when given
+    /// `dest`, before any conflict handling: inside `dest` or `dest` itself
+    /// (see [`path_utils::resolve_destination`]), with the name sanitized
+    /// and normalized as these options say. A symlink source followed by
+    /// [`MoveOptions::symlink_policy`] keeps the name of what it points to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mvln::sanitize::Sanitizer;
+    /// use mvln::MoveOptions;
+    /// use std::path::Path;
+    ///
+    /// let options = MoveOptions::builder().sanitize(Sanitizer::default()).build();
+    /// let dest = options.resolve_destination(Path::new("10:30.txt"), Path::new("/mnt/usb/"));
+    /// assert_eq!(dest, Path::new("/mnt/usb/10_30.txt"));
+    /// ```
+    #[must_use]
+    pub fn resolve_destination(&self, source: &Path, dest: &Path) -> PathBuf {
+        resolve_destination(source, dest, self).0
+    }
+}
+
+/// Builder for [`MoveOptions`], created by [`MoveOptions::builder`].
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct MoveOptionsBuilder {
+    options: MoveOptions,
+}
+
+impl MoveOptionsBuilder {
+    /// How symlink targets are expressed (relative by default).
+    pub fn symlink_style(mut self, style: SymlinkStyle) -> Self {
+        self.options.symlink_style = style;
+        self
+    }
+
+    /// Which source symlinks to follow (see [`SymlinkPolicy`]).
+    pub fn symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.options.symlink_policy = policy;
+        self
+    }
+
+    /// Overwrite an existing destination.
+    pub fn force(mut self, force: bool) -> Self {
+        self.options.force = force;
+        self
+    }
+
+    /// Only plan the operation, without changing anything.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.options.dry_run = dry_run;
+        self
+    }
+
+    /// Link the entries of moved directories down to `depth` instead of
+    /// the directory itself (see [`MoveOptions::link_depth`]).
+    pub fn link_depth(mut self, depth: usize) -> Self {
+        self.options.link_depth = depth;
+        self
+    }
+
+    /// Leave no symlink behind (see [`MoveOptions::no_link`]).
+    pub fn no_link(mut self, no_link: bool) -> Self {
+        self.options.no_link = no_link;
+        self
+    }
+
+    /// Copy across filesystems in chunks of `size` bytes (see
+    /// [`MoveOptions::buffer_size`]).
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.options.buffer_size = Some(size);
+        self
+    }
+
+    /// Bypass the page cache when copying across filesystems (see
+    /// [`MoveOptions::direct_io`]).
+    pub fn direct_io(mut self, direct_io: bool) -> Self {
+        self.options.direct_io = direct_io;
+        self
+    }
+
+    /// Carry over exactly these attributes to copies (see
+    /// [`MoveOptions::preserve`]).
+    pub fn preserve(mut self, preserve: PreserveSet) -> Self {
+        self.options.preserve = preserve;
+        self
+    }
+
+    /// Also restore (or not) the owner of copied files (see
+    /// [`PreserveSet::OWNER`]).
+    pub fn preserve_owner(mut self, preserve: bool) -> Self {
+        self.options.preserve.set(PreserveSet::OWNER, preserve);
+        self
+    }
+
+    /// Also restore (or not) the group of copied files (see
+    /// [`PreserveSet::GROUP`]).
+    pub fn preserve_group(mut self, preserve: bool) -> Self {
+        self.options.preserve.set(PreserveSet::GROUP, preserve);
+        self
+    }
+
+    /// Restore owners and groups by ID alone (see
+    /// [`MoveOptions::numeric_ids`]).
+    pub fn numeric_ids(mut self, numeric: bool) -> Self {
+        self.options.numeric_ids = numeric;
+        self
+    }
+
+    /// Change the permissions of moved files and directories at the
+    /// destination (see [`MoveOptions::chmod`]).
+    pub fn chmod(mut self, chmod: Chmod) -> Self {
+        self.options.chmod = Some(chmod);
+        self
+    }
+
+    /// Remove (or keep) the macOS quarantine attribute of moved files (see
+    /// [`MoveOptions::strip_quarantine`]).
+    pub fn strip_quarantine(mut self, strip: bool) -> Self {
+        self.options.strip_quarantine = strip;
+        self
+    }
+
+    /// Normalize destination names to `form` (see
+    /// [`MoveOptions::normalize`]).
+    pub fn normalize(mut self, form: Normalization) -> Self {
+        self.options.normalize = form;
+        self
+    }
+
+    /// Rewrite destination names that FAT, exFAT or NTFS would reject (see
+    /// [`MoveOptions::sanitize`]).
+    pub fn sanitize(mut self, sanitizer: Sanitizer) -> Self {
+        self.options.sanitize = Some(sanitizer);
+        self
+    }
+
+    /// Ask `callback` how to resolve existing destinations instead of
+    /// failing (see [`MoveOptions::on_conflict`]).
+    pub fn on_conflict<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&ConflictInfo<'_>) -> Resolution + Send + Sync + 'static,
+    {
+        self.options.on_conflict = Some(ConflictHandler::new(callback));
+        self
+    }
+
+    /// Take existing destination files with the source's size and
+    /// modification time as already moved (see [`MoveOptions::skip_same`]).
+    pub fn skip_same(mut self, skip: bool) -> Self {
+        self.options.skip_same = skip;
+        self
+    }
+
+    /// Back up existing destinations in `style` instead of failing (see
+    /// [`MoveOptions::backup`]).
+    pub fn backup(mut self, style: BackupStyle) -> Self {
+        self.options.backup = Some(style);
+        self
+    }
+
+    /// Ask `callback` before moving across filesystems (see
+    /// [`MoveOptions::on_cross_device`]).
+    pub fn on_cross_device<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&CrossDeviceInfo<'_>) -> bool + Send + Sync + 'static,
+    {
+        self.options.on_cross_device = Some(CrossDeviceHandler::new(callback));
+        self
+    }
+
+    /// Fail instead of copying across filesystems (see
+    /// [`MoveOptions::no_cross_device`]).
+    pub fn no_cross_device(mut self, no_cross_device: bool) -> Self {
+        self.options.no_cross_device = no_cross_device;
+        self
+    }
+
+    /// Copy trees of small files through streamed packs (see
+    /// [`MoveOptions::pack`]).
+    pub fn pack(mut self, pack: bool) -> Self {
+        self.options.pack = pack;
+        self
+    }
+
+    /// Retry copies failing with a stale file handle (see
+    /// [`MoveOptions::stale_retries`]).
+    pub fn stale_retries(mut self, retries: u32) -> Self {
+        self.options.stale_retries = retries;
+        self
+    }
+
+    /// Leave directories with these names out of copied trees (see
+    /// [`MoveOptions::skip_dirs`]).
+    pub fn skip_dirs<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options.skip_dirs = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Report the progress of directory trees copied across filesystems
+    /// to `callback` (see [`MoveOptions::on_progress`]).
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&CopyProgress<'_>) + Send + Sync + 'static,
+    {
+        self.options.on_progress = Some(ProgressHandler::new(callback));
+        self
+    }
+
+    /// Whether the destination is a directory to move into or the new
+    /// path itself (see [`MoveOptions::dir_behavior`]).
+    pub fn dir_behavior(mut self, behavior: DirBehavior) -> Self {
+        self.options.dir_behavior = behavior;
+        self
+    }
+
+    /// Finish building.
+    #[must_use]
+    pub fn build(self) -> MoveOptions {
+        self.options
+    }
+}
+
+/// Which symlinks a move follows on the source side.
+///
+/// Symlinks created by mvln itself are unaffected; this only decides
+/// whether a symlink found among the sources is moved as a link or stands
+/// for the data it points to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SymlinkPolicy {
+    /// Move symlinks as links, both as the source itself and inside copied
+    /// trees (the default).
+    #[default]
+    NeverFollow,
+    /// Follow a source that is a symlink, as with
+    /// [`FollowTopLevelOnly`](Self::FollowTopLevelOnly), and also copy the
+    /// targets of symlinks inside directory trees copied across
+    /// filesystems. A rename within one filesystem keeps nested symlinks
+    /// as they are.
+    Follow,
+    /// Follow a source that is a symlink: its (fully resolved) target is
+    /// moved and replaced by a symlink, so the original link keeps working
+    /// through it. Symlinks inside directories are moved as links.
+    FollowTopLevelOnly,
+}
+
+impl SymlinkPolicy {
+    /// Whether a source that is itself a symlink is followed.
+    #[must_use]
+    pub fn follows_source(self) -> bool {
+        self != Self::NeverFollow
+    }
+
+    /// Whether symlinks inside copied directory trees are followed.
+    #[must_use]
+    pub fn follows_nested(self) -> bool {
+        self == Self::Follow
+    }
 }
 
 /// Result of a successful `move_and_link` operation.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MoveResult {
     /// The source path (now a symlink).
     pub source: PathBuf,
     /// The destination path (where file was moved).
     pub dest: PathBuf,
-    /// The symlink target (what the symlink points to).
+    /// The symlink target (what the symlink points to, or would point to
+    /// with [`MoveOptions::no_link`]).
     pub symlink_target: PathBuf,
+    /// Individual `(link, target)` pairs when a directory was replaced by a
+    /// link farm (see [`MoveOptions::link_depth`]); empty when a single
+    /// symlink was created.
+    pub farm: Vec<(PathBuf, PathBuf)>,
+    /// Existing identical file the destination was hard-linked to instead
+    /// of moving the data (see [`link_duplicate`]).
+    pub hardlinked_from: Option<PathBuf>,
+    /// Where an existing destination was moved aside to, with
+    /// [`MoveOptions::backup`] or if the conflict handler chose
+    /// [`Resolution::Backup`].
+    pub backup: Option<PathBuf>,
+    /// Whether the destination already held the data (see
+    /// [`MoveOptions::skip_same`]), so the source was removed rather than
+    /// moved.
+    pub skipped_same: bool,
+    /// `(source, destination)` of every entry whose name was changed by
+    /// [`MoveOptions::sanitize`], starting with the moved item itself.
+    pub sanitized: Vec<(PathBuf, PathBuf)>,
 }
 
+/// `(source, destination)` of entries renamed by [`MoveOptions::sanitize`].
+type Renames = Vec<(PathBuf, PathBuf)>;
+
 /// Move a file to destination and create a symlink at the original location.
 ///
 /// # Safety Guarantees
@@ -56,242 +487,1028 @@ pub fn move_and_link<P: AsRef<Path>, Q: AsRef<Path>>(
     dest: Q,
     options: &MoveOptions,
 ) -> Result<MoveResult> {
-    let source = source.as_ref();
-    let dest = dest.as_ref();
-
-    // Step 1: Verify source exists (including dangling symlinks)
-    // Use symlink_metadata instead of exists() to detect dangling symlinks
-    // Also distinguish between "not found" and other I/O errors (permission denied, etc.)
-    match source.symlink_metadata() {
-        Ok(_) => {} // Source exists
-        Err(e) if e.kind() == ErrorKind::NotFound => {
-            return Err(MvlnError::SourceNotFound {
-                path: source.to_path_buf(),
-            });
-        }
-        Err(e) => {
-            return Err(MvlnError::SourceAccessError {
-                path: source.to_path_buf(),
-                reason: e.to_string(),
-            });
-        }
-    }
+    move_and_link_with(source.as_ref(), dest.as_ref(), None, options)
+}
+
+/// Like [`move_and_link`], but store the data as a hard link to `existing`.
+///
+/// `existing` must be a regular file with the same contents as `source`
+/// (e.g. found by [`crate::dedupe::DedupeIndex`]). Instead of moving the
+/// bytes, the destination is created as a hard link to `existing`, the source
+/// file is removed and the usual symlink is created in its place. If the hard
+/// link cannot be created (for example because `existing` is on another
+/// filesystem), this falls back to a normal move.
+/// [`MoveResult::hardlinked_from`] tells which way was taken.
+///
+/// Directories are always moved normally.
+///
+/// # Errors
+///
+/// Same as [`move_and_link`].
+pub fn link_duplicate<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
+    source: P,
+    dest: Q,
+    existing: R,
+    options: &MoveOptions,
+) -> Result<MoveResult> {
+    move_and_link_with(
+        source.as_ref(),
+        dest.as_ref(),
+        Some(existing.as_ref()),
+        options,
+    )
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "info",
+        name = "move_and_link",
+        skip_all,
+        fields(source = %source.display(), dest = %dest.display(), dry_run = options.dry_run),
+        err(Display)
+    )
+)]
+fn move_and_link_with(
+    source: &Path,
+    dest: &Path,
+    duplicate: Option<&Path>,
+    options: &MoveOptions,
+) -> Result<MoveResult> {
+    // Step 1: Verify source exists (including dangling symlinks), and
+    // resolve it if it is a symlink the policy follows
+    let source_stat = stat_source(source)?;
+    let resolved;
+    let (source, source_stat) =
+        if source_stat.is_symlink() && options.symlink_policy.follows_source() {
+            resolved = follow_source(source)?;
+            let stat = stat_source(&resolved)?;
+            (resolved.as_path(), stat)
+        } else {
+            (source, source_stat)
+        };
 
     // Step 2: Resolve destination path
     // If dest is a directory, append source filename
-    let dest = resolve_destination(source, dest);
+    let (dest, sanitized) = resolve_destination(source, dest, options);
+    let dest_stat = Snapshot::of(&dest);
 
-    // Step 2.5: Check source != dest (prevent self-move data loss)
-    // Use absolute_path_no_follow to handle symlinks correctly - don't follow them.
-    let source_canonical = absolute_path_no_follow(source);
-    let dest_canonical = absolute_path_no_follow(&dest);
+    // Step 2.5: Check source != dest and dest is not inside source
+    let dest_canonical = check_overlap(source, &source_stat, &dest, &dest_stat)?;
+    let source_is_real_dir = source_stat.is_real_dir();
 
-    if source_canonical == dest_canonical {
-        return Err(MvlnError::SameSourceAndDest {
-            path: source.to_path_buf(),
-        });
-    }
+    // Step 3: Check destination doesn't exist (unless force, --backup or
+    // --skip-same), or ask the conflict handler what to do about it
+    let mut dest = dest;
+    let existing = check_destination(source, &source_stat, &mut dest, &dest_stat, options)?;
+    let skipped_same = matches!(existing, Existing::Same);
+    // A renamed destination is vacant by construction
+    let (dest_stat, dest_canonical) = if dest_stat.exists() && matches!(existing, Existing::Vacant)
+    {
+        let stat = Snapshot::missing();
+        let canonical = absolute_path_of(&dest, &stat);
+        (stat, canonical)
+    } else {
+        (dest_stat, dest_canonical)
+    };
 
-    // Step 2.6: Check dest is not inside source (prevent infinite recursion)
-    // This can happen when moving a directory to its own subdirectory,
-    // e.g., `mvln dir dir/subdir` would cause copy_dir_recursive to loop forever.
-    // Only check for actual directories (not symlinks to directories).
-    let source_is_symlink = source
-        .symlink_metadata()
-        .map(|m| m.is_symlink())
-        .unwrap_or(false);
-    let source_is_real_dir = !source_is_symlink && source.is_dir();
-    if source_is_real_dir && dest_canonical.starts_with(&source_canonical) {
-        return Err(MvlnError::DestinationInsideSource {
-            src: source.to_path_buf(),
-            dest: dest.clone(),
-        });
-    }
+    // Step 3.5: Check the destination fits the limits of its filesystem
+    // before anything is created there
+    PathLimits::of(&dest).check(&dest)?;
 
-    // Step 3: Check destination doesn't exist (unless force)
-    // Use symlink_metadata to detect dangling symlinks at destination
-    let dest_exists = dest.symlink_metadata().is_ok();
-    if dest_exists && !options.force {
-        return Err(MvlnError::DestinationExists { path: dest.clone() });
-    }
+    let mut sanitized: Renames = sanitized
+        .then(|| (source.to_path_buf(), dest.clone()))
+        .into_iter()
+        .collect();
 
     // Step 4: Compute symlink target
-    let symlink_target = compute_symlink_target(source, &dest, options.absolute);
+    let symlink_target = compute_symlink_target(source, &dest, &options.symlink_style);
+
+    let use_farm = source_is_real_dir && options.link_depth > 0 && !options.no_link;
+
+    // A duplicate is only usable for files, and never when it is the very
+    // destination a forced move is about to replace.
+    let duplicate = duplicate.filter(|existing| {
+        !source_is_real_dir && absolute_path_of(existing, &Snapshot::of(existing)) != dest_canonical
+    });
 
     // Step 5: Dry-run mode - return without making changes
     if options.dry_run {
+        event!(DEBUG, dest = %dest.display(), "dry run, nothing changed");
+        let farm = if use_farm {
+            plan_link_farm(source, source, &dest, options)?.1
+        } else {
+            Vec::new()
+        };
         return Ok(MoveResult {
             source: source.to_path_buf(),
-            dest,
             symlink_target,
+            farm,
+            hardlinked_from: duplicate.map(Path::to_path_buf),
+            backup: match existing {
+                Existing::Backup(backup) => Some(backup),
+                Existing::Vacant | Existing::Replace | Existing::Same => None,
+            },
+            skipped_same,
+            dest,
+            sanitized,
         });
     }
 
-    // Step 6: Create destination parent directories
-    if let Some(parent) = dest.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent).map_err(|e| MvlnError::CreateDirFailed {
-                path: parent.to_path_buf(),
-                reason: e.to_string(),
-            })?;
-        }
+    // Step 5.5: Refuse, or ask before, a move that can only be done by
+    // copying
+    if duplicate.is_none() && !skipped_same {
+        confirm_cross_device(source, &dest, options)?;
+    }
+    // Checked again if a rename fails across filesystems anyway, but the
+    // destination must not be cleared for a move that will be refused
+    if source_is_real_dir
+        && !options.skip_dirs.is_empty()
+        && device::crosses_filesystems(source, &dest) != Some(false)
+    {
+        check_skipped_dirs(source, options)?;
     }
 
-    // Step 7: Remove destination if force and exists
-    if dest_exists && options.force {
-        remove_existing_destination(source, &dest, source_is_real_dir)?;
+    // Step 6: Create destination parent directories (an existing
+    // destination implies an existing parent)
+    if !dest_stat.exists() {
+        create_parent(&dest)?;
     }
 
-    // Step 8: Move the file/directory
-    move_file(source, &dest)?;
+    // Step 7: Remove (or move aside) the existing destination
+    let backup = clear_destination(source, &dest, &dest_stat, existing, source_is_real_dir)?;
+
+    // Step 8: Move the file/directory, or hard-link an identical copy, or
+    // only remove the source if the destination already has it
+    let (hardlinked_from, renamed) = place(
+        source,
+        &source_stat,
+        &dest,
+        duplicate,
+        skipped_same,
+        options,
+    )?;
+    sanitized.extend(renamed);
+
+    // Step 9: Create symlink (or link farm) at original location, unless
+    // only moving
+    let farm = if use_farm {
+        create_link_farm(source, &dest, options)?
+    } else {
+        if !options.no_link {
+            create_symlink(source, &dest, &symlink_target)?;
+        }
+        Vec::new()
+    };
 
-    // Step 9: Create symlink at original location
-    create_symlink(source, &dest, &symlink_target)?;
+    // Step 10: Apply --chmod and --strip-quarantine to what was moved
+    adjust_moved(&dest, options)?;
 
     Ok(MoveResult {
         source: source.to_path_buf(),
-        dest,
         symlink_target,
+        farm,
+        hardlinked_from,
+        backup,
+        skipped_same,
+        dest,
+        sanitized,
     })
 }
 
-/// Resolve destination path: if dest is directory, append source filename.
-fn resolve_destination(source: &Path, dest: &Path) -> PathBuf {
-    if dest.is_dir() {
-        if let Some(filename) = source.file_name() {
-            return dest.join(filename);
-        }
+/// Check that moving `source` to `dest` neither moves it onto itself nor
+/// into itself. Returns the absolute path of `dest`.
+fn check_overlap(
+    source: &Path,
+    source_stat: &Snapshot,
+    dest: &Path,
+    dest_stat: &Snapshot,
+) -> Result<PathBuf> {
+    // Prevent self-move data loss. Use absolute_path_of to handle symlinks
+    // correctly - don't follow them.
+    let source_canonical = absolute_path_of(source, source_stat);
+    let dest_canonical = absolute_path_of(dest, dest_stat);
+
+    if source_canonical == dest_canonical {
+        return Err(MvlnError::SameSourceAndDest {
+            path: source.to_path_buf(),
+        });
+    }
+
+    // Prevent infinite recursion. This can happen when moving a directory
+    // to its own subdirectory, e.g., `mvln dir dir/subdir` would cause
+    // copy_dir_tree to loop forever. Only check for actual directories
+    // (not symlinks to directories).
+    if source_stat.is_real_dir() && dest_canonical.starts_with(&source_canonical) {
+        return Err(MvlnError::DestinationInsideSource {
+            src: source.to_path_buf(),
+            dest: dest.to_path_buf(),
+        });
     }
-    dest.to_path_buf()
+    Ok(dest_canonical)
 }
 
-/// Remove existing destination for force-overwrite.
-/// Checks type compatibility and removes the destination appropriately.
-fn remove_existing_destination(source: &Path, dest: &Path, source_is_real_dir: bool) -> Result<()> {
-    // Type mismatch check: prevent replacing directory with file or vice versa.
-    // This protects against accidental deletion of entire directory trees.
-    // Symlinks at destination are always replaceable (they're just pointers).
-    if !dest.is_symlink() {
-        let dest_is_dir = dest.is_dir();
-        if source_is_real_dir != dest_is_dir {
-            return Err(MvlnError::TypeMismatch {
-                src: source.to_path_buf(),
-                dest: dest.to_path_buf(),
-                src_type: if source_is_real_dir {
-                    "directory"
-                } else {
-                    "file"
-                },
-                dest_type: if dest_is_dir { "directory" } else { "file" },
-            });
-        }
+/// Change what was moved to `dest` as [`MoveOptions::chmod`] and
+/// [`MoveOptions::strip_quarantine`] ask.
+fn adjust_moved(dest: &Path, options: &MoveOptions) -> Result<()> {
+    if let Some(chmod) = &options.chmod {
+        chmod.apply_tree(dest)?;
+        event!(DEBUG, mode = %chmod, "changed permissions");
     }
 
-    // Use symlink_metadata to check file type without following symlinks.
-    // This is more robust than relying on is_symlink()/is_dir() order,
-    // as symlink_metadata explicitly does not follow symlinks.
-    let dest_meta = dest.symlink_metadata().map_err(|e| MvlnError::MoveFailed {
-        src: source.to_path_buf(),
-        dest: dest.to_path_buf(),
-        reason: format!("failed to read destination metadata: {e}"),
-    })?;
+    // A rename keeps the quarantine attribute, and so may a copy
+    if options.strip_quarantine {
+        #[cfg(feature = "tracing")]
+        if let Err(e) = preserve::strip_quarantine(dest) {
+            event!(WARN, dest = %dest.display(), error = %e, "could not strip quarantine");
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = preserve::strip_quarantine(dest);
+    }
+    Ok(())
+}
 
-    if dest_meta.is_symlink() {
-        // Remove symlink itself, not the target
-        fs::remove_file(dest).map_err(|e| MvlnError::MoveFailed {
-            src: source.to_path_buf(),
-            dest: dest.to_path_buf(),
-            reason: format!("failed to remove existing symlink: {e}"),
-        })?;
-    } else if dest_meta.is_dir() {
-        // Actual directory (not symlink), safe to remove recursively
-        fs::remove_dir_all(dest).map_err(|e| MvlnError::MoveFailed {
+/// Fail with [`MoveOptions::no_cross_device`], or ask
+/// [`MoveOptions::on_cross_device`] if set, when moving `source` to `dest`
+/// crosses filesystems.
+fn confirm_cross_device(source: &Path, dest: &Path, options: &MoveOptions) -> Result<()> {
+    if !options.no_cross_device && options.on_cross_device.is_none() {
+        return Ok(());
+    }
+    if device::crosses_filesystems(source, dest) != Some(true) {
+        return Ok(());
+    }
+    if options.no_cross_device {
+        return Err(MvlnError::CrossDevice {
             src: source.to_path_buf(),
             dest: dest.to_path_buf(),
-            reason: format!("failed to remove existing directory: {e}"),
-        })?;
+        });
+    }
+    let Some(handler) = &options.on_cross_device else {
+        return Ok(());
+    };
+    let size = crate::size::tree_size(source).unwrap_or_default();
+    let info = CrossDeviceInfo {
+        source,
+        dest,
+        bytes: size.bytes,
+        files: size.files,
+    };
+    if handler.confirm(&info) {
+        event!(
+            DEBUG,
+            bytes = info.bytes,
+            "confirmed copy across filesystems"
+        );
+        Ok(())
     } else {
-        // Regular file
-        fs::remove_file(dest).map_err(|e| MvlnError::MoveFailed {
+        Err(MvlnError::CrossDeviceDeclined {
             src: source.to_path_buf(),
             dest: dest.to_path_buf(),
-            reason: format!("failed to remove existing file: {e}"),
-        })?;
+        })
     }
-
-    Ok(())
 }
 
-/// Compute absolute path for a path without following symlinks.
-/// If the path is a symlink, canonicalize the parent and join with filename.
-/// If the path doesn't exist, build absolute path from parent.
-fn absolute_path_no_follow(path: &Path) -> PathBuf {
-    let is_symlink = path
-        .symlink_metadata()
-        .map(|m| m.is_symlink())
-        .unwrap_or(false);
-
-    if is_symlink {
-        // For symlinks, canonicalize parent and join with filename
-        std::fs::canonicalize(path.parent().unwrap_or(Path::new("."))).map_or_else(
-            |_| path.to_path_buf(),
-            |p| p.join(path.file_name().unwrap_or_default()),
-        )
-    } else if let Ok(canonical) = path.canonicalize() {
-        canonical
-    } else {
-        // Path doesn't exist - build absolute path from parent
-        // SAFETY: We must always return an absolute path to ensure starts_with() checks
-        // work correctly. If parent canonicalization fails (e.g., parent doesn't exist),
-        // fall back to joining with current working directory rather than returning
-        // a relative path, which would cause incorrect starts_with() comparisons.
-        path.parent()
-            .map(|p| {
-                if p.as_os_str().is_empty() {
-                    Path::new(".")
-                } else {
-                    p
-                }
-            })
-            .and_then(|p| p.canonicalize().ok())
-            .map_or_else(
-                || {
-                    // Fallback: ensure absolute path even if parent doesn't exist
-                    if path.is_absolute() {
-                        path.to_path_buf()
-                    } else {
-                        std::env::current_dir()
-                            .unwrap_or_else(|_| PathBuf::from("."))
-                            .join(path)
-                    }
-                },
-                |p| p.join(path.file_name().unwrap_or_default()),
-            )
+/// Create the missing parent directories of `dest`.
+fn create_parent(dest: &Path) -> Result<()> {
+    if let Some(parent) = dest.parent().filter(|parent| !parent.exists()) {
+        fs::create_dir_all(parent).map_err(|e| MvlnError::CreateDirFailed {
+            path: parent.to_path_buf(),
+            source: e,
+        })?;
+        event!(DEBUG, dir = %parent.display(), "created destination directory");
     }
+    Ok(())
 }
 
-/// Move file or directory from source to dest.
-/// Uses rename for same filesystem, falls back to copy+remove for cross-filesystem.
-fn move_file(source: &Path, dest: &Path) -> Result<()> {
-    // Try atomic rename first
-    match fs::rename(source, dest) {
-        Ok(()) => Ok(()),
-        Err(e) if is_cross_device_error(&e) => {
-            // Cross-filesystem: copy then remove
-            copy_and_remove(source, dest)
-        }
-        Err(e) => Err(MvlnError::MoveFailed {
+/// Move `source` to `dest`, or hard-link `duplicate` there instead and
+/// remove `source`, or only remove `source` if `skipped_same`. Returns the
+/// duplicate if it was used, and the entries of a copied tree renamed by
+/// [`MoveOptions::sanitize`].
+fn place(
+    source: &Path,
+    source_stat: &Snapshot,
+    dest: &Path,
+    duplicate: Option<&Path>,
+    skipped_same: bool,
+    options: &MoveOptions,
+) -> Result<(Option<PathBuf>, Renames)> {
+    if skipped_same {
+        fs::remove_file(source).map_err(|e| MvlnError::RemoveFailed {
             src: source.to_path_buf(),
             dest: dest.to_path_buf(),
-            reason: e.to_string(),
-        }),
+            source: e,
+        })?;
+        event!(DEBUG, "same size and mtime at destination, removed source");
+        return Ok((None, Vec::new()));
+    }
+    match duplicate {
+        Some(existing) if fs::hard_link(existing, dest).is_ok() => {
+            fs::remove_file(source).map_err(|e| MvlnError::RemoveFailed {
+                src: source.to_path_buf(),
+                dest: dest.to_path_buf(),
+                source: e,
+            })?;
+            event!(DEBUG, existing = %existing.display(), "hard-linked identical file");
+            Ok((Some(existing.to_path_buf()), Vec::new()))
+        }
+        _ => Ok((None, move_file(source, source_stat, dest, options)?)),
     }
 }
 
-/// Check if error is cross-device link error (EXDEV).
-fn is_cross_device_error(e: &std::io::Error) -> bool {
-    #[cfg(unix)]
-    {
+/// The `symlink_metadata` of a path, taken once and passed through the
+/// steps of a move so the same path isn't stat'ed over and over, which is
+/// slow on network filesystems.
+///
+/// It describes the path itself, never a symlink's target, and is not
+/// refreshed: steps that must see the current state (such as verifying a
+/// copy) still look at the disk, and the final rename refuses to replace a
+/// destination that appeared after it was found vacant.
+#[derive(Debug)]
+struct Snapshot(Option<fs::Metadata>);
+
+impl Snapshot {
+    /// Stat `path`; a missing path is a snapshot of nothing, not an error.
+    fn take(path: &Path) -> std::io::Result<Self> {
+        match path.symlink_metadata() {
+            Ok(meta) => Ok(Self(Some(meta))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self(None)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stat `path`, treating any error as the path being missing.
+    fn of(path: &Path) -> Self {
+        Self::take(path).unwrap_or(Self(None))
+    }
+
+    /// A snapshot of a path known to be vacant.
+    fn missing() -> Self {
+        Self(None)
+    }
+
+    /// Whether anything, even a dangling symlink, is at the path.
+    fn exists(&self) -> bool {
+        self.0.is_some()
+    }
+
+    fn is_symlink(&self) -> bool {
+        self.0.as_ref().is_some_and(fs::Metadata::is_symlink)
+    }
+
+    /// Whether the path is a directory itself, not a symlink to one.
+    fn is_real_dir(&self) -> bool {
+        self.0.as_ref().is_some_and(fs::Metadata::is_dir)
+    }
+
+    /// Whether both paths are regular files of the same size, last
+    /// modified in the same second. Sub-second precision is ignored, as
+    /// filesystems keep different amounts of it.
+    fn same_size_and_mtime(&self, other: &Self) -> bool {
+        let (Some(a), Some(b)) = (&self.0, &other.0) else {
+            return false;
+        };
+        let second = |meta: &fs::Metadata| {
+            meta.modified()
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+        };
+        a.is_file()
+            && b.is_file()
+            && a.len() == b.len()
+            && second(a).is_some_and(|t| Some(t) == second(b))
+    }
+}
+
+/// Snapshot the source of a move, failing if it is missing.
+///
+/// Uses `symlink_metadata` instead of `exists()` to detect dangling symlinks,
+/// and distinguishes "not found" from other I/O errors (permission denied,
+/// etc.).
+fn stat_source(source: &Path) -> Result<Snapshot> {
+    match Snapshot::take(source) {
+        Ok(stat) if stat.exists() => Ok(stat),
+        Ok(_) => Err(MvlnError::SourceNotFound {
+            path: source.to_path_buf(),
+        }),
+        Err(e) => Err(MvlnError::SourceAccessError {
+            path: source.to_path_buf(),
+            source: e,
+        }),
+    }
+}
+
+/// The fully resolved target of the symlink `source`, for policies that
+/// follow it.
+fn follow_source(source: &Path) -> Result<PathBuf> {
+    match fs::canonicalize(source) {
+        Ok(resolved) => {
+            event!(DEBUG, resolved = %resolved.display(), "following source symlink");
+            Ok(resolved)
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Err(MvlnError::DanglingSymlink {
+            path: source.to_path_buf(),
+            target: fs::read_link(source).unwrap_or_default(),
+        }),
+        Err(e) => Err(MvlnError::SourceAccessError {
+            path: source.to_path_buf(),
+            source: e,
+        }),
+    }
+}
+
+/// What to do about the path at a move's destination.
+enum Existing {
+    /// Nothing is there.
+    Vacant,
+    /// Remove it first (`force` or [`Resolution::Overwrite`]).
+    Replace,
+    /// Rename it to this path first ([`MoveOptions::backup`] or
+    /// [`Resolution::Backup`]).
+    Backup(PathBuf),
+    /// Keep it: it is the same file ([`MoveOptions::skip_same`]).
+    Same,
+}
+
+/// Act on the decision of [`check_destination`], returning the backup path
+/// if the existing destination was moved aside.
+fn clear_destination(
+    source: &Path,
+    dest: &Path,
+    dest_stat: &Snapshot,
+    existing: Existing,
+    source_is_real_dir: bool,
+) -> Result<Option<PathBuf>> {
+    match existing {
+        Existing::Vacant | Existing::Same => Ok(None),
+        Existing::Replace => {
+            remove_existing_destination(source, dest, dest_stat, source_is_real_dir)?;
+            Ok(None)
+        }
+        Existing::Backup(backup) => {
+            fs::rename(dest, &backup).map_err(|e| MvlnError::MoveFailed {
+                src: dest.to_path_buf(),
+                dest: backup.clone(),
+                source: e,
+            })?;
+            event!(INFO, backup = %backup.display(), "moved existing destination aside");
+            Ok(Some(backup))
+        }
+    }
+}
+
+/// Decide what to do about an existing `dest`, consulting the conflict
+/// handler if there is one. [`Resolution::Rename`] changes `dest` to a
+/// free name.
+fn check_destination(
+    source: &Path,
+    source_stat: &Snapshot,
+    dest: &mut PathBuf,
+    dest_stat: &Snapshot,
+    options: &MoveOptions,
+) -> Result<Existing> {
+    // The snapshot comes from symlink_metadata, so it sees dangling symlinks
+    if !dest_stat.exists() {
+        return Ok(Existing::Vacant);
+    }
+    if options.skip_same && source_stat.same_size_and_mtime(dest_stat) {
+        return Ok(Existing::Same);
+    }
+    if let Some(style) = options.backup {
+        return Ok(Existing::Backup(style.backup_path(dest, SystemTime::now())));
+    }
+    if options.force {
+        return Ok(Existing::Replace);
+    }
+    let Some(handler) = &options.on_conflict else {
+        return Err(MvlnError::DestinationExists { path: dest.clone() });
+    };
+
+    let resolution = handler.resolve(&ConflictInfo {
+        source,
+        dest,
+        source_is_dir: source_stat.is_real_dir(),
+        dest_is_dir: dest_stat.is_real_dir(),
+    });
+    event!(INFO, dest = %dest.display(), ?resolution, "resolved conflict");
+    let taken = |p: &Path| p.symlink_metadata().is_ok();
+    match resolution {
+        Resolution::Overwrite => Ok(Existing::Replace),
+        Resolution::Backup => Ok(Existing::Backup(
+            options
+                .backup
+                .unwrap_or_default()
+                .backup_path(dest, SystemTime::now()),
+        )),
+        Resolution::Rename => {
+            *dest = unique_name(dest, taken);
+            Ok(Existing::Vacant)
+        }
+        Resolution::Skip => Err(MvlnError::ConflictSkipped {
+            src: source.to_path_buf(),
+            dest: dest.clone(),
+        }),
+        Resolution::Abort => Err(MvlnError::ConflictAborted {
+            src: source.to_path_buf(),
+            dest: dest.clone(),
+        }),
+    }
+}
+
+/// Create the symlink at `source` for data already present at `dest`.
+///
+/// This is the second half of [`move_and_link`] on its own, for repairing
+/// setups where the data was moved by another tool. `dest` is used as-is
+/// (it is not joined with the source's file name) and must exist. Missing
+/// parent directories of `source` are created.
+///
+/// An existing symlink at `source` is replaced only when `options.force` is
+/// set; any other existing file or directory is never touched.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - `dest` does not exist
+/// - `source` already exists (and is not a symlink replaced with force)
+/// - `source` and `dest` are the same path
+/// - Symlink creation fails
+pub fn link_only<P: AsRef<Path>, Q: AsRef<Path>>(
+    source: P,
+    dest: Q,
+    options: &MoveOptions,
+) -> Result<MoveResult> {
+    link_only_impl(source.as_ref(), dest.as_ref(), options)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "info",
+        name = "link_only",
+        skip_all,
+        fields(source = %source.display(), dest = %dest.display(), dry_run = options.dry_run),
+        err(Display)
+    )
+)]
+fn link_only_impl(source: &Path, dest: &Path, options: &MoveOptions) -> Result<MoveResult> {
+    if let Err(e) = dest.symlink_metadata() {
+        return Err(MvlnError::InvalidPath {
+            path: dest.to_path_buf(),
+            reason: e.to_string(),
+        });
+    }
+
+    if absolute_path_of(source, &Snapshot::of(source))
+        == absolute_path_of(dest, &Snapshot::of(dest))
+    {
+        return Err(MvlnError::SameSourceAndDest {
+            path: source.to_path_buf(),
+        });
+    }
+
+    if let Ok(meta) = source.symlink_metadata() {
+        if !meta.is_symlink() || !options.force {
+            return Err(MvlnError::InvalidPath {
+                path: source.to_path_buf(),
+                reason: "already exists (use -f to replace an existing symlink)".to_string(),
+            });
+        }
+    }
+
+    let symlink_target = compute_symlink_target(source, dest, &options.symlink_style);
+
+    if !options.dry_run {
+        if let Some(parent) = source.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| MvlnError::CreateDirFailed {
+                path: parent.to_path_buf(),
+                source: e,
+            })?;
+        }
+        create_symlink(source, dest, &symlink_target)?;
+    }
+
+    Ok(MoveResult {
+        source: source.to_path_buf(),
+        dest: dest.to_path_buf(),
+        symlink_target,
+        farm: Vec::new(),
+        hardlinked_from: None,
+        backup: None,
+        skipped_same: false,
+        sanitized: Vec::new(),
+    })
+}
+
+/// Options for [`restore`].
+///
+/// Like [`MoveOptions`], built with [`RestoreOptions::builder`] or from
+/// [`Default`].
+///
+/// # Examples
+///
+/// ```
+/// use mvln::RestoreOptions;
+///
+/// let options = RestoreOptions::builder().dry_run(true).build();
+/// assert!(options.dry_run);
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct RestoreOptions {
+    /// Only check that the link can be restored, without changing anything.
+    pub dry_run: bool,
+    /// Copy back across filesystems in chunks of this many bytes (see
+    /// [`MoveOptions::buffer_size`]).
+    pub buffer_size: Option<usize>,
+    /// Write files copied back across filesystems with direct I/O (see
+    /// [`MoveOptions::direct_io`]).
+    pub direct_io: bool,
+    /// Attributes carried over when the data is copied back (see
+    /// [`MoveOptions::preserve`]).
+    pub preserve: PreserveSet,
+    /// Restore owners and groups by ID alone (see
+    /// [`MoveOptions::numeric_ids`]).
+    pub numeric_ids: bool,
+}
+
+impl RestoreOptions {
+    /// Start building options from the defaults.
+    pub fn builder() -> RestoreOptionsBuilder {
+        RestoreOptionsBuilder::default()
+    }
+
+    /// The options [`move_file`] needs to bring the data back.
+    fn move_options(&self) -> MoveOptions {
+        MoveOptions {
+            buffer_size: self.buffer_size,
+            direct_io: self.direct_io,
+            preserve: self.preserve,
+            numeric_ids: self.numeric_ids,
+            ..MoveOptions::default()
+        }
+    }
+}
+
+/// Builder for [`RestoreOptions`], created by [`RestoreOptions::builder`].
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct RestoreOptionsBuilder {
+    options: RestoreOptions,
+}
+
+impl RestoreOptionsBuilder {
+    /// Only check the link, without changing anything.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.options.dry_run = dry_run;
+        self
+    }
+
+    /// Copy back across filesystems in chunks of `size` bytes.
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.options.buffer_size = Some(size);
+        self
+    }
+
+    /// Bypass the page cache when copying back across filesystems.
+    pub fn direct_io(mut self, direct_io: bool) -> Self {
+        self.options.direct_io = direct_io;
+        self
+    }
+
+    /// Carry over exactly these attributes when copying back.
+    pub fn preserve(mut self, preserve: PreserveSet) -> Self {
+        self.options.preserve = preserve;
+        self
+    }
+
+    /// Restore owners and groups by ID alone.
+    pub fn numeric_ids(mut self, numeric: bool) -> Self {
+        self.options.numeric_ids = numeric;
+        self
+    }
+
+    /// Finish building.
+    #[must_use]
+    pub fn build(self) -> RestoreOptions {
+        self.options
+    }
+}
+
+/// Result of a successful [`restore`].
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RestoreResult {
+    /// Where the data is now: the path of the symlink it replaced.
+    pub path: PathBuf,
+    /// Where the data was moved back from (the link's resolved target).
+    pub from: PathBuf,
+    /// The target as stored in the symlink.
+    pub symlink_target: PathBuf,
+}
+
+/// Undo a move: put the data a symlink points to back in the symlink's
+/// place.
+///
+/// The target is first moved next to the link under a hidden staging name
+/// (`.<name>.mvln-restore`), by a rename or, across filesystems, by a
+/// verified copy after which the target is removed, exactly as
+/// [`move_and_link`] moves data. Only then is the symlink replaced by it,
+/// atomically for files. Until that point the symlink still leads to a
+/// complete copy of the data.
+///
+/// Only the link itself is followed: if its target is a symlink too, that
+/// symlink is what comes back.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - `link` is not a symlink ([`MvlnError::InvalidPath`])
+/// - its target does not exist ([`MvlnError::DanglingSymlink`])
+/// - its target is a directory containing the link
+///   ([`MvlnError::DestinationInsideSource`])
+/// - moving the data back fails; if the symlink could not be replaced, the
+///   data is put back at the target where possible
+///
+/// # Examples
+///
+/// ```no_run
+/// use mvln::{restore, RestoreOptions};
+///
+/// let result = restore("/home/me/dataset", &RestoreOptions::default())?;
+/// println!("moved {} back", result.from.display());
+/// # Ok::<(), mvln::MvlnError>(())
+/// ```
+pub fn restore<P: AsRef<Path>>(link: P, options: &RestoreOptions) -> Result<RestoreResult> {
+    restore_impl(link.as_ref(), options)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "info",
+        name = "restore",
+        skip_all,
+        fields(link = %link.display(), dry_run = options.dry_run),
+        err(Display)
+    )
+)]
+fn restore_impl(link: &Path, options: &RestoreOptions) -> Result<RestoreResult> {
+    let symlink_target = links::read_symlink(link)?;
+    let from = links::resolve_target(link, &symlink_target);
+    let from_stat = match Snapshot::take(&from) {
+        Ok(stat) if stat.exists() => stat,
+        Ok(_) => {
+            return Err(MvlnError::DanglingSymlink {
+                path: link.to_path_buf(),
+                target: symlink_target,
+            })
+        }
+        Err(e) => {
+            return Err(MvlnError::SourceAccessError {
+                path: from,
+                source: e,
+            })
+        }
+    };
+
+    let from_canonical = absolute_path_of(&from, &from_stat);
+    let link_canonical = absolute_path_of(link, &Snapshot::of(link));
+    if from_stat.is_real_dir() && link_canonical.starts_with(&from_canonical) {
+        return Err(MvlnError::DestinationInsideSource {
+            src: from,
+            dest: link.to_path_buf(),
+        });
+    }
+
+    let result = RestoreResult {
+        path: link.to_path_buf(),
+        from,
+        symlink_target,
+    };
+    if options.dry_run {
+        event!(DEBUG, from = %result.from.display(), "dry run, nothing changed");
+        return Ok(result);
+    }
+
+    // Stage the data beside the link, leaving the link intact meanwhile
+    let mut staging_name = std::ffi::OsString::from(".");
+    staging_name.push(link.file_name().unwrap_or_default());
+    staging_name.push(".mvln-restore");
+    let staging = unique_name(&link.with_file_name(staging_name), |p| {
+        p.symlink_metadata().is_ok()
+    });
+    move_file(&result.from, &from_stat, &staging, &options.move_options())?;
+    event!(DEBUG, staging = %staging.display(), "moved data beside the link");
+
+    // A rename replaces a symlink by a file atomically, but not by a
+    // directory
+    let replaced = if from_stat.is_real_dir() {
+        fs::remove_file(link).and_then(|()| fs::rename(&staging, link))
+    } else {
+        fs::rename(&staging, link)
+    };
+    if let Err(e) = replaced {
+        // Put the data back where the link (if still there) leads
+        let _ = fs::rename(&staging, &result.from);
+        return Err(MvlnError::MoveFailed {
+            src: result.from,
+            dest: link.to_path_buf(),
+            source: e,
+        });
+    }
+
+    event!(INFO, from = %result.from.display(), "restored");
+    Ok(result)
+}
+
+/// Resolve destination path: if dest is a directory to move into (see
+/// [`MoveOptions::dir_behavior`]), append source filename. The name is
+/// rewritten by [`MoveOptions::sanitize`] (returning whether that changed
+/// it), then normalized or taken from an existing entry that differs from
+/// it only in normalization (see [`normalize::resolve`]).
+fn resolve_destination(source: &Path, dest: &Path, options: &MoveOptions) -> (PathBuf, bool) {
+    let mut dest = path_utils::resolve_destination(source, dest, options.dir_behavior);
+    let mut sanitized = false;
+    if let (Some(sanitizer), Some(name)) = (&options.sanitize, dest.file_name()) {
+        if let Cow::Owned(name) = sanitizer.name(name) {
+            dest.set_file_name(name);
+            sanitized = true;
+        }
+    }
+    (normalize::resolve(&dest, options.normalize), sanitized)
+}
+
+/// Remove existing destination for force-overwrite.
+/// Checks type compatibility and removes the destination appropriately.
+fn remove_existing_destination(
+    source: &Path,
+    dest: &Path,
+    dest_stat: &Snapshot,
+    source_is_real_dir: bool,
+) -> Result<()> {
+    // Type mismatch check: prevent replacing directory with file or vice versa.
+    // This protects against accidental deletion of entire directory trees.
+    // Symlinks at destination are always replaceable (they're just pointers).
+    if !dest_stat.is_symlink() {
+        let dest_is_dir = dest_stat.is_real_dir();
+        if source_is_real_dir != dest_is_dir {
+            return Err(MvlnError::TypeMismatch {
+                src: source.to_path_buf(),
+                dest: dest.to_path_buf(),
+                src_type: if source_is_real_dir {
+                    "directory"
+                } else {
+                    "file"
+                },
+                dest_type: if dest_is_dir { "directory" } else { "file" },
+            });
+        }
+    }
+
+    // The snapshot comes from symlink_metadata, so a symlink is removed
+    // itself and never followed into its target.
+    let removed = if dest_stat.is_real_dir() {
+        // Actual directory (not symlink), safe to remove recursively
+        fs::remove_dir_all(dest)
+    } else {
+        // Symlink or regular file
+        #[cfg(windows)]
+        clear_read_only(dest, dest_stat);
+        fs::remove_file(dest)
+    };
+    removed.map_err(|e| MvlnError::MoveFailed {
+        src: source.to_path_buf(),
+        dest: dest.to_path_buf(),
+        source: e,
+    })?;
+
+    event!(INFO, dest = %dest.display(), "removed existing destination (--force)");
+    Ok(())
+}
+
+/// Clear the ReadOnly attribute of the file at `dest`, without which
+/// Windows refuses to delete it.
+#[cfg(windows)]
+#[allow(clippy::permissions_set_readonly_false)]
+fn clear_read_only(dest: &Path, dest_stat: &Snapshot) {
+    if let Some(meta) = dest_stat
+        .0
+        .as_ref()
+        .filter(|meta| meta.permissions().readonly())
+    {
+        let mut permissions = meta.permissions();
+        permissions.set_readonly(false);
+        let _ = fs::set_permissions(dest, permissions);
+    }
+}
+
+/// Compute absolute path for a path without following symlinks.
+/// If the path is a symlink, canonicalize the parent and join with filename.
+/// If the path doesn't exist, build absolute path from parent.
+fn absolute_path_of(path: &Path, stat: &Snapshot) -> PathBuf {
+    if stat.is_symlink() {
+        // For symlinks, canonicalize parent and join with filename
+        std::fs::canonicalize(path.parent().unwrap_or(Path::new("."))).map_or_else(
+            |_| path.to_path_buf(),
+            |p| p.join(path.file_name().unwrap_or_default()),
+        )
+    } else if let Some(canonical) = stat.exists().then(|| path.canonicalize().ok()).flatten() {
+        canonical
+    } else {
+        // Path doesn't exist - build absolute path from parent
+        // SAFETY: We must always return an absolute path to ensure starts_with() checks
+        // work correctly. If parent canonicalization fails (e.g., parent doesn't exist),
+        // fall back to joining with current working directory rather than returning
+        // a relative path, which would cause incorrect starts_with() comparisons.
+        path.parent()
+            .map(|p| {
+                if p.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    p
+                }
+            })
+            .and_then(|p| p.canonicalize().ok())
+            .map_or_else(
+                || {
+                    // Fallback: ensure absolute path even if parent doesn't exist
+                    if path.is_absolute() {
+                        path.to_path_buf()
+                    } else {
+                        std::env::current_dir()
+                            .unwrap_or_else(|_| PathBuf::from("."))
+                            .join(path)
+                    }
+                },
+                |p| p.join(path.file_name().unwrap_or_default()),
+            )
+    }
+}
+
+/// Move file or directory from source to dest.
+/// Uses rename for same filesystem, falls back to copy+remove for cross-filesystem.
+/// Returns the entries renamed by [`MoveOptions::sanitize`] in a copy.
+fn move_file(
+    source: &Path,
+    source_stat: &Snapshot,
+    dest: &Path,
+    options: &MoveOptions,
+) -> Result<Renames> {
+    // Try atomic rename first
+    match rename_no_replace(source, dest) {
+        Ok(()) => {
+            event!(DEBUG, "renamed");
+            Ok(Vec::new())
+        }
+        // Created by someone else since the destination was checked
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Err(MvlnError::DestinationExists {
+            path: dest.to_path_buf(),
+        }),
+        Err(e) if is_cross_device_error(&e) && options.no_cross_device => {
+            Err(MvlnError::CrossDevice {
+                src: source.to_path_buf(),
+                dest: dest.to_path_buf(),
+            })
+        }
+        Err(e) if is_cross_device_error(&e) => {
+            // Cross-filesystem: copy then remove
+            event!(DEBUG, "rename crossed filesystems, copying instead");
+            copy_and_remove(source, source_stat, dest, options)
+        }
+        Err(e) => Err(MvlnError::MoveFailed {
+            src: source.to_path_buf(),
+            dest: dest.to_path_buf(),
+            source: e,
+        }),
+    }
+}
+
+/// Rename `source` to `dest`, which was found vacant, failing with
+/// [`ErrorKind::AlreadyExists`] rather than replacing whatever appeared
+/// there since. Atomic on Linux; elsewhere, and on filesystems without
+/// `RENAME_NOREPLACE`, `dest` is checked just before the rename.
+fn rename_no_replace(source: &Path, dest: &Path) -> std::io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use rustix::fs::{renameat_with, RenameFlags, CWD};
+        use rustix::io::Errno;
+        match renameat_with(CWD, source, CWD, dest, RenameFlags::NOREPLACE) {
+            Err(Errno::INVAL | Errno::NOSYS) => {}
+            result => return result.map_err(Into::into),
+        }
+    }
+    if dest.symlink_metadata().is_ok() {
+        return Err(std::io::Error::from(ErrorKind::AlreadyExists));
+    }
+    fs::rename(source, dest)
+}
+
+/// Check if error is cross-device link error (EXDEV).
+fn is_cross_device_error(e: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
         e.raw_os_error() == Some(libc::EXDEV)
     }
     #[cfg(windows)]
@@ -307,206 +1524,718 @@ fn is_cross_device_error(e: &std::io::Error) -> bool {
     }
 }
 
-/// Copy source to dest, verify, then remove source.
-fn copy_and_remove(source: &Path, dest: &Path) -> Result<()> {
-    // SAFETY: Check symlink FIRST before checking is_dir().
-    // is_dir() follows symlinks, which could lead to:
+/// Copy source to dest, verify, then remove source. Returns the entries
+/// of a copied tree renamed by [`MoveOptions::sanitize`].
+fn copy_and_remove(
+    source: &Path,
+    source_stat: &Snapshot,
+    dest: &Path,
+    options: &MoveOptions,
+) -> Result<Renames> {
+    // SAFETY: Check symlink FIRST before checking for a directory.
+    // Following a symlink here could lead to:
     // 1. Copying target contents instead of the symlink itself
     // 2. Traversing outside the source tree
     // 3. remove_dir_all following the symlink and deleting target contents
-    if source.is_symlink() {
+    if source_stat.is_symlink() {
         // Copy the symlink itself, not its target
-        let target = fs::read_link(source).map_err(|e| MvlnError::CopyFailed {
+        copy_symlink(source, dest, options)?;
+
+        // Remove the original symlink (not its target)
+        fs::remove_file(source).map_err(|e| MvlnError::RemoveFailed {
             src: source.to_path_buf(),
             dest: dest.to_path_buf(),
-            reason: format!("failed to read symlink: {e}"),
+            source: e,
         })?;
 
-        #[cfg(unix)]
-        std::os::unix::fs::symlink(&target, dest).map_err(|e| MvlnError::CopyFailed {
+        event!(DEBUG, "copied symlink and removed the original");
+        return Ok(Vec::new());
+    }
+
+    // Not a symlink - proceed with regular file/directory copy
+    if source_stat.is_real_dir() {
+        check_skipped_dirs(source, options)?;
+    }
+    let options = &network_options(dest, options);
+    let renamed = if source_stat.is_real_dir() {
+        check_tree_lengths(source, dest, options)?;
+        copy_dir_tree(source, dest, MAX_COPY_DEPTH, options)?
+    } else {
+        copy_file(source, dest, options)?;
+        Vec::new()
+    };
+
+    // Verify copy succeeded before removing source
+    // NOTE: TOCTOU (Time-of-Check Time-of-Use) race condition warning.
+    // There is a window between verifying dest.exists() and removing source.
+    // If dest is deleted by another process in this window, source removal
+    // will cause data loss. Platform-specific atomic exchange (e.g., renameat2
+    // with RENAME_EXCHANGE on Linux) would be safer, but is not portable.
+    // Do not use mvln in highly concurrent modification environments.
+    if !dest.exists() {
+        return Err(MvlnError::CopyFailed {
             src: source.to_path_buf(),
             dest: dest.to_path_buf(),
-            reason: format!("failed to create symlink: {e}"),
-        })?;
+            source: std::io::Error::new(ErrorKind::NotFound, "destination not found after copy"),
+        });
+    }
 
-        #[cfg(not(unix))]
-        {
-            return Err(MvlnError::CopyFailed {
-                src: source.to_path_buf(),
-                dest: dest.to_path_buf(),
-                reason: "symlinks not supported on this platform".to_string(),
-            });
-        }
+    // Remove source (see TOCTOU warning above)
+    let remove_result = if source_stat.is_real_dir() {
+        remove_copied(source, options)
+    } else {
+        fs::remove_file(source)
+    };
 
-        // Remove the original symlink (not its target)
-        fs::remove_file(source).map_err(|e| MvlnError::RemoveFailed {
+    if let Err(e) = remove_result {
+        return Err(MvlnError::RemoveFailed {
             src: source.to_path_buf(),
             dest: dest.to_path_buf(),
-            reason: format!("failed to remove symlink: {e}"),
+            source: e,
+        });
+    }
+
+    event!(DEBUG, "copied, verified and removed source");
+    Ok(renamed)
+}
+
+/// `options` adjusted for copying onto `dest`: onto a network filesystem,
+/// in chunks of [`copy::NETWORK_BUFFER_SIZE`] unless a buffer size is set,
+/// and with at least [`copy::NETWORK_STALE_RETRIES`] retries.
+fn network_options<'a>(dest: &Path, options: &'a MoveOptions) -> Cow<'a, MoveOptions> {
+    if device::network_filesystem(dest).is_none() {
+        return Cow::Borrowed(options);
+    }
+    event!(DEBUG, dest = %dest.display(), "copying onto a network filesystem");
+    let mut options = options.clone();
+    options.buffer_size.get_or_insert(copy::NETWORK_BUFFER_SIZE);
+    options.stale_retries = options.stale_retries.max(copy::NETWORK_STALE_RETRIES);
+    Cow::Owned(options)
+}
+
+/// Deepest directory nesting copied when a move crosses filesystems.
+///
+/// Deeper trees fail with [`MvlnError::TooDeep`] and the source is left in
+/// place. Real trees never get close, so hitting this almost always means a
+/// pathological or maliciously crafted tree.
+pub const MAX_COPY_DEPTH: usize = 4096;
+
+/// Most files copied at once when a move crosses filesystems.
+///
+/// Directory trees are copied by up to this many threads (fewer on machines
+/// with fewer cores), which cuts the time spent on trees of many small files
+/// where per-file latency, not bandwidth, is the limit.
+pub const MAX_COPY_THREADS: usize = 8;
+
+/// Directories `--skip-vcs` leaves out of copied trees: version control
+/// metadata and package and build caches, which archived copies rarely
+/// need and which often hold most of a project's files.
+pub const VCS_DIRS: &[&str] = &[".git", ".hg", "node_modules", "target", "__pycache__"];
+
+/// Copy a directory tree, nested at most `max_depth` levels deep.
+///
+/// Entries are streamed from a [`WalkDir`]: directories and symlinks are
+/// created in walk order on this thread, while regular files are handed to
+/// a pool of up to [`MAX_COPY_THREADS`] workers through a bounded queue.
+/// Memory use depends on the number of directories rather than files, even
+/// for directories with millions of them. With [`MoveOptions::pack`] the
+/// files go through [`pack::copy_files`] instead.
+///
+/// Returns the `(source, destination)` of entries renamed by
+/// [`MoveOptions::sanitize`].
+fn copy_dir_tree(
+    source: &Path,
+    dest: &Path,
+    max_depth: usize,
+    options: &MoveOptions,
+) -> Result<Renames> {
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(MAX_COPY_THREADS);
+    // The first error of any worker; the others stop once it is set
+    let failed: Mutex<Option<MvlnError>> = Mutex::new(None);
+    let has_failed = || lock(&failed).is_some();
+
+    // Directory metadata is applied once every file is in place (adding
+    // entries would bump the mtime again, and a read-only directory would
+    // reject them), so finished directories wait here, deepest first.
+    let mut done_dirs: Vec<OpenDir> = Vec::new();
+    let mut renamed = Vec::new();
+    // With `PreserveSet::LINKS`, further names of a file copied once are
+    // hard-linked to its copy after the workers are done
+    let preserve_links = options.preserve.contains(PreserveSet::LINKS);
+    let mut copies: HashMap<DirId, PathBuf> = HashMap::new();
+    let mut hard_links: Vec<(PathBuf, PathBuf)> = Vec::new();
+    let tracker = options.on_progress.as_ref().map(|handler| {
+        let (files, bytes) = tree_totals(source, options);
+        Tracker::new(handler, source, files, bytes)
+    });
+    let progress = tracker.as_ref();
+    let (jobs, queue) = mpsc::sync_channel::<(PathBuf, PathBuf)>(workers * 4);
+    let queue = Mutex::new(queue);
+    let walked = thread::scope(|scope| {
+        if options.pack {
+            scope.spawn(|| pack::copy_files(&queue, options, progress, &failed));
+        } else {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let job = lock(&queue).recv();
+                    let Ok((src_path, dest_path)) = job else {
+                        break;
+                    };
+                    if has_failed() {
+                        continue;
+                    }
+                    match copy_file(&src_path, &dest_path, options) {
+                        Ok(()) => {
+                            if let Some(progress) = progress {
+                                progress.copied(&src_path, &dest_path);
+                            }
+                        }
+                        Err(e) => {
+                            lock(&failed).get_or_insert(e);
+                        }
+                    }
+                });
+            }
+        }
+        let (dirs, names) = (&mut done_dirs, &mut renamed);
+        let walked = walk_dir_tree(source, dest, max_depth, options, dirs, names, |job| {
+            let id = if preserve_links {
+                link_id(&job.0)
+            } else {
+                None
+            };
+            if let Some(id) = id {
+                match copies.entry(id) {
+                    Entry::Occupied(copy) => {
+                        hard_links.push((copy.get().clone(), job.1));
+                        return true;
+                    }
+                    Entry::Vacant(slot) => {
+                        slot.insert(job.1.clone());
+                    }
+                }
+            }
+            // Stop walking once a worker failed; its error is reported
+            !has_failed() && jobs.send(job).is_ok()
+        });
+        // Let the workers finish the queue and exit
+        drop(jobs);
+        walked
+    });
+    walked?;
+    if let Some(e) = failed.into_inner().unwrap_or_else(PoisonError::into_inner) {
+        return Err(e);
+    }
+    for (copy, dest_path) in hard_links {
+        fs::hard_link(&copy, &dest_path).map_err(|e| MvlnError::CopyFailed {
+            src: copy,
+            dest: dest_path,
+            source: e,
         })?;
+    }
+    for dir in &done_dirs {
+        copy_dir_metadata(dir, options.preserve);
+    }
+    if let Some(tracker) = &tracker {
+        tracker.finish();
+    }
+    Ok(renamed)
+}
 
+/// Fail with [`MvlnError::SkippedDirKept`] if copying the tree `source`
+/// would leave a directory out (see [`MoveOptions::skip_dirs`]) while the
+/// source is to be replaced by a link: the directory stays in place, and
+/// with it the source.
+fn check_skipped_dirs(source: &Path, options: &MoveOptions) -> Result<()> {
+    if options.skip_dirs.is_empty() || options.no_link {
         return Ok(());
     }
+    let follow = options.symlink_policy.follows_nested();
+    let skipped = WalkDir::new(source)
+        .follow_links(follow)
+        .into_iter()
+        .flatten()
+        .find(|entry| is_skipped(entry, options));
+    match skipped {
+        Some(entry) => Err(MvlnError::SkippedDirKept {
+            src: source.to_path_buf(),
+            dir: entry.into_path(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Remove the tree `source` once it was copied, except the directories
+/// [`MoveOptions::skip_dirs`] left out of the copy, which stay in place
+/// along with the directories holding them. Symlinks are removed, never
+/// followed, unless they point to a directory that was left out.
+fn remove_copied(source: &Path, options: &MoveOptions) -> std::io::Result<()> {
+    if options.skip_dirs.is_empty() {
+        return fs::remove_dir_all(source);
+    }
+    let kept = |entry: &walkdir::DirEntry| {
+        entry.depth() > 0
+            && entry.path().is_dir()
+            && options
+                .skip_dirs
+                .iter()
+                .any(|name| entry.file_name() == name.as_str())
+    };
+    let mut dirs = Vec::new();
+    for entry in WalkDir::new(source).into_iter().filter_entry(|e| !kept(e)) {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            dirs.push(entry.into_path());
+        } else {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    // Deepest first: a directory still holding a kept one stays too
+    for dir in dirs.iter().rev() {
+        match fs::remove_dir(dir) {
+            Err(e) if e.kind() == ErrorKind::DirectoryNotEmpty => {}
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+/// Number of regular files in the tree `source`, and their total size,
+/// leaving out the directories [`MoveOptions::skip_dirs`] skips.
+fn tree_totals(source: &Path, options: &MoveOptions) -> (u64, u64) {
+    let follow = options.symlink_policy.follows_nested();
+    let walk = WalkDir::new(source).follow_links(follow).into_iter();
+    walk.filter_entry(|e| !is_skipped(e, options))
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .fold((0, 0), |(files, bytes), entry| {
+            let len = entry.metadata().map_or(0, |m| m.len());
+            (files + 1, bytes + len)
+        })
+}
 
-    // Not a symlink - proceed with regular file/directory copy
-    if source.is_dir() {
-        copy_dir_recursive(source, dest)?;
-    } else {
-        fs::copy(source, dest).map_err(|e| MvlnError::CopyFailed {
-            src: source.to_path_buf(),
-            dest: dest.to_path_buf(),
-            reason: e.to_string(),
-        })?;
+/// Check where every entry of the tree `source` would go under `dest`
+/// against the limits of the filesystem of `dest`, so a name or path that
+/// is too long fails the move before anything is copied.
+///
+/// Names are taken as [`MoveOptions::sanitize`] would rewrite them (without
+/// the suffix added on a clash). Entries that cannot be read are left for
+/// the copy to report.
+fn check_tree_lengths(source: &Path, dest: &Path, options: &MoveOptions) -> Result<()> {
+    let limits = PathLimits::of(dest);
+    let follow = options.symlink_policy.follows_nested();
+    // The destinations of the directories on the path to the current entry
+    let mut dirs = vec![dest.to_path_buf()];
+    let walk = WalkDir::new(source).follow_links(follow).min_depth(1);
+    for entry in walk.into_iter().filter_entry(|e| !is_skipped(e, options)) {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        dirs.truncate(entry.depth());
+        let name = entry.file_name();
+        let name = match &options.sanitize {
+            Some(sanitizer) => sanitizer.name(name),
+            None => Cow::Borrowed(name),
+        };
+        let path = limits.join(&dirs[entry.depth() - 1], &name)?;
+        if entry.file_type().is_dir() {
+            dirs.push(path);
+        }
+    }
+    Ok(())
+}
 
-        // Attempt to preserve modification time
-        if let Ok(metadata) = source.metadata() {
-            if let Ok(mtime) = metadata.modified() {
-                if let Ok(dest_file) = fs::File::open(dest) {
-                    let _ = dest_file.set_modified(mtime);
+/// Walk `source`, creating directories and symlinks under `dest` and
+/// passing each regular file to `copy` as a `(source, dest)` pair until it
+/// returns `false`. Directories are added to `done_dirs` as the walk leaves
+/// them, and entries renamed by [`MoveOptions::sanitize`] to `renamed`.
+///
+/// Unless [`SymlinkPolicy::Follow`] is set, symlinks are copied as links, so
+/// cycles through them are harmless. Otherwise their targets are copied
+/// instead and only dangling symlinks are kept as links. Either way, a directory that
+/// is one of its own ancestors (a bind mount of a parent, or a followed
+/// symlink to one) fails with [`MvlnError::FilesystemLoop`].
+fn walk_dir_tree(
+    source: &Path,
+    dest: &Path,
+    max_depth: usize,
+    options: &MoveOptions,
+    done_dirs: &mut Vec<OpenDir>,
+    renamed: &mut Renames,
+    mut copy: impl FnMut((PathBuf, PathBuf)) -> bool,
+) -> Result<()> {
+    // The directories on the path to the current entry, by depth, and
+    // their identities
+    let mut open_dirs: Vec<OpenDir> = Vec::new();
+    let mut open_ids: Vec<Option<DirId>> = Vec::new();
+    let follow = options.symlink_policy.follows_nested();
+    let walk = WalkDir::new(source).follow_links(follow).into_iter();
+    for entry in walk.filter_entry(|e| !is_skipped(e, options)) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let src_path = e.path().unwrap_or(source).to_path_buf();
+                if let Some(ancestor) = e.loop_ancestor() {
+                    return Err(MvlnError::FilesystemLoop {
+                        path: src_path,
+                        ancestor: ancestor.to_path_buf(),
+                    });
+                }
+                // A followed symlink that turns out to be dangling
+                let dangling = follow
+                    && e.depth() > 0
+                    && e.io_error()
+                        .is_some_and(|io| io.kind() == ErrorKind::NotFound)
+                    && src_path.symlink_metadata().is_ok_and(|m| m.is_symlink());
+                if !dangling {
+                    return Err(MvlnError::CopyFailed {
+                        src: src_path,
+                        dest: dest.to_path_buf(),
+                        source: e.into(),
+                    });
                 }
+                while open_dirs.len() > e.depth() {
+                    done_dirs.extend(open_dirs.pop());
+                    open_ids.pop();
+                }
+                if let Some(parent) = open_dirs.last() {
+                    let dest_path = entry_dest(&src_path, &parent.dest, options, renamed);
+                    copy_symlink(&src_path, &dest_path, options)?;
+                }
+                continue;
+            }
+        };
+        while open_dirs.len() > entry.depth() {
+            done_dirs.extend(open_dirs.pop());
+            open_ids.pop();
+        }
+        let src_path = entry.path();
+        let dest_path = match open_dirs.last() {
+            Some(parent) => entry_dest(src_path, &parent.dest, options, renamed),
+            None => dest.to_path_buf(),
+        };
+
+        // SAFETY: Unless asked to, the walk does not follow symlinks, so a
+        // symlink is never descended into (which could escape the source
+        // tree) and is copied as a link rather than as its target's
+        // contents. Removing the source afterwards never follows them.
+        let file_type = entry.file_type();
+        if file_type.is_symlink() {
+            copy_symlink(src_path, &dest_path, options)?;
+        } else if file_type.is_dir() {
+            if entry.depth() > max_depth {
+                return Err(MvlnError::TooDeep {
+                    path: src_path.to_path_buf(),
+                    limit: max_depth,
+                });
+            }
+            let id = dir_id(&entry);
+            if let Some(at) = id.and_then(|id| open_ids.iter().position(|&open| open == Some(id))) {
+                return Err(MvlnError::FilesystemLoop {
+                    path: src_path.to_path_buf(),
+                    ancestor: open_dirs[at].source.clone(),
+                });
             }
+            fs::create_dir_all(&dest_path).map_err(|e| MvlnError::CreateDirFailed {
+                path: dest_path.clone(),
+                source: e,
+            })?;
+            copy_dir_attrs(&entry, &dest_path, options)?;
+            open_dirs.push(OpenDir {
+                source: src_path.to_path_buf(),
+                dest: dest_path,
+                metadata: entry.metadata().ok(),
+            });
+            open_ids.push(id);
+        } else if !copy((src_path.to_path_buf(), dest_path)) {
+            break;
         }
     }
+    while let Some(dir) = open_dirs.pop() {
+        done_dirs.push(dir);
+    }
+    Ok(())
+}
 
-    // Verify copy succeeded before removing source
-    // NOTE: TOCTOU (Time-of-Check Time-of-Use) race condition warning.
-    // There is a window between verifying dest.exists() and removing source.
-    // If dest is deleted by another process in this window, source removal
-    // will cause data loss. Platform-specific atomic exchange (e.g., renameat2
-    // with RENAME_EXCHANGE on Linux) would be safer, but is not portable.
-    // Do not use mvln in highly concurrent modification environments.
-    if !dest.exists() {
-        return Err(MvlnError::CopyFailed {
-            src: source.to_path_buf(),
-            dest: dest.to_path_buf(),
-            reason: "destination not found after copy".to_string(),
-        });
+/// Whether `entry` is a directory inside a copied tree that
+/// [`MoveOptions::skip_dirs`] leaves out.
+fn is_skipped(entry: &walkdir::DirEntry, options: &MoveOptions) -> bool {
+    let skipped = entry.depth() > 0
+        && entry.file_type().is_dir()
+        && options
+            .skip_dirs
+            .iter()
+            .any(|name| entry.file_name() == name.as_str());
+    if skipped {
+        event!(DEBUG, path = %entry.path().display(), "left out of the copy");
     }
+    skipped
+}
 
-    // Remove source (see TOCTOU warning above)
-    let remove_result = if source.is_dir() {
-        fs::remove_dir_all(source)
-    } else {
-        fs::remove_file(source)
+/// Where the entry `src_path` of a copied tree goes in the directory
+/// `dir`: under its own name, or under the name [`MoveOptions::sanitize`]
+/// gives it, recorded in `renamed`. A rewritten name never takes the name
+/// of a sibling or of an earlier rename; it gets a ` (1)` suffix instead.
+fn entry_dest(
+    src_path: &Path,
+    dir: &Path,
+    options: &MoveOptions,
+    renamed: &mut Renames,
+) -> PathBuf {
+    let name = src_path.file_name().unwrap_or_default();
+    let Some(Cow::Owned(fixed)) = options.sanitize.as_ref().map(|s| s.name(name)) else {
+        return dir.join(name);
     };
+    let src_dir = src_path.parent().unwrap_or(Path::new(""));
+    let dest_path = unique_name(&dir.join(fixed), |candidate| {
+        let sibling = candidate
+            .file_name()
+            .is_some_and(|name| src_dir.join(name).symlink_metadata().is_ok());
+        sibling || renamed.iter().any(|(_, taken)| taken == candidate)
+    });
+    renamed.push((src_path.to_path_buf(), dest_path.clone()));
+    dest_path
+}
 
-    if let Err(e) = remove_result {
-        return Err(MvlnError::RemoveFailed {
-            src: source.to_path_buf(),
-            dest: dest.to_path_buf(),
-            reason: e.to_string(),
-        });
-    }
+/// A directory being copied. Its metadata is taken before its entries are
+/// read, which updates its access time.
+struct OpenDir {
+    source: PathBuf,
+    dest: PathBuf,
+    metadata: Option<fs::Metadata>,
+}
 
+/// Give the directory just created at `dest_path` the ownership and
+/// extended attributes of `entry`, as configured. Its mode and mtime wait
+/// until its contents are in place (see [`copy_dir_metadata`]).
+fn copy_dir_attrs(
+    entry: &walkdir::DirEntry,
+    dest_path: &Path,
+    options: &MoveOptions,
+) -> Result<()> {
+    let failed = |e| MvlnError::CopyFailed {
+        src: entry.path().to_path_buf(),
+        dest: dest_path.to_path_buf(),
+        source: e,
+    };
+    if options.preserve.intersects(PreserveSet::OWNERSHIP) {
+        let metadata = entry.metadata().map_err(|e| failed(e.into()))?;
+        ownership::restore(&metadata, dest_path, options).map_err(failed)?;
+    }
+    if options.preserve.copies_xattrs() && cfg!(unix) {
+        let source = fs::File::open(entry.path()).map_err(failed)?;
+        let dest = fs::File::open(dest_path).map_err(failed)?;
+        preserve::copy_xattrs(&source, &dest, options.preserve).map_err(failed)?;
+    }
     Ok(())
 }
 
-/// Recursively copy a directory.
-fn copy_dir_recursive(source: &Path, dest: &Path) -> Result<()> {
-    fs::create_dir_all(dest).map_err(|e| MvlnError::CreateDirFailed {
-        path: dest.to_path_buf(),
-        reason: e.to_string(),
-    })?;
+/// What identifies a directory (or file): its device and inode numbers.
+type DirId = (u64, u64);
 
-    for entry in fs::read_dir(source).map_err(|e| MvlnError::CopyFailed {
-        src: source.to_path_buf(),
-        dest: dest.to_path_buf(),
-        reason: e.to_string(),
-    })? {
-        let entry = entry.map_err(|e| MvlnError::CopyFailed {
-            src: source.to_path_buf(),
-            dest: dest.to_path_buf(),
-            reason: e.to_string(),
-        })?;
+#[cfg(unix)]
+fn dir_id(entry: &walkdir::DirEntry) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = entry.metadata().ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
 
-        let src_path = entry.path();
-        let dest_path = dest.join(entry.file_name());
-
-        // SAFETY: Check symlink FIRST before is_dir().
-        // is_dir() follows symlinks, which could cause:
-        // 1. Recursing into directories outside the source tree
-        // 2. Copying target contents instead of the symlink itself
-        if src_path.is_symlink() {
-            // Copy the symlink itself, not its target
-            let target = fs::read_link(&src_path).map_err(|e| MvlnError::CopyFailed {
-                src: src_path.clone(),
-                dest: dest_path.clone(),
-                reason: format!("failed to read symlink: {e}"),
-            })?;
+#[cfg(not(unix))]
+fn dir_id(_entry: &walkdir::DirEntry) -> Option<DirId> {
+    None
+}
 
-            #[cfg(unix)]
-            std::os::unix::fs::symlink(&target, &dest_path).map_err(|e| MvlnError::CopyFailed {
-                src: src_path.clone(),
-                dest: dest_path.clone(),
-                reason: format!("failed to create symlink: {e}"),
-            })?;
+/// The identity of the file at `path` if it has more than one name.
+#[cfg(unix)]
+fn link_id(path: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = path.metadata().ok()?;
+    (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
 
-            #[cfg(not(unix))]
-            {
-                return Err(MvlnError::CopyFailed {
-                    src: src_path.clone(),
-                    dest: dest_path,
-                    reason: "symlinks not supported on this platform".to_string(),
-                });
-            }
+#[cfg(not(unix))]
+fn link_id(_path: &Path) -> Option<DirId> {
+    None
+}
 
-            // Continue to next entry - do NOT recurse into the symlink
-            continue;
-        }
+/// Copy the regular file `src_path` to `dest_path` with the attributes
+/// selected by `options` (see [`copy::copy_file`]).
+fn copy_file(src_path: &Path, dest_path: &Path, options: &MoveOptions) -> Result<()> {
+    copy::copy_file(src_path, dest_path, options)
+        .map(|_| ())
+        .map_err(|e| MvlnError::CopyFailed {
+            src: src_path.to_path_buf(),
+            dest: dest_path.to_path_buf(),
+            source: e,
+        })
+}
 
-        // Not a symlink - check if directory or regular file
-        if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dest_path)?;
-        } else {
-            fs::copy(&src_path, &dest_path).map_err(|e| MvlnError::CopyFailed {
-                src: src_path.clone(),
-                dest: dest_path.clone(),
-                reason: e.to_string(),
-            })?;
+/// Lock `mutex`, also if another thread panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
-            // Attempt to preserve modification time
-            if let Ok(metadata) = src_path.metadata() {
-                if let Ok(mtime) = metadata.modified() {
-                    if let Ok(dest_file) = fs::File::open(&dest_path) {
-                        let _ = dest_file.set_modified(mtime);
-                    }
-                }
-            }
-        }
+/// Copy the symlink `src_path` itself (not its target) to `dest_path`,
+/// restoring its ownership if configured.
+fn copy_symlink(src_path: &Path, dest_path: &Path, options: &MoveOptions) -> Result<()> {
+    let copy_failed = |e| MvlnError::CopyFailed {
+        src: src_path.to_path_buf(),
+        dest: dest_path.to_path_buf(),
+        source: e,
+    };
+    let target = fs::read_link(src_path).map_err(copy_failed)?;
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&target, dest_path).map_err(copy_failed)?;
+        let metadata = src_path.symlink_metadata().map_err(copy_failed)?;
+        ownership::restore(&metadata, dest_path, options).map_err(copy_failed)
     }
 
-    // Attempt to preserve directory permissions and modification time
-    if let Ok(metadata) = source.metadata() {
-        // Preserve permissions
-        let perms = metadata.permissions();
-        let _ = fs::set_permissions(dest, perms);
+    #[cfg(not(unix))]
+    {
+        let _ = (target, options);
+        Err(MvlnError::CopyFailed {
+            src: src_path.to_path_buf(),
+            dest: dest_path.to_path_buf(),
+            source: std::io::Error::new(
+                ErrorKind::Unsupported,
+                "symlinks not supported on this platform",
+            ),
+        })
+    }
+}
 
-        // Preserve modification time
-        if let Ok(mtime) = metadata.modified() {
-            if let Ok(dest_file) = fs::File::open(dest) {
-                let _ = dest_file.set_modified(mtime);
-            }
+/// Attempt to preserve directory permissions and access and modification
+/// times, as far as `preserve` asks for them.
+fn copy_dir_metadata(dir: &OpenDir, preserve: PreserveSet) {
+    let Some(metadata) = &dir.metadata else {
+        return;
+    };
+    // Preserve permissions
+    if preserve.contains(PreserveSet::MODE) {
+        let _ = fs::set_permissions(&dir.dest, metadata.permissions());
+    }
+
+    // Preserve access and modification times
+    if preserve.contains(PreserveSet::TIMESTAMPS) {
+        if let Ok(dest_dir) = fs::File::open(&dir.dest) {
+            let _ = preserve::copy_times(metadata, &dest_dir);
         }
     }
+}
+
+/// Directories and `(link, target)` pairs making up a link farm.
+type FarmPlan = (Vec<PathBuf>, Vec<(PathBuf, PathBuf)>);
+
+/// Plan a link farm at `source` mirroring `dest` down to `link_depth`.
+///
+/// The structure is read from `tree`, which is `dest` after the move and
+/// `source` itself in dry-run mode. Directories at depth below the limit
+/// become real directories; everything else becomes a symlink into `dest`.
+fn plan_link_farm(
+    tree: &Path,
+    source: &Path,
+    dest: &Path,
+    options: &MoveOptions,
+) -> Result<FarmPlan> {
+    let mut plan = (vec![source.to_path_buf()], Vec::new());
+    plan_link_farm_level(tree, source, dest, options, options.link_depth, &mut plan)?;
+    Ok(plan)
+}
+
+fn plan_link_farm_level(
+    tree: &Path,
+    source: &Path,
+    dest: &Path,
+    options: &MoveOptions,
+    depth: usize,
+    plan: &mut FarmPlan,
+) -> Result<()> {
+    let read_err = |e: std::io::Error| MvlnError::SourceAccessError {
+        path: tree.to_path_buf(),
+        source: e,
+    };
+    let mut entries = fs::read_dir(tree)
+        .map_err(read_err)?
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(read_err)?;
+    entries.sort_by_key(fs::DirEntry::file_name);
 
+    for entry in entries {
+        let name = entry.file_name();
+        let link = source.join(&name);
+        let target = dest.join(&name);
+        let is_real_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if is_real_dir && depth > 1 {
+            plan.0.push(link.clone());
+            plan_link_farm_level(&entry.path(), &link, &target, options, depth - 1, plan)?;
+        } else {
+            let symlink_target = compute_symlink_target(&link, &target, &options.symlink_style);
+            plan.1.push((link, symlink_target));
+        }
+    }
     Ok(())
 }
 
+/// Replace the moved directory at `source` with a link farm into `dest`.
+///
+/// On failure the partially built farm is removed again (it only contains
+/// directories and symlinks created here), so the recovery command printed
+/// for [`MvlnError::SymlinkFailed`] still applies.
+fn create_link_farm(
+    source: &Path,
+    dest: &Path,
+    options: &MoveOptions,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let (dirs, links) = plan_link_farm(dest, source, dest, options)?;
+    let failed = |link: &Path, source: std::io::Error| MvlnError::SymlinkFailed {
+        link: link.to_path_buf(),
+        target: dest.to_path_buf(),
+        source,
+    };
+
+    // Only clean up after this point: the top-level directory is ours
+    fs::create_dir(source).map_err(|e| failed(source, e))?;
+
+    let build = || -> Result<()> {
+        for dir in dirs.iter().skip(1) {
+            fs::create_dir(dir).map_err(|e| failed(dir, e))?;
+        }
+        for (link, target) in &links {
+            let resolved = link
+                .strip_prefix(source)
+                .map_or_else(|_| dest.to_path_buf(), |rel| dest.join(rel));
+            // Report the farm's root as the target: that is what recovery moves back
+            create_symlink(link, &resolved, target).map_err(|e| match e {
+                MvlnError::SymlinkFailed { source, .. } => failed(link, source),
+                e => e,
+            })?;
+        }
+        Ok(())
+    };
+
+    build().inspect_err(|_| {
+        let _ = fs::remove_dir_all(source);
+    })?;
+    Ok(links)
+}
+
 /// Create symlink at source location pointing to destination.
 fn create_symlink(source: &Path, dest: &Path, symlink_target: &Path) -> Result<()> {
     // Remove any existing file/symlink at source location
     // (source was moved, so it shouldn't exist, but handle edge cases)
-    if source.exists() || source.is_symlink() {
-        match fs::remove_file(source) {
-            Ok(()) => {}
-            Err(e) if e.kind() == ErrorKind::NotFound => {}
-            Err(e) => {
-                return Err(MvlnError::SymlinkFailed {
-                    link: source.to_path_buf(),
-                    target: symlink_target.to_path_buf(),
-                    reason: format!("failed to remove existing file at source: {e}"),
-                });
-            }
+    match fs::remove_file(source) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => {
+            return Err(MvlnError::SymlinkFailed {
+                link: source.to_path_buf(),
+                target: symlink_target.to_path_buf(),
+                source: e,
+            });
         }
     }
 
@@ -517,7 +2246,7 @@ fn create_symlink(source: &Path, dest: &Path, symlink_target: &Path) -> Result<(
             MvlnError::SymlinkFailed {
                 link: source.to_path_buf(),
                 target: dest.to_path_buf(),
-                reason: e.to_string(),
+                source: e,
             }
         })?;
     }
@@ -532,7 +2261,7 @@ fn create_symlink(source: &Path, dest: &Path, symlink_target: &Path) -> Result<(
         .map_err(|e| MvlnError::SymlinkFailed {
             link: source.to_path_buf(),
             target: dest.to_path_buf(),
-            reason: e.to_string(),
+            source: e,
         })?;
     }
 
@@ -541,9 +2270,602 @@ fn create_symlink(source: &Path, dest: &Path, symlink_target: &Path) -> Result<(
         return Err(MvlnError::SymlinkFailed {
             link: source.to_path_buf(),
             target: dest.to_path_buf(),
-            reason: "symlinks not supported on this platform".to_string(),
+            source: std::io::Error::new(
+                ErrorKind::Unsupported,
+                "symlinks not supported on this platform",
+            ),
         });
     }
 
+    event!(
+        DEBUG,
+        link = %source.display(),
+        target = %symlink_target.display(),
+        "created symlink"
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create `levels` nested directories under `root` with a file at the
+    /// bottom, returning the deepest directory.
+    fn nested(root: &Path, levels: usize) -> PathBuf {
+        let mut dir = root.to_path_buf();
+        for _ in 0..levels {
+            dir.push("d");
+        }
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("leaf.txt"), "leaf").unwrap();
+        dir
+    }
+
+    #[test]
+    fn copy_dir_tree_copies_deep_trees() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        let leaf = nested(&source, 200);
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("leaf.txt", leaf.join("link")).unwrap();
+
+        let dest = tmp.path().join("dest");
+        copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &MoveOptions::default()).unwrap();
+
+        let copied = dest.join(leaf.strip_prefix(&source).unwrap());
+        assert_eq!(fs::read_to_string(copied.join("leaf.txt")).unwrap(), "leaf");
+        #[cfg(unix)]
+        assert_eq!(
+            fs::read_link(copied.join("link")).unwrap(),
+            Path::new("leaf.txt")
+        );
+    }
+
+    #[test]
+    fn copy_dir_tree_leaves_out_skipped_dirs() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        fs::create_dir_all(source.join(".git/objects")).unwrap();
+        fs::create_dir_all(source.join("lib/target")).unwrap();
+        fs::write(source.join(".git/HEAD"), "ref").unwrap();
+        fs::write(source.join("lib/target/out.o"), "obj").unwrap();
+        fs::write(source.join("lib/main.rs"), "fn main() {}").unwrap();
+        // Only directories are left out
+        fs::write(source.join("target"), "a file").unwrap();
+
+        let dest = tmp.path().join("dest");
+        let options = MoveOptions::builder()
+            .skip_dirs(VCS_DIRS.iter().copied())
+            .build();
+        copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &options).unwrap();
+
+        assert!(dest.join("lib/main.rs").exists());
+        assert!(dest.join("target").is_file());
+        assert!(!dest.join(".git").exists());
+        assert!(!dest.join("lib/target").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn remove_copied_keeps_skipped_dirs() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        let outside = tmp.path().join("outside");
+        fs::create_dir_all(source.join("lib/target")).unwrap();
+        fs::create_dir_all(source.join("docs")).unwrap();
+        fs::create_dir(&outside).unwrap();
+        fs::write(source.join("lib/target/out.o"), "obj").unwrap();
+        fs::write(source.join("lib/main.rs"), "fn main() {}").unwrap();
+        fs::write(outside.join("keep.txt"), "keep").unwrap();
+        std::os::unix::fs::symlink(&outside, source.join("docs/link")).unwrap();
+
+        let options = MoveOptions::builder()
+            .skip_dirs(VCS_DIRS.iter().copied())
+            .build();
+        remove_copied(&source, &options).unwrap();
+
+        assert!(source.join("lib/target/out.o").exists());
+        assert!(!source.join("lib/main.rs").exists());
+        assert!(!source.join("docs").exists());
+        assert!(outside.join("keep.txt").exists());
+
+        // Without skipped directories left, nothing of the source remains
+        fs::remove_dir_all(source.join("lib/target")).unwrap();
+        remove_copied(&source, &options).unwrap();
+        assert!(!source.exists());
+    }
+
+    #[test]
+    fn copy_dir_tree_rejects_trees_beyond_the_limit() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        nested(&source, 5);
+        let dest = tmp.path().join("dest");
+
+        copy_dir_tree(&source, &dest, 5, &MoveOptions::default()).unwrap();
+        let error = copy_dir_tree(
+            &source,
+            &tmp.path().join("dest2"),
+            4,
+            &MoveOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(error, MvlnError::TooDeep { limit: 4, .. }));
+        assert_eq!(error.paths(), [source.join("d/d/d/d/d")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dir_tree_keeps_symlink_cycles_as_links() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        fs::create_dir_all(source.join("a/b")).unwrap();
+        std::os::unix::fs::symlink("../..", source.join("a/b/up")).unwrap();
+        std::os::unix::fs::symlink("self", source.join("self")).unwrap();
+
+        let dest = tmp.path().join("dest");
+        copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &MoveOptions::default()).unwrap();
+        assert_eq!(
+            fs::read_link(dest.join("a/b/up")).unwrap(),
+            Path::new("../..")
+        );
+        assert_eq!(fs::read_link(dest.join("self")).unwrap(), Path::new("self"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dir_tree_follows_nested_symlinks_when_asked() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let outside = tmp.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("data.txt"), "data").unwrap();
+        let source = tmp.path().join("src");
+        fs::create_dir_all(&source).unwrap();
+        std::os::unix::fs::symlink(&outside, source.join("dir")).unwrap();
+        std::os::unix::fs::symlink("missing", source.join("dangling")).unwrap();
+
+        let options = MoveOptions::builder()
+            .symlink_policy(SymlinkPolicy::Follow)
+            .build();
+        let dest = tmp.path().join("dest");
+        copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &options).unwrap();
+        assert!(dest.join("dir").symlink_metadata().unwrap().is_dir());
+        assert_eq!(
+            fs::read_to_string(dest.join("dir/data.txt")).unwrap(),
+            "data"
+        );
+        assert_eq!(
+            fs::read_link(dest.join("dangling")).unwrap(),
+            Path::new("missing")
+        );
+
+        // A followed cycle is a loop, not an endless copy
+        std::os::unix::fs::symlink("..", outside.join("up")).unwrap();
+        let error = copy_dir_tree(&source, &tmp.path().join("again"), MAX_COPY_DEPTH, &options)
+            .unwrap_err();
+        assert!(matches!(error, MvlnError::FilesystemLoop { .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dir_tree_restores_ownership() {
+        use std::os::unix::fs::MetadataExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("sub/file.txt"), "data").unwrap();
+        std::os::unix::fs::symlink("sub/file.txt", source.join("link")).unwrap();
+
+        let options = MoveOptions::builder()
+            .preserve_owner(true)
+            .preserve_group(true)
+            .numeric_ids(true)
+            .build();
+        let dest = tmp.path().join("dest");
+        copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &options).unwrap();
+        for path in ["sub", "sub/file.txt", "link"] {
+            let original = source.join(path).symlink_metadata().unwrap();
+            let copied = dest.join(path).symlink_metadata().unwrap();
+            assert_eq!(
+                (copied.uid(), copied.gid()),
+                (original.uid(), original.gid())
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dir_tree_preserves_access_and_modification_times() {
+        use std::time::{Duration, SystemTime};
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("sub/file.txt"), "data").unwrap();
+        let accessed = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let modified = accessed + Duration::from_secs(100);
+        let times = fs::FileTimes::new()
+            .set_accessed(accessed)
+            .set_modified(modified);
+        for path in ["sub/file.txt", "sub"] {
+            fs::File::open(source.join(path))
+                .unwrap()
+                .set_times(times)
+                .unwrap();
+        }
+
+        let dest = tmp.path().join("dest");
+        copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &MoveOptions::default()).unwrap();
+        for path in ["sub/file.txt", "sub"] {
+            let metadata = dest.join(path).metadata().unwrap();
+            assert_eq!(metadata.accessed().unwrap(), accessed, "{path}");
+            assert_eq!(metadata.modified().unwrap(), modified, "{path}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dir_tree_preserves_hard_links_when_asked() {
+        use std::os::unix::fs::MetadataExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("a.txt"), "shared").unwrap();
+        fs::hard_link(source.join("a.txt"), source.join("sub/b.txt")).unwrap();
+
+        let ino = |path: PathBuf| path.metadata().unwrap().ino();
+        let dest = tmp.path().join("plain");
+        copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &MoveOptions::default()).unwrap();
+        assert_ne!(ino(dest.join("a.txt")), ino(dest.join("sub/b.txt")));
+
+        let options = MoveOptions::builder()
+            .preserve(PreserveSet::default() | PreserveSet::LINKS)
+            .build();
+        let dest = tmp.path().join("linked");
+        copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &options).unwrap();
+        assert_eq!(ino(dest.join("a.txt")), ino(dest.join("sub/b.txt")));
+        assert_eq!(
+            fs::read_to_string(dest.join("sub/b.txt")).unwrap(),
+            "shared"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follow_top_level_moves_the_link_target() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let data = tmp.path().join("data.txt");
+        fs::write(&data, "data").unwrap();
+        let link = tmp.path().join("link");
+        std::os::unix::fs::symlink("data.txt", &link).unwrap();
+        let dest = tmp.path().join("archive");
+        fs::create_dir(&dest).unwrap();
+
+        let options = MoveOptions::builder()
+            .symlink_policy(SymlinkPolicy::FollowTopLevelOnly)
+            .build();
+        let result = move_and_link(&link, &dest, &options).unwrap();
+        assert_eq!(
+            result.source,
+            fs::canonicalize(tmp.path()).unwrap().join("data.txt")
+        );
+        assert_eq!(fs::read_to_string(dest.join("data.txt")).unwrap(), "data");
+        assert!(data.symlink_metadata().unwrap().is_symlink());
+        assert_eq!(fs::read_link(&link).unwrap(), Path::new("data.txt"));
+        assert_eq!(fs::read_to_string(&link).unwrap(), "data");
+
+        let dangling = tmp.path().join("dangling");
+        std::os::unix::fs::symlink("missing", &dangling).unwrap();
+        let error = move_and_link(&dangling, &dest, &options).unwrap_err();
+        assert!(matches!(error, MvlnError::DanglingSymlink { .. }));
+    }
+
+    #[test]
+    fn no_link_only_moves() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("a.txt");
+        fs::write(&source, "data").unwrap();
+        let dest = tmp.path().join("b.txt");
+
+        let options = MoveOptions::builder().no_link(true).build();
+        let result = move_and_link(&source, &dest, &options).unwrap();
+        assert_eq!(result.dest, dest);
+        assert!(source.symlink_metadata().is_err());
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "data");
+    }
+
+    #[test]
+    fn cross_device_handler_is_not_asked_within_one_filesystem() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("a.txt");
+        fs::write(&source, "data").unwrap();
+        let options = MoveOptions::builder()
+            .on_cross_device(|info| panic!("asked about {}", info.dest.display()))
+            .build();
+
+        let result = move_and_link(&source, tmp.path().join("sub/b.txt"), &options).unwrap();
+        assert_eq!(fs::read_to_string(&result.dest).unwrap(), "data");
+    }
+
+    #[test]
+    fn skip_same_removes_the_source_of_an_earlier_copy() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("a.txt");
+        let dest = tmp.path().join("b.txt");
+        fs::write(&source, "data").unwrap();
+        fs::copy(&source, &dest).unwrap();
+        let mtime = fs::metadata(&source).unwrap().modified().unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&dest)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        let options = MoveOptions::builder().skip_same(true).build();
+
+        // A different size is still a conflict
+        fs::write(tmp.path().join("c.txt"), "other data").unwrap();
+        assert!(matches!(
+            move_and_link(tmp.path().join("c.txt"), &dest, &options),
+            Err(MvlnError::DestinationExists { .. })
+        ));
+
+        let result = move_and_link(&source, &dest, &options).unwrap();
+        assert!(result.skipped_same);
+        assert!(source.is_symlink());
+        assert_eq!(fs::read_to_string(&source).unwrap(), "data");
+    }
+
+    #[test]
+    fn destination_appearing_after_the_snapshot_is_not_overwritten() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("a.txt");
+        let dest = tmp.path().join("b.txt");
+        fs::write(&source, "mine").unwrap();
+        let source_stat = stat_source(&source).unwrap();
+        let dest_stat = Snapshot::of(&dest);
+        assert!(!dest_stat.exists());
+
+        // Another process creates the destination after it was checked
+        fs::write(&dest, "theirs").unwrap();
+        let options = MoveOptions::default();
+        assert!(matches!(
+            place(&source, &source_stat, &dest, None, false, &options),
+            Err(MvlnError::DestinationExists { .. })
+        ));
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "theirs");
+        assert_eq!(fs::read_to_string(&source).unwrap(), "mine");
+    }
+
+    /// The outcome of moving `source` to `dest` worked out with fresh
+    /// `fs::metadata` calls instead of [`Snapshot`]s.
+    #[cfg(unix)]
+    fn expected_outcome(source: &Path, dest: &Path) -> std::result::Result<PathBuf, &'static str> {
+        let dest = if fs::metadata(dest).is_ok_and(|m| m.is_dir()) {
+            dest.join(source.file_name().unwrap())
+        } else {
+            dest.to_path_buf()
+        };
+        if fs::symlink_metadata(source).is_ok_and(|m| m.is_dir()) && dest.starts_with(source) {
+            return Err("inside source");
+        }
+        if fs::symlink_metadata(&dest).is_ok() {
+            return Err("exists");
+        }
+        Ok(dest)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn snapshots_plan_moves_as_fresh_metadata_does() {
+        use std::os::unix::fs::symlink;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let src = tmp.path().join("src");
+        let dst = tmp.path().join("dst");
+        fs::create_dir_all(src.join("dir")).unwrap();
+        fs::create_dir_all(dst.join("dir")).unwrap();
+        for root in [&src, &dst] {
+            fs::write(root.join("file"), "data").unwrap();
+            symlink(root.join("file"), root.join("link")).unwrap();
+            symlink(root.join("dir"), root.join("dir-link")).unwrap();
+            symlink(root.join("missing"), root.join("dangling")).unwrap();
+        }
+        let names = ["file", "dir", "link", "dir-link", "dangling", "missing"];
+        let options = MoveOptions::builder().dry_run(true).build();
+
+        for source in names.iter().take(5).map(|name| src.join(name)) {
+            let stat = Snapshot::of(&source);
+            assert_eq!(stat.exists(), source.symlink_metadata().is_ok());
+            assert_eq!(stat.is_symlink(), source.is_symlink());
+            assert_eq!(stat.is_real_dir(), source.is_dir() && !source.is_symlink());
+
+            let dests = names.iter().map(|name| dst.join(name));
+            for dest in dests.chain([src.join("dir")]) {
+                let outcome = match move_and_link(&source, &dest, &options) {
+                    Ok(result) => Ok(result.dest),
+                    Err(MvlnError::DestinationInsideSource { .. }) => Err("inside source"),
+                    Err(MvlnError::DestinationExists { .. }) => Err("exists"),
+                    Err(e) => panic!("{} -> {}: {e}", source.display(), dest.display()),
+                };
+                assert_eq!(
+                    outcome,
+                    expected_outcome(&source, &dest),
+                    "{} -> {}",
+                    source.display(),
+                    dest.display()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn backup_keeps_every_generation() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dest = tmp.path().join("b.txt");
+        fs::write(&dest, "old").unwrap();
+        let options = MoveOptions::builder()
+            .backup(BackupStyle::Timestamp)
+            .no_link(true)
+            .build();
+
+        for data in ["first", "second"] {
+            let source = tmp.path().join("a.txt");
+            fs::write(&source, data).unwrap();
+            let result = move_and_link(&source, &dest, &options).unwrap();
+            let backup = result.backup.unwrap();
+            let name = backup.file_name().unwrap().to_string_lossy().into_owned();
+            assert!(name.starts_with("b.txt."), "{name}");
+            assert_eq!(backup.extension().unwrap(), "bak");
+        }
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "second");
+        let mut backups: Vec<String> = fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|e| fs::read_to_string(e.unwrap().path()).unwrap())
+            .collect();
+        backups.sort();
+        assert_eq!(backups, ["first", "old", "second"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restore_moves_the_data_back() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let archive = tmp.path().join("archive");
+        fs::create_dir(&archive).unwrap();
+        let file = tmp.path().join("a.txt");
+        fs::write(&file, "data").unwrap();
+        let dir = tmp.path().join("dir");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("b.txt"), "nested").unwrap();
+        for source in [&file, &dir] {
+            move_and_link(source, &archive, &MoveOptions::default()).unwrap();
+        }
+
+        let result = restore(&file, &RestoreOptions::default()).unwrap();
+        assert_eq!(result.from, archive.join("a.txt"));
+        assert_eq!(result.symlink_target, Path::new("archive/a.txt"));
+        assert!(file.symlink_metadata().unwrap().is_file());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "data");
+        assert!(!result.from.exists());
+
+        restore(&dir, &RestoreOptions::default()).unwrap();
+        assert!(dir.symlink_metadata().unwrap().is_dir());
+        assert_eq!(fs::read_to_string(dir.join("b.txt")).unwrap(), "nested");
+        assert_eq!(fs::read_dir(&archive).unwrap().count(), 0);
+        assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restore_validates_the_link() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("file.txt");
+        fs::write(&file, "data").unwrap();
+        let error = restore(&file, &RestoreOptions::default()).unwrap_err();
+        assert!(matches!(error, MvlnError::InvalidPath { .. }));
+
+        let dangling = tmp.path().join("dangling");
+        std::os::unix::fs::symlink("missing", &dangling).unwrap();
+        let error = restore(&dangling, &RestoreOptions::default()).unwrap_err();
+        assert!(matches!(error, MvlnError::DanglingSymlink { .. }));
+
+        let inner = tmp.path().join("up");
+        std::os::unix::fs::symlink(".", &inner).unwrap();
+        let error = restore(&inner, &RestoreOptions::default()).unwrap_err();
+        assert!(matches!(error, MvlnError::DestinationInsideSource { .. }));
+
+        let link = tmp.path().join("link");
+        std::os::unix::fs::symlink("file.txt", &link).unwrap();
+        let options = RestoreOptions::builder().dry_run(true).build();
+        assert_eq!(restore(&link, &options).unwrap().from, file);
+        assert!(link.symlink_metadata().unwrap().is_symlink());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dir_tree_sanitizes_names() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        fs::create_dir_all(source.join("q&a?")).unwrap();
+        fs::write(source.join("q&a?/10:30.txt"), "notes").unwrap();
+        fs::write(source.join("a:b"), "colon").unwrap();
+        fs::write(source.join("a_b"), "underscore").unwrap();
+
+        let dest = tmp.path().join("dest");
+        let options = MoveOptions::builder()
+            .sanitize(Sanitizer::default())
+            .build();
+        let renamed = copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &options).unwrap();
+
+        let read = |path: &str| fs::read_to_string(dest.join(path)).unwrap();
+        assert_eq!(read("q&a_/10_30.txt"), "notes");
+        assert_eq!(read("a_b (1)"), "colon");
+        assert_eq!(read("a_b"), "underscore");
+        assert_eq!(renamed.len(), 3);
+        assert!(renamed.contains(&(source.join("q&a?/10:30.txt"), dest.join("q&a_/10_30.txt"))));
+    }
+
+    #[test]
+    fn check_tree_lengths_rejects_entries_beyond_path_max() {
+        use crate::limits::DEFAULT_PATH_MAX;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let component = "d".repeat(200);
+        let mut source = tmp.path().join("src");
+        while source.as_os_str().len() < DEFAULT_PATH_MAX - 1000 {
+            source.push(&component);
+        }
+        fs::create_dir_all(&source).unwrap();
+        let source = tmp.path().join("src");
+
+        let options = MoveOptions::default();
+        check_tree_lengths(&source, &tmp.path().join("dest"), &options).unwrap();
+        let dest = tmp.path().join("x".repeat(250)).join("y".repeat(250));
+        let dest = dest.join("z".repeat(250)).join("w".repeat(250));
+        let error = check_tree_lengths(&source, &dest, &options).unwrap_err();
+        assert!(matches!(error, MvlnError::PathTooLong { .. }), "{error}");
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn copy_dir_tree_copies_many_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("src");
+        for dir in ["a", "a/b", "c"] {
+            fs::create_dir_all(source.join(dir)).unwrap();
+            for i in 0..100 {
+                fs::write(source.join(dir).join(format!("{i}.txt")), i.to_string()).unwrap();
+            }
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let read_only = fs::Permissions::from_mode(0o555);
+            fs::set_permissions(source.join("a/b"), read_only).unwrap();
+        }
+
+        let dest = tmp.path().join("dest");
+        copy_dir_tree(&source, &dest, MAX_COPY_DEPTH, &MoveOptions::default()).unwrap();
+
+        for dir in ["a", "a/b", "c"] {
+            for i in 0..100 {
+                let copied = dest.join(dir).join(format!("{i}.txt"));
+                assert_eq!(fs::read_to_string(copied).unwrap(), i.to_string());
+            }
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dest.join("a/b")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o555);
+            for dir in [&source, &dest] {
+                let writable = fs::Permissions::from_mode(0o755);
+                fs::set_permissions(dir.join("a/b"), writable).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Restoring the owner and group of files copied across filesystems.
+//!
+//! A copy is owned by whoever made it, so when root archives other users'
+//! data everything would end up owned by root. With [`PreserveSet::OWNER`]
+//! and [`PreserveSet::GROUP`] in [`MoveOptions::preserve`] each copied
+//! file, directory and symlink is given back the IDs of its source.
+//!
+//! By default an ID is only restored if a user (or group) with that ID
+//! still exists in `/etc/passwd` (or `/etc/group`); files of deleted
+//! accounts stay owned by the user running mvln rather than by a number
+//! nobody answers to. [`MoveOptions::numeric_ids`] restores IDs as they
+//! are. Ownership is Unix-only; elsewhere these options are ignored.
+
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+#[cfg(unix)]
+use std::sync::OnceLock;
+
+use crate::operation::MoveOptions;
+#[cfg(unix)]
+use crate::preserve::PreserveSet;
+
+/// Give `path` the owner and/or group recorded in `metadata` (of its
+/// source), as configured by `options`. Symlinks are changed themselves,
+/// not their targets.
+///
+/// # Errors
+///
+/// Returns the error of the underlying `lchown`, typically permission
+/// denied when not running as root.
+#[cfg(unix)]
+pub fn restore(metadata: &Metadata, path: &Path, options: &MoveOptions) -> io::Result<()> {
+    use std::os::unix::fs::{lchown, MetadataExt};
+
+    static USERS: OnceLock<Vec<u32>> = OnceLock::new();
+    static GROUPS: OnceLock<Vec<u32>> = OnceLock::new();
+
+    let pick = |wanted: bool, id: u32, known: &OnceLock<Vec<u32>>, file: &str| {
+        let exists = || {
+            known
+                .get_or_init(|| known_ids(&std::fs::read_to_string(file).unwrap_or_default()))
+                .contains(&id)
+        };
+        (wanted && (options.numeric_ids || exists())).then_some(id)
+    };
+    let uid = pick(
+        options.preserve.contains(PreserveSet::OWNER),
+        metadata.uid(),
+        &USERS,
+        "/etc/passwd",
+    );
+    let gid = pick(
+        options.preserve.contains(PreserveSet::GROUP),
+        metadata.gid(),
+        &GROUPS,
+        "/etc/group",
+    );
+    if uid.is_none() && gid.is_none() {
+        return Ok(());
+    }
+    lchown(path, uid, gid)
+}
+
+#[cfg(not(unix))]
+pub fn restore(_metadata: &Metadata, _path: &Path, _options: &MoveOptions) -> io::Result<()> {
+    Ok(())
+}
+
+/// The IDs in the third field of `passwd` or `group` style `contents`.
+#[cfg(unix)]
+fn known_ids(contents: &str) -> Vec<u32> {
+    contents
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split(':').nth(2)?.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn known_ids_reads_the_id_field() {
+        let passwd = "root:x:0:0:root:/root:/bin/sh\n# comment\nalice:x:1000:1000::/home/alice:/bin/sh\nbroken\n";
+        assert_eq!(known_ids(passwd), [0, 1000]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restore_keeps_own_ids() {
+        use std::os::unix::fs::MetadataExt;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let source = tmp.path().join("source");
+        let dest = tmp.path().join("dest");
+        std::fs::write(&source, "data").unwrap();
+        std::fs::write(&dest, "data").unwrap();
+        let metadata = source.symlink_metadata().unwrap();
+
+        // Changing a file to the IDs it already has is always allowed
+        let options = MoveOptions::builder()
+            .preserve_owner(true)
+            .preserve_group(true)
+            .numeric_ids(true)
+            .build();
+        restore(&metadata, &dest, &options).unwrap();
+        let copied = dest.symlink_metadata().unwrap();
+        assert_eq!(
+            (copied.uid(), copied.gid()),
+            (metadata.uid(), metadata.gid())
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Streamed packs for copying trees of many small files.
+//!
+//! With [`MoveOptions::pack`], the files of a directory tree copied across
+//! filesystems don't each go through a staged copy (see [`crate::copy`]).
+//! Instead one thread reads small files whole and packs them, with their
+//! metadata, into batches of about [`PACK_BATCH_BYTES`], and a pool of
+//! threads unpacks each batch by writing the files straight to their
+//! destinations. Reading the source and writing the destination overlap,
+//! and a small file costs one open, read and close on each side, with
+//! none of the staging, preallocation and rename of a normal copy.
+//!
+//! Batches pass through a bounded queue, so no more than a few of them are
+//! held in memory at once, and nothing is written to a temporary archive.
+//! Files larger than [`PACK_MAX_FILE`], and every file when extended
+//! attributes are preserved, travel through the pack as references and are
+//! copied as usual by the thread unpacking them.
+
+use std::fs::{File, Metadata};
+use std::io::Read;
+use std::mem;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+use crate::copy;
+use crate::error::MvlnError;
+use crate::operation::{MoveOptions, MAX_COPY_THREADS};
+use crate::progress::Tracker;
+
+/// Largest file read whole into a pack; larger ones are copied one by one.
+pub const PACK_MAX_FILE: u64 = 1 << 20;
+
+/// Size a batch grows to before it is handed over to be unpacked.
+pub const PACK_BATCH_BYTES: usize = 8 << 20;
+
+/// Most entries in one batch, so batches of empty files are unpacked in
+/// parallel too.
+const PACK_BATCH_ENTRIES: usize = 1024;
+
+/// Batches waiting to be unpacked, besides those being unpacked.
+const QUEUED_BATCHES: usize = 2;
+
+/// One file of a batch.
+struct Entry {
+    source: PathBuf,
+    dest: PathBuf,
+    /// The contents and metadata of the file, or `None` for a file left to
+    /// [`copy::copy_file`].
+    data: Option<(Vec<u8>, Metadata)>,
+}
+
+/// Copy the `(source, dest)` pairs from `jobs` through streamed packs,
+/// until the queue closes. The first error is stored in `failed`; once it
+/// is set, the remaining jobs are drained without being copied.
+pub(crate) fn copy_files(
+    jobs: &Mutex<Receiver<(PathBuf, PathBuf)>>,
+    options: &MoveOptions,
+    progress: Option<&Tracker<'_>>,
+    failed: &Mutex<Option<MvlnError>>,
+) {
+    let workers = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(MAX_COPY_THREADS);
+    let (batches, queue) = mpsc::sync_channel::<Vec<Entry>>(QUEUED_BATCHES);
+    let queue = Mutex::new(queue);
+    let has_failed = || lock(failed).is_some();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let Ok(batch) = lock(&queue).recv() else {
+                    break;
+                };
+                for entry in batch {
+                    if has_failed() {
+                        break;
+                    }
+                    if let Err(e) = unpack(entry, options, progress) {
+                        lock(failed).get_or_insert(e);
+                    }
+                }
+            });
+        }
+
+        let mut batch = Vec::new();
+        let mut bytes = 0;
+        loop {
+            let Ok((source, dest)) = lock(jobs).recv() else {
+                break;
+            };
+            if has_failed() {
+                continue;
+            }
+            match pack(source, dest, options) {
+                Ok(entry) => {
+                    bytes += entry.data.as_ref().map_or(0, |(data, _)| data.len());
+                    batch.push(entry);
+                }
+                Err(e) => {
+                    lock(failed).get_or_insert(e);
+                    continue;
+                }
+            }
+            if bytes >= PACK_BATCH_BYTES || batch.len() >= PACK_BATCH_ENTRIES {
+                bytes = 0;
+                if batches.send(mem::take(&mut batch)).is_err() {
+                    break;
+                }
+            }
+        }
+        if !batch.is_empty() {
+            let _ = batches.send(batch);
+        }
+        // Let the workers finish the queue and exit
+        drop(batches);
+    });
+}
+
+/// Read `source` whole if it is small enough to pack.
+fn pack(source: PathBuf, dest: PathBuf, options: &MoveOptions) -> Result<Entry, MvlnError> {
+    let read = || {
+        let mut file = File::open(&source)?;
+        let metadata = file.metadata()?;
+        if metadata.len() > PACK_MAX_FILE || options.preserve.copies_xattrs() {
+            return Ok(None);
+        }
+        let mut data = Vec::with_capacity(usize::try_from(metadata.len()).unwrap_or(0));
+        file.read_to_end(&mut data)?;
+        Ok(Some((data, metadata)))
+    };
+    match read() {
+        Ok(data) => Ok(Entry { source, dest, data }),
+        Err(e) => Err(MvlnError::CopyFailed {
+            src: source,
+            dest,
+            source: e,
+        }),
+    }
+}
+
+/// Write the file of `entry` to its destination, counting it in
+/// `progress`.
+fn unpack(
+    entry: Entry,
+    options: &MoveOptions,
+    progress: Option<&Tracker<'_>>,
+) -> Result<(), MvlnError> {
+    let written = match &entry.data {
+        Some((data, metadata)) => copy::retry_stale(options, || {
+            copy::write_whole(&entry.dest, data, metadata, options)
+        }),
+        None => copy::copy_file(&entry.source, &entry.dest, options).map(|_| ()),
+    };
+    if let Err(e) = written {
+        return Err(MvlnError::CopyFailed {
+            src: entry.source,
+            dest: entry.dest,
+            source: e,
+        });
+    }
+    if let Some(progress) = progress {
+        progress.copied(&entry.source, &entry.dest);
+    }
+    Ok(())
+}
+
+/// Lock `mutex`, also if another thread panicked while holding it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn packs_small_files_and_copies_large_ones() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let (src, dest) = (tmp.path().join("src"), tmp.path().join("dest"));
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        let big = vec![7u8; usize::try_from(PACK_MAX_FILE).unwrap() + 1];
+        fs::write(src.join("big"), &big).unwrap();
+
+        let (jobs, queue) = mpsc::channel();
+        for i in 0..2000 {
+            let name = format!("{i}.txt");
+            fs::write(src.join(&name), &name).unwrap();
+            jobs.send((src.join(&name), dest.join(&name))).unwrap();
+        }
+        jobs.send((src.join("big"), dest.join("big"))).unwrap();
+        drop(jobs);
+
+        let failed = Mutex::new(None);
+        copy_files(&Mutex::new(queue), &MoveOptions::default(), None, &failed);
+        assert!(failed.into_inner().unwrap().is_none());
+        assert_eq!(
+            fs::read_to_string(dest.join("1999.txt")).unwrap(),
+            "1999.txt"
+        );
+        assert_eq!(fs::read(dest.join("big")).unwrap(), big);
+    }
+}
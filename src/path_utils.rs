@@ -1,6 +1,26 @@
-//! Path utilities for symlink target computation.
+//! Path utilities for symlink target computation and destination naming.
 
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{MvlnError, Result};
+
+/// How the target stored in a new symlink is expressed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SymlinkStyle {
+    /// Relative to the directory containing the link (the default).
+    /// Portable across different mount points.
+    #[default]
+    Relative,
+    /// Absolute path to the target.
+    Absolute,
+    /// Relative to the given directory instead of the link's own directory.
+    ///
+    /// Useful when links are read through a different path than the one
+    /// they are created at, e.g. a tree that is later mounted or synced
+    /// elsewhere.
+    RelativeTo(PathBuf),
+}
 
 /// Compute the symlink target path.
 ///
@@ -11,69 +31,394 @@ use std::path::{Path, PathBuf};
 ///
 /// * `link_location` - Where the symlink will be created
 /// * `target_file` - The actual file the symlink should point to
-/// * `absolute` - If true, return absolute path; otherwise compute relative
+/// * `style` - Whether to produce a relative or absolute target
 ///
 /// # Examples
 ///
 /// ```
-/// use mvln::path_utils::compute_symlink_target;
+/// use mvln::path_utils::{compute_symlink_target, SymlinkStyle};
 ///
 /// // Relative path computation
-/// let target = compute_symlink_target("/a/b/link", "/a/c/file", false);
+/// let target = compute_symlink_target("/a/b/link", "/a/c/file", &SymlinkStyle::Relative);
 /// assert_eq!(target.to_str().unwrap(), "../c/file");
 ///
 /// // Absolute path
-/// let target = compute_symlink_target("/a/b/link", "/a/c/file", true);
+/// let target = compute_symlink_target("/a/b/link", "/a/c/file", &SymlinkStyle::Absolute);
 /// assert_eq!(target.to_str().unwrap(), "/a/c/file");
+///
+/// // Relative to another directory
+/// let style = SymlinkStyle::RelativeTo("/a".into());
+/// let target = compute_symlink_target("/a/b/link", "/a/c/file", &style);
+/// assert_eq!(target.to_str().unwrap(), "c/file");
 /// ```
 pub fn compute_symlink_target<P: AsRef<Path>, Q: AsRef<Path>>(
     link_location: P,
     target_file: Q,
-    absolute: bool,
+    style: &SymlinkStyle,
+) -> PathBuf {
+    // Without a current directory, relative paths are used as they are
+    let cwd = std::env::current_dir().unwrap_or_default();
+    compute_symlink_target_in(&cwd, link_location, target_file, style)
+}
+
+/// Compute the symlink target path, with relative paths taken relative to
+/// `base` instead of the current directory.
+///
+/// This is [`compute_symlink_target`] without the hidden dependence on the
+/// process: the result only depends on the arguments, so it suits tests and
+/// daemons whose current directory is `/`.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::path_utils::{compute_symlink_target_in, SymlinkStyle};
+/// use std::path::Path;
+///
+/// let base = Path::new("/home/user");
+/// let target = compute_symlink_target_in(base, "notes/link", "/mnt/usb/file", &SymlinkStyle::Relative);
+/// assert_eq!(target, Path::new("../../../mnt/usb/file"));
+///
+/// let target = compute_symlink_target_in(base, "link", "archive/file", &SymlinkStyle::Absolute);
+/// assert_eq!(target, Path::new("/home/user/archive/file"));
+/// ```
+pub fn compute_symlink_target_in<P: AsRef<Path>, Q: AsRef<Path>>(
+    base: &Path,
+    link_location: P,
+    target_file: Q,
+    style: &SymlinkStyle,
 ) -> PathBuf {
     let target_file = target_file.as_ref();
 
-    if absolute {
-        // For absolute mode, return absolute path WITHOUT resolving symlinks.
-        // IMPORTANT: Do NOT use canonicalize() here because:
-        // 1. If target_file is a symlink, canonicalize resolves it to its target
-        // 2. This causes the new symlink to point to the wrong location
-        // 3. We want the symlink to point to dest itself, not what dest pointed to
-        if target_file.is_absolute() {
-            // Already absolute, use as-is
-            target_file.to_path_buf()
-        } else {
-            // Relative path, convert to absolute based on current directory
-            std::env::current_dir()
-                .map_or_else(|_| target_file.to_path_buf(), |cwd| cwd.join(target_file))
+    let link_dir = match style {
+        SymlinkStyle::Absolute => {
+            // For absolute mode, return absolute path WITHOUT resolving symlinks.
+            // IMPORTANT: Do NOT use canonicalize() here because:
+            // 1. If target_file is a symlink, canonicalize resolves it to its target
+            // 2. This causes the new symlink to point to the wrong location
+            // 3. We want the symlink to point to dest itself, not what dest pointed to
+            return base.join(target_file);
+        }
+        // Get the parent directory of the link (the symlink lives here)
+        SymlinkStyle::Relative => link_location.as_ref().parent().unwrap_or(Path::new(".")),
+        SymlinkStyle::RelativeTo(dir) => dir.as_path(),
+    };
+
+    // Compute relative path from link directory to target.
+    // Normalize both paths to absolute before computing relative path.
+    // diff_paths returns None when mixing relative/absolute paths.
+    let abs_link_dir = base.join(link_dir);
+    let abs_target = base.join(target_file);
+
+    // Use pathdiff to compute relative path (now both are absolute)
+    pathdiff::diff_paths(&abs_target, &abs_link_dir).unwrap_or_else(|| target_file.to_path_buf())
+}
+
+/// Whether a destination is a directory to move into or the new path
+/// itself, for [`resolve_destination`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum DirBehavior {
+    /// Into the destination if it is a directory (or a symlink to one) or
+    /// is written with a trailing slash, like `archive/`, which is created
+    /// if missing; otherwise the destination is the new path. The default,
+    /// as with `mv`.
+    #[default]
+    Auto,
+    /// Always into the destination, which is created if missing (like
+    /// `mv -t`).
+    IntoDirectory,
+    /// The destination is the new path even if it is a directory (`-T`,
+    /// like `mv -T`): an existing one is then a conflict, which only a
+    /// directory source replaces, with `force`.
+    NoTargetDirectory,
+}
+
+/// Where `source` ends up when moved to `dest`: `dest` itself, or the
+/// source's name inside it, depending on `behavior`.
+///
+/// This is the path [`move_and_link`](crate::operation::move_and_link)
+/// resolves before any renaming by [`MoveOptions::sanitize`] or
+/// [`MoveOptions::normalize`]; [`MoveOptions::resolve_destination`] applies
+/// those too.
+///
+/// [`MoveOptions::sanitize`]: crate::operation::MoveOptions::sanitize
+/// [`MoveOptions::normalize`]: crate::operation::MoveOptions::normalize
+/// [`MoveOptions::resolve_destination`]: crate::operation::MoveOptions::resolve_destination
+///
+/// # Examples
+///
+/// ```
+/// use mvln::path_utils::{resolve_destination, DirBehavior};
+/// use std::path::Path;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let archive = dir.path().join("archive");
+/// std::fs::create_dir(&archive).unwrap();
+///
+/// let into = resolve_destination(Path::new("a.txt"), &archive, DirBehavior::Auto);
+/// assert_eq!(into, archive.join("a.txt"));
+///
+/// let exact = resolve_destination(Path::new("a.txt"), &archive, DirBehavior::NoTargetDirectory);
+/// assert_eq!(exact, archive);
+///
+/// // A trailing slash names a directory, even one that doesn't exist yet
+/// let new = resolve_destination(Path::new("a.txt"), Path::new("new/"), DirBehavior::Auto);
+/// assert_eq!(new, Path::new("new/a.txt"));
+/// ```
+#[must_use]
+pub fn resolve_destination(source: &Path, dest: &Path, behavior: DirBehavior) -> PathBuf {
+    let into = match behavior {
+        DirBehavior::Auto => has_trailing_separator(dest) || dest.is_dir(),
+        DirBehavior::IntoDirectory => true,
+        DirBehavior::NoTargetDirectory => false,
+    };
+    match source.file_name() {
+        Some(name) if into => dest.join(name),
+        _ => dest.to_path_buf(),
+    }
+}
+
+/// Whether `path` is written with a trailing separator (`dir/`), which
+/// names a directory.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::path_utils::has_trailing_separator;
+///
+/// assert!(has_trailing_separator("archive/".as_ref()));
+/// assert!(!has_trailing_separator("archive".as_ref()));
+/// assert!(!has_trailing_separator("/".as_ref()));
+/// ```
+#[must_use]
+pub fn has_trailing_separator(path: &Path) -> bool {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    bytes.len() > 1
+        && bytes
+            .last()
+            .is_some_and(|&b| std::path::is_separator(char::from(b)))
+}
+
+/// Find a free variant of `path` by appending ` (1)`, ` (2)`, ... to its stem.
+///
+/// `taken` reports whether a candidate is already in use. The extension is
+/// kept, so `photo.jpg` becomes `photo (1).jpg`. If `path` itself is free it
+/// is returned unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::path_utils::unique_name;
+/// use std::path::{Path, PathBuf};
+///
+/// let taken = [PathBuf::from("dir/photo.jpg"), PathBuf::from("dir/photo (1).jpg")];
+/// let free = unique_name(Path::new("dir/photo.jpg"), |p| taken.iter().any(|t| t == p));
+/// assert_eq!(free, PathBuf::from("dir/photo (2).jpg"));
+/// ```
+pub fn unique_name<F: Fn(&Path) -> bool>(path: &Path, taken: F) -> PathBuf {
+    if !taken(path) {
+        return path.to_path_buf();
+    }
+
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut n: u64 = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{stem} ({n}){ext}"));
+        if !taken(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Make `path` absolute against the current directory without resolving
+/// symlinks.
+///
+/// Unlike [`Path::canonicalize`], the path need not exist and a symlink
+/// stays a symlink, which is what a new link must point at. `.` and `..`
+/// are kept; pass the result to [`normalize_path`] to clean them up. If the
+/// current directory cannot be read, `path` is returned unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::path_utils::absolute_path_no_follow;
+///
+/// let path = absolute_path_no_follow("notes.txt");
+/// assert!(path.is_absolute());
+/// assert!(path.ends_with("notes.txt"));
+/// assert_eq!(absolute_path_no_follow("/a/b"), std::path::Path::new("/a/b"));
+/// ```
+#[must_use]
+pub fn absolute_path_no_follow<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    std::env::current_dir().map_or_else(|_| path.to_path_buf(), |cwd| cwd.join(path))
+}
+
+/// Resolve `.` and `..` components of `path` without touching the
+/// filesystem.
+///
+/// `..` removes the component before it; at the root it is dropped, and at
+/// the start of a relative path it is kept. Note that this differs from
+/// what the filesystem does when that component is a symlink to a
+/// directory. An empty result is `.`, and a trailing separator is not kept.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::path_utils::normalize_path;
+/// use std::path::Path;
+///
+/// assert_eq!(normalize_path("/a/b/./../c"), Path::new("/a/c"));
+/// assert_eq!(normalize_path("../a/../../b"), Path::new("../../b"));
+/// assert_eq!(normalize_path("/.."), Path::new("/"));
+/// assert_eq!(normalize_path("a/.."), Path::new("."));
+/// ```
+#[must_use]
+pub fn normalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.as_ref().components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                Some(Component::RootDir) => {}
+                _ => out.push(component),
+            },
+            other => out.push(other),
         }
+    }
+    if out.as_os_str().is_empty() {
+        out.push(".");
+    }
+    out
+}
+
+/// Expand a leading `~` / `~user` and `$VAR` / `${VAR}` references in a path.
+///
+/// Destinations often come from configuration files and profiles that never
+/// pass through a shell, so mvln performs the expansions itself:
+///
+/// - `~` and `~/...` expand to `$HOME`
+/// - `~user/...` expands to that user's home directory (Unix only)
+/// - `$VAR` and `${VAR}` expand to the variable's value; `$$` is a literal `$`
+///
+/// Paths that are not valid UTF-8 are returned unchanged.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::InvalidPath`] if a referenced variable is unset or
+/// the home directory cannot be determined.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mvln::path_utils::expand_path;
+///
+/// let dest = expand_path("~/archive/$USER")?;
+/// # Ok::<(), mvln::MvlnError>(())
+/// ```
+pub fn expand_path<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+    let path = path.as_ref();
+    let Some(text) = path.to_str() else {
+        return Ok(path.to_path_buf());
+    };
+    let error = |reason: String| MvlnError::InvalidPath {
+        path: path.to_path_buf(),
+        reason,
+    };
+
+    let text = expand_vars(text, |name| std::env::var(name).ok()).map_err(error)?;
+    Ok(PathBuf::from(expand_tilde(&text).map_err(error)?))
+}
+
+/// Expand a leading `~` or `~user`.
+fn expand_tilde(text: &str) -> std::result::Result<String, String> {
+    let Some(rest) = text.strip_prefix('~') else {
+        return Ok(text.to_string());
+    };
+    let (user, tail) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+
+    let home = if user.is_empty() {
+        std::env::var("HOME")
+            .ok()
+            .filter(|h| !h.is_empty())
+            .ok_or_else(|| "cannot expand '~': HOME is not set".to_string())?
     } else {
-        // Compute relative path from link location to target
-        let link_location = link_location.as_ref();
+        user_home(user).ok_or_else(|| format!("cannot expand '~{user}': unknown user"))?
+    };
+    Ok(format!("{home}{tail}"))
+}
 
-        // Get the parent directory of the link (the symlink lives here)
-        let link_dir = link_location.parent().unwrap_or(Path::new("."));
+/// Look up a user's home directory in the password database.
+#[cfg(unix)]
+fn user_home(user: &str) -> Option<String> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.len() >= 6 && fields[0] == user).then(|| fields[5].to_string())
+    })
+}
 
-        // Normalize both paths to absolute before computing relative path.
-        // diff_paths returns None when mixing relative/absolute paths.
-        let abs_link_dir = if link_dir.is_absolute() {
-            link_dir.to_path_buf()
-        } else {
-            std::env::current_dir()
-                .map_or_else(|_| link_dir.to_path_buf(), |cwd| cwd.join(link_dir))
-        };
+/// Look up a user's home directory (unsupported on this platform).
+#[cfg(not(unix))]
+fn user_home(_user: &str) -> Option<String> {
+    None
+}
 
-        let abs_target = if target_file.is_absolute() {
-            target_file.to_path_buf()
+/// Expand `$VAR`, `${VAR}` and `$$` using `lookup`.
+fn expand_vars<F: Fn(&str) -> Option<String>>(
+    text: &str,
+    lookup: F,
+) -> std::result::Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = rest.find('$') {
+        out.push_str(&rest[..idx]);
+        let after = &rest[idx + 1..];
+
+        let (name, remainder) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| "unterminated '${'".to_string())?;
+            (&braced[..end], &braced[end + 1..])
+        } else if let Some(remainder) = after.strip_prefix('$') {
+            out.push('$');
+            rest = remainder;
+            continue;
         } else {
-            std::env::current_dir()
-                .map_or_else(|_| target_file.to_path_buf(), |cwd| cwd.join(target_file))
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
         };
 
-        // Use pathdiff to compute relative path (now both are absolute)
-        pathdiff::diff_paths(&abs_target, &abs_link_dir)
-            .unwrap_or_else(|| target_file.to_path_buf())
+        if name.is_empty() {
+            // A lone '$' is kept literally
+            out.push('$');
+        } else {
+            let value =
+                lookup(name).ok_or_else(|| format!("environment variable '{name}' is not set"))?;
+            out.push_str(&value);
+        }
+        rest = remainder;
     }
+    out.push_str(rest);
+    Ok(out)
 }
 
 #[cfg(test)]
@@ -82,7 +427,7 @@ mod tests {
 
     #[test]
     fn absolute_path_returns_target_directly() {
-        let result = compute_symlink_target("/a/b/link", "/x/y/file", true);
+        let result = compute_symlink_target("/a/b/link", "/x/y/file", &SymlinkStyle::Absolute);
         // In absolute mode, we try to canonicalize first.
         // Since /x/y/file doesn't exist in tests, canonicalize fails
         // and we return the absolute path as-is.
@@ -92,40 +437,167 @@ mod tests {
     #[test]
     fn relative_path_same_directory() {
         // Link at /a/b/link pointing to /a/b/file -> just "file"
-        let result = compute_symlink_target("/a/b/link", "/a/b/file", false);
+        let result = compute_symlink_target("/a/b/link", "/a/b/file", &SymlinkStyle::Relative);
         assert_eq!(result, PathBuf::from("file"));
     }
 
     #[test]
     fn relative_path_sibling_directory() {
         // Link at /a/b/link pointing to /a/c/file -> ../c/file
-        let result = compute_symlink_target("/a/b/link", "/a/c/file", false);
+        let result = compute_symlink_target("/a/b/link", "/a/c/file", &SymlinkStyle::Relative);
         assert_eq!(result, PathBuf::from("../c/file"));
     }
 
     #[test]
     fn relative_path_different_branches() {
         // Link at /a/b/c/link pointing to /x/y/file -> ../../../x/y/file
-        let result = compute_symlink_target("/a/b/c/link", "/x/y/file", false);
+        let result = compute_symlink_target("/a/b/c/link", "/x/y/file", &SymlinkStyle::Relative);
         assert_eq!(result, PathBuf::from("../../../x/y/file"));
     }
 
     #[test]
     fn absolute_mode_with_relative_target() {
         // When absolute=true and target is relative, convert to absolute
-        let result = compute_symlink_target("/a/b/link", "relative/file.txt", true);
+        let result =
+            compute_symlink_target("/a/b/link", "relative/file.txt", &SymlinkStyle::Absolute);
         // Result should be absolute (joined with current directory)
         assert!(
             result.is_absolute(),
-            "Expected absolute path, got: {:?}",
-            result
+            "Expected absolute path, got: {result:?}"
+        );
+    }
+
+    #[test]
+    fn relative_paths_are_taken_from_base() {
+        let base = Path::new("/srv/data");
+        let style = SymlinkStyle::Relative;
+        assert_eq!(
+            compute_symlink_target_in(base, "in/link", "out/file", &style),
+            PathBuf::from("../out/file")
+        );
+        assert_eq!(
+            compute_symlink_target_in(base, "link", "/srv/file", &style),
+            PathBuf::from("../file")
+        );
+        let style = SymlinkStyle::RelativeTo("share".into());
+        assert_eq!(
+            compute_symlink_target_in(base, "x/link", "share/a/file", &style),
+            PathBuf::from("a/file")
+        );
+        assert_eq!(
+            compute_symlink_target_in(base, "link", "/abs/file", &SymlinkStyle::Absolute),
+            PathBuf::from("/abs/file")
+        );
+    }
+
+    #[test]
+    fn resolve_destination_into_or_as_dest() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let file = dir.join("file");
+        std::fs::write(&file, "").unwrap();
+        let source = Path::new("src/a.txt");
+
+        assert_eq!(
+            resolve_destination(source, dir, DirBehavior::Auto),
+            dir.join("a.txt")
+        );
+        assert_eq!(resolve_destination(source, &file, DirBehavior::Auto), file);
+        assert_eq!(
+            resolve_destination(source, &dir.join("new"), DirBehavior::IntoDirectory),
+            dir.join("new/a.txt")
+        );
+        assert_eq!(
+            resolve_destination(source, Path::new("new/"), DirBehavior::NoTargetDirectory),
+            Path::new("new/")
         );
     }
 
+    #[test]
+    fn normalize_path_resolves_dots() {
+        assert_eq!(normalize_path("/a/b/./../c"), PathBuf::from("/a/c"));
+        assert_eq!(normalize_path("./a/./b/"), PathBuf::from("a/b"));
+        assert_eq!(normalize_path("a/../../b"), PathBuf::from("../b"));
+        assert_eq!(normalize_path("/../a"), PathBuf::from("/a"));
+        assert_eq!(normalize_path(""), PathBuf::from("."));
+    }
+
+    #[test]
+    fn absolute_path_no_follow_keeps_symlinks() {
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(absolute_path_no_follow("a/../b"), cwd.join("a/../b"));
+        assert_eq!(absolute_path_no_follow("/x/y"), PathBuf::from("/x/y"));
+    }
+
+    #[test]
+    fn unique_name_returns_free_path_unchanged() {
+        let result = unique_name(Path::new("a/file.txt"), |_| false);
+        assert_eq!(result, PathBuf::from("a/file.txt"));
+    }
+
+    #[test]
+    fn unique_name_without_extension() {
+        let result = unique_name(Path::new("a/README"), |p| p == Path::new("a/README"));
+        assert_eq!(result, PathBuf::from("a/README (1)"));
+    }
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "ROOT" => Some("/mnt/nas".to_string()),
+            "USER" => Some("alice".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expand_vars_plain_and_braced() {
+        assert_eq!(
+            expand_vars("$ROOT/${USER}_files", lookup).unwrap(),
+            "/mnt/nas/alice_files"
+        );
+    }
+
+    #[test]
+    fn expand_vars_literal_dollars() {
+        assert_eq!(expand_vars("a$$b/$/c", lookup).unwrap(), "a$b/$/c");
+    }
+
+    #[test]
+    fn expand_vars_unset_is_error() {
+        assert!(expand_vars("$NOPE/x", lookup).is_err());
+        assert!(expand_vars("${ROOT", lookup).is_err());
+    }
+
+    #[test]
+    fn expand_tilde_only_at_start() {
+        assert_eq!(expand_tilde("a/~/b").unwrap(), "a/~/b");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn expand_tilde_user_from_passwd() {
+        assert_eq!(
+            expand_tilde("~root/x").unwrap(),
+            format!("{}/x", user_home("root").unwrap())
+        );
+        assert!(expand_tilde("~no-such-user-mvln").is_err());
+    }
+
+    #[test]
+    fn relative_to_base_directory() {
+        let style = SymlinkStyle::RelativeTo(PathBuf::from("/mnt/share/links"));
+        let result = compute_symlink_target("/a/b/link", "/mnt/share/data/file", &style);
+        assert_eq!(result, PathBuf::from("../data/file"));
+    }
+
     #[test]
     fn absolute_mode_with_absolute_target() {
         // When absolute=true and target is already absolute, keep as-is
-        let result = compute_symlink_target("/a/b/link", "/absolute/path/file.txt", true);
+        let result = compute_symlink_target(
+            "/a/b/link",
+            "/absolute/path/file.txt",
+            &SymlinkStyle::Absolute,
+        );
         assert_eq!(result, PathBuf::from("/absolute/path/file.txt"));
     }
 }
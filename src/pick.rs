@@ -0,0 +1,159 @@
+//! `--pick`: choose which sources to move at a line prompt.
+//!
+//! The planned sources are listed on stderr, numbered and with their sizes,
+//! all selected; those that would be copied to another filesystem are
+//! marked. The user toggles entries by number or range until the
+//! selection is right, then presses enter. Unlike `--review` this needs no
+//! terminal UI: answers are read line by line from stdin, so they can be
+//! piped in, and the answer letters are the same in every language.
+
+use std::io::{self, BufRead, Write};
+
+use fluent::FluentArgs;
+use mvln::i18n::{self, MessageKey};
+use mvln::plan::MovePlan;
+use mvln::size::{disk_usage, format_size};
+
+use crate::Bundle;
+
+/// An understood answer to the selection prompt.
+#[derive(Debug, PartialEq, Eq)]
+enum Answer {
+    /// Move the selected sources.
+    Apply,
+    /// Move nothing.
+    Cancel,
+    /// Select everything.
+    All,
+    /// Select nothing.
+    None,
+    /// Flip these entries, by index.
+    Toggle(Vec<usize>),
+}
+
+/// Let the user deselect sources of `plan`. Returns `false` if they
+/// cancelled the run; end of input cancels too, so a truncated pipe moves
+/// nothing.
+pub fn pick(plan: &mut MovePlan, bundle: &Bundle) -> bool {
+    let sizes: Vec<u64> = plan
+        .iter()
+        .map(|planned| disk_usage(&planned.source).unwrap_or(0))
+        .collect();
+    let copied = i18n::simple_msg(bundle, MessageKey::PickCrossDevice);
+    let marks: Vec<String> = plan
+        .crosses_filesystems()
+        .into_iter()
+        .map(|crosses| {
+            if crosses == Some(true) {
+                format!("  {copied}")
+            } else {
+                String::new()
+            }
+        })
+        .collect();
+    let mut selected = vec![true; plan.len()];
+
+    let stdin = io::stdin();
+    let mut line = String::new();
+    loop {
+        eprintln!("{}", i18n::simple_msg(bundle, MessageKey::PickTitle));
+        let width = plan.len().to_string().len();
+        for (index, planned) in plan.iter().enumerate() {
+            eprintln!(
+                "  {:>width$} [{}] {}  {}{}",
+                index + 1,
+                if selected[index] { 'x' } else { ' ' },
+                planned.source.display(),
+                format_size(sizes[index]),
+                marks[index],
+            );
+        }
+        let mut args = FluentArgs::new();
+        args.set("selected", selected.iter().filter(|&&s| s).count());
+        args.set("total", plan.len());
+        let size = sizes
+            .iter()
+            .zip(&selected)
+            .filter(|(_, &s)| s)
+            .map(|(size, _)| size)
+            .sum();
+        args.set("size", format_size(size));
+        eprint!("{} ", i18n::msg(bundle, MessageKey::PickHelp, Some(&args)));
+        let _ = io::stderr().flush();
+
+        line.clear();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {}
+        }
+        match parse_answer(&line, plan.len()) {
+            Some(Answer::Apply) => break,
+            Some(Answer::Cancel) => return false,
+            Some(Answer::All) => selected.fill(true),
+            Some(Answer::None) => selected.fill(false),
+            Some(Answer::Toggle(indices)) => {
+                for index in indices {
+                    selected[index] = !selected[index];
+                }
+            }
+            None => {}
+        }
+    }
+
+    let mut keep = selected.into_iter();
+    plan.moves.retain(|_| keep.next().unwrap_or(true));
+    true
+}
+
+/// Parse an answer: empty, `q`, `a`, `n`, or 1-based numbers and ranges
+/// such as `2 4-6` (commas work as separators too). Numbers outside
+/// `1..=len` make the whole answer invalid.
+fn parse_answer(answer: &str, len: usize) -> Option<Answer> {
+    let answer = answer.trim();
+    match answer.to_ascii_lowercase().as_str() {
+        "" => return Some(Answer::Apply),
+        "q" | "quit" => return Some(Answer::Cancel),
+        "a" | "all" => return Some(Answer::All),
+        "n" | "none" => return Some(Answer::None),
+        _ => {}
+    }
+    let number = |s: &str| {
+        s.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=len).contains(n))
+    };
+    let mut indices = Vec::new();
+    for part in answer.split([' ', ',']).filter(|p| !p.is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (number(first)?, number(last)?),
+            None => (number(part)?, number(part)?),
+        };
+        if first > last {
+            return None;
+        }
+        indices.extend(first - 1..last);
+    }
+    Some(Answer::Toggle(indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_numbers_ranges_and_letters() {
+        assert_eq!(parse_answer("\n", 5), Some(Answer::Apply));
+        assert_eq!(parse_answer("Q\n", 5), Some(Answer::Cancel));
+        assert_eq!(parse_answer("a", 5), Some(Answer::All));
+        assert_eq!(parse_answer("none", 5), Some(Answer::None));
+        assert_eq!(
+            parse_answer("2 4-5,1\n", 5),
+            Some(Answer::Toggle(vec![1, 3, 4, 0]))
+        );
+        assert_eq!(parse_answer("6", 5), None);
+        assert_eq!(parse_answer("0", 5), None);
+        assert_eq!(parse_answer("4-2", 5), None);
+        assert_eq!(parse_answer("x", 5), None);
+    }
+}
@@ -0,0 +1,875 @@
+//! Planned batches of moves.
+//!
+//! A [`MovePlan`] is the list of `(source, destination)` pairs a run will
+//! execute. Building the plan up front lets routing features (rules,
+//! per-extension folders, ...) decide every destination before anything on
+//! disk is touched, and lets the CLI print or review the plan as a whole.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::date::DateTime;
+use crate::device::Devices;
+use crate::error::{MvlnError, Result};
+use crate::observer::MvlnObserver;
+use crate::operation::{move_and_link, MoveOptions, MoveResult};
+use crate::path_utils::unique_name;
+use crate::rules::{extension_dir, modified_time};
+use crate::size::disk_usage;
+
+/// A single planned move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlannedMove {
+    /// The file or directory to move.
+    pub source: PathBuf,
+    /// Where to move it. If this is an existing directory, the source's
+    /// file name is appended when the move is executed.
+    pub dest: PathBuf,
+}
+
+/// How sources are laid out under a destination directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Layout {
+    /// Every source goes directly into the destination.
+    #[default]
+    Flat,
+    /// Sources go into a subdirectory named after their lowercased
+    /// extension (`dest/pdf/`, `dest/jpg/`, `dest/noext/`).
+    ByExtension,
+    /// Sources go into a subdirectory derived from their modification time
+    /// (UTC) using a `strftime`-style format, e.g. `%Y/%m` for `dest/2024/06/`.
+    ByDate(String),
+    /// Like [`Layout::ByDate`], but using the date photos were taken, from
+    /// their EXIF metadata (camera local time), and the modification time
+    /// for files without one. Requires the `exif` feature.
+    #[cfg(feature = "exif")]
+    ByExifDate(String),
+}
+
+/// Order in which planned moves are executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SortKey {
+    /// By source file name, then full path.
+    Name,
+    /// By total size, smallest first.
+    Size,
+    /// By modification time, oldest first.
+    Mtime,
+}
+
+/// Default format for [`Layout::ByDate`].
+pub const DEFAULT_DATE_FORMAT: &str = "%Y/%m";
+
+/// Default format for [`Layout::ByExifDate`].
+#[cfg(feature = "exif")]
+pub const DEFAULT_EXIF_DATE_FORMAT: &str = "%Y/%m/%d";
+
+impl Layout {
+    /// Subdirectory of the destination that `source` belongs in, if any.
+    #[must_use]
+    pub fn subdir(&self, source: &Path) -> Option<PathBuf> {
+        match self {
+            Self::Flat => None,
+            Self::ByExtension => Some(PathBuf::from(extension_dir(source))),
+            Self::ByDate(format) => Some(PathBuf::from(
+                DateTime::from_system_time(modified_time(source)).format(format),
+            )),
+            #[cfg(feature = "exif")]
+            Self::ByExifDate(format) => {
+                let date = crate::exif::capture_date(source)
+                    .unwrap_or_else(|| DateTime::from_system_time(modified_time(source)));
+                Some(PathBuf::from(date.format(format)))
+            }
+        }
+    }
+}
+
+/// What a batch does when one of its moves fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorPolicy {
+    /// Record the failure and continue with the remaining moves.
+    #[default]
+    KeepGoing,
+    /// Stop at the first failure; the remaining moves are not attempted.
+    FailFast,
+}
+
+/// The outcome of [`MovePlan::execute`].
+#[derive(Debug, Default)]
+pub struct BatchOutcome {
+    /// The completed moves, in plan order.
+    pub moved: Vec<MoveResult>,
+    /// The failed moves and their errors, in plan order.
+    pub failed: Vec<(PlannedMove, MvlnError)>,
+    /// The moves skipped by the conflict handler, and those not attempted
+    /// because the batch stopped early ([`ErrorPolicy::FailFast`] or
+    /// [`Resolution::Abort`](crate::conflict::Resolution::Abort)).
+    pub skipped: Vec<PlannedMove>,
+}
+
+impl BatchOutcome {
+    /// Whether every move in the plan completed.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.skipped.is_empty()
+    }
+
+    /// Turn the outcome into a single result.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::BatchOperationFailed`] if any move failed. The
+    /// individual errors are in [`BatchOutcome::failed`].
+    pub fn into_result(self) -> Result<Vec<MoveResult>> {
+        if self.failed.is_empty() {
+            Ok(self.moved)
+        } else {
+            Err(MvlnError::BatchOperationFailed {
+                count: self.failed.len(),
+                data_at_risk: self
+                    .failed
+                    .iter()
+                    .filter(|(_, e)| e.is_data_at_risk())
+                    .count(),
+            })
+        }
+    }
+}
+
+/// An ordered list of planned moves.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MovePlan {
+    /// The moves, in execution order.
+    pub moves: Vec<PlannedMove>,
+}
+
+impl MovePlan {
+    /// Create an empty plan.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plan moving every source into `dest` using `layout`.
+    ///
+    /// With [`Layout::Flat`] each move targets `dest` itself (which may be a
+    /// directory or, for a single source, a file path). Other layouts treat
+    /// `dest` as a root directory and plan the full final path of each source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mvln::plan::{Layout, MovePlan};
+    /// use std::path::PathBuf;
+    ///
+    /// let sources = vec![PathBuf::from("a.PDF"), PathBuf::from("b.jpg")];
+    /// let plan = MovePlan::build(&sources, "/archive", &Layout::ByExtension);
+    /// assert_eq!(plan.moves[0].dest, PathBuf::from("/archive/pdf/a.PDF"));
+    /// assert_eq!(plan.moves[1].dest, PathBuf::from("/archive/jpg/b.jpg"));
+    /// ```
+    #[must_use]
+    pub fn build<D: AsRef<Path>>(sources: &[PathBuf], dest: D, layout: &Layout) -> Self {
+        let dest = dest.as_ref();
+        let mut plan = Self::new();
+        for source in sources {
+            let target = match (layout.subdir(source), source.file_name()) {
+                (Some(subdir), Some(name)) => dest.join(subdir).join(name),
+                _ => dest.to_path_buf(),
+            };
+            plan.push(source, target);
+        }
+        plan
+    }
+
+    /// Give every move a distinct, currently unused final destination.
+    ///
+    /// Destinations that are directories are resolved to `dir/<file name>`.
+    /// When that path already exists on disk, or an earlier move in the plan
+    /// already targets it, ` (1)`, ` (2)`, ... is appended to the file stem.
+    pub fn rename_conflicts(&mut self) {
+        let mut claimed: Vec<PathBuf> = Vec::with_capacity(self.moves.len());
+        for planned in &mut self.moves {
+            let target = match planned.source.file_name() {
+                Some(name) if planned.dest.is_dir() => planned.dest.join(name),
+                _ => planned.dest.clone(),
+            };
+            let target = unique_name(&target, |candidate| {
+                candidate.symlink_metadata().is_ok() || claimed.iter().any(|c| c == candidate)
+            });
+            claimed.push(target.clone());
+            planned.dest = target;
+        }
+    }
+
+    /// Move directories entry by entry, `max_depth` levels deep, instead of
+    /// as a whole.
+    ///
+    /// Each move of a directory is replaced by moves of its entries (sorted
+    /// by name) into the corresponding directory at the destination, and so
+    /// on for subdirectories down to `max_depth` levels. Directories at that
+    /// depth are moved as whole subtrees, or, with `skip_deeper`, left where
+    /// they are; the skipped moves are returned. Empty directories are
+    /// always moved whole, and directories that cannot be read are kept as
+    /// they were planned.
+    ///
+    /// The source directories stay in place as real directories, holding
+    /// the symlinks to their moved entries, which suits trees too big to
+    /// move (or review, or cap with [`limit`](Self::limit)) in one piece.
+    pub fn split_dirs(&mut self, max_depth: usize, skip_deeper: bool) -> Vec<PlannedMove> {
+        let mut moves = Vec::with_capacity(self.moves.len());
+        let mut skipped = Vec::new();
+        for planned in self.moves.drain(..) {
+            let target = match planned.source.file_name() {
+                Some(name) if planned.dest.is_dir() => planned.dest.join(name),
+                _ => planned.dest.clone(),
+            };
+            split_dir(
+                planned,
+                &target,
+                max_depth,
+                skip_deeper,
+                &mut moves,
+                &mut skipped,
+            );
+        }
+        self.moves = moves;
+        skipped
+    }
+
+    /// Reorder the moves by `key`, reversing the order if `reverse` is set.
+    ///
+    /// The sort is stable, so moves with equal keys keep their relative
+    /// order. Sizes and times that cannot be read sort as zero / the epoch.
+    pub fn sort(&mut self, key: SortKey, reverse: bool) {
+        match key {
+            SortKey::Name => self.moves.sort_by(|a, b| {
+                a.source
+                    .file_name()
+                    .cmp(&b.source.file_name())
+                    .then_with(|| a.source.cmp(&b.source))
+            }),
+            SortKey::Size => self
+                .moves
+                .sort_by_cached_key(|m| disk_usage(&m.source).unwrap_or(0)),
+            SortKey::Mtime => self.moves.sort_by_cached_key(|m| modified_time(&m.source)),
+        }
+        if reverse {
+            self.moves.reverse();
+        }
+    }
+
+    /// Cap the plan at `max_files` moves and `max_bytes` bytes.
+    ///
+    /// Moves are kept in order while they fit; a move that would exceed the
+    /// byte limit is deferred, but later, smaller moves may still fit. The
+    /// deferred moves are removed from the plan and returned so they can be
+    /// reported (and picked up by the next run). Sources whose size cannot
+    /// be determined count as zero bytes.
+    pub fn limit(&mut self, max_files: Option<usize>, max_bytes: Option<u64>) -> Vec<PlannedMove> {
+        if max_files.is_none() && max_bytes.is_none() {
+            return Vec::new();
+        }
+        let mut kept = Vec::new();
+        let mut deferred = Vec::new();
+        let mut bytes = 0u64;
+        for planned in self.moves.drain(..) {
+            let full = max_files.is_some_and(|max| kept.len() >= max);
+            let size = if max_bytes.is_some() && !full {
+                disk_usage(&planned.source).unwrap_or(0)
+            } else {
+                0
+            };
+            if full || max_bytes.is_some_and(|max| bytes.saturating_add(size) > max) {
+                deferred.push(planned);
+            } else {
+                bytes = bytes.saturating_add(size);
+                kept.push(planned);
+            }
+        }
+        self.moves = kept;
+        deferred
+    }
+
+    /// Whether each move crosses filesystems (see [`crate::device`]), in
+    /// order; `None` where that cannot be told. Every directory involved is
+    /// looked up once, so this is cheap even for large batches.
+    #[must_use]
+    pub fn crosses_filesystems(&self) -> Vec<Option<bool>> {
+        let mut devices = Devices::new();
+        self.moves
+            .iter()
+            .map(|planned| devices.crosses(&planned.source, &planned.dest))
+            .collect()
+    }
+
+    /// Remove the moves that cross filesystems, for runs that must not
+    /// copy (see [`MoveOptions::no_cross_device`]), and return them. Moves
+    /// that cannot be told apart are kept; they still fail when executed.
+    pub fn remove_cross_device(&mut self) -> Vec<PlannedMove> {
+        let crossing = self.crosses_filesystems();
+        let mut crossing = crossing.into_iter();
+        let (removed, kept) = self
+            .moves
+            .drain(..)
+            .partition(|_| crossing.next().flatten() == Some(true));
+        self.moves = kept;
+        removed
+    }
+
+    /// Execute the plan with [`move_and_link`], in order.
+    ///
+    /// A failed move does not undo the earlier ones. With
+    /// [`ErrorPolicy::KeepGoing`] every move is attempted; with
+    /// [`ErrorPolicy::FailFast`] the batch stops at the first failure. A
+    /// conflict handler answering
+    /// [`Resolution::Abort`](crate::conflict::Resolution::Abort) always
+    /// stops it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mvln::plan::{ErrorPolicy, Layout, MovePlan};
+    /// use mvln::MoveOptions;
+    /// use std::path::PathBuf;
+    ///
+    /// let sources = vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")];
+    /// let plan = MovePlan::build(&sources, "/archive", &Layout::Flat);
+    /// let outcome = plan.execute(&MoveOptions::default(), ErrorPolicy::FailFast);
+    /// for (planned, error) in &outcome.failed {
+    ///     eprintln!("{}: {error}", planned.source.display());
+    /// }
+    /// ```
+    #[must_use]
+    pub fn execute(&self, options: &MoveOptions, policy: ErrorPolicy) -> BatchOutcome {
+        self.execute_with(options, policy, &())
+    }
+
+    /// Like [`MovePlan::execute`], reporting each step to `observer`.
+    #[must_use]
+    pub fn execute_with(
+        &self,
+        options: &MoveOptions,
+        policy: ErrorPolicy,
+        observer: &dyn MvlnObserver,
+    ) -> BatchOutcome {
+        observer.on_planned(self);
+        let mut outcome = BatchOutcome::default();
+        let mut moves = self.moves.iter();
+        for planned in moves.by_ref() {
+            observer.on_started(planned);
+            match move_and_link(&planned.source, &planned.dest, options) {
+                Ok(result) => {
+                    observer.on_moved(&result.source, &result.dest);
+                    if result.farm.is_empty() {
+                        observer.on_linked(&result.source, &result.symlink_target);
+                    }
+                    for (link, target) in &result.farm {
+                        observer.on_linked(link, target);
+                    }
+                    outcome.moved.push(result);
+                }
+                Err(MvlnError::ConflictSkipped { .. } | MvlnError::CrossDeviceDeclined { .. }) => {
+                    observer.on_skipped(planned);
+                    outcome.skipped.push(planned.clone());
+                }
+                Err(e) => {
+                    observer.on_failed(planned, &e);
+                    let abort = matches!(e, MvlnError::ConflictAborted { .. });
+                    outcome.failed.push((planned.clone(), e));
+                    if abort || policy == ErrorPolicy::FailFast {
+                        break;
+                    }
+                }
+            }
+        }
+        for planned in moves {
+            observer.on_skipped(planned);
+            outcome.skipped.push(planned.clone());
+        }
+        outcome
+    }
+
+    /// Execute the plan lazily, one move per call to `next`.
+    ///
+    /// Unlike [`MovePlan::execute`], nothing is collected: results are
+    /// handed over as they happen and the caller decides whether to go on.
+    /// Dropping the iterator stops the batch. See [`execute_each`] to
+    /// stream moves that are not in a plan.
+    pub fn execute_each<'a>(
+        &'a self,
+        options: &'a MoveOptions,
+    ) -> ExecuteEach<'a, std::iter::Cloned<std::slice::Iter<'a, PlannedMove>>> {
+        execute_each(self.moves.iter().cloned(), options)
+    }
+
+    /// Append a move to the plan.
+    pub fn push<S: Into<PathBuf>, D: Into<PathBuf>>(&mut self, source: S, dest: D) {
+        self.moves.push(PlannedMove {
+            source: source.into(),
+            dest: dest.into(),
+        });
+    }
+
+    /// Number of planned moves.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Whether the plan contains no moves.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Iterate over the planned moves.
+    pub fn iter(&self) -> std::slice::Iter<'_, PlannedMove> {
+        self.moves.iter()
+    }
+}
+
+/// Plan `planned`, whose final path is `target`, into `moves`: as is, or
+/// split into its entries while `levels` remain (see
+/// [`MovePlan::split_dirs`]).
+fn split_dir(
+    planned: PlannedMove,
+    target: &Path,
+    levels: usize,
+    skip_deeper: bool,
+    moves: &mut Vec<PlannedMove>,
+    skipped: &mut Vec<PlannedMove>,
+) {
+    let is_real_dir = planned.source.symlink_metadata().is_ok_and(|m| m.is_dir());
+    if !is_real_dir {
+        moves.push(planned);
+        return;
+    }
+    if levels == 0 {
+        if skip_deeper {
+            skipped.push(planned);
+        } else {
+            moves.push(planned);
+        }
+        return;
+    }
+    let mut names: Vec<_> = match fs::read_dir(&planned.source) {
+        Ok(entries) => entries.flatten().map(|entry| entry.file_name()).collect(),
+        Err(_) => Vec::new(),
+    };
+    if names.is_empty() {
+        moves.push(planned);
+        return;
+    }
+    names.sort();
+    for name in names {
+        let entry_target = target.join(&name);
+        // An existing directory would get the name appended once more
+        let dest = if entry_target.is_dir() {
+            target.to_path_buf()
+        } else {
+            entry_target.clone()
+        };
+        let entry = PlannedMove {
+            source: planned.source.join(&name),
+            dest,
+        };
+        split_dir(
+            entry,
+            &entry_target,
+            levels - 1,
+            skip_deeper,
+            moves,
+            skipped,
+        );
+    }
+}
+
+/// Execute moves from any iterator, one per call to `next`.
+///
+/// This is the streaming counterpart of [`MovePlan::execute`]: the moves
+/// can come straight from a directory walk, and memory stays flat however
+/// many there are.
+///
+/// # Examples
+///
+/// ```no_run
+/// use mvln::plan::{execute_each, PlannedMove};
+/// use mvln::MoveOptions;
+/// use std::path::PathBuf;
+///
+/// let moves = std::fs::read_dir("/data/huge").unwrap().flatten().map(|entry| PlannedMove {
+///     source: entry.path(),
+///     dest: PathBuf::from("/archive"),
+/// });
+/// let options = MoveOptions::default();
+/// for (planned, result) in execute_each(moves, &options) {
+///     if let Err(error) = result {
+///         eprintln!("{}: {error}", planned.source.display());
+///         break;
+///     }
+/// }
+/// ```
+pub fn execute_each<I>(moves: I, options: &MoveOptions) -> ExecuteEach<'_, I::IntoIter>
+where
+    I: IntoIterator<Item = PlannedMove>,
+{
+    ExecuteEach {
+        moves: moves.into_iter(),
+        options,
+    }
+}
+
+/// Iterator returned by [`execute_each`], yielding each move with its
+/// result.
+#[derive(Debug)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct ExecuteEach<'a, I> {
+    moves: I,
+    options: &'a MoveOptions,
+}
+
+impl<I: Iterator<Item = PlannedMove>> Iterator for ExecuteEach<'_, I> {
+    type Item = (PlannedMove, Result<MoveResult>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let planned = self.moves.next()?;
+        let result = move_and_link(&planned.source, &planned.dest, self.options);
+        Some((planned, result))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.moves.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for &'a MovePlan {
+    type Item = &'a PlannedMove;
+    type IntoIter = std::slice::Iter<'a, PlannedMove>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_layout_targets_dest_directly() {
+        let sources = vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")];
+        let plan = MovePlan::build(&sources, "dest", &Layout::Flat);
+        assert!(plan.iter().all(|m| m.dest == Path::new("dest")));
+    }
+
+    #[test]
+    fn split_dirs_moves_entries_down_to_the_limit() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let src = tmp.path().join("src");
+        fs::create_dir_all(src.join("sub/deep")).unwrap();
+        fs::create_dir_all(src.join("empty")).unwrap();
+        fs::write(src.join("a.txt"), "a").unwrap();
+        fs::write(src.join("sub/b.txt"), "b").unwrap();
+        fs::write(src.join("sub/deep/c.txt"), "c").unwrap();
+        let dest = tmp.path().join("dest");
+        fs::create_dir(&dest).unwrap();
+        let pairs = |plan: &[PlannedMove]| -> Vec<(PathBuf, PathBuf)> {
+            plan.iter()
+                .map(|m| {
+                    let source = m.source.strip_prefix(&src).unwrap().to_path_buf();
+                    (source, m.dest.strip_prefix(&dest).unwrap().to_path_buf())
+                })
+                .collect()
+        };
+
+        let sources = vec![src.clone()];
+        let mut plan = MovePlan::build(&sources, &dest, &Layout::Flat);
+        assert!(plan.split_dirs(1, false).is_empty());
+        assert_eq!(
+            pairs(&plan.moves),
+            [
+                ("a.txt".into(), "src/a.txt".into()),
+                ("empty".into(), "src/empty".into()),
+                ("sub".into(), "src/sub".into()),
+            ]
+        );
+
+        let mut plan = MovePlan::build(&sources, &dest, &Layout::Flat);
+        let skipped = plan.split_dirs(2, true);
+        assert_eq!(
+            pairs(&plan.moves),
+            [
+                ("a.txt".into(), "src/a.txt".into()),
+                ("empty".into(), "src/empty".into()),
+                ("sub/b.txt".into(), "src/sub/b.txt".into()),
+            ]
+        );
+        assert_eq!(
+            pairs(&skipped),
+            [("sub/deep".into(), "src/sub/deep".into())]
+        );
+
+        // Existing destination directories get the entry name appended
+        fs::create_dir_all(dest.join("src/sub")).unwrap();
+        let mut plan = MovePlan::build(&sources, &dest, &Layout::Flat);
+        plan.split_dirs(1, false);
+        assert_eq!(plan.moves[2].dest, dest.join("src"));
+    }
+
+    #[test]
+    fn by_extension_uses_noext_for_bare_names() {
+        let sources = vec![PathBuf::from("dir/README")];
+        let plan = MovePlan::build(&sources, "dest", &Layout::ByExtension);
+        assert_eq!(plan.moves[0].dest, PathBuf::from("dest/noext/README"));
+    }
+
+    #[test]
+    fn rename_conflicts_between_sources_and_existing_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dest = tmp.path().join("dest");
+        std::fs::create_dir(&dest).unwrap();
+        std::fs::write(dest.join("a.txt"), "existing").unwrap();
+
+        let sources = vec![PathBuf::from("x/a.txt"), PathBuf::from("y/a.txt")];
+        let mut plan = MovePlan::build(&sources, &dest, &Layout::Flat);
+        plan.rename_conflicts();
+
+        assert_eq!(plan.moves[0].dest, dest.join("a (1).txt"));
+        assert_eq!(plan.moves[1].dest, dest.join("a (2).txt"));
+    }
+
+    #[test]
+    fn by_date_uses_modification_time() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("photo.jpg");
+        std::fs::write(&file, "").unwrap();
+        let expected = DateTime::from_system_time(modified_time(&file)).format("%Y/%m");
+
+        let plan = MovePlan::build(
+            std::slice::from_ref(&file),
+            "dest",
+            &Layout::ByDate(DEFAULT_DATE_FORMAT.to_string()),
+        );
+        assert_eq!(
+            plan.moves[0].dest,
+            Path::new("dest").join(expected).join("photo.jpg")
+        );
+    }
+
+    #[cfg(feature = "exif")]
+    #[test]
+    fn by_exif_date_falls_back_to_modification_time() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("scan.jpg");
+        std::fs::write(&file, "not really a JPEG").unwrap();
+        let expected = DateTime::from_system_time(modified_time(&file)).format("%Y/%m/%d");
+
+        let layout = Layout::ByExifDate(DEFAULT_EXIF_DATE_FORMAT.to_string());
+        assert_eq!(layout.subdir(&file), Some(PathBuf::from(expected)));
+    }
+
+    #[test]
+    fn limit_defers_moves_beyond_caps() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut plan = MovePlan::new();
+        for (name, size) in [("a", 60), ("b", 60), ("c", 30), ("d", 10)] {
+            let path = tmp.path().join(name);
+            std::fs::write(&path, vec![0u8; size]).unwrap();
+            plan.push(path, "dest");
+        }
+
+        let mut by_bytes = plan.clone();
+        let deferred = by_bytes.limit(None, Some(100));
+        let kept: Vec<_> = by_bytes.iter().map(|m| m.source.clone()).collect();
+        assert_eq!(
+            kept,
+            vec![
+                tmp.path().join("a"),
+                tmp.path().join("c"),
+                tmp.path().join("d")
+            ]
+        );
+        assert_eq!(deferred.len(), 1);
+
+        let mut by_count = plan;
+        let deferred = by_count.limit(Some(2), None);
+        assert_eq!(by_count.len(), 2);
+        assert_eq!(deferred[0].source, tmp.path().join("c"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn moves_within_one_filesystem_are_kept() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut plan = MovePlan::new();
+        plan.push(tmp.path().join("a"), tmp.path().join("archive/a"));
+        plan.push(tmp.path().join("b"), tmp.path());
+
+        assert_eq!(plan.crosses_filesystems(), vec![Some(false); 2]);
+        assert!(plan.remove_cross_device().is_empty());
+        assert_eq!(plan.len(), 2);
+    }
+
+    #[test]
+    fn sort_by_size_and_name() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut plan = MovePlan::new();
+        for (name, size) in [("b", 30), ("a", 10), ("c", 20)] {
+            let path = tmp.path().join(name);
+            std::fs::write(&path, vec![0u8; size]).unwrap();
+            plan.push(path, "dest");
+        }
+        let names = |plan: &MovePlan| -> Vec<String> {
+            plan.iter()
+                .map(|m| m.source.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+
+        plan.sort(SortKey::Size, true);
+        assert_eq!(names(&plan), ["b", "c", "a"]);
+        plan.sort(SortKey::Name, false);
+        assert_eq!(names(&plan), ["a", "b", "c"]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn plan_round_trips_through_serde() {
+        let plan = MovePlan::build(
+            &[PathBuf::from("a.pdf")],
+            Path::new("dest"),
+            &Layout::ByExtension,
+        );
+        let json = serde_json::to_string(&plan).unwrap();
+        assert_eq!(
+            json,
+            r#"{"moves":[{"source":"a.pdf","dest":"dest/pdf/a.pdf"}]}"#
+        );
+        assert_eq!(serde_json::from_str::<MovePlan>(&json).unwrap(), plan);
+    }
+
+    #[test]
+    fn execute_stops_at_first_failure_with_fail_fast() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let dest = dir.path().join("dest");
+        std::fs::create_dir(&dest).unwrap();
+        let sources: Vec<PathBuf> = ["a.txt", "missing.txt", "c.txt"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        std::fs::write(&sources[0], "a").unwrap();
+        std::fs::write(&sources[2], "c").unwrap();
+        let plan = MovePlan::build(&sources, &dest, &Layout::Flat);
+
+        let outcome = plan.execute(&MoveOptions::default(), ErrorPolicy::FailFast);
+        assert_eq!(outcome.moved.len(), 1);
+        assert_eq!(outcome.failed[0].0.source, sources[1]);
+        assert_eq!(outcome.skipped, vec![plan.moves[2].clone()]);
+        assert!(!dest.join("c.txt").exists());
+
+        // Keep-going attempts the rest; a.txt is already moved, so its
+        // destination now exists and it fails too
+        let outcome = plan.execute(&MoveOptions::default(), ErrorPolicy::KeepGoing);
+        assert_eq!(outcome.moved.len(), 1);
+        assert!(outcome.skipped.is_empty());
+        assert!(dest.join("c.txt").exists());
+        assert!(matches!(
+            outcome.into_result(),
+            Err(MvlnError::BatchOperationFailed { count: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn execute_with_reports_events_in_order() {
+        use std::cell::RefCell;
+
+        #[derive(Default)]
+        struct Recorder(RefCell<Vec<String>>);
+
+        impl MvlnObserver for Recorder {
+            fn on_planned(&self, plan: &MovePlan) {
+                self.0.borrow_mut().push(format!("planned {}", plan.len()));
+            }
+            fn on_started(&self, planned: &PlannedMove) {
+                let name = planned.source.file_name().unwrap().to_string_lossy();
+                self.0.borrow_mut().push(format!("started {name}"));
+            }
+            fn on_moved(&self, _source: &Path, dest: &Path) {
+                let name = dest.file_name().unwrap().to_string_lossy();
+                self.0.borrow_mut().push(format!("moved {name}"));
+            }
+            fn on_linked(&self, _link: &Path, target: &Path) {
+                self.0
+                    .borrow_mut()
+                    .push(format!("linked {}", target.display()));
+            }
+            fn on_skipped(&self, planned: &PlannedMove) {
+                let name = planned.source.file_name().unwrap().to_string_lossy();
+                self.0.borrow_mut().push(format!("skipped {name}"));
+            }
+            fn on_failed(&self, planned: &PlannedMove, _error: &MvlnError) {
+                let name = planned.source.file_name().unwrap().to_string_lossy();
+                self.0.borrow_mut().push(format!("failed {name}"));
+            }
+        }
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let dest = dir.path().join("dest");
+        std::fs::create_dir(&dest).unwrap();
+        let sources: Vec<PathBuf> = ["a.txt", "missing.txt", "c.txt"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        std::fs::write(&sources[0], "a").unwrap();
+        std::fs::write(&sources[2], "c").unwrap();
+        let plan = MovePlan::build(&sources, &dest, &Layout::Flat);
+
+        let recorder = Recorder::default();
+        let outcome = plan.execute_with(&MoveOptions::default(), ErrorPolicy::FailFast, &recorder);
+        assert_eq!(outcome.moved.len(), 1);
+        assert_eq!(
+            recorder.0.into_inner(),
+            [
+                "planned 3",
+                "started a.txt",
+                "moved a.txt",
+                "linked dest/a.txt",
+                "started missing.txt",
+                "failed missing.txt",
+                "skipped c.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn execute_each_moves_lazily() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let dest = dir.path().join("dest");
+        std::fs::create_dir(&dest).unwrap();
+        let moves: Vec<PlannedMove> = ["a.txt", "b.txt"]
+            .iter()
+            .map(|name| {
+                let source = dir.path().join(name);
+                std::fs::write(&source, name).unwrap();
+                PlannedMove {
+                    source,
+                    dest: dest.clone(),
+                }
+            })
+            .collect();
+
+        let options = MoveOptions::default();
+        let mut results = execute_each(moves, &options);
+        let (planned, result) = results.next().unwrap();
+        assert_eq!(result.unwrap().dest, dest.join("a.txt"));
+        assert!(planned.source.is_symlink());
+        // b.txt is only moved when asked for
+        assert!(!dest.join("b.txt").exists());
+        assert!(results.next().unwrap().1.is_ok());
+        assert!(results.next().is_none());
+    }
+}
@@ -0,0 +1,266 @@
+//! Which attributes survive a move across filesystems.
+//!
+//! A rename keeps everything about a file, but a copy only has what mvln
+//! carries over. [`MoveOptions::preserve`](crate::MoveOptions::preserve)
+//! picks which attributes that is, like `cp --preserve`:
+//!
+//! - [`MODE`](PreserveSet::MODE): permission bits (default)
+//! - [`TIMESTAMPS`](PreserveSet::TIMESTAMPS): access and modification times
+//!   (default)
+//! - [`OWNER`](PreserveSet::OWNER) and [`GROUP`](PreserveSet::GROUP), together
+//!   `ownership`: see [`crate::ownership`]
+//! - [`XATTR`](PreserveSet::XATTR): extended attributes of files and
+//!   directories (Linux and macOS)
+//! - [`FINDER`](PreserveSet::FINDER): just the Finder's extended attributes
+//!   (macOS; default): tags, color labels and other `com.apple.metadata:`
+//!   entries, and `com.apple.FinderInfo`
+//! - [`LINKS`](PreserveSet::LINKS): hard links between files of a copied
+//!   tree, which are otherwise copied once per name
+//!
+//! Separately, [`strip_quarantine`] drops the macOS quarantine attribute
+//! that browsers put on downloads, which both a rename and an `xattr` copy
+//! would otherwise keep.
+
+use std::fs::{File, FileTimes, Metadata};
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+bitflags::bitflags! {
+    /// A set of file attributes to carry over to copies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mvln::preserve::PreserveSet;
+    ///
+    /// let set: PreserveSet = "ownership,links".parse().unwrap();
+    /// assert!(set.contains(PreserveSet::OWNER | PreserveSet::GROUP));
+    /// assert!(!set.contains(PreserveSet::MODE));
+    /// assert_eq!("all".parse(), Ok(PreserveSet::all()));
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct PreserveSet: u8 {
+        /// Permission bits.
+        const MODE = 1;
+        /// The owning user.
+        const OWNER = 1 << 1;
+        /// The owning group.
+        const GROUP = 1 << 2;
+        /// Access and modification times.
+        const TIMESTAMPS = 1 << 3;
+        /// Extended attributes.
+        const XATTR = 1 << 4;
+        /// Hard links within a copied tree.
+        const LINKS = 1 << 5;
+        /// Finder tags, labels and metadata (a subset of `XATTR`).
+        const FINDER = 1 << 6;
+        /// Owner and group.
+        const OWNERSHIP = Self::OWNER.bits() | Self::GROUP.bits();
+    }
+}
+
+impl Default for PreserveSet {
+    /// Mode, timestamps and Finder metadata.
+    fn default() -> Self {
+        Self::MODE | Self::TIMESTAMPS | Self::FINDER
+    }
+}
+
+impl PreserveSet {
+    /// Whether any extended attributes are copied on this platform, i.e.
+    /// [`XATTR`](Self::XATTR), or [`FINDER`](Self::FINDER) on macOS.
+    #[must_use]
+    pub fn copies_xattrs(self) -> bool {
+        self.contains(Self::XATTR) || (cfg!(target_os = "macos") && self.contains(Self::FINDER))
+    }
+
+    /// Whether the extended attribute `name` is copied.
+    fn copies_xattr(self, name: &[u8]) -> bool {
+        self.contains(Self::XATTR)
+            || (self.contains(Self::FINDER)
+                && (name.starts_with(b"com.apple.metadata:") || name == b"com.apple.FinderInfo"))
+    }
+}
+
+impl FromStr for PreserveSet {
+    type Err = String;
+
+    /// Parse a comma-separated list of `mode`, `ownership`, `timestamps`,
+    /// `xattr`, `finder`, `links` and `all`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(|name| match name.trim() {
+                "mode" => Ok(Self::MODE),
+                "ownership" => Ok(Self::OWNERSHIP),
+                "timestamps" => Ok(Self::TIMESTAMPS),
+                "xattr" => Ok(Self::XATTR),
+                "finder" => Ok(Self::FINDER),
+                "links" => Ok(Self::LINKS),
+                "all" => Ok(Self::all()),
+                other => Err(format!(
+                    "unknown attribute {other:?}: expected mode, ownership, timestamps, xattr, finder, links or all"
+                )),
+            })
+            .try_fold(Self::empty(), |set, flag| Ok(set | flag?))
+    }
+}
+
+/// Give `dest` the access and modification times recorded in `metadata`
+/// (of its source, taken before the source was read).
+///
+/// # Errors
+///
+/// Returns the error of reading the times or of `futimens`.
+pub fn copy_times(metadata: &Metadata, dest: &File) -> io::Result<()> {
+    let times = FileTimes::new()
+        .set_accessed(metadata.accessed()?)
+        .set_modified(metadata.modified()?);
+    dest.set_times(times)
+}
+
+/// Copy the extended attributes of the open file or directory `source`
+/// that `set` asks for to `dest` (see [`PreserveSet::copies_xattrs`]).
+/// Does nothing if either filesystem does not support them.
+///
+/// # Errors
+///
+/// Returns the error of reading or setting an attribute, e.g. permission
+/// denied for the `trusted.` namespace when not running as root.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+pub fn copy_xattrs(source: &File, dest: &File, set: PreserveSet) -> io::Result<()> {
+    use rustix::fs::{fgetxattr, flistxattr, fsetxattr, XattrFlags};
+    use rustix::io::Errno;
+
+    let size = match flistxattr(source, &mut []) {
+        Ok(0) | Err(Errno::OPNOTSUPP) => return Ok(()),
+        Ok(size) => size,
+        Err(e) => return Err(e.into()),
+    };
+    let mut names = vec![0; size];
+    let len = flistxattr(source, &mut names)?;
+    let names: Vec<u8> = names[..len].iter().map(|c| c.to_ne_bytes()[0]).collect();
+    let names = names.split(|&b| b == 0);
+    for name in names.filter(|name| !name.is_empty() && set.copies_xattr(name)) {
+        let mut value = vec![0; fgetxattr(source, name, &mut [])?];
+        let len = fgetxattr(source, name, &mut value)?;
+        match fsetxattr(dest, name, &value[..len], XattrFlags::empty()) {
+            Err(Errno::OPNOTSUPP) => return Ok(()),
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+pub fn copy_xattrs(_source: &File, _dest: &File, _set: PreserveSet) -> io::Result<()> {
+    Ok(())
+}
+
+/// The attribute macOS uses to make Gatekeeper check downloaded files.
+pub const QUARANTINE: &str = "com.apple.quarantine";
+
+/// Remove the [`QUARANTINE`] attribute from `path` and, if it is a
+/// directory, everything below it. Symlinks are changed themselves and not
+/// followed. Does nothing except on macOS.
+///
+/// # Errors
+///
+/// Returns the first error of walking the tree or removing the attribute,
+/// other than the attribute not being there.
+pub fn strip_quarantine(path: &Path) -> io::Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        use rustix::fs::lremovexattr;
+        use rustix::io::Errno;
+
+        for entry in walkdir::WalkDir::new(path) {
+            match lremovexattr(entry?.path(), QUARANTINE) {
+                Ok(()) | Err(Errno::NOATTR | Errno::OPNOTSUPP) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_attribute_lists() {
+        assert_eq!("mode".parse(), Ok(PreserveSet::MODE));
+        assert_eq!(
+            "timestamps, xattr".parse(),
+            Ok(PreserveSet::TIMESTAMPS | PreserveSet::XATTR)
+        );
+        assert_eq!("all,mode".parse(), Ok(PreserveSet::all()));
+        assert!("mode,acl".parse::<PreserveSet>().is_err());
+        assert!("".parse::<PreserveSet>().is_err());
+        assert_eq!(
+            PreserveSet::default(),
+            PreserveSet::MODE | PreserveSet::TIMESTAMPS | PreserveSet::FINDER
+        );
+    }
+
+    #[test]
+    fn finder_covers_only_finder_xattrs() {
+        let finder = PreserveSet::FINDER;
+        assert!(finder.copies_xattr(b"com.apple.metadata:_kMDItemUserTags"));
+        assert!(finder.copies_xattr(b"com.apple.FinderInfo"));
+        assert!(!finder.copies_xattr(b"com.apple.quarantine"));
+        assert!(!finder.copies_xattr(b"user.mvln"));
+        assert!(PreserveSet::XATTR.copies_xattr(b"user.mvln"));
+        assert!(!PreserveSet::MODE.copies_xattrs());
+        assert!(PreserveSet::XATTR.copies_xattrs());
+        assert_eq!(finder.copies_xattrs(), cfg!(target_os = "macos"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn copies_user_xattrs() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let (source, dest) = (tmp.path().join("source"), tmp.path().join("dest"));
+        std::fs::write(&source, "data").unwrap();
+        std::fs::write(&dest, "data").unwrap();
+        if rustix::fs::setxattr(
+            &source,
+            "user.mvln",
+            b"yes",
+            rustix::fs::XattrFlags::empty(),
+        )
+        .is_err()
+        {
+            // The test filesystem doesn't support user xattrs
+            return;
+        }
+
+        let files = (File::open(&source).unwrap(), File::open(&dest).unwrap());
+        copy_xattrs(&files.0, &files.1, PreserveSet::XATTR).unwrap();
+        let mut value = [0; 8];
+        let len = rustix::fs::getxattr(&dest, "user.mvln", &mut value).unwrap();
+        assert_eq!(&value[..len], b"yes");
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn strips_quarantine_below_a_directory() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("download.zip");
+        std::fs::write(&file, "data").unwrap();
+        rustix::fs::setxattr(
+            &file,
+            QUARANTINE,
+            b"0081;00000000;Safari;",
+            rustix::fs::XattrFlags::empty(),
+        )
+        .unwrap();
+
+        strip_quarantine(tmp.path()).unwrap();
+        assert!(rustix::fs::getxattr(&file, QUARANTINE, &mut [0; 64]).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! Progress of directory trees copied across filesystems.
+//!
+//! Copying a large tree can take hours, and a byte count alone doesn't say
+//! whether the time goes into a few huge files or into a `node_modules`
+//! with a hundred thousand tiny ones. A [`ProgressHandler`] set with
+//! [`MoveOptionsBuilder::on_progress`](crate::operation::MoveOptionsBuilder::on_progress)
+//! is told, every [`PROGRESS_INTERVAL`], how many of the tree's files and
+//! bytes have been copied and which directory the last copied file is in.
+//!
+//! The totals come from a walk of the tree before the copy starts, which
+//! only happens when a handler is set.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Least time between two reports of the same copy.
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How far the copy of a directory tree has got, passed to a
+/// [`ProgressHandler`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct CopyProgress<'a> {
+    /// The directory being copied.
+    pub source: &'a Path,
+    /// The directory of the last file copied, relative to `source` (empty
+    /// for files at the top of the tree).
+    pub dir: &'a Path,
+    /// Files copied so far.
+    pub files: u64,
+    /// Files in the tree.
+    pub total_files: u64,
+    /// Bytes copied so far.
+    pub bytes: u64,
+    /// Bytes in the tree.
+    pub total_bytes: u64,
+    /// Whether this is the last report, sent once the whole tree is copied.
+    pub finished: bool,
+}
+
+/// Callback told about the progress of directory trees copied across
+/// filesystems (see [`crate::progress`]). Called from the copying threads.
+#[derive(Clone)]
+pub struct ProgressHandler(Arc<dyn Fn(&CopyProgress<'_>) + Send + Sync>);
+
+impl ProgressHandler {
+    /// Wrap a callback.
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&CopyProgress<'_>) + Send + Sync + 'static,
+    {
+        Self(Arc::new(callback))
+    }
+
+    /// Pass `progress` to the callback.
+    pub fn report(&self, progress: &CopyProgress<'_>) {
+        (self.0)(progress);
+    }
+}
+
+impl fmt::Debug for ProgressHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressHandler(..)")
+    }
+}
+
+/// Counts the files of one tree as they are copied, and reports to a
+/// [`ProgressHandler`] at most every [`PROGRESS_INTERVAL`].
+pub(crate) struct Tracker<'a> {
+    handler: &'a ProgressHandler,
+    source: &'a Path,
+    total_files: u64,
+    total_bytes: u64,
+    files: AtomicU64,
+    bytes: AtomicU64,
+    last_report: Mutex<Instant>,
+}
+
+impl<'a> Tracker<'a> {
+    /// Track the copy of `source`, which holds `total_files` files of
+    /// `total_bytes` bytes in all.
+    pub(crate) fn new(
+        handler: &'a ProgressHandler,
+        source: &'a Path,
+        total_files: u64,
+        total_bytes: u64,
+    ) -> Self {
+        Self {
+            handler,
+            source,
+            total_files,
+            total_bytes,
+            files: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            last_report: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Count the file `src_path`, just copied to `dest_path`.
+    pub(crate) fn copied(&self, src_path: &Path, dest_path: &Path) {
+        let len = fs::symlink_metadata(dest_path).map_or(0, |m| m.len());
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        let bytes = self.bytes.fetch_add(len, Ordering::Relaxed) + len;
+        // Another thread is reporting: this file is in the next report
+        let Ok(mut last_report) = self.last_report.try_lock() else {
+            return;
+        };
+        if last_report.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        *last_report = Instant::now();
+        let dir = src_path
+            .parent()
+            .and_then(|dir| dir.strip_prefix(self.source).ok())
+            .unwrap_or(Path::new(""));
+        self.handler.report(&CopyProgress {
+            source: self.source,
+            dir,
+            files,
+            total_files: self.total_files.max(files),
+            bytes,
+            total_bytes: self.total_bytes.max(bytes),
+            finished: false,
+        });
+    }
+
+    /// Send the last report, once every file is in place.
+    pub(crate) fn finish(&self) {
+        let _held = self
+            .last_report
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let files = self.files.load(Ordering::Relaxed).max(self.total_files);
+        let bytes = self.bytes.load(Ordering::Relaxed).max(self.total_bytes);
+        self.handler.report(&CopyProgress {
+            source: self.source,
+            dir: Path::new(""),
+            files,
+            total_files: files,
+            bytes,
+            total_bytes: bytes,
+            finished: true,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_at_most_every_interval_and_when_finished() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::create_dir(tmp.path().join("sub")).unwrap();
+        fs::write(tmp.path().join("sub/a"), "abc").unwrap();
+        fs::write(tmp.path().join("b"), "de").unwrap();
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&reports);
+        let handler = ProgressHandler::new(move |p| {
+            let report = (p.dir.to_path_buf(), p.files, p.bytes, p.finished);
+            seen.lock().unwrap().push(report);
+        });
+        let tracker = Tracker::new(&handler, tmp.path(), 2, 5);
+        *tracker.last_report.lock().unwrap() -= PROGRESS_INTERVAL;
+        let a = tmp.path().join("sub/a");
+        tracker.copied(&a, &a);
+        // Too soon after the last report
+        let b = tmp.path().join("b");
+        tracker.copied(&b, &b);
+        tracker.finish();
+
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [
+                ("sub".into(), 1, 3, false),
+                (std::path::PathBuf::new(), 2, 5, true)
+            ]
+        );
+    }
+}
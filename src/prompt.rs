@@ -0,0 +1,246 @@
+//! The `-i/--interactive` conflict prompt, the `--confirm-cross-device`
+//! question, the notice before a directory is copied across filesystems
+//! and the `--progress` line shown while it is.
+//!
+//! These are a [`ConflictHandler`](mvln::conflict::ConflictHandler), a
+//! [`CrossDeviceHandler`](mvln::device::CrossDeviceHandler) and a
+//! [`ProgressHandler`](mvln::progress::ProgressHandler) like any other:
+//! they ask on stderr and read the answer from stdin, so answers can also
+//! be piped in. The answer letters are the same in every language.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use fluent::FluentArgs;
+use mvln::conflict::{ConflictInfo, Resolution};
+use mvln::device::CrossDeviceInfo;
+use mvln::i18n::{self, MessageKey};
+use mvln::progress::CopyProgress;
+use mvln::size::format_size;
+
+/// The answer `--yes` gives to the conflict prompt: nothing is lost and the
+/// move still happens.
+const ACCEPT_CONFLICT: Resolution = Resolution::Backup;
+
+/// An understood answer to the conflict prompt.
+#[derive(Debug, PartialEq, Eq)]
+enum Answer {
+    /// Resolve this conflict only.
+    Once(Resolution),
+    /// Overwrite this and every later conflict without asking.
+    All,
+}
+
+/// Set once the user answers "all"; one run is one batch, so this lasts
+/// for the rest of the process.
+static OVERWRITE_ALL: AtomicBool = AtomicBool::new(false);
+
+/// Ask the user how to resolve `conflict`, repeating the question until
+/// the answer is understood. End of input skips the move.
+///
+/// With `yes` the question is still printed, followed by the automatic
+/// answer, so unattended runs leave a trace of what was decided.
+pub fn ask_conflict(lang: Option<&str>, yes: bool, conflict: &ConflictInfo<'_>) -> Resolution {
+    if OVERWRITE_ALL.load(Ordering::Relaxed) {
+        return Resolution::Overwrite;
+    }
+
+    // Handlers must be Send + Sync, which a bundle is not, so build one per
+    // question; conflicts are rare and the user is the bottleneck anyway
+    let bundle = lang.map_or_else(i18n::init, i18n::init_with_locale);
+    let mut args = FluentArgs::new();
+    args.set("dest", conflict.dest.display().to_string());
+    let question = i18n::msg(&bundle, MessageKey::PromptConflict, Some(&args));
+    if yes {
+        eprintln!("{question} b");
+        return ACCEPT_CONFLICT;
+    }
+
+    let stdin = io::stdin();
+    let mut answer = String::new();
+    loop {
+        eprint!("{question} ");
+        let _ = io::stderr().flush();
+        answer.clear();
+        match stdin.lock().read_line(&mut answer) {
+            Ok(0) | Err(_) => return Resolution::Skip,
+            Ok(_) => {}
+        }
+        match parse_answer(&answer) {
+            Some(Answer::Once(resolution)) => return resolution,
+            Some(Answer::All) => {
+                OVERWRITE_ALL.store(true, Ordering::Relaxed);
+                return Resolution::Overwrite;
+            }
+            None => {}
+        }
+    }
+}
+
+/// Tell the user how much is about to be copied to another filesystem when
+/// `source` is a directory, so a long copy does not come as a surprise.
+/// Dry runs show the same notice for the copies they would make.
+pub fn announce_copy(lang: Option<&str>, source: &Path, dest: &Path, files: u64, bytes: u64) {
+    if !source.symlink_metadata().is_ok_and(|meta| meta.is_dir()) {
+        return;
+    }
+    let bundle = lang.map_or_else(i18n::init, i18n::init_with_locale);
+    let mut args = FluentArgs::new();
+    args.set("src", source.display().to_string());
+    args.set("dest", dest.display().to_string());
+    args.set("files", files);
+    args.set("size", format_size(bytes));
+    eprintln!(
+        "{}",
+        i18n::msg(&bundle, MessageKey::OpCrossDeviceCopy, Some(&args))
+    );
+}
+
+/// The directory of the last `--progress` line printed to a pipe, which
+/// only gets a new line when the copy moves on to another directory.
+static LAST_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Show how far the copy of a directory has got: on a terminal, by
+/// rewriting one line as the copy goes on; elsewhere, with a line for
+/// each directory the copy reaches.
+pub fn show_progress(lang: Option<&str>, progress: &CopyProgress<'_>) {
+    let dir = progress_dir(progress.source, progress.dir);
+    let tty = io::stderr().is_terminal();
+    if !tty {
+        let mut last = LAST_DIR.lock().unwrap_or_else(PoisonError::into_inner);
+        if progress.finished {
+            *last = None;
+        } else if last.as_ref() == Some(&dir) {
+            return;
+        } else {
+            *last = Some(dir.clone());
+        }
+    }
+    let bundle = lang.map_or_else(i18n::init, i18n::init_with_locale);
+    let mut args = FluentArgs::new();
+    args.set("dir", dir.display().to_string());
+    args.set("files", progress.files);
+    args.set("total", progress.total_files);
+    args.set("size", format_size(progress.bytes));
+    args.set("total_size", format_size(progress.total_bytes));
+    let line = i18n::msg(&bundle, MessageKey::OpCopyProgress, Some(&args));
+    if !tty {
+        eprintln!("{line}");
+    } else if progress.finished {
+        eprintln!("\r\x1b[2K{line}");
+    } else {
+        eprint!("\r\x1b[2K{line}");
+        let _ = io::stderr().flush();
+    }
+}
+
+/// The directory a progress line names: the first two levels of `dir`
+/// inside the tree, followed by `…` if it is deeper, or the name of the
+/// tree itself.
+fn progress_dir(source: &Path, dir: &Path) -> PathBuf {
+    let mut levels = dir
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)));
+    let shown: PathBuf = levels.by_ref().take(2).collect();
+    if shown.as_os_str().is_empty() {
+        return source
+            .file_name()
+            .map_or_else(|| source.to_path_buf(), PathBuf::from);
+    }
+    if levels.next().is_some() {
+        shown.join("…")
+    } else {
+        shown
+    }
+}
+
+/// Ask the user whether to copy `info.source` onto another filesystem,
+/// repeating the question until the answer is y or n. End of input
+/// declines, leaving the source where it is.
+///
+/// With `yes` the question is printed followed by `y`, as for conflicts.
+pub fn ask_cross_device(lang: Option<&str>, yes: bool, info: &CrossDeviceInfo<'_>) -> bool {
+    let bundle = lang.map_or_else(i18n::init, i18n::init_with_locale);
+    let mut args = FluentArgs::new();
+    args.set("src", info.source.display().to_string());
+    args.set("dest", info.dest.display().to_string());
+    args.set("size", format_size(info.bytes));
+    let question = i18n::msg(&bundle, MessageKey::PromptCrossDevice, Some(&args));
+    if yes {
+        eprintln!("{question} y");
+        return true;
+    }
+
+    let stdin = io::stdin();
+    let mut answer = String::new();
+    loop {
+        eprint!("{question} ");
+        let _ = io::stderr().flush();
+        answer.clear();
+        match stdin.lock().read_line(&mut answer) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {}
+        }
+        match answer.trim().chars().next().map(|c| c.to_ascii_lowercase()) {
+            Some('y') => return true,
+            Some('n') => return false,
+            _ => {}
+        }
+    }
+}
+
+/// Parse an answer by its first letter, e.g. `o` or `overwrite`.
+fn parse_answer(answer: &str) -> Option<Answer> {
+    let resolution = match answer.trim().chars().next()?.to_ascii_lowercase() {
+        'o' => Resolution::Overwrite,
+        's' => Resolution::Skip,
+        'b' => Resolution::Backup,
+        'r' => Resolution::Rename,
+        'a' => return Some(Answer::All),
+        'q' => Resolution::Abort,
+        _ => return None,
+    };
+    Some(Answer::Once(resolution))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_names_the_first_two_levels() {
+        let source = Path::new("/home/me/monorepo");
+        assert_eq!(
+            progress_dir(source, Path::new("node_modules/react/cjs")),
+            Path::new("node_modules/react/…")
+        );
+        assert_eq!(
+            progress_dir(source, Path::new("packages/web")),
+            Path::new("packages/web")
+        );
+        assert_eq!(progress_dir(source, Path::new("")), Path::new("monorepo"));
+    }
+
+    #[test]
+    fn parses_answers_by_first_letter() {
+        assert_eq!(
+            parse_answer("o\n"),
+            Some(Answer::Once(Resolution::Overwrite))
+        );
+        assert_eq!(
+            parse_answer("  Backup"),
+            Some(Answer::Once(Resolution::Backup))
+        );
+        assert_eq!(
+            parse_answer("rename"),
+            Some(Answer::Once(Resolution::Rename))
+        );
+        assert_eq!(parse_answer("all"), Some(Answer::All));
+        assert_eq!(parse_answer("q"), Some(Answer::Once(Resolution::Abort)));
+        assert_eq!(parse_answer("\n"), None);
+        assert_eq!(parse_answer("x"), None);
+        assert_eq!(parse_answer("b"), Some(Answer::Once(ACCEPT_CONFLICT)));
+    }
+}
@@ -0,0 +1,470 @@
+//! Finding and finishing moves cut short by a crash.
+//!
+//! Before each move the CLI appends a [`Begin`](Action::Begin) entry to the
+//! journal and, once the move ends, a [`Move`](Action::Move) or
+//! [`Failed`](Action::Failed) entry for the same path. A begun move without
+//! either was interrupted: by a crash, a power cut or a `kill -9`. [`pending`]
+//! finds those moves and tells from the disk how far each one got; [`finish`]
+//! completes them.
+//!
+//! A run that is still going also has begun moves without an ending, so the
+//! journal is only scanned while no other run is active. Every run holds an
+//! [`ActiveRun`] registration for as long as it may begin moves.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use mvln::journal::Journal;
+//! use mvln::recovery::{self, ActiveRun};
+//! use mvln::MoveOptions;
+//!
+//! let journal = Journal::open_default();
+//! let mut run = ActiveRun::start(&journal)?;
+//! if run.is_exclusive() {
+//!     for pending in recovery::pending(&journal)? {
+//!         recovery::finish(&pending, &journal, &MoveOptions::default())?;
+//!     }
+//! }
+//! run.share()?;
+//! // ... move files ...
+//! # Ok::<(), mvln::MvlnError>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::copy::STAGING_SUFFIX;
+use crate::error::{MvlnError, Result};
+use crate::hash::hash_file;
+use crate::journal::{Action, Entry, Journal};
+use crate::links::{read_symlink, resolve_target};
+use crate::operation::{link_only, move_and_link, MoveOptions};
+use crate::path_utils::normalize_path;
+
+/// Registration of a run that may begin moves, held until it ends.
+///
+/// Runs share the registration; [`ActiveRun::start`] only gets it
+/// exclusively when no other run is active, which is when the journal can
+/// be scanned for interrupted moves. The registration is released when the
+/// value is dropped.
+#[derive(Debug)]
+pub struct ActiveRun {
+    /// Open handle carrying the lock.
+    file: File,
+    /// Path of the lock file.
+    path: PathBuf,
+    /// Whether the lock is held exclusively.
+    exclusive: bool,
+}
+
+impl ActiveRun {
+    /// Register a run using `journal`.
+    ///
+    /// The registration is exclusive if no other run is active. Otherwise
+    /// it is shared, after waiting for a run that holds it exclusively to
+    /// [share](Self::share) it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::LockFailed`] if the lock file next to the
+    /// journal cannot be created or locked.
+    pub fn start(journal: &Journal) -> Result<Self> {
+        let mut path = journal.path().as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+        let error = |source| MvlnError::LockFailed {
+            path: path.clone(),
+            source,
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(error)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(error)?;
+
+        let exclusive = match file.try_lock() {
+            Ok(()) => true,
+            Err(TryLockError::WouldBlock) => {
+                file.lock_shared().map_err(error)?;
+                false
+            }
+            Err(TryLockError::Error(e)) => return Err(error(e)),
+        };
+        Ok(Self {
+            file,
+            path,
+            exclusive,
+        })
+    }
+
+    /// Whether no other run was active when this one started, so no begun
+    /// move in the journal belongs to a live run.
+    #[must_use]
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    /// Let other runs start. Call this after handling the pending moves and
+    /// before beginning new ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::LockFailed`] if the lock cannot be changed.
+    pub fn share(&mut self) -> Result<()> {
+        if !self.exclusive {
+            return Ok(());
+        }
+        let error = |source| MvlnError::LockFailed {
+            path: self.path.clone(),
+            source,
+        };
+        // A run starting in between finds the journal as it is now: every
+        // pending move handled and nothing new begun
+        self.file.unlock().map_err(error)?;
+        self.file.lock_shared().map_err(error)?;
+        self.exclusive = false;
+        Ok(())
+    }
+}
+
+/// How far an interrupted move got, judged from the disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// The symlink is in place; only the journal entry is missing.
+    Done,
+    /// The data is at the destination but nothing is at the source: the
+    /// symlink still has to be created.
+    Moved,
+    /// The source is intact and nothing is at the destination.
+    NotMoved,
+    /// Both the source and the destination exist: a copy across
+    /// filesystems was made, possibly in part, but the source not removed.
+    Copied,
+    /// Neither the source nor the destination exists.
+    Missing,
+}
+
+impl State {
+    /// Whether finishing the move only records it or creates the missing
+    /// symlink, which is safe to do without asking.
+    #[must_use]
+    pub fn is_automatic(self) -> bool {
+        matches!(self, Self::Done | Self::Moved)
+    }
+}
+
+/// A move that was begun but never ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pending {
+    /// The [`Begin`](Action::Begin) or [`BeginMoveOnly`](Action::BeginMoveOnly)
+    /// entry: the source as its link and the planned destination as its
+    /// target.
+    pub entry: Entry,
+    /// How far the move got.
+    pub state: State,
+}
+
+impl Pending {
+    /// Whether the move leaves a symlink (it was not begun with `--no-link`).
+    #[must_use]
+    pub fn links(&self) -> bool {
+        self.entry.action == Action::Begin
+    }
+}
+
+/// How [`finish`] completed a pending move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// The move had finished; it was only recorded.
+    Recorded,
+    /// The source was removed and the symlink created as needed.
+    Completed,
+    /// The move was run again, resuming a staged copy if there was one.
+    Redone,
+    /// Nothing was left to move; the move was recorded as failed.
+    Dropped,
+}
+
+/// Find the begun moves in `journal` that never ended, oldest first.
+///
+/// Only meaningful while no other run is active (see [`ActiveRun`]).
+///
+/// # Errors
+///
+/// Returns [`MvlnError::JournalError`] if the journal cannot be read.
+pub fn pending(journal: &Journal) -> Result<Vec<Pending>> {
+    let mut open: HashMap<PathBuf, (usize, Entry)> = HashMap::new();
+    for (index, entry) in journal.entries()?.into_iter().enumerate() {
+        match entry.action {
+            Action::Begin | Action::BeginMoveOnly => {
+                open.insert(entry.link.clone(), (index, entry));
+            }
+            Action::Move | Action::MoveOnly | Action::Failed => {
+                open.remove(&entry.link);
+            }
+            Action::Adopt | Action::Link => {}
+        }
+    }
+    let mut open: Vec<(usize, Entry)> = open.into_values().collect();
+    open.sort_by_key(|(index, _)| *index);
+    Ok(open
+        .into_iter()
+        .map(|(_, entry)| Pending {
+            state: state_of(&entry),
+            entry,
+        })
+        .collect())
+}
+
+/// Judge how far the move recorded in a begin `entry` got.
+fn state_of(entry: &Entry) -> State {
+    let source = match entry.link.symlink_metadata() {
+        Ok(meta) if meta.is_symlink() => read_symlink(&entry.link).is_ok_and(|stored| {
+            resolve_target(&entry.link, &stored) != normalize_path(&entry.target)
+        }),
+        Ok(_) => true,
+        Err(_) => false,
+    };
+    let dest = entry.target.symlink_metadata().is_ok();
+    match (source, dest) {
+        (false, true) if entry.link.is_symlink() => State::Done,
+        (false, true) => State::Moved,
+        (true, false) => State::NotMoved,
+        (true, true) => State::Copied,
+        (false, false) => State::Missing,
+    }
+}
+
+/// Complete a pending move and record it in `journal`.
+///
+/// Symlinks are created with `options`; existing destinations are never
+/// overwritten, except that a partial copy of a source directory is removed
+/// before the directory is moved again. With `options.dry_run`, only tells
+/// what would be done.
+///
+/// # Errors
+///
+/// Returns the error of the move or symlink creation, for example
+/// [`MvlnError::DestinationExists`] if a different file was left at the
+/// destination, or [`MvlnError::JournalError`] if the outcome cannot be
+/// recorded.
+pub fn finish(pending: &Pending, journal: &Journal, options: &MoveOptions) -> Result<Resolution> {
+    let Entry { link, target, .. } = &pending.entry;
+    let mut options = options.clone();
+    options.no_link = !pending.links();
+    options.force = false;
+    options.on_conflict = None;
+
+    let resolution = match pending.state {
+        State::Done => Resolution::Recorded,
+        State::Moved if !pending.links() => Resolution::Recorded,
+        State::Moved => {
+            link_only(link, target, &options)?;
+            Resolution::Completed
+        }
+        State::Missing => Resolution::Dropped,
+        State::Copied if same_contents(link, target) => {
+            if !options.dry_run {
+                fs::remove_file(link).map_err(|e| MvlnError::RemoveFailed {
+                    src: link.clone(),
+                    dest: target.clone(),
+                    source: e,
+                })?;
+                if pending.links() {
+                    link_only(link, target, &options)?;
+                }
+            }
+            Resolution::Completed
+        }
+        State::Copied | State::NotMoved => {
+            if !options.dry_run {
+                if pending.state == State::Copied && is_real_dir(link) && is_real_dir(target) {
+                    fs::remove_dir_all(target).map_err(|e| MvlnError::MoveFailed {
+                        src: link.clone(),
+                        dest: target.clone(),
+                        source: e,
+                    })?;
+                }
+                move_and_link(link, target, &options)?;
+            }
+            Resolution::Redone
+        }
+    };
+
+    if !options.dry_run {
+        let action = match resolution {
+            Resolution::Dropped => Action::Failed,
+            _ if pending.links() => Action::Move,
+            _ => Action::MoveOnly,
+        };
+        journal.append(&Entry::new(action, link, target))?;
+    }
+    Ok(resolution)
+}
+
+/// Whether `a` and `b` are regular files with the same contents.
+fn same_contents(a: &Path, b: &Path) -> bool {
+    let (Ok(meta_a), Ok(meta_b)) = (a.symlink_metadata(), b.symlink_metadata()) else {
+        return false;
+    };
+    if !meta_a.is_file() || !meta_b.is_file() || meta_a.len() != meta_b.len() {
+        return false;
+    }
+    matches!((hash_file(a), hash_file(b)), (Ok(x), Ok(y)) if x == y)
+}
+
+fn is_real_dir(path: &Path) -> bool {
+    path.symlink_metadata().is_ok_and(|meta| meta.is_dir())
+}
+
+/// A partial copy of a file left in a destination directory (see
+/// [`crate::copy`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagedCopy {
+    /// The staging file.
+    pub path: PathBuf,
+    /// The destination it is a partial copy of.
+    pub dest: PathBuf,
+}
+
+/// Find the staged copies directly inside `dir`.
+///
+/// Copying the same source to the same destination again resumes them.
+/// Unreadable directories yield nothing.
+#[must_use]
+pub fn staged_copies(dir: &Path) -> Vec<StagedCopy> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut staged: Vec<StagedCopy> = entries
+        .filter_map(io::Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let dest = name
+                .to_str()?
+                .strip_prefix('.')?
+                .strip_suffix(STAGING_SUFFIX)?;
+            (!dest.is_empty()).then(|| StagedCopy {
+                path: entry.path(),
+                dest: dir.join(dest),
+            })
+        })
+        .collect();
+    staged.sort_by(|a, b| a.path.cmp(&b.path));
+    staged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn begin(journal: &Journal, source: &Path, dest: &Path) {
+        journal
+            .append(&Entry::new(Action::Begin, source, dest))
+            .unwrap();
+    }
+
+    #[test]
+    fn finds_moves_without_an_ending() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let journal = Journal::open(tmp.path().join("journal"));
+        let (a, b) = (tmp.path().join("a"), tmp.path().join("b"));
+        begin(&journal, &a, &tmp.path().join("x/a"));
+        begin(&journal, &b, &tmp.path().join("x/b"));
+        journal
+            .append(&Entry::new(Action::Failed, &a, &tmp.path().join("x/a")))
+            .unwrap();
+
+        let pending = pending(&journal).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].entry.link, b);
+        assert_eq!(pending[0].state, State::Missing);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn classifies_and_finishes_by_disk_state() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let journal = Journal::open(tmp.path().join("journal"));
+        let (src, dest) = (tmp.path().join("src"), tmp.path().join("dest"));
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+
+        // Renamed but not linked
+        fs::write(dest.join("moved"), "m").unwrap();
+        begin(&journal, &src.join("moved"), &dest.join("moved"));
+        // Copied, source not removed
+        fs::write(src.join("copied"), "c").unwrap();
+        fs::write(dest.join("copied"), "c").unwrap();
+        begin(&journal, &src.join("copied"), &dest.join("copied"));
+        // Never started
+        fs::write(src.join("todo"), "t").unwrap();
+        begin(&journal, &src.join("todo"), &dest.join("todo"));
+
+        let found = pending(&journal).unwrap();
+        let states: Vec<State> = found.iter().map(|p| p.state).collect();
+        assert_eq!(states, [State::Moved, State::Copied, State::NotMoved]);
+
+        let options = MoveOptions::default();
+        let resolutions: Vec<Resolution> = found
+            .iter()
+            .map(|p| finish(p, &journal, &options).unwrap())
+            .collect();
+        assert_eq!(
+            resolutions,
+            [
+                Resolution::Completed,
+                Resolution::Completed,
+                Resolution::Redone
+            ]
+        );
+        for name in ["moved", "copied", "todo"] {
+            assert!(src.join(name).is_symlink(), "{name}");
+            assert!(dest.join(name).is_file(), "{name}");
+        }
+        assert!(pending(&journal).unwrap().is_empty());
+        assert_eq!(
+            journal
+                .find_link(&src.join("todo"))
+                .unwrap()
+                .unwrap()
+                .action,
+            Action::Move
+        );
+    }
+
+    #[test]
+    fn exclusive_only_when_alone() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let journal = Journal::open(tmp.path().join("state/journal"));
+        let mut first = ActiveRun::start(&journal).unwrap();
+        assert!(first.is_exclusive());
+        first.share().unwrap();
+        let second = ActiveRun::start(&journal).unwrap();
+        assert!(!second.is_exclusive());
+        drop((first, second));
+        assert!(ActiveRun::start(&journal).unwrap().is_exclusive());
+    }
+
+    #[test]
+    fn lists_staged_copies() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::write(tmp.path().join(".big.iso.mvln-tmp"), "").unwrap();
+        fs::write(tmp.path().join(".big.iso.mvln-tmp.state"), "").unwrap();
+        fs::write(tmp.path().join("other"), "").unwrap();
+        assert_eq!(
+            staged_copies(tmp.path()),
+            [StagedCopy {
+                path: tmp.path().join(".big.iso.mvln-tmp"),
+                dest: tmp.path().join("big.iso"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Moves to `user@host:path` destinations (the `ssh` feature).
+
+use fluent::FluentArgs;
+use mvln::error::{MvlnError, Result};
+use mvln::i18n::{self, MessageKey};
+use mvln::ssh::{move_to_remote, Placeholder, Remote};
+use std::path::PathBuf;
+
+use crate::cli::Cli;
+use crate::logfile::{self, Kind};
+use crate::{batch_result, print_summary, reject_directory, report, Bundle};
+
+/// Move `sources` to `remote` over SFTP, one at a time, leaving a stub, an
+/// sshfs symlink or (with `--no-link`) nothing in their place.
+pub fn run_remote(cli: &Cli, sources: &[PathBuf], remote: &Remote, bundle: &Bundle) -> Result<()> {
+    let placeholder = match (&cli.sshfs, cli.no_link) {
+        (Some(mount), _) => Placeholder::Sshfs(mount.clone()),
+        (None, true) => Placeholder::None,
+        (None, false) => Placeholder::Stub,
+    };
+    let mut errors = Vec::new();
+    let mut moved = 0;
+    let mut placeholders = 0;
+    let mut rejected = 0;
+    for source in sources {
+        // As for local moves, a directory needs -w
+        if !cli.whole_dir && source.symlink_metadata().is_ok_and(|m| m.is_dir()) {
+            errors.push(reject_directory(source, bundle));
+            rejected += 1;
+            continue;
+        }
+        if cli.dry_run {
+            print_move(&source.display().to_string(), &remote.to_string(), bundle);
+            continue;
+        }
+        if source.is_symlink() {
+            let error = MvlnError::RemoteFailed {
+                src: source.clone(),
+                dest: remote.to_string(),
+                reason: "symlinks are not moved to remote hosts".to_string(),
+            };
+            report(bundle, &error);
+            errors.push(error);
+            continue;
+        }
+        match move_to_remote(source, remote, &placeholder, cli.force) {
+            Ok(result) => {
+                moved += 1;
+                if cli.verbose {
+                    print_move(&result.source.display().to_string(), &result.url, bundle);
+                }
+                logfile::write(
+                    Kind::Action,
+                    &format!("{} -> {}", result.source.display(), result.url),
+                );
+                if let Some(path) = result.placeholder {
+                    placeholders += 1;
+                    logfile::write(Kind::Action, &format!("placeholder {}", path.display()));
+                }
+            }
+            Err(error) => {
+                if matches!(error, MvlnError::RemoteStubFailed { .. }) {
+                    moved += 1;
+                }
+                report(bundle, &error);
+                errors.push(error);
+            }
+        }
+    }
+
+    if cli.dry_run {
+        println!("{}", i18n::msg(bundle, MessageKey::OpDryRun, None));
+        return batch_result(&errors);
+    }
+    print_summary(
+        moved,
+        placeholders,
+        rejected,
+        errors.len() - rejected,
+        bundle,
+    );
+    batch_result(&errors)
+}
+
+/// Print that `src` is moved to `dest`.
+fn print_move(src: &str, dest: &str, bundle: &Bundle) {
+    let mut args = FluentArgs::new();
+    args.set("src", src.to_string());
+    args.set("dest", dest.to_string());
+    println!("{}", i18n::msg(bundle, MessageKey::OpMoving, Some(&args)));
+}
@@ -0,0 +1,354 @@
+//! `--review`: pick the planned moves in a terminal UI.
+//!
+//! The plan is shown as a tree of destination directories with the moves
+//! into each below it. Every move starts selected; the user toggles moves
+//! (or whole directories) off and then applies the rest. The UI draws on
+//! stderr, so stdout can still be redirected to capture the echoed commands.
+
+use std::io::{self, Stderr};
+
+use fluent::FluentArgs;
+use mvln::error::Result;
+use mvln::i18n::{self, MessageKey};
+use mvln::plan::{MovePlan, PlannedMove};
+use mvln::size::{disk_usage, format_size};
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+
+use crate::Bundle;
+
+/// A planned move and whether it is still selected.
+struct Entry {
+    planned: PlannedMove,
+    size: u64,
+    selected: bool,
+}
+
+/// A line of the tree.
+enum Row {
+    /// A destination directory and the indices of the moves into it.
+    Dir(String, Vec<usize>),
+    /// A move, by index.
+    Move(usize),
+}
+
+/// What a key press asks for.
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Continue,
+    Apply,
+    Cancel,
+}
+
+/// The state of the review screen.
+struct Review {
+    entries: Vec<Entry>,
+    rows: Vec<Row>,
+    state: ListState,
+}
+
+impl Review {
+    fn new(plan: &MovePlan) -> Self {
+        let entries: Vec<Entry> = plan
+            .iter()
+            .map(|planned| Entry {
+                planned: planned.clone(),
+                size: disk_usage(&planned.source).unwrap_or(0),
+                selected: true,
+            })
+            .collect();
+
+        // Group by final directory, in order of first appearance
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let dir = final_dir(&entry.planned);
+            match groups.iter_mut().find(|(d, _)| *d == dir) {
+                Some((_, members)) => members.push(index),
+                None => groups.push((dir, vec![index])),
+            }
+        }
+        let mut rows = Vec::new();
+        for (dir, members) in groups {
+            rows.extend(members.iter().map(|&index| Row::Move(index)));
+            let at = rows.len() - members.len();
+            rows.insert(at, Row::Dir(dir, members));
+        }
+
+        Self {
+            entries,
+            rows,
+            state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    fn handle(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Outcome {
+        match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                return Outcome::Cancel;
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+            KeyCode::Home | KeyCode::Char('g') => self.state.select_first(),
+            KeyCode::End | KeyCode::Char('G') => self.state.select_last(),
+            KeyCode::Char(' ') => self.toggle_current(),
+            KeyCode::Char('a') => {
+                let all = self.entries.iter().all(|e| e.selected);
+                for entry in &mut self.entries {
+                    entry.selected = !all;
+                }
+            }
+            KeyCode::Enter => return Outcome::Apply,
+            KeyCode::Esc | KeyCode::Char('q') => return Outcome::Cancel,
+            _ => {}
+        }
+        Outcome::Continue
+    }
+
+    /// Toggle the move under the cursor, or every move in its directory.
+    fn toggle_current(&mut self) {
+        let Some(row) = self.state.selected().and_then(|i| self.rows.get(i)) else {
+            return;
+        };
+        match row {
+            Row::Move(index) => {
+                let entry = &mut self.entries[*index];
+                entry.selected = !entry.selected;
+            }
+            Row::Dir(_, members) => {
+                let all = members.iter().all(|&i| self.entries[i].selected);
+                for &index in members {
+                    self.entries[index].selected = !all;
+                }
+            }
+        }
+    }
+
+    /// The selected moves, in plan order.
+    fn selected(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|e| e.selected)
+    }
+
+    fn render(&mut self, frame: &mut Frame<'_>, bundle: &Bundle) {
+        let [list_area, status_area, help_area] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let items: Vec<ListItem<'_>> = self
+            .rows
+            .iter()
+            .map(|row| ListItem::new(self.row_line(row)))
+            .collect();
+        let title = i18n::simple_msg(bundle, MessageKey::ReviewTitle);
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.state);
+
+        let mut args = FluentArgs::new();
+        args.set("selected", self.selected().count());
+        args.set("total", self.entries.len());
+        args.set("size", format_size(self.selected().map(|e| e.size).sum()));
+        // Bidi isolation marks would take up cells of their own
+        let status = i18n::msg(bundle, MessageKey::ReviewSelected, Some(&args))
+            .replace(['\u{2068}', '\u{2069}'], "");
+        frame.render_widget(Paragraph::new(status), status_area);
+        let help = i18n::simple_msg(bundle, MessageKey::ReviewHelp);
+        frame.render_widget(Paragraph::new(help), help_area);
+    }
+
+    fn row_line(&self, row: &Row) -> Line<'static> {
+        match row {
+            Row::Dir(dir, members) => {
+                let selected = members.iter().filter(|&&i| self.entries[i].selected);
+                let mark = match selected.count() {
+                    0 => "[ ]",
+                    n if n == members.len() => "[x]",
+                    _ => "[-]",
+                };
+                let size = members.iter().map(|&i| self.entries[i].size).sum();
+                Line::from(format!("{mark} {dir}/  ({})", format_size(size)))
+            }
+            Row::Move(index) => {
+                let entry = &self.entries[*index];
+                let mark = if entry.selected { "[x]" } else { "[ ]" };
+                Line::from(format!(
+                    "    {mark} {}  ({})",
+                    entry.planned.source.display(),
+                    format_size(entry.size)
+                ))
+            }
+        }
+    }
+}
+
+/// Directory a planned move ends up in.
+fn final_dir(planned: &PlannedMove) -> String {
+    let dir = if planned.dest.is_dir() {
+        planned.dest.as_path()
+    } else {
+        planned
+            .dest
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("."))
+    };
+    dir.display().to_string()
+}
+
+/// Restores the terminal when dropped, also on errors.
+struct TerminalGuard(Terminal<CrosstermBackend<Stderr>>);
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        enable_raw_mode()?;
+        let mut stderr = io::stderr();
+        if let Err(e) = execute!(stderr, EnterAlternateScreen) {
+            let _ = disable_raw_mode();
+            return Err(e);
+        }
+        Ok(Self(Terminal::new(CrosstermBackend::new(stderr))?))
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.0.backend_mut(), LeaveAlternateScreen);
+        let _ = self.0.show_cursor();
+    }
+}
+
+/// Let the user deselect moves from `plan`, which is cut down to the
+/// selected ones. Returns `false` if the user cancelled the run.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::Io`](mvln::MvlnError::Io) if the terminal cannot be
+/// set up or read.
+pub fn review(plan: &mut MovePlan, bundle: &Bundle) -> Result<bool> {
+    let mut review = Review::new(plan);
+    let mut terminal = TerminalGuard::new()?;
+    loop {
+        terminal.0.draw(|frame| review.render(frame, bundle))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match review.handle(key.code, key.modifiers) {
+            Outcome::Continue => {}
+            Outcome::Apply => break,
+            Outcome::Cancel => return Ok(false),
+        }
+    }
+    drop(terminal);
+
+    plan.moves = review.selected().map(|e| e.planned.clone()).collect();
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    fn sample() -> (tempfile::TempDir, Review) {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut plan = MovePlan::new();
+        for (name, dir) in [("a.pdf", "pdf"), ("b.jpg", "jpg"), ("c.pdf", "pdf")] {
+            let source = tmp.path().join(name);
+            std::fs::write(&source, vec![0u8; 10]).unwrap();
+            plan.push(&source, tmp.path().join("dest").join(dir).join(name));
+        }
+        (tmp, Review::new(&plan))
+    }
+
+    fn selected_names(review: &Review) -> Vec<String> {
+        review
+            .selected()
+            .map(|e| {
+                e.planned
+                    .source
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn groups_moves_by_destination_directory() {
+        let (_tmp, review) = sample();
+        let shape: Vec<_> = review
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Dir(dir, _) => format!("dir {}", dir.rsplit('/').next().unwrap()),
+                Row::Move(index) => format!("move {index}"),
+            })
+            .collect();
+        assert_eq!(shape, ["dir pdf", "move 0", "move 2", "dir jpg", "move 1"]);
+    }
+
+    #[test]
+    fn toggles_moves_and_directories() {
+        let (_tmp, mut review) = sample();
+        let none = KeyModifiers::NONE;
+
+        // Deselect the pdf directory, then re-select c.pdf alone
+        assert_eq!(review.handle(KeyCode::Char(' '), none), Outcome::Continue);
+        assert_eq!(selected_names(&review), ["b.jpg"]);
+        review.handle(KeyCode::Down, none);
+        review.handle(KeyCode::Down, none);
+        review.handle(KeyCode::Char(' '), none);
+        assert_eq!(selected_names(&review), ["b.jpg", "c.pdf"]);
+
+        review.handle(KeyCode::Char('a'), none);
+        assert_eq!(selected_names(&review), ["a.pdf", "b.jpg", "c.pdf"]);
+        review.handle(KeyCode::Char('a'), none);
+        assert!(selected_names(&review).is_empty());
+
+        assert_eq!(review.handle(KeyCode::Enter, none), Outcome::Apply);
+        assert_eq!(review.handle(KeyCode::Char('q'), none), Outcome::Cancel);
+        assert_eq!(
+            review.handle(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Outcome::Cancel
+        );
+    }
+
+    #[test]
+    fn renders_marks_and_totals() {
+        let (_tmp, mut review) = sample();
+        review.handle(KeyCode::Down, KeyModifiers::NONE);
+        review.handle(KeyCode::Char(' '), KeyModifiers::NONE);
+
+        let bundle = i18n::init_with_locale("en-US");
+        let mut terminal = Terminal::new(TestBackend::new(80, 10)).unwrap();
+        terminal
+            .draw(|frame| review.render(frame, &bundle))
+            .unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect();
+        assert!(screen.contains("[-] "));
+        assert!(screen.contains("[ ] "));
+        assert!(screen.contains("2 of 3 selected"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Pattern-to-destination routing rules.
+//!
+//! Rules map glob patterns to destination subdirectories and are read from
+//! the `[rules]` table of the configuration file:
+//!
+//! ```toml
+//! [rules]
+//! "*.iso" = "images/"
+//! "*.log" = "logs/{YYYY}/"
+//! ```
+//!
+//! Rules are tried in file order and the first match wins. Templates may use
+//! `{YYYY}`, `{MM}` and `{DD}` (from the file's modification time, in UTC)
+//! and `{ext}` (the lowercased extension, or `noext`).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::{Config, Value};
+use crate::date::DateTime;
+use crate::error::{MvlnError, Result};
+use crate::plan::MovePlan;
+
+/// A single routing rule.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// Pattern matched against the file name.
+    pub pattern: glob::Pattern,
+    /// Destination subdirectory template.
+    pub template: String,
+}
+
+/// An ordered set of routing rules.
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    /// Rules in priority order.
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Read the `[rules]` table from a configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::ConfigError`] if a pattern is invalid or a
+    /// destination is not a string.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let error = |reason: String| MvlnError::ConfigError {
+            path: config.path.clone().unwrap_or_default(),
+            reason: format!("rules: {reason}"),
+        };
+
+        let table = match config.root.get("rules") {
+            None => return Ok(Self::default()),
+            Some(Value::Table(t)) => t,
+            Some(_) => return Err(error("'rules' must be a table".to_string())),
+        };
+
+        let mut rules = Vec::new();
+        for (pattern, value) in table.iter() {
+            let Value::String(template) = value else {
+                return Err(error(format!(
+                    "destination for '{pattern}' must be a string"
+                )));
+            };
+            let pattern = glob::Pattern::new(pattern)
+                .map_err(|e| error(format!("invalid pattern '{pattern}': {e}")))?;
+            rules.push(Rule {
+                pattern,
+                template: template.clone(),
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    /// Whether no rules are defined.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Find the destination subdirectory for `path`, if any rule matches.
+    #[must_use]
+    pub fn route(&self, path: &Path) -> Option<PathBuf> {
+        let name = path.file_name()?.to_string_lossy();
+        let rule = self.rules.iter().find(|r| r.pattern.matches(&name))?;
+        Some(PathBuf::from(expand_template(&rule.template, path)))
+    }
+}
+
+/// Expand `{YYYY}`, `{MM}`, `{DD}` and `{ext}` placeholders for `path`.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::rules::expand_template;
+/// use std::path::Path;
+///
+/// assert_eq!(expand_template("docs/{ext}/", Path::new("a.PDF")), "docs/pdf/");
+/// ```
+#[must_use]
+pub fn expand_template(template: &str, path: &Path) -> String {
+    let mut out = template.replace("{ext}", &extension_dir(path));
+    if out.contains("{YYYY}") || out.contains("{MM}") || out.contains("{DD}") {
+        let date = DateTime::from_system_time(modified_time(path));
+        out = out
+            .replace("{YYYY}", &date.format("%Y"))
+            .replace("{MM}", &date.format("%m"))
+            .replace("{DD}", &date.format("%d"));
+    }
+    out
+}
+
+/// Folder name for a file's extension: lowercased, or `noext` if it has none.
+#[must_use]
+pub fn extension_dir(path: &Path) -> String {
+    path.extension().map_or_else(
+        || "noext".to_string(),
+        |ext| ext.to_string_lossy().to_lowercase(),
+    )
+}
+
+/// Modification time of `path` (not following symlinks), or now if unknown.
+#[must_use]
+pub fn modified_time(path: &Path) -> SystemTime {
+    path.symlink_metadata()
+        .and_then(|m| m.modified())
+        .unwrap_or_else(|_| SystemTime::now())
+}
+
+/// Plan moves for every top-level file in `src_dir` matched by `rules`.
+///
+/// Matched files are routed to `dest_root/<rule destination>/<file name>`.
+/// Directories, symlinks (typically files organized by an earlier run) and
+/// unmatched files are left alone. Entries are planned in name order.
+///
+/// # Errors
+///
+/// Returns an error if `src_dir` cannot be read.
+pub fn organize(src_dir: &Path, dest_root: &Path, rules: &RuleSet) -> Result<MovePlan> {
+    let read_error = |e: std::io::Error| MvlnError::SourceAccessError {
+        path: src_dir.to_path_buf(),
+        source: e,
+    };
+
+    let mut entries = fs::read_dir(src_dir)
+        .map_err(read_error)?
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(read_error)?;
+    entries.sort_by_key(fs::DirEntry::file_name);
+
+    let mut plan = MovePlan::new();
+    for entry in entries {
+        let is_file = entry.file_type().is_ok_and(|t| t.is_file());
+        if !is_file {
+            continue;
+        }
+        let path = entry.path();
+        if let Some(subdir) = rules.route(&path) {
+            plan.push(&path, dest_root.join(subdir).join(entry.file_name()));
+        }
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(text: &str) -> RuleSet {
+        RuleSet::from_config(&Config::parse_str(text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = rules("[rules]\n\"*.iso\" = \"images/\"\n\"*\" = \"misc/\"\n");
+        assert_eq!(
+            rules.route(Path::new("/x/a.iso")),
+            Some(PathBuf::from("images/"))
+        );
+        assert_eq!(
+            rules.route(Path::new("/x/a.txt")),
+            Some(PathBuf::from("misc/"))
+        );
+    }
+
+    #[test]
+    fn unmatched_file_is_not_routed() {
+        let rules = rules("[rules]\n\"*.iso\" = \"images/\"\n");
+        assert_eq!(rules.route(Path::new("a.txt")), None);
+    }
+
+    #[test]
+    fn missing_rules_table_is_empty() {
+        assert!(rules("[profile.a]\n").is_empty());
+    }
+
+    #[test]
+    fn invalid_pattern_is_rejected() {
+        let config = Config::parse_str("[rules]\n\"[abc\" = \"x/\"\n").unwrap();
+        assert!(RuleSet::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn template_uses_modification_date() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let file = tmp.path().join("app.log");
+        fs::write(&file, "log").unwrap();
+        let expected = DateTime::from_system_time(modified_time(&file)).format("logs/%Y/%m/");
+
+        assert_eq!(expand_template("logs/{YYYY}/{MM}/", &file), expected);
+    }
+
+    #[test]
+    fn organize_plans_only_matching_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::write(tmp.path().join("b.iso"), "").unwrap();
+        fs::write(tmp.path().join("a.txt"), "").unwrap();
+        fs::create_dir(tmp.path().join("sub.iso")).unwrap();
+
+        let rules = rules("[rules]\n\"*.iso\" = \"images\"\n");
+        let plan = organize(tmp.path(), Path::new("/archive"), &rules).unwrap();
+
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan.moves[0].source, tmp.path().join("b.iso"));
+        assert_eq!(plan.moves[0].dest, PathBuf::from("/archive/images/b.iso"));
+    }
+}
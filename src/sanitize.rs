@@ -0,0 +1,193 @@
+//! `--sanitize`: names the destination filesystem would reject.
+//!
+//! FAT, exFAT and NTFS (as used by Windows) do not allow `"*:<>?\|` or
+//! control characters in names, nor names ending in a space or dot, nor
+//! device names such as `CON` or `lpt1.txt`. Offloading a Unix tree to
+//! such a drive would fail file by file; a [`Sanitizer`] instead gives each
+//! offending name a legal spelling by putting its replacement in place of
+//! every bad character (and after a device name's stem).
+//!
+//! Names are rewritten for the moved item itself and, when a directory is
+//! copied across filesystems, for everything inside it. The changes are
+//! listed in [`MoveResult::sanitized`](crate::operation::MoveResult::sanitized).
+
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::str::FromStr;
+
+/// Characters FAT, exFAT and NTFS reject besides `/` and control
+/// characters.
+const ILLEGAL: &str = "\"*:<>?\\|";
+
+/// Names Windows reserves for devices, with or without an extension.
+const RESERVED: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Rewrites names that are illegal on FAT, exFAT and NTFS.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::sanitize::Sanitizer;
+/// use std::ffi::OsStr;
+///
+/// let sanitizer = Sanitizer::default();
+/// assert_eq!(sanitizer.name(OsStr::new("10:30 notes?.txt")), OsStr::new("10_30 notes_.txt"));
+/// assert_eq!(sanitizer.name(OsStr::new("aux.c")), OsStr::new("aux_.c"));
+///
+/// let sanitizer: Sanitizer = "-".parse().unwrap();
+/// assert_eq!(sanitizer.name(OsStr::new("a|b.")), OsStr::new("a-b-"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+pub struct Sanitizer {
+    replacement: String,
+}
+
+impl Default for Sanitizer {
+    /// Replace with `_`.
+    fn default() -> Self {
+        Self {
+            replacement: "_".to_string(),
+        }
+    }
+}
+
+impl Sanitizer {
+    /// What bad characters are replaced with.
+    #[must_use]
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// `name` with every illegal character, and each trailing space or
+    /// dot, replaced. Names that are not valid UTF-8 are converted lossily
+    /// first, since these filesystems store UTF-16.
+    #[must_use]
+    pub fn name<'a>(&self, name: &'a OsStr) -> Cow<'a, OsStr> {
+        let text = name.to_string_lossy();
+        let kept = text.trim_end_matches([' ', '.']);
+        let mut fixed = String::with_capacity(text.len());
+        for c in kept.chars() {
+            if c.is_control() || c == '/' || ILLEGAL.contains(c) {
+                fixed.push_str(&self.replacement);
+            } else {
+                fixed.push(c);
+            }
+        }
+        for _ in kept.len()..text.len() {
+            fixed.push_str(&self.replacement);
+        }
+        let stem_len = fixed.find('.').unwrap_or(fixed.len());
+        if RESERVED
+            .iter()
+            .any(|device| fixed[..stem_len].eq_ignore_ascii_case(device))
+        {
+            fixed.insert_str(stem_len, &self.replacement);
+        }
+
+        if name == fixed.as_str() {
+            Cow::Borrowed(name)
+        } else {
+            Cow::Owned(OsString::from(fixed))
+        }
+    }
+}
+
+impl FromStr for Sanitizer {
+    type Err = String;
+
+    /// Use `s` as the replacement, which must be legal itself.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty()
+            || s.chars()
+                .any(|c| c.is_control() || c == '/' || ILLEGAL.contains(c))
+        {
+            return Err(format!(
+                "invalid replacement {s:?}: must be non-empty and not contain / {ILLEGAL} or control characters"
+            ));
+        }
+        Ok(Self {
+            replacement: s.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Sanitizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.replacement)
+    }
+}
+
+impl From<Sanitizer> for String {
+    fn from(sanitizer: Sanitizer) -> Self {
+        sanitizer.replacement
+    }
+}
+
+impl TryFrom<String> for Sanitizer {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitized(name: &str) -> String {
+        Sanitizer::default()
+            .name(OsStr::new(name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn replaces_illegal_characters() {
+        assert_eq!(sanitized("a\"b*c:d<e>f?g\\h|i"), "a_b_c_d_e_f_g_h_i");
+        assert_eq!(sanitized("tab\there"), "tab_here");
+        assert_eq!(sanitized("draft. "), "draft__");
+        assert_eq!(sanitized("fine name.txt"), "fine name.txt");
+        assert!(matches!(
+            Sanitizer::default().name(OsStr::new("fine")),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn renames_device_names() {
+        assert_eq!(sanitized("CON"), "CON_");
+        assert_eq!(sanitized("com1.log"), "com1_.log");
+        assert_eq!(sanitized("console.log"), "console.log");
+        assert_eq!(sanitized("nul."), "nul_");
+    }
+
+    #[test]
+    fn validates_the_replacement() {
+        assert_eq!("~".parse::<Sanitizer>().unwrap().replacement(), "~");
+        for bad in ["", ":", "a/b", "\n"] {
+            assert!(bad.parse::<Sanitizer>().is_err(), "{bad:?}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn converts_invalid_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = OsStr::from_bytes(b"caf\xe9.txt");
+        assert_eq!(
+            Sanitizer::default().name(name),
+            OsStr::new("caf\u{fffd}.txt")
+        );
+    }
+}
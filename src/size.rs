@@ -0,0 +1,234 @@
+//! File sizes: parsing, formatting and size-based selection.
+
+use std::path::Path;
+
+use walkdir::WalkDir;
+
+/// Parse a human-readable size such as `1G`, `500M`, `64KiB` or `1.5GB`.
+///
+/// Suffixes are case-insensitive. `K`, `M`, `G`, `T` and `KiB`..`TiB` are
+/// powers of 1024; `KB`, `MB`, `GB` and `TB` are powers of 1000 (as in GNU
+/// coreutils). A bare number or a `B` suffix means bytes.
+///
+/// # Errors
+///
+/// Returns a description of the problem if `text` is not a valid size.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::size::parse_size;
+///
+/// assert_eq!(parse_size("1G"), Ok(1 << 30));
+/// assert_eq!(parse_size("1.5KB"), Ok(1500));
+/// assert_eq!(parse_size("42"), Ok(42));
+/// ```
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, suffix) = text.split_at(split);
+    if number.is_empty() {
+        return Err(format!("invalid size {text:?}: expected a number"));
+    }
+
+    let multiplier: u64 = match suffix.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        other => return Err(format!("invalid size {text:?}: unknown unit {other:?}")),
+    };
+
+    if let Ok(whole) = number.parse::<u64>() {
+        return whole
+            .checked_mul(multiplier)
+            .ok_or_else(|| format!("invalid size {text:?}: too large"));
+    }
+    let fractional: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size {text:?}: expected a number"))?;
+    scale(fractional, multiplier).ok_or_else(|| format!("invalid size {text:?}: too large"))
+}
+
+/// Multiply a fractional count by a unit, rounding to whole bytes.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn scale(value: f64, multiplier: u64) -> Option<u64> {
+    let bytes = (value * multiplier as f64).round();
+    (bytes >= 0.0 && bytes < 2f64.powi(64)).then_some(bytes as u64)
+}
+
+/// Format a byte count for humans, e.g. `1.5 GiB`.
+#[must_use]
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut unit = 0;
+    let mut scaled = bytes;
+    while scaled >= 1024 && unit < UNITS.len() - 1 {
+        scaled /= 1024;
+        unit += 1;
+    }
+    if unit == 0 {
+        return format!("{bytes} B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let value = bytes as f64 / (1u64 << (10 * unit)) as f64;
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Total apparent size of `path` in bytes.
+///
+/// Directories are summed recursively; symlinks count as their own size and
+/// are not followed. Entries that cannot be read are skipped.
+///
+/// # Errors
+///
+/// Returns an error if `path` itself cannot be examined.
+pub fn disk_usage<P: AsRef<Path>>(path: P) -> std::io::Result<u64> {
+    tree_size(path).map(|size| size.bytes)
+}
+
+/// Size and number of files of a tree, as counted by [`tree_size`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeSize {
+    /// Total apparent size, in bytes.
+    pub bytes: u64,
+    /// Files and symlinks, not counting directories.
+    pub files: u64,
+}
+
+/// Total apparent size of `path` and the number of files in it, from one
+/// walk of the tree.
+///
+/// Counted as by [`disk_usage`]; a `path` that is not a directory is one
+/// file.
+///
+/// # Errors
+///
+/// Returns an error if `path` itself cannot be examined.
+pub fn tree_size<P: AsRef<Path>>(path: P) -> std::io::Result<TreeSize> {
+    let meta = path.as_ref().symlink_metadata()?;
+    if !meta.is_dir() {
+        return Ok(TreeSize {
+            bytes: meta.len(),
+            files: 1,
+        });
+    }
+    // Streamed, so huge directories don't need their paths in memory
+    let total = WalkDir::new(path)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(std::result::Result::ok)
+        .filter(|entry| !entry.file_type().is_dir())
+        .filter_map(|entry| entry.metadata().ok())
+        .fold(TreeSize::default(), |total, meta| TreeSize {
+            bytes: total.bytes.saturating_add(meta.len()),
+            files: total.files + 1,
+        });
+    Ok(total)
+}
+
+/// Size bounds for selecting sources (`--larger-than` / `--smaller-than`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeFilter {
+    /// Select only sources strictly larger than this many bytes.
+    pub larger_than: Option<u64>,
+    /// Select only sources strictly smaller than this many bytes.
+    pub smaller_than: Option<u64>,
+}
+
+impl SizeFilter {
+    /// Whether no bound is set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.larger_than.is_none() && self.smaller_than.is_none()
+    }
+
+    /// Whether a size lies within the bounds.
+    #[must_use]
+    pub fn matches(&self, size: u64) -> bool {
+        self.larger_than.is_none_or(|min| size > min)
+            && self.smaller_than.is_none_or(|max| size < max)
+    }
+
+    /// Whether the source at `path` lies within the bounds.
+    ///
+    /// Directories are measured by their total size. Paths whose size
+    /// cannot be determined are kept, so that moving them reports the
+    /// underlying problem instead of silently skipping them.
+    #[must_use]
+    pub fn matches_path(&self, path: &Path) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        disk_usage(path).map_or(true, |size| self.matches(size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(parse_size("10k"), Ok(10 * 1024));
+        assert_eq!(parse_size("2MiB"), Ok(2 << 20));
+        assert_eq!(parse_size("3 MB"), Ok(3_000_000));
+        assert_eq!(parse_size("0.5G"), Ok(1 << 29));
+        assert_eq!(parse_size("7B"), Ok(7));
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("G").is_err());
+        assert!(parse_size("10 parsecs").is_err());
+        assert!(parse_size("99999999999T").is_err());
+    }
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 << 30), "3.0 GiB");
+    }
+
+    #[test]
+    fn filter_bounds_are_exclusive() {
+        let filter = SizeFilter {
+            larger_than: Some(10),
+            smaller_than: Some(20),
+        };
+        assert!(!filter.matches(10));
+        assert!(filter.matches(11));
+        assert!(!filter.matches(20));
+    }
+
+    #[test]
+    fn directory_size_is_summed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::create_dir(tmp.path().join("sub")).unwrap();
+        fs::write(tmp.path().join("a"), [0u8; 100]).unwrap();
+        fs::write(tmp.path().join("sub/b"), [0u8; 50]).unwrap();
+        assert_eq!(disk_usage(tmp.path()).unwrap(), 150);
+        assert_eq!(
+            tree_size(tmp.path()).unwrap(),
+            TreeSize {
+                bytes: 150,
+                files: 2
+            }
+        );
+    }
+}
@@ -0,0 +1,512 @@
+//! Destinations on other machines, for `user@host:/path`.
+//!
+//! [`move_to_remote`] moves a file or directory into a directory on an SSH
+//! server, taking mvln from "another disk" to "another machine":
+//!
+//! 1. The source is uploaded with `sftp` (times and modes preserved) under
+//!    a temporary name next to its destination.
+//! 2. The upload is verified: the SHA-256 of every file, computed on the
+//!    server with `sha256sum`, must match the local one.
+//! 3. The upload is renamed into place and the local source removed.
+//! 4. A [`Placeholder`] takes the place of the source: a `NAME.url` stub
+//!    holding the `sftp://` URL of the data, or a symlink into a local
+//!    sshfs mount of the destination.
+//!
+//! If anything fails before step 3, the temporary upload is removed and
+//! the source is left alone.
+//!
+//! The system's `ssh` and `sftp` commands do the work, so hosts, ports,
+//! keys and jump hosts come from `~/.ssh/config`. They run in batch mode:
+//! authentication must not need a password prompt (use keys or an agent).
+//! The server needs a POSIX shell, `find` and `sha256sum`.
+//!
+//! Requires the `ssh` feature.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use walkdir::WalkDir;
+
+use crate::error::{MvlnError, Result};
+use crate::hash::{hash_file, Digest};
+use crate::operation::SymlinkStyle;
+use crate::path_utils::compute_symlink_target;
+
+/// Suffix of the temporary name an upload has until it is verified.
+pub const PARTIAL_SUFFIX: &str = ".mvln-partial";
+
+/// A directory on an SSH server, written `[user@]host:path` as for `scp`.
+///
+/// A relative `path` (or none) is relative to the user's home directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remote {
+    /// The user to log in as, if not the default of `~/.ssh/config`.
+    pub user: Option<String>,
+    /// The host name or `~/.ssh/config` alias.
+    pub host: String,
+    /// The directory on the server.
+    pub path: String,
+}
+
+impl Remote {
+    /// Parse a destination written `[user@]host:path`.
+    ///
+    /// `None` for local paths: those with a `/` before the first `:`, and
+    /// Windows drive letters such as `C:\`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mvln::ssh::Remote;
+    ///
+    /// let remote = Remote::parse("me@nas:/srv/archive").unwrap();
+    /// assert_eq!(remote.user.as_deref(), Some("me"));
+    /// assert_eq!(remote.host, "nas");
+    /// assert_eq!(remote.path, "/srv/archive");
+    ///
+    /// assert_eq!(Remote::parse("./a:b"), None);
+    /// assert_eq!(Remote::parse("C:\\Users"), None);
+    /// ```
+    #[must_use]
+    pub fn parse(dest: &str) -> Option<Self> {
+        let (login, path) = dest.split_once(':')?;
+        if login.contains(['/', '\\']) || login.len() < 2 {
+            return None;
+        }
+        let (user, host) = match login.rsplit_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host),
+            None => (None, login),
+        };
+        if host.is_empty() || host.starts_with('-') || user.as_deref() == Some("") {
+            return None;
+        }
+        Some(Self {
+            user,
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    /// `user@host`, or `host`, as `ssh` takes it.
+    #[must_use]
+    pub fn login(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{user}@{}", self.host),
+            None => self.host.clone(),
+        }
+    }
+
+    /// The path on the server of `name` in this directory.
+    #[must_use]
+    pub fn join(&self, name: &str) -> String {
+        match self.dir() {
+            "" => name.to_string(),
+            dir if dir.ends_with('/') => format!("{dir}{name}"),
+            dir => format!("{dir}/{name}"),
+        }
+    }
+
+    /// The `sftp://` URL of `path` on the server. Paths relative to the
+    /// home directory start with `/~/`.
+    #[must_use]
+    pub fn url(&self, path: &str) -> String {
+        let path = path.strip_prefix('/').map_or_else(
+            || format!("~/{}", path.trim_start_matches("~/")),
+            str::to_string,
+        );
+        let path: String = path
+            .bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                    char::from(b).to_string()
+                }
+                _ => format!("%{b:02X}"),
+            })
+            .collect();
+        format!("sftp://{}/{path}", self.login())
+    }
+
+    /// The directory, with `.` standing for the home directory.
+    fn path_or_home(&self) -> &str {
+        match self.dir() {
+            "" => ".",
+            dir => dir,
+        }
+    }
+
+    /// The directory with a leading `~/` left out: commands run in the
+    /// home directory, and quoting them would keep the shell from
+    /// expanding it.
+    fn dir(&self) -> &str {
+        match self.path.as_str() {
+            "~" => "",
+            path => path.strip_prefix("~/").unwrap_or(path),
+        }
+    }
+}
+
+impl fmt::Display for Remote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.login(), self.path)
+    }
+}
+
+/// What takes the place of a source moved to another machine.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Placeholder {
+    /// A `NAME.url` file next to where the source was, holding the
+    /// `sftp://` URL of the data as an Internet shortcut, which file
+    /// managers open.
+    #[default]
+    Stub,
+    /// A symlink into this local sshfs mount of the destination directory,
+    /// through which the data stays reachable at a path.
+    Sshfs(PathBuf),
+    /// Nothing, like a plain `mv`.
+    None,
+}
+
+/// The result of a successful [`move_to_remote`].
+#[derive(Debug, Clone)]
+pub struct RemoteResult {
+    /// The source that was moved.
+    pub source: PathBuf,
+    /// Where the data now is, on the server.
+    pub dest: String,
+    /// The `sftp://` URL of the data.
+    pub url: String,
+    /// The stub or symlink left at the source, if any.
+    pub placeholder: Option<PathBuf>,
+}
+
+/// Move `source` into the directory `remote`, leaving `placeholder` in
+/// its place (see the [module documentation](self)). An existing
+/// destination is replaced only with `force`.
+///
+/// # Errors
+///
+/// Returns [`MvlnError::RemoteFailed`] if the source can't be uploaded,
+/// the upload doesn't match, or the destination exists, in which case the
+/// source is untouched; [`MvlnError::RemoveFailed`] if the source can't be
+/// removed after the upload; and [`MvlnError::RemoteStubFailed`] if the
+/// placeholder can't be created.
+pub fn move_to_remote(
+    source: &Path,
+    remote: &Remote,
+    placeholder: &Placeholder,
+    force: bool,
+) -> Result<RemoteResult> {
+    let fail = |reason: String| MvlnError::RemoteFailed {
+        src: source.to_path_buf(),
+        dest: remote.to_string(),
+        reason,
+    };
+    let name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| fail("the source has no UTF-8 file name".to_string()))?;
+    let dest = remote.join(name);
+    let staging = format!("{dest}{PARTIAL_SUFFIX}");
+    let local = local_digests(source, &staging).map_err(fail)?;
+
+    // Create the directory, and make sure the destination is free
+    let dir = quote(remote.path_or_home());
+    let quoted = quote(&dest);
+    let prepare = if force {
+        format!("mkdir -p -- {dir}")
+    } else {
+        format!(
+            "mkdir -p -- {dir} && if test -e {quoted} || test -L {quoted}; then echo exists; fi"
+        )
+    };
+    let output = ssh(remote, &prepare).map_err(fail)?;
+    if String::from_utf8_lossy(&output.stdout).trim() == "exists" {
+        return Err(fail(format!("{dest} already exists")));
+    }
+
+    let uploaded = upload(source, remote, &staging)
+        .and_then(|()| verify(remote, &staging, &local))
+        .and_then(|()| {
+            // An existing destination is only replaced once the upload checks out
+            let mut commit = format!("mv -- {} {quoted}", quote(&staging));
+            if force {
+                commit = format!("rm -rf -- {quoted} && {commit}");
+            }
+            ssh(remote, &commit).map(drop)
+        });
+    if let Err(reason) = uploaded {
+        // Best effort: the source is still in place either way
+        let _ = ssh(remote, &format!("rm -rf -- {}", quote(&staging)));
+        return Err(fail(reason));
+    }
+
+    let removed = if source.is_dir() {
+        fs::remove_dir_all(source)
+    } else {
+        fs::remove_file(source)
+    };
+    removed.map_err(|e| MvlnError::RemoveFailed {
+        src: source.to_path_buf(),
+        dest: PathBuf::from(remote.url(&dest)),
+        source: e,
+    })?;
+
+    let url = remote.url(&dest);
+    let placeholder = leave_placeholder(source, name, &url, placeholder).map_err(|e| {
+        MvlnError::RemoteStubFailed {
+            src: source.to_path_buf(),
+            url: url.clone(),
+            source: e,
+        }
+    })?;
+    Ok(RemoteResult {
+        source: source.to_path_buf(),
+        dest,
+        url,
+        placeholder,
+    })
+}
+
+/// The contents of the `.url` stub left for data moved to `url`.
+///
+/// # Examples
+///
+/// ```
+/// let stub = mvln::ssh::stub("sftp://nas/srv/a.iso");
+/// assert_eq!(stub, "[InternetShortcut]\nURL=sftp://nas/srv/a.iso\n");
+/// ```
+#[must_use]
+pub fn stub(url: &str) -> String {
+    format!("[InternetShortcut]\nURL={url}\n")
+}
+
+/// Create `placeholder` at `source`, now moved to `url` as `name`.
+fn leave_placeholder(
+    source: &Path,
+    name: &str,
+    url: &str,
+    placeholder: &Placeholder,
+) -> io::Result<Option<PathBuf>> {
+    match placeholder {
+        Placeholder::Stub => {
+            let mut path = source.as_os_str().to_owned();
+            path.push(".url");
+            let path = PathBuf::from(path);
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)?
+                .write_all(stub(url).as_bytes())?;
+            Ok(Some(path))
+        }
+        Placeholder::Sshfs(mount) => {
+            let target = compute_symlink_target(source, mount.join(name), &SymlinkStyle::Relative);
+            symlink(&target, source)?;
+            Ok(Some(source.to_path_buf()))
+        }
+        Placeholder::None => Ok(None),
+    }
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+/// The digest of every file of `source`, by the path it will have on the
+/// server when uploaded as `staging`. Trees holding anything but
+/// directories and regular files are refused, since `sftp` would follow
+/// their symlinks.
+fn local_digests(
+    source: &Path,
+    staging: &str,
+) -> std::result::Result<BTreeMap<String, Digest>, String> {
+    let mut digests = BTreeMap::new();
+    for entry in WalkDir::new(source).sort_by_file_name() {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let kind = entry.file_type();
+        if kind.is_dir() {
+            continue;
+        }
+        if !kind.is_file() {
+            return Err(format!(
+                "{} is not a regular file or directory",
+                entry.path().display()
+            ));
+        }
+        let relative = entry.path().strip_prefix(source).unwrap_or(Path::new(""));
+        let mut remote = staging.to_string();
+        for part in relative.components() {
+            let part = part
+                .as_os_str()
+                .to_str()
+                .ok_or_else(|| format!("{} is not valid UTF-8", entry.path().display()))?;
+            remote.push('/');
+            remote.push_str(part);
+        }
+        let digest = hash_file(entry.path()).map_err(|e| e.to_string())?;
+        digests.insert(remote, digest);
+    }
+    Ok(digests)
+}
+
+/// Upload `source` to `staging` on the server.
+fn upload(source: &Path, remote: &Remote, staging: &str) -> std::result::Result<(), String> {
+    let flags = if source.is_dir() { "-r -p" } else { "-p" };
+    let local = source
+        .to_str()
+        .ok_or_else(|| format!("{} is not valid UTF-8", source.display()))?;
+    let batch = format!(
+        "put {flags} {} {}\n",
+        sftp_quote(local),
+        sftp_quote(staging)
+    );
+    let mut child = Command::new("sftp")
+        .args(["-q", "-b", "-", &remote.login()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("sftp: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(batch.as_bytes())
+            .map_err(|e| format!("sftp: {e}"))?;
+    }
+    let output = child.wait_with_output().map_err(|e| format!("sftp: {e}"))?;
+    checked("sftp", output).map(drop)
+}
+
+/// Check the digests of the files under `staging` on the server against
+/// `local`.
+fn verify(
+    remote: &Remote,
+    staging: &str,
+    local: &BTreeMap<String, Digest>,
+) -> std::result::Result<(), String> {
+    let script = format!("find {} -type f -exec sha256sum {{}} +", quote(staging));
+    let output = ssh(remote, &script)?;
+    let mut uploaded = BTreeMap::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        // Escaped names (with a backslash or line break) start with `\`
+        // and never match, failing the check
+        let Some((digest, path)) = line.split_once("  ") else {
+            continue;
+        };
+        uploaded.insert(path.to_string(), digest.to_string());
+    }
+    for (path, digest) in local {
+        if uploaded.remove(path).as_deref() != Some(&digest.to_hex()) {
+            return Err(format!("{path} does not match after the upload"));
+        }
+    }
+    match uploaded.into_keys().next() {
+        Some(extra) => Err(format!("unexpected file {extra} in the upload")),
+        None => Ok(()),
+    }
+}
+
+/// Run `script` with the login shell on the server.
+fn ssh(remote: &Remote, script: &str) -> std::result::Result<Output, String> {
+    let output = Command::new("ssh")
+        .args(["-o", "BatchMode=yes", &remote.login(), script])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("ssh: {e}"))?;
+    checked("ssh", output)
+}
+
+/// `output` if the command succeeded, or its error message.
+fn checked(command: &str, output: Output) -> std::result::Result<Output, String> {
+    if output.status.success() {
+        return Ok(output);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.lines().rfind(|line| !line.trim().is_empty()) {
+        Some(line) => Err(format!("{command}: {}", line.trim())),
+        None => Err(format!("{command} failed ({})", output.status)),
+    }
+}
+
+/// Quote `s` as one word for a POSIX shell.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quote `s` as one argument of an `sftp` batch command.
+fn sftp_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', r"\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scp_style_destinations() {
+        let remote = Remote::parse("nas:backups").unwrap();
+        assert_eq!(remote.user, None);
+        assert_eq!(remote.join("a.iso"), "backups/a.iso");
+        assert_eq!(remote.url("backups/a.iso"), "sftp://nas/~/backups/a.iso");
+
+        let remote = Remote::parse("me@nas.local:").unwrap();
+        assert_eq!(remote.login(), "me@nas.local");
+        assert_eq!(remote.join("a b"), "a b");
+        assert_eq!(remote.url("/srv/a b"), "sftp://me@nas.local/srv/a%20b");
+
+        for local in ["dir/a:b", "D:\\x", "-oProxyCommand=x:y", "@h:x", "plain"] {
+            assert_eq!(Remote::parse(local), None, "{local}");
+        }
+    }
+
+    #[test]
+    fn tilde_paths_are_relative_to_the_home_directory() {
+        let remote = Remote::parse("nas:~/x").unwrap();
+        assert_eq!(remote.to_string(), "nas:~/x");
+        let dest = remote.join("a.iso");
+        assert_eq!(dest, "x/a.iso");
+        assert_eq!(quote(remote.path_or_home()), "'x'");
+        assert_eq!(quote(&dest), "'x/a.iso'");
+        assert_eq!(remote.url(&dest), "sftp://nas/~/x/a.iso");
+
+        let remote = Remote::parse("nas:~").unwrap();
+        assert_eq!(quote(remote.path_or_home()), "'.'");
+        assert_eq!(remote.join("a.iso"), "a.iso");
+    }
+
+    #[test]
+    fn quotes_for_the_shell_and_sftp() {
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(sftp_quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+    }
+
+    #[test]
+    fn lists_local_files_by_their_staged_path() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("photos");
+        fs::create_dir_all(dir.join("2024")).unwrap();
+        fs::write(dir.join("2024/a.jpg"), "abc").unwrap();
+
+        let digests = local_digests(&dir, "/srv/photos.mvln-partial").unwrap();
+        assert_eq!(
+            digests.keys().collect::<Vec<_>>(),
+            ["/srv/photos.mvln-partial/2024/a.jpg"]
+        );
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("2024", dir.join("latest")).unwrap();
+            assert!(local_digests(&dir, "x").is_err());
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Content-addressed storage layout.
+//!
+//! In content-addressed mode a destination directory acts as a store: each
+//! moved file is kept once under `objects/<first two hex digits>/<sha256>`
+//! and every original path becomes a symlink to its object. Files with the
+//! same contents, from any number of runs, share a single object.
+//!
+//! Objects are shared, so editing a file through one of its symlinks changes
+//! it for every path linking to the same object.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::error::{MvlnError, Result};
+use crate::hash::{hash_file, Digest};
+use crate::operation::{link_only, move_and_link, MoveOptions, MoveResult};
+use crate::path_utils::compute_symlink_target;
+
+/// Directory under the store root holding the objects.
+pub const OBJECTS_DIR: &str = "objects";
+
+/// Result of storing a file.
+#[derive(Debug)]
+pub struct Stored {
+    /// The move result; `dest` is the object path.
+    pub result: MoveResult,
+    /// Content hash of the file.
+    pub digest: Digest,
+    /// Whether an identical object already existed, so the source was
+    /// removed instead of moved.
+    pub reused: bool,
+}
+
+/// A content-addressed store rooted at a directory.
+#[derive(Debug, Clone)]
+pub struct Store {
+    root: PathBuf,
+}
+
+impl Store {
+    /// Use `root` as the store root. Nothing is created until a file is
+    /// stored.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The store root.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of the object for `digest`.
+    #[must_use]
+    pub fn object_path(&self, digest: &Digest) -> PathBuf {
+        let hex = digest.to_hex();
+        self.root.join(OBJECTS_DIR).join(&hex[..2]).join(hex)
+    }
+
+    /// Move `source` into the store and replace it with a symlink.
+    ///
+    /// If the store already holds an object with the same contents, the
+    /// source is removed and linked to that object instead. Only regular
+    /// files can be stored. The symlink style and dry-run flag are taken
+    /// from `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is missing or not a regular file, if
+    /// it cannot be hashed, or if moving or linking fails (see
+    /// [`move_and_link`]).
+    pub fn insert<P: AsRef<Path>>(&self, source: P, options: &MoveOptions) -> Result<Stored> {
+        let source = source.as_ref();
+        let meta = source.symlink_metadata().map_err(|e| match e.kind() {
+            ErrorKind::NotFound => MvlnError::SourceNotFound {
+                path: source.to_path_buf(),
+            },
+            _ => MvlnError::SourceAccessError {
+                path: source.to_path_buf(),
+                source: e,
+            },
+        })?;
+        if !meta.is_file() {
+            return Err(MvlnError::InvalidPath {
+                path: source.to_path_buf(),
+                reason: "only regular files can be stored in content-addressed mode".to_string(),
+            });
+        }
+
+        let digest = hash_file(source).map_err(|e| MvlnError::SourceAccessError {
+            path: source.to_path_buf(),
+            source: e,
+        })?;
+        let object = self.object_path(&digest);
+
+        let Ok(object_meta) = object.symlink_metadata() else {
+            let result = move_and_link(source, &object, options)?;
+            return Ok(Stored {
+                result,
+                digest,
+                reused: false,
+            });
+        };
+
+        if !object_meta.is_file() || object_meta.len() != meta.len() {
+            return Err(MvlnError::InvalidPath {
+                path: object,
+                reason: "store object does not match its hash".to_string(),
+            });
+        }
+
+        let result = if options.dry_run {
+            MoveResult {
+                source: source.to_path_buf(),
+                symlink_target: compute_symlink_target(source, &object, &options.symlink_style),
+                dest: object,
+                farm: Vec::new(),
+                hardlinked_from: None,
+                backup: None,
+                skipped_same: false,
+                sanitized: Vec::new(),
+            }
+        } else {
+            // The object already holds these bytes, so the source copy can go
+            fs::remove_file(source).map_err(|e| MvlnError::RemoveFailed {
+                src: source.to_path_buf(),
+                dest: object.clone(),
+                source: e,
+            })?;
+            link_only(source, &object, options)?
+        };
+
+        Ok(Stored {
+            result,
+            digest,
+            reused: true,
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_files_share_one_object() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = Store::new(tmp.path().join("store"));
+        let a = tmp.path().join("a.txt");
+        let b = tmp.path().join("b.txt");
+        fs::write(&a, "same").unwrap();
+        fs::write(&b, "same").unwrap();
+
+        let first = store.insert(&a, &MoveOptions::default()).unwrap();
+        let second = store.insert(&b, &MoveOptions::default()).unwrap();
+
+        assert!(!first.reused);
+        assert!(second.reused);
+        assert_eq!(first.result.dest, second.result.dest);
+        assert_eq!(first.result.dest, store.object_path(&first.digest));
+        assert!(a.is_symlink() && b.is_symlink());
+        assert_eq!(fs::read_to_string(&b).unwrap(), "same");
+    }
+
+    #[test]
+    fn object_path_uses_hash_prefix() {
+        let store = Store::new("/store");
+        let digest = crate::hash::hash_bytes(b"abc");
+        assert_eq!(
+            store.object_path(&digest),
+            PathBuf::from("/store/objects/ba").join(digest.to_hex())
+        );
+    }
+
+    #[test]
+    fn directories_are_rejected() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = Store::new(tmp.path().join("store"));
+        let err = store
+            .insert(tmp.path(), &MoveOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, MvlnError::InvalidPath { .. }));
+    }
+}
@@ -0,0 +1,155 @@
+//! `SHA256SUMS` manifests of moved files.
+//!
+//! With `--write-sums FILE`, every file a batch moves is hashed at its
+//! destination and listed in FILE, relative to the destination root, in
+//! the format of GNU `sha256sum`. The archive can then be verified later,
+//! without mvln, by running `sha256sum -c FILE` in the destination root.
+//!
+//! Names containing a backslash or a line break are escaped the way
+//! `sha256sum` does: the line starts with a backslash, and `\` and newline
+//! in the name are written as `\\` and `\n`.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::hash::{hash_file, Digest};
+
+/// The files of a manifest, with their digests.
+///
+/// # Examples
+///
+/// ```
+/// use mvln::sums::Manifest;
+///
+/// let root = tempfile::tempdir().unwrap();
+/// std::fs::create_dir(root.path().join("docs")).unwrap();
+/// std::fs::write(root.path().join("docs/a.txt"), "abc").unwrap();
+///
+/// let mut sums = Manifest::new(root.path());
+/// sums.add(&root.path().join("docs")).unwrap();
+/// assert_eq!(
+///     sums.render(),
+///     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  docs/a.txt\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    root: PathBuf,
+    entries: Vec<(PathBuf, Digest)>,
+}
+
+impl Manifest {
+    /// An empty manifest with paths relative to `root`.
+    #[must_use]
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Hash `path`, or every regular file beneath it if it is a directory,
+    /// and add it to the manifest. Symlinks are not followed or listed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first I/O error walking the tree or reading a file.
+    pub fn add(&mut self, path: &Path) -> io::Result<()> {
+        for entry in WalkDir::new(path).sort_by_file_name() {
+            let entry = entry.map_err(io::Error::from)?;
+            if entry.file_type().is_file() {
+                let digest = hash_file(entry.path())?;
+                let file = entry.path().canonicalize()?;
+                let listed = match file.strip_prefix(&self.root) {
+                    Ok(relative) => relative.to_path_buf(),
+                    // Outside the root, e.g. in a content-addressed store
+                    Err(_) => file,
+                };
+                self.entries.push((listed, digest));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of files listed.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no file is listed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The manifest in `sha256sum` format, in the order the files were
+    /// added.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for (path, digest) in &self.entries {
+            let name = path.to_string_lossy();
+            if name.contains(['\\', '\n', '\r']) {
+                let escaped = name
+                    .replace('\\', "\\\\")
+                    .replace('\n', "\\n")
+                    .replace('\r', "\\r");
+                let _ = writeln!(text, "\\{digest}  {escaped}");
+            } else {
+                let _ = writeln!(text, "{digest}  {name}");
+            }
+        }
+        text
+    }
+
+    /// Write the manifest to `path`, replacing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns any I/O error writing the file.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_files_relative_to_the_root() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(tmp.path().join("dir/sub")).unwrap();
+        fs::write(tmp.path().join("dir/sub/b"), "").unwrap();
+        fs::write(tmp.path().join("dir/a"), "abc").unwrap();
+        fs::write(tmp.path().join("top.txt"), "abc").unwrap();
+
+        let mut sums = Manifest::new(tmp.path());
+        sums.add(&tmp.path().join("top.txt")).unwrap();
+        sums.add(&tmp.path().join("dir")).unwrap();
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(
+            sums.render(),
+            format!("{abc}  top.txt\n{abc}  dir/a\n{empty}  dir/sub/b\n")
+        );
+        assert_eq!(sums.len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn escapes_names_like_sha256sum() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::write(tmp.path().join("a\\b\nc"), "abc").unwrap();
+
+        let mut sums = Manifest::new(tmp.path());
+        sums.add(&tmp.path().join("a\\b\nc")).unwrap();
+        assert!(sums.render().starts_with("\\ba7816bf"));
+        assert!(sums.render().ends_with("  a\\\\b\\nc\n"));
+    }
+}
@@ -0,0 +1,132 @@
+//! System log backends for `--log syslog|journald`.
+//!
+//! Both speak the local datagram protocols directly, so no daemon-specific
+//! library is needed: classic syslog messages (RFC 3164 framing, facility
+//! `user`) go to `/dev/log`, and journald's native protocol (`KEY=value`
+//! fields) goes to `/run/systemd/journal/socket`. Run log events are mapped
+//! to syslog priorities: errors are `err`, everything else `info`.
+
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+
+use mvln::error::{MvlnError, Result};
+
+use crate::cli::LogTarget;
+use crate::logfile::Kind;
+
+/// Identifier the messages are tagged with.
+const IDENTIFIER: &str = "mvln";
+
+/// `user` facility (1), shifted as in the PRI field.
+const FACILITY_USER: u8 = 1 << 3;
+
+/// A connected system log socket.
+#[derive(Debug)]
+pub struct SystemLog {
+    target: LogTarget,
+    socket: UnixDatagram,
+}
+
+impl SystemLog {
+    /// Connect to the default socket of `target`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MvlnError::LogFileFailed`] naming the socket if no daemon
+    /// is listening on it.
+    pub fn connect(target: LogTarget) -> Result<Self> {
+        Self::connect_to(target, &default_socket(target))
+    }
+
+    fn connect_to(target: LogTarget, path: &Path) -> Result<Self> {
+        let error = |e: std::io::Error| MvlnError::LogFileFailed {
+            path: path.to_path_buf(),
+            source: e,
+        };
+        let socket = UnixDatagram::unbound().map_err(error)?;
+        socket.connect(path).map_err(error)?;
+        Ok(Self { target, socket })
+    }
+
+    /// Send one event. Failures are ignored, like for the log file.
+    pub fn send(&self, kind: Kind, text: &str) {
+        let datagram = match self.target {
+            LogTarget::Syslog => syslog_datagram(kind, text),
+            LogTarget::Journald => journald_datagram(kind, text),
+        };
+        let _ = self.socket.send(&datagram);
+    }
+}
+
+fn default_socket(target: LogTarget) -> PathBuf {
+    match target {
+        // macOS has no /dev/log
+        LogTarget::Syslog if cfg!(target_os = "macos") => PathBuf::from("/var/run/syslog"),
+        LogTarget::Syslog => PathBuf::from("/dev/log"),
+        LogTarget::Journald => PathBuf::from("/run/systemd/journal/socket"),
+    }
+}
+
+/// Syslog severity of an event.
+fn severity(kind: Kind) -> u8 {
+    match kind {
+        Kind::Error => 3,
+        Kind::Run | Kind::Action | Kind::Decision => 6,
+    }
+}
+
+/// `<PRI>mvln[PID]: TEXT`; the daemon adds the timestamp and host.
+fn syslog_datagram(kind: Kind, text: &str) -> Vec<u8> {
+    format!(
+        "<{}>{IDENTIFIER}[{}]: {}",
+        FACILITY_USER | severity(kind),
+        std::process::id(),
+        text.replace('\n', " ")
+    )
+    .into_bytes()
+}
+
+/// journald native protocol: one `KEY=value` field per line.
+fn journald_datagram(kind: Kind, text: &str) -> Vec<u8> {
+    format!(
+        "MESSAGE={}\nPRIORITY={}\nSYSLOG_IDENTIFIER={IDENTIFIER}\nSYSLOG_PID={}\nMVLN_KIND={}\n",
+        text.replace('\n', " "),
+        severity(kind),
+        std::process::id(),
+        kind.label()
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_datagrams_with_priorities() {
+        let pid = std::process::id();
+        let line = String::from_utf8(syslog_datagram(Kind::Error, "boom\nagain")).unwrap();
+        assert_eq!(line, format!("<11>mvln[{pid}]: boom again"));
+
+        let fields = String::from_utf8(journald_datagram(Kind::Action, "mv a b")).unwrap();
+        assert!(fields.starts_with("MESSAGE=mv a b\nPRIORITY=6\n"));
+        assert!(fields.contains("SYSLOG_IDENTIFIER=mvln\n"));
+        assert!(fields.ends_with("MVLN_KIND=ACTION\n"));
+    }
+
+    #[test]
+    fn sends_to_socket() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("log.sock");
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let log = SystemLog::connect_to(LogTarget::Syslog, &path).unwrap();
+        log.send(Kind::Run, "exit status 0");
+
+        let mut buf = [0; 256];
+        let len = server.recv(&mut buf).unwrap();
+        assert!(String::from_utf8_lossy(&buf[..len]).starts_with("<14>mvln["));
+
+        assert!(SystemLog::connect_to(LogTarget::Journald, &dir.path().join("none")).is_err());
+    }
+}
@@ -13,8 +13,20 @@ use std::process::Command;
 use tempfile::TempDir;
 
 /// Helper to get the mvln binary command
+///
+/// State (journal, locks) and data (translations) go to scratch directories
+/// so tests never touch the real per-user files.
 fn mvln_cmd() -> Command {
-    Command::new(env!("CARGO_BIN_EXE_mvln"))
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_mvln"));
+    cmd.env(
+        "MVLN_STATE_DIR",
+        std::env::temp_dir().join("mvln-integration-state"),
+    );
+    cmd.env(
+        "MVLN_DATA_DIR",
+        std::env::temp_dir().join("mvln-integration-data"),
+    );
+    cmd
 }
 
 #[test]
@@ -348,6 +360,714 @@ fn test_force_file_to_directory_moves_into() {
     assert!(src_file.is_symlink());
 }
 
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn test_differently_normalized_destination_conflicts() {
+    let tmp = TempDir::new().unwrap();
+    let (src_dir, dest_dir) = (tmp.path().join("src"), tmp.path().join("dest"));
+    fs::create_dir_all(&src_dir).unwrap();
+    fs::create_dir_all(&dest_dir).unwrap();
+    // NFC "café.txt" next to an NFD one already at the destination
+    fs::write(src_dir.join("caf\u{e9}.txt"), "new").unwrap();
+    fs::write(dest_dir.join("cafe\u{301}.txt"), "old").unwrap();
+    fs::write(src_dir.join("cr\u{e8}me.txt"), "creme").unwrap();
+
+    mvln_cmd()
+        .args(["--lang", "en-US"])
+        .arg(src_dir.join("caf\u{e9}.txt"))
+        .arg(&dest_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("mvln::dest_exists"));
+    assert!(!dest_dir.join("caf\u{e9}.txt").exists());
+
+    mvln_cmd()
+        .args(["--normalize", "nfd"])
+        .arg(src_dir.join("cr\u{e8}me.txt"))
+        .arg(&dest_dir)
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("cre\u{300}me.txt")).unwrap(),
+        "creme"
+    );
+}
+
+#[test]
+fn test_sanitize_renames_and_reports() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("report: final?.txt");
+    let dest_dir = tmp.path().join("usb");
+    fs::write(&src, "data").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .args(["--lang", "en-US", "--sanitize"])
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Renamed"))
+        .stdout(predicate::str::contains("(--sanitize)"));
+    let dest = dest_dir.join("report_ final_.txt");
+    assert_eq!(fs::read_to_string(&dest).unwrap(), "data");
+    assert_eq!(
+        fs::canonicalize(&src).unwrap(),
+        fs::canonicalize(&dest).unwrap()
+    );
+}
+
+#[test]
+fn test_existing_destination_reports_code_and_fix() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("a.txt");
+    let dest = tmp.path().join("b.txt");
+    fs::write(&src, "a").unwrap();
+    fs::write(&dest, "b").unwrap();
+
+    mvln_cmd()
+        .args(["--lang", "en-US"])
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error[mvln::dest_exists]"))
+        .stderr(predicate::str::contains(format!(
+            "  --> {}",
+            dest.display()
+        )))
+        .stderr(predicate::str::contains("= help: Rerun with -f/--force"));
+}
+
+#[test]
+fn test_overlong_destination_name_fails_before_moving() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("a.txt");
+    let name = "n".repeat(300);
+    let dest = tmp.path().join("archive").join(&name);
+    fs::write(&src, "a").unwrap();
+
+    mvln_cmd()
+        .args(["--lang", "en-US"])
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error[mvln::name_too_long]"))
+        .stderr(predicate::str::contains(&name))
+        .stderr(predicate::str::contains("= note: Nothing was moved"));
+    assert_eq!(fs::read_to_string(&src).unwrap(), "a");
+    assert!(!tmp.path().join("archive").exists());
+}
+
+#[test]
+fn test_fail_fast_skips_remaining_sources() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    let a = tmp.path().join("a.txt");
+    // Sources are processed in sorted order
+    let missing = tmp.path().join("b.txt");
+    let c = tmp.path().join("c.txt");
+    fs::write(&a, "a").unwrap();
+    fs::write(&c, "c").unwrap();
+
+    mvln_cmd()
+        .args(["--lang", "en-US", "--fail-fast"])
+        .args([&a, &missing, &c, &dest])
+        .assert()
+        .code(1)
+        .stdout(predicate::function(|out: &str| {
+            out.replace(['\u{2068}', '\u{2069}'], "")
+                .contains("1 skipped, 1 failed")
+        }));
+
+    assert!(dest.join("a.txt").exists());
+    assert!(c.is_file() && !c.is_symlink());
+    assert!(!dest.join("c.txt").exists());
+}
+
+#[test]
+fn test_interactive_prompts_on_conflict() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    let a = tmp.path().join("a.txt");
+    let b = tmp.path().join("b.txt");
+    for path in [&a, &b] {
+        fs::write(path, "new").unwrap();
+        fs::write(dest.join(path.file_name().unwrap()), "old").unwrap();
+    }
+
+    // An unknown answer is asked again; then back up a, skip b
+    let mut cmd = mvln_cmd();
+    cmd.args(["--lang", "en-US", "-i"]).args([&a, &b, &dest]);
+    assert_cmd::Command::from_std(cmd)
+        .write_stdin("x\nb\ns\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("[o]verwrite, [s]kip").count(3))
+        .stdout(predicate::str::contains(format!(
+            "mv {} '{}~'",
+            dest.join("a.txt").display(),
+            dest.join("a.txt").display()
+        )));
+
+    assert_eq!(fs::read_to_string(dest.join("a.txt~")).unwrap(), "old");
+    assert!(a.is_symlink());
+    assert_eq!(fs::read_to_string(dest.join("b.txt")).unwrap(), "old");
+    assert!(!b.is_symlink());
+}
+
+#[test]
+fn test_timestamped_backup_of_existing_destination() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    let src = tmp.path().join("report.pdf");
+    fs::write(&src, "new").unwrap();
+    fs::write(dest.join("report.pdf"), "old").unwrap();
+
+    mvln_cmd()
+        .args(["--lang", "en-US", "--backup=timestamp"])
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".bak"));
+
+    assert!(src.is_symlink());
+    assert_eq!(fs::read_to_string(dest.join("report.pdf")).unwrap(), "new");
+    let backups: Vec<String> = fs::read_dir(&dest)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name != "report.pdf")
+        .collect();
+    assert_eq!(backups.len(), 1);
+    assert!(backups[0].starts_with("report.pdf.20"), "{backups:?}");
+    assert_eq!(
+        std::path::Path::new(&backups[0]).extension().unwrap(),
+        "bak"
+    );
+    assert_eq!(fs::read_to_string(dest.join(&backups[0])).unwrap(), "old");
+}
+
+#[test]
+fn test_skip_same_links_an_earlier_copy() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    let src = tmp.path().join("big.iso");
+    fs::write(&src, "image").unwrap();
+    // A previous run copied the file but was stopped before removing it
+    fs::copy(&src, dest.join("big.iso")).unwrap();
+    let mtime = fs::metadata(&src).unwrap().modified().unwrap();
+    fs::File::options()
+        .write(true)
+        .open(dest.join("big.iso"))
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+
+    mvln_cmd()
+        .args(["--lang", "en-US"])
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .failure();
+    // Only the source is removed, so that is what is echoed and counted
+    mvln_cmd()
+        .args(["--lang", "en-US", "--skip-same"])
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("rm {}", src.display())))
+        .stdout(predicate::str::contains("mv ").not())
+        // The summary only mentions skipped files if there are any
+        .stdout(predicate::str::contains("skipped"));
+
+    assert!(src.is_symlink());
+    assert_eq!(fs::read_to_string(&src).unwrap(), "image");
+}
+
+#[test]
+fn test_confirm_cross_device_asks_only_across_filesystems() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("file.txt");
+    fs::write(&src, "data").unwrap();
+
+    // A rename needs no confirmation
+    let mut cmd = mvln_cmd();
+    cmd.args(["--lang", "en-US", "--confirm-cross-device"])
+        .arg(&src)
+        .arg(tmp.path().join("dest/"));
+    assert_cmd::Command::from_std(cmd)
+        .write_stdin("")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("another filesystem").not());
+    assert!(src.is_symlink());
+
+    // Declining a copy onto another filesystem leaves the source alone
+    let Ok(shm) = TempDir::new_in("/dev/shm") else {
+        return;
+    };
+    if mvln::device::crosses_filesystems(tmp.path(), &shm.path().join("x")) != Some(true) {
+        return;
+    }
+    let other = tmp.path().join("other.txt");
+    fs::write(&other, "data").unwrap();
+    let mut cmd = mvln_cmd();
+    cmd.args(["--lang", "en-US", "--confirm-cross-device"])
+        .arg(&other)
+        .arg(shm.path());
+    assert_cmd::Command::from_std(cmd)
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("is on another filesystem: moving"));
+    assert!(!other.is_symlink());
+    assert!(!shm.path().join("other.txt").exists());
+}
+
+#[test]
+fn test_no_cross_device_fails_instead_of_copying() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("file.txt");
+    fs::write(&src, "data").unwrap();
+
+    let mut cmd = mvln_cmd();
+    cmd.arg("--no-cross-device")
+        .arg(&src)
+        .arg(tmp.path().join("dest/"));
+    cmd.assert().success();
+    assert!(src.is_symlink());
+
+    let Ok(shm) = TempDir::new_in("/dev/shm") else {
+        return;
+    };
+    if mvln::device::crosses_filesystems(tmp.path(), &shm.path().join("x")) != Some(true) {
+        return;
+    }
+    let other = tmp.path().join("other.txt");
+    fs::write(&other, "data").unwrap();
+    // Refused while planning, so a dry run already reports it
+    for dry_run in [true, false] {
+        let mut cmd = mvln_cmd();
+        cmd.args(["--lang", "en-US", "--no-cross-device"]);
+        if dry_run {
+            cmd.arg("--dry-run");
+        }
+        cmd.arg(&other).arg(shm.path());
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("mvln::cross_device"));
+    }
+    assert!(!other.is_symlink());
+    assert!(!shm.path().join("other.txt").exists());
+}
+
+#[test]
+fn test_cross_device_directory_copy_shows_its_size() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("photos");
+    fs::create_dir_all(src.join("2024")).unwrap();
+    fs::write(src.join("a.jpg"), [0u8; 100]).unwrap();
+    fs::write(src.join("2024/b.jpg"), [0u8; 100]).unwrap();
+
+    let Ok(shm) = TempDir::new_in("/dev/shm") else {
+        return;
+    };
+    if mvln::device::crosses_filesystems(tmp.path(), &shm.path().join("x")) != Some(true) {
+        return;
+    }
+    for dry_run in [true, false] {
+        let mut cmd = mvln_cmd();
+        cmd.args(["--lang", "en-US", "-w"]);
+        if dry_run {
+            cmd.arg("--dry-run");
+        }
+        cmd.arg(&src).arg(shm.path());
+        cmd.assert()
+            .success()
+            .stderr(predicate::str::contains("to another filesystem:"))
+            .stderr(predicate::str::contains("files"));
+    }
+    assert!(src.is_symlink());
+    assert_eq!(
+        fs::read(shm.path().join("photos/2024/b.jpg"))
+            .unwrap()
+            .len(),
+        100
+    );
+}
+
+#[test]
+fn test_write_sums_lists_moved_files() {
+    let tmp = TempDir::new().unwrap();
+    let photos = tmp.path().join("photos");
+    fs::create_dir_all(photos.join("2024")).unwrap();
+    fs::write(photos.join("2024/a.jpg"), "abc").unwrap();
+    fs::write(tmp.path().join("notes.txt"), "").unwrap();
+    let archive = tmp.path().join("archive");
+    fs::create_dir(&archive).unwrap();
+    let sums = tmp.path().join("SHA256SUMS");
+
+    let mut cmd = mvln_cmd();
+    cmd.arg("-w")
+        .arg("--write-sums")
+        .arg(&sums)
+        .arg(&photos)
+        .arg(tmp.path().join("notes.txt"))
+        .arg(&archive);
+    cmd.assert().success();
+
+    assert_eq!(
+        fs::read_to_string(&sums).unwrap(),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  notes.txt\n\
+         ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  photos/2024/a.jpg\n"
+    );
+    // Checkable with the standard tool, where it is installed
+    if let Ok(status) = Command::new("sha256sum")
+        .arg("-c")
+        .arg("--quiet")
+        .arg(&sums)
+        .current_dir(&archive)
+        .status()
+    {
+        assert!(status.success());
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_check_open_leaves_files_being_written() {
+    let tmp = TempDir::new().unwrap();
+    let log = tmp.path().join("app.log");
+    let archive = tmp.path().join("archive");
+    // Held open by this test process, which mvln can inspect
+    let _writing = fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&log)
+        .unwrap();
+
+    let mut cmd = mvln_cmd();
+    cmd.args(["--lang", "en-US", "--check-open"])
+        .arg(&log)
+        .arg(&archive);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("is open for writing by"));
+    assert!(!log.is_symlink());
+
+    let mut cmd = mvln_cmd();
+    cmd.args(["--check-open=fail"]).arg(&log).arg(&archive);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("mvln::open_for_writing"));
+    assert!(!log.is_symlink());
+
+    let mut cmd = mvln_cmd();
+    cmd.args(["--check-open=warn"]).arg(&log).arg(&archive);
+    cmd.assert().success();
+    assert!(log.is_symlink());
+}
+
+#[test]
+fn test_pack_copies_trees_of_small_files() {
+    let tmp = TempDir::new().unwrap();
+    let Ok(shm) = TempDir::new_in("/dev/shm") else {
+        return;
+    };
+    if mvln::device::crosses_filesystems(tmp.path(), &shm.path().join("x")) != Some(true) {
+        return;
+    }
+    let src = tmp.path().join("repo");
+    for dir in ["a", "a/b", "c"] {
+        fs::create_dir_all(src.join(dir)).unwrap();
+        for i in 0..200 {
+            fs::write(src.join(dir).join(format!("{i}.txt")), i.to_string()).unwrap();
+        }
+    }
+    let mtime = fs::metadata(src.join("a/b/7.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+
+    let mut cmd = mvln_cmd();
+    cmd.args(["-w", "--pack"]).arg(&src).arg(shm.path());
+    cmd.assert().success();
+
+    let dest = shm.path().join("repo");
+    for dir in ["a", "a/b", "c"] {
+        for i in 0..200 {
+            let copied = dest.join(dir).join(format!("{i}.txt"));
+            assert_eq!(fs::read_to_string(copied).unwrap(), i.to_string());
+        }
+    }
+    let copied = fs::metadata(dest.join("a/b/7.txt")).unwrap();
+    assert_eq!(copied.modified().unwrap(), mtime);
+    assert!(src.is_symlink());
+}
+
+#[test]
+fn test_skip_vcs_leaves_caches_out_of_copies() {
+    let tmp = TempDir::new().unwrap();
+    let Ok(shm) = TempDir::new_in("/dev/shm") else {
+        return;
+    };
+    if mvln::device::crosses_filesystems(tmp.path(), &shm.path().join("x")) != Some(true) {
+        return;
+    }
+    let src = tmp.path().join("project");
+    for dir in [".git", "node_modules/left-pad", "src/__pycache__"] {
+        fs::create_dir_all(src.join(dir)).unwrap();
+    }
+    fs::write(src.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+    fs::write(src.join("node_modules/left-pad/index.js"), "").unwrap();
+    fs::write(src.join("src/__pycache__/app.pyc"), "").unwrap();
+    fs::write(src.join("src/app.py"), "print()").unwrap();
+
+    // What is left out stays in place, so the source cannot become a link
+    let mut cmd = mvln_cmd();
+    cmd.args(["-w", "--skip-vcs", "--keep-dir", "node_modules"])
+        .arg(&src)
+        .arg(shm.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("mvln::skipped_dir_kept"));
+    let dest = shm.path().join("project");
+    assert!(!dest.exists());
+    assert!(src.join("src/app.py").exists());
+
+    let mut cmd = mvln_cmd();
+    cmd.args([
+        "-w",
+        "--no-link",
+        "--skip-vcs",
+        "--keep-dir",
+        "node_modules",
+    ])
+    .arg(&src)
+    .arg(shm.path());
+    cmd.assert().success();
+
+    assert_eq!(
+        fs::read_to_string(dest.join("src/app.py")).unwrap(),
+        "print()"
+    );
+    assert!(dest.join("node_modules/left-pad/index.js").exists());
+    assert!(!dest.join(".git").exists());
+    assert!(!dest.join("src/__pycache__").exists());
+    // Only what was copied is removed from the source
+    assert_eq!(
+        fs::read_to_string(src.join(".git/HEAD")).unwrap(),
+        "ref: refs/heads/main"
+    );
+    assert!(src.join("src/__pycache__/app.pyc").exists());
+    assert!(!src.join("src/app.py").exists());
+    assert!(!src.join("node_modules").exists());
+}
+
+#[test]
+fn test_progress_reports_directory_copies() {
+    let tmp = TempDir::new().unwrap();
+    let Ok(shm) = TempDir::new_in("/dev/shm") else {
+        return;
+    };
+    if mvln::device::crosses_filesystems(tmp.path(), &shm.path().join("x")) != Some(true) {
+        return;
+    }
+    let src = tmp.path().join("repo");
+    fs::create_dir_all(src.join("node_modules/react")).unwrap();
+    for i in 0..20 {
+        fs::write(src.join("node_modules/react").join(i.to_string()), "0123").unwrap();
+    }
+
+    let mut cmd = mvln_cmd();
+    cmd.args(["-w", "--progress"]).arg(&src).arg(shm.path());
+    let output = cmd.assert().success().get_output().clone();
+
+    let stderr = String::from_utf8_lossy(&output.stderr).replace(['\u{2068}', '\u{2069}'], "");
+    assert!(
+        stderr.contains("Copying repo: 20/20 files, 80 B/80 B"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_pick_moves_only_selected_sources() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    let sources: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+        .iter()
+        .map(|name| tmp.path().join(name))
+        .collect();
+    for path in &sources {
+        fs::write(path, "data").unwrap();
+    }
+
+    // Deselect b and c, reselect c, then apply
+    let mut cmd = mvln_cmd();
+    cmd.args(["--lang", "en-US", "--pick"])
+        .args(&sources)
+        .arg(&dest);
+    assert_cmd::Command::from_std(cmd)
+        .write_stdin("2-3\n3\n\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Sources to move:").count(3));
+
+    assert!(sources[0].is_symlink());
+    assert!(!sources[1].is_symlink());
+    assert!(sources[2].is_symlink());
+
+    // End of input cancels
+    let other = tmp.path().join("d.txt");
+    fs::write(&other, "data").unwrap();
+    let mut cmd = mvln_cmd();
+    cmd.args(["--lang", "en-US", "--pick"])
+        .arg(&other)
+        .arg(&dest);
+    assert_cmd::Command::from_std(cmd)
+        .write_stdin("")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Selection cancelled"));
+    assert!(!other.is_symlink());
+}
+
+#[test]
+fn test_interactive_all_overwrites_remaining_conflicts() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    let sources: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+        .iter()
+        .map(|name| tmp.path().join(name))
+        .collect();
+    for path in &sources {
+        fs::write(path, "new").unwrap();
+        fs::write(dest.join(path.file_name().unwrap()), "old").unwrap();
+    }
+
+    // Skip a, then overwrite b and everything after it
+    let mut cmd = mvln_cmd();
+    cmd.args(["--lang", "en-US", "-i"])
+        .args(&sources)
+        .arg(&dest);
+    assert_cmd::Command::from_std(cmd)
+        .write_stdin("s\na\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("overwrite [a]ll").count(2));
+
+    assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "old");
+    assert_eq!(fs::read_to_string(dest.join("b.txt")).unwrap(), "new");
+    assert_eq!(fs::read_to_string(dest.join("c.txt")).unwrap(), "new");
+}
+
+#[test]
+fn test_yes_answers_prompts_without_reading_stdin() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    let a = tmp.path().join("a.txt");
+    fs::write(&a, "new").unwrap();
+    fs::write(dest.join("a.txt"), "old").unwrap();
+
+    // stdin is empty, which would skip the move without --yes
+    mvln_cmd()
+        .args(["--lang", "en-US", "-i", "-y"])
+        .args([&a, &dest])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("[q]uit? b"));
+
+    assert_eq!(fs::read_to_string(dest.join("a.txt~")).unwrap(), "old");
+    assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "new");
+    assert!(a.is_symlink());
+}
+
+#[test]
+fn test_hooks_run_around_each_move() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    let keep = tmp.path().join("keep.txt");
+    let skip = tmp.path().join("skip.txt");
+    fs::write(&keep, "k").unwrap();
+    fs::write(&skip, "s").unwrap();
+    let log = tmp.path().join("hooks.log");
+
+    // The pre-hook vetoes skip.txt
+    let pre = format!(
+        "echo \"pre $MVLN_SOURCE $MVLN_DEST\" >> {0}; case $MVLN_SOURCE in *skip*) exit 1;; esac",
+        log.display()
+    );
+    let post = format!(
+        "echo \"post $MVLN_LINK $MVLN_LINK_TARGET\" >> {}",
+        log.display()
+    );
+    mvln_cmd()
+        .args(["--lang", "en-US", "--pre-hook", &pre, "--post-hook", &post])
+        .args([&keep, &skip, &dest])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Error[mvln::hook_failed]"));
+
+    let lines: Vec<String> = fs::read_to_string(&log)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            format!("pre {} {}", keep.display(), dest.join("keep.txt").display()),
+            format!("post {} dest/keep.txt", keep.display()),
+            format!("pre {} {}", skip.display(), dest.join("skip.txt").display()),
+        ]
+    );
+    assert!(keep.is_symlink());
+    assert!(skip.is_file() && !skip.is_symlink());
+}
+
+#[test]
+fn test_exit_codes_distinguish_failures() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("a.txt");
+    let dest = tmp.path().join("b.txt");
+    fs::write(&src, "a").unwrap();
+    fs::write(&dest, "b").unwrap();
+
+    // Some operations failed
+    mvln_cmd().arg(&src).arg(&dest).assert().code(1);
+
+    // Usage error
+    mvln_cmd().arg(&src).assert().code(2);
+    mvln_cmd()
+        .args(["--profile", "missing"])
+        .arg(&src)
+        .arg(&dest)
+        .env("MVLN_CONFIG", tmp.path().join("none.toml"))
+        .assert()
+        .code(2);
+
+    // Nothing matched
+    let pattern = tmp.path().join("*.nomatch");
+    mvln_cmd()
+        .args(["--lang", "en-US"])
+        .arg(&pattern)
+        .arg(tmp.path())
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("Error[mvln::no_matches]"));
+}
+
 #[test]
 fn test_force_directory_to_file_rejected() {
     let tmp = TempDir::new().unwrap();
@@ -367,7 +1087,7 @@ fn test_force_directory_to_file_rejected() {
         .arg(&dest_file)
         .assert()
         .failure()
-        .stderr(predicate::str::contains("type mismatch"));
+        .stderr(predicate::str::contains("Type mismatch"));
 
     // THEN: Both source and destination should be unchanged
     assert!(src_dir.is_dir(), "Source directory should still exist");
@@ -503,3 +1223,1538 @@ fn test_force_directory_replaces_directory() {
         "inner.txt should be gone (replaced)"
     );
 }
+
+#[test]
+fn test_locked_destination_is_rejected() {
+    let tmp = TempDir::new().unwrap();
+    let state = tmp.path().join("state");
+    let src = tmp.path().join("file.txt");
+    let dest_dir = tmp.path().join("dest");
+
+    fs::write(&src, "content").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    // GIVEN: another run holds the lock for the destination
+    let _held = mvln::lock::RunLock::acquire_in(state.join("locks"), &dest_dir, None).unwrap();
+
+    // WHEN: mvln runs against the same destination
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Another mvln run"));
+
+    // THEN: nothing was moved
+    assert!(!src.is_symlink());
+    assert!(!dest_dir.join("file.txt").exists());
+
+    // --no-lock bypasses the lock
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .arg("--no-lock")
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success();
+    assert!(src.is_symlink());
+}
+
+#[test]
+fn test_profile_supplies_destination_and_excludes() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    let dest_dir = tmp.path().join("archive");
+    let keep = tmp.path().join("big.iso");
+    let skip = tmp.path().join("big.iso.part");
+
+    fs::write(&keep, "iso").unwrap();
+    fs::write(&skip, "partial").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+    fs::write(
+        &config,
+        format!(
+            "[profile.offload]\ndest = \"{}\"\nexclude = [\"*.part\"]\n",
+            dest_dir.display()
+        ),
+    )
+    .unwrap();
+
+    // WHEN: only sources are given, the destination comes from the profile
+    mvln_cmd()
+        .current_dir(tmp.path())
+        .arg("--config")
+        .arg(&config)
+        .arg("--profile")
+        .arg("offload")
+        .arg("big.iso*")
+        .assert()
+        .success();
+
+    // THEN: the matching file is moved, the excluded one is untouched
+    assert!(keep.is_symlink());
+    assert!(dest_dir.join("big.iso").exists());
+    assert!(!skip.is_symlink());
+    assert!(!dest_dir.join("big.iso.part").exists());
+}
+
+#[test]
+fn test_unknown_profile_fails() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    fs::write(&config, "[profile.a]\n").unwrap();
+
+    mvln_cmd()
+        .arg("--config")
+        .arg(&config)
+        .arg("--profile")
+        .arg("missing")
+        .arg("x")
+        .arg("y")
+        .assert()
+        .failure()
+        .stderr(
+            predicate::str::contains("Profile not found").and(predicate::str::contains("missing")),
+        );
+}
+
+#[test]
+fn test_preset_bundles_excludes_and_layout() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    let dest_dir = tmp.path().join("archive");
+    let done = tmp.path().join("report.pdf");
+    let partial = tmp.path().join("movie.mkv.crdownload");
+    fs::write(&config, "").unwrap();
+    fs::write(&done, "pdf").unwrap();
+    fs::write(&partial, "partial").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+    let modified = fs::metadata(&done).unwrap().modified().unwrap();
+    let month = mvln::date::DateTime::from_system_time(modified).format("%Y/%m");
+
+    mvln_cmd()
+        .arg("--config")
+        .arg(&config)
+        .args(["--preset", "downloads-archive"])
+        .arg(&done)
+        .arg(&partial)
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    assert!(dest_dir.join(month).join("report.pdf").exists());
+    assert!(done.is_symlink());
+    assert!(partial.is_file());
+}
+
+#[test]
+fn test_unknown_preset_lists_the_presets() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    fs::write(&config, "[preset.scans]\nby-ext = true\n").unwrap();
+
+    mvln_cmd()
+        .arg("--config")
+        .arg(&config)
+        .args(["--preset", "missing", "x", "y"])
+        .assert()
+        .failure()
+        .stderr(
+            predicate::str::contains("Preset not found")
+                .and(predicate::str::contains("media-offload"))
+                .and(predicate::str::contains("scans")),
+        );
+}
+
+#[test]
+fn test_log_file_records_actions_and_errors() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("a.txt");
+    let dest = tmp.path().join("dest");
+    let log = tmp.path().join("run.log");
+    fs::write(&src, "a").unwrap();
+    fs::create_dir(&dest).unwrap();
+
+    mvln_cmd()
+        .arg("--no-commands")
+        .arg("--log-file")
+        .arg(&log)
+        .arg(&src)
+        .arg(tmp.path().join("missing.txt"))
+        .arg(&dest)
+        .assert()
+        .failure();
+
+    let text = fs::read_to_string(&log).unwrap();
+    assert!(text.contains(" ACTION   mv "), "{text}");
+    assert!(text.contains(" ACTION   ln -s "), "{text}");
+    assert!(
+        text.contains(" ERROR    [mvln::source_not_found] "),
+        "{text}"
+    );
+    assert!(
+        text.trim_end().ends_with("RUN      exit status 1"),
+        "{text}"
+    );
+
+    // Later runs append
+    let other = tmp.path().join("b.txt");
+    fs::write(&other, "b").unwrap();
+    mvln_cmd()
+        .arg("--log-file")
+        .arg(&log)
+        .arg("--dry-run")
+        .arg(&other)
+        .arg(&dest)
+        .assert()
+        .success();
+    let text = fs::read_to_string(&log).unwrap();
+    assert!(text.contains(" DECISION dry run"), "{text}");
+    assert_eq!(text.matches("exit status").count(), 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_file_name_does_not_panic() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let tmp = TempDir::new().unwrap();
+    let name = OsStr::from_bytes(b"f\xff");
+    fs::write(tmp.path().join(name), "f").unwrap();
+
+    mvln_cmd()
+        .current_dir(tmp.path())
+        .arg(name)
+        .arg("d/")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("panicked").not());
+}
+
+#[test]
+fn test_errors_are_localized() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    fs::write(&config, "[profile.a]\n").unwrap();
+
+    mvln_cmd()
+        .args(["--lang", "zh-CN", "--config"])
+        .arg(&config)
+        .args(["--profile", "missing", "x", "y"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("配置方案不存在"));
+}
+
+#[test]
+fn test_organize_routes_files_by_rules() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    let inbox = tmp.path().join("inbox");
+    let archive = tmp.path().join("archive");
+
+    fs::create_dir(&inbox).unwrap();
+    fs::write(inbox.join("disk.iso"), "iso").unwrap();
+    fs::write(inbox.join("notes.txt"), "notes").unwrap();
+    fs::write(
+        &config,
+        "[rules]\n\"*.iso\" = \"images/\"\n\"*.log\" = \"logs/{YYYY}/\"\n",
+    )
+    .unwrap();
+
+    mvln_cmd()
+        .arg("--config")
+        .arg(&config)
+        .arg("organize")
+        .arg(&inbox)
+        .arg(&archive)
+        .assert()
+        .success();
+
+    // Matched file is routed and linked back
+    assert!(inbox.join("disk.iso").is_symlink());
+    assert_eq!(
+        fs::read_to_string(archive.join("images/disk.iso")).unwrap(),
+        "iso"
+    );
+    // Unmatched file stays where it is
+    assert!(!inbox.join("notes.txt").is_symlink());
+}
+
+#[test]
+fn test_organize_without_rules_fails() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    fs::write(&config, "").unwrap();
+
+    mvln_cmd()
+        .arg("--config")
+        .arg(&config)
+        .arg("organize")
+        .arg(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no [rules] defined"));
+}
+
+#[test]
+fn test_by_ext_sorts_into_extension_directories() {
+    let tmp = TempDir::new().unwrap();
+    let dest_dir = tmp.path().join("sorted");
+
+    fs::write(tmp.path().join("report.PDF"), "pdf").unwrap();
+    fs::write(tmp.path().join("photo.jpg"), "jpg").unwrap();
+    fs::write(tmp.path().join("Makefile"), "make").unwrap();
+
+    mvln_cmd()
+        .current_dir(tmp.path())
+        .arg("--by-ext")
+        .arg("report.PDF")
+        .arg("photo.jpg")
+        .arg("Makefile")
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    assert!(dest_dir.join("pdf/report.PDF").is_file());
+    assert!(dest_dir.join("jpg/photo.jpg").is_file());
+    assert!(dest_dir.join("noext/Makefile").is_file());
+    assert!(tmp.path().join("report.PDF").is_symlink());
+    assert_eq!(
+        fs::read_to_string(tmp.path().join("photo.jpg")).unwrap(),
+        "jpg"
+    );
+}
+
+#[test]
+fn test_by_date_sorts_into_date_directories() {
+    let tmp = TempDir::new().unwrap();
+    let dest_dir = tmp.path().join("photos");
+    let src = tmp.path().join("img.jpg");
+    fs::write(&src, "jpg").unwrap();
+
+    // 2024-06-01T12:30:01Z
+    let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_717_245_001);
+    fs::File::options()
+        .write(true)
+        .open(&src)
+        .unwrap()
+        .set_modified(mtime)
+        .unwrap();
+
+    mvln_cmd()
+        .arg("--by-date=%Y/%m/%d")
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    assert!(dest_dir.join("2024/06/01/img.jpg").is_file());
+    assert!(src.is_symlink());
+}
+
+#[test]
+fn test_rename_on_conflict_keeps_both_files() {
+    let tmp = TempDir::new().unwrap();
+    let dir_a = tmp.path().join("a");
+    let dir_b = tmp.path().join("b");
+    let dest_dir = tmp.path().join("dest");
+
+    fs::create_dir(&dir_a).unwrap();
+    fs::create_dir(&dir_b).unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+    fs::write(dir_a.join("log.txt"), "from a").unwrap();
+    fs::write(dir_b.join("log.txt"), "from b").unwrap();
+
+    mvln_cmd()
+        .current_dir(tmp.path())
+        .arg("--rename-on-conflict")
+        .arg("*/log.txt")
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("log.txt")).unwrap(),
+        "from a"
+    );
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("log (1).txt")).unwrap(),
+        "from b"
+    );
+    // Each symlink resolves to its own file
+    assert_eq!(fs::read_to_string(dir_b.join("log.txt")).unwrap(), "from b");
+}
+
+#[test]
+fn test_destination_expands_environment_variables() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("file.txt");
+    let dest_dir = tmp.path().join("dest");
+    fs::write(&src, "content").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .env("MVLN_TEST_ROOT", tmp.path())
+        .arg(&src)
+        .arg("${MVLN_TEST_ROOT}/dest")
+        .assert()
+        .success();
+
+    assert!(dest_dir.join("file.txt").is_file());
+    assert!(src.is_symlink());
+}
+
+#[test]
+fn test_convert_links_to_relative() {
+    let tmp = TempDir::new().unwrap();
+    let target = tmp.path().join("archive/file.txt");
+    let link = tmp.path().join("work/file.txt");
+    fs::create_dir(tmp.path().join("archive")).unwrap();
+    fs::create_dir(tmp.path().join("work")).unwrap();
+    fs::write(&target, "content").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    // Dry run reports the change but leaves the link alone
+    mvln_cmd()
+        .arg("convert-links")
+        .arg("--dry-run")
+        .arg(&link)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ln -sfn ../archive/file.txt"));
+    assert_eq!(fs::read_link(&link).unwrap(), target);
+
+    mvln_cmd()
+        .arg("convert-links")
+        .arg("--to-relative")
+        .arg(&link)
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_link(&link).unwrap(),
+        std::path::PathBuf::from("../archive/file.txt")
+    );
+    assert_eq!(fs::read_to_string(&link).unwrap(), "content");
+}
+
+#[test]
+fn test_convert_links_rejects_regular_file() {
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("file.txt");
+    fs::write(&file, "content").unwrap();
+
+    mvln_cmd()
+        .arg("convert-links")
+        .arg("--to-absolute")
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not a symlink"));
+}
+
+#[test]
+fn test_adopt_records_existing_symlink() {
+    let tmp = TempDir::new().unwrap();
+    let state = tmp.path().join("state");
+    let target = tmp.path().join("data.txt");
+    let link = tmp.path().join("link.txt");
+    fs::write(&target, "content").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .arg("adopt")
+        .arg(&link)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Adopted"));
+
+    // Adopting again is a no-op
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .arg("adopt")
+        .arg(&link)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Adopted").not());
+
+    let journal = fs::read_to_string(state.join("journal")).unwrap();
+    assert_eq!(journal.lines().count(), 1);
+    assert!(journal.contains("\tadopt\t"));
+}
+
+#[test]
+fn test_move_is_recorded_in_journal() {
+    let tmp = TempDir::new().unwrap();
+    let state = tmp.path().join("state");
+    let src = tmp.path().join("file.txt");
+    let dest_dir = tmp.path().join("dest");
+    fs::write(&src, "content").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    let journal = fs::read_to_string(state.join("journal")).unwrap();
+    assert!(journal.contains("\tmove\t"));
+    assert!(journal.contains(&dest_dir.join("file.txt").display().to_string()));
+}
+
+#[test]
+fn test_no_link_moves_without_symlink() {
+    let tmp = TempDir::new().unwrap();
+    let state = tmp.path().join("state");
+    let src = tmp.path().join("file.txt");
+    let dest_dir = tmp.path().join("dest");
+    fs::write(&src, "content").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .args(["--lang", "en-US", "--no-link"])
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ln -s").not());
+
+    assert!(src.symlink_metadata().is_err());
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("file.txt")).unwrap(),
+        "content"
+    );
+    let journal = fs::read_to_string(state.join("journal")).unwrap();
+    assert!(journal.contains("\tmove-only\t"));
+}
+
+#[test]
+fn test_interrupted_move_is_finished_on_next_run() {
+    let tmp = TempDir::new().unwrap();
+    let state = tmp.path().join("state");
+    let src = tmp.path().join("file.txt");
+    let dest_dir = tmp.path().join("dest");
+    fs::create_dir(&dest_dir).unwrap();
+    fs::create_dir(&state).unwrap();
+    // Renamed into place, then killed before the symlink was created
+    fs::write(dest_dir.join("file.txt"), "content").unwrap();
+    fs::write(
+        state.join("journal"),
+        format!(
+            "1\tbegin\t{}\t{}\n",
+            src.display(),
+            dest_dir.join("file.txt").display()
+        ),
+    )
+    .unwrap();
+    let other = tmp.path().join("other.txt");
+    fs::write(&other, "other").unwrap();
+
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .args(["--lang", "en-US"])
+        .arg(&other)
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Finished interrupted move"));
+
+    assert!(src.is_symlink());
+    assert_eq!(fs::read_to_string(&src).unwrap(), "content");
+    assert!(other.is_symlink());
+}
+
+#[test]
+fn test_resume_finishes_moves_left_in_place() {
+    let tmp = TempDir::new().unwrap();
+    let state = tmp.path().join("state");
+    let src = tmp.path().join("file.txt");
+    let dest_dir = tmp.path().join("dest");
+    fs::create_dir(&dest_dir).unwrap();
+    fs::create_dir(&state).unwrap();
+    fs::write(&src, "content").unwrap();
+    fs::write(
+        state.join("journal"),
+        format!(
+            "1\tbegin\t{}\t{}\n",
+            src.display(),
+            dest_dir.join("file.txt").display()
+        ),
+    )
+    .unwrap();
+
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .args(["--lang", "en-US", "adopt"])
+        .arg(tmp.path().join("missing"))
+        .assert()
+        .stderr(predicate::str::contains("mvln resume"));
+    assert!(!src.is_symlink());
+
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .args(["--lang", "en-US", "resume"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Finished interrupted move"));
+
+    assert!(src.is_symlink());
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("file.txt")).unwrap(),
+        "content"
+    );
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .args(["--lang", "en-US", "resume"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No interrupted moves"));
+}
+
+#[test]
+fn test_link_only_repairs_missing_symlink() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("file.txt");
+    let dest_dir = tmp.path().join("archive");
+    fs::create_dir(&dest_dir).unwrap();
+    fs::write(dest_dir.join("file.txt"), "content").unwrap();
+
+    mvln_cmd()
+        .arg("link-only")
+        .arg(&src)
+        .arg(dest_dir.join("file.txt"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ln -s archive/file.txt"));
+
+    assert!(src.is_symlink());
+    assert_eq!(fs::read_to_string(&src).unwrap(), "content");
+}
+
+#[test]
+fn test_stow_links_package_into_target() {
+    let tmp = TempDir::new().unwrap();
+    let pkg = tmp.path().join("dotfiles/zsh");
+    let home = tmp.path().join("home");
+    fs::create_dir_all(pkg.join(".config/zsh")).unwrap();
+    fs::write(pkg.join(".zshrc"), "rc").unwrap();
+    fs::write(pkg.join(".config/zsh/aliases"), "aliases").unwrap();
+    fs::create_dir(&home).unwrap();
+
+    mvln_cmd()
+        .arg("stow")
+        .arg(&pkg)
+        .arg("--target")
+        .arg(&home)
+        .assert()
+        .success();
+
+    assert!(home.join(".zshrc").is_symlink());
+    assert!(!home.join(".config").is_symlink());
+    assert_eq!(
+        fs::read_to_string(home.join(".config/zsh/aliases")).unwrap(),
+        "aliases"
+    );
+}
+
+#[test]
+fn test_stow_conflict_changes_nothing() {
+    let tmp = TempDir::new().unwrap();
+    let pkg = tmp.path().join("pkg");
+    let home = tmp.path().join("home");
+    fs::create_dir_all(&pkg).unwrap();
+    fs::create_dir_all(&home).unwrap();
+    fs::write(pkg.join("a"), "").unwrap();
+    fs::write(pkg.join("b"), "").unwrap();
+    fs::write(home.join("b"), "mine").unwrap();
+
+    mvln_cmd()
+        .arg("stow")
+        .arg(&pkg)
+        .arg("-t")
+        .arg(&home)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+
+    assert!(!home.join("a").exists());
+    assert_eq!(fs::read_to_string(home.join("b")).unwrap(), "mine");
+}
+
+#[test]
+fn test_link_depth_links_entries_individually() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("data");
+    let dest_dir = tmp.path().join("archive");
+    fs::create_dir_all(src.join("inner")).unwrap();
+    fs::write(src.join("file.txt"), "content").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .arg("-w")
+        .arg("--link-depth")
+        .arg("1")
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    assert!(!src.is_symlink());
+    assert!(src.join("file.txt").is_symlink());
+    // At depth 1, subdirectories are linked as a whole
+    assert!(src.join("inner").is_symlink());
+    assert!(dest_dir.join("data/file.txt").is_file());
+}
+
+#[test]
+fn test_link_depth_requires_whole_dir() {
+    mvln_cmd()
+        .args(["--link-depth", "1", "a", "b"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--whole-dir"));
+}
+
+#[test]
+fn test_max_depth_moves_entries_individually() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("data");
+    let dest_dir = tmp.path().join("archive");
+    fs::create_dir_all(src.join("inner/deep")).unwrap();
+    fs::write(src.join("file.txt"), "content").unwrap();
+    fs::write(src.join("inner/nested.txt"), "nested").unwrap();
+    fs::write(src.join("inner/deep/leaf.txt"), "leaf").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .args(["-w", "--max-depth", "2", "--skip-deeper"])
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    assert!(!src.is_symlink());
+    assert!(!src.join("inner").is_symlink());
+    assert!(src.join("file.txt").is_symlink());
+    assert!(src.join("inner/nested.txt").is_symlink());
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("data/inner/nested.txt")).unwrap(),
+        "nested"
+    );
+    // Below the limit, left in place
+    assert!(src.join("inner/deep/leaf.txt").is_file());
+    assert!(!dest_dir.join("data/inner/deep").exists());
+}
+
+#[test]
+fn test_dangling_policy() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("broken");
+    let dest_dir = tmp.path().join("archive");
+    std::os::unix::fs::symlink("missing-target", &src).unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .env("MVLN_LANG", "en-US")
+        .args(["--dangling", "error"])
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("mvln::dangling_symlink"));
+    assert_eq!(
+        fs::read_link(&src).unwrap(),
+        std::path::Path::new("missing-target")
+    );
+
+    mvln_cmd()
+        .env("MVLN_LANG", "en-US")
+        .args(["--dangling", "skip"])
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Skipping dangling symlink"));
+    assert_eq!(
+        fs::read_link(&src).unwrap(),
+        std::path::Path::new("missing-target")
+    );
+    assert!(dest_dir.read_dir().unwrap().next().is_none());
+
+    // The default archives the broken link
+    mvln_cmd().arg(&src).arg(&dest_dir).assert().success();
+    assert_eq!(
+        fs::read_link(dest_dir.join("broken")).unwrap(),
+        std::path::Path::new("missing-target")
+    );
+}
+
+#[test]
+fn test_dereference_moves_the_link_target() {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join("real")).unwrap();
+    let real = tmp.path().join("real/file.txt");
+    fs::write(&real, "content").unwrap();
+    let link = tmp.path().join("link");
+    std::os::unix::fs::symlink("real/file.txt", &link).unwrap();
+    let dest_dir = tmp.path().join("archive");
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .arg("-L")
+        .arg(&link)
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ln -sfn"));
+
+    let moved = dest_dir.join("file.txt");
+    assert!(moved.is_file() && !moved.is_symlink());
+    assert!(real.is_symlink());
+    assert_eq!(
+        fs::read_link(&link).unwrap(),
+        std::path::Path::new("archive/file.txt")
+    );
+    assert_eq!(fs::read_to_string(&link).unwrap(), "content");
+}
+
+#[test]
+fn test_flatten_links_repoints_the_whole_chain() {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join("real")).unwrap();
+    fs::write(tmp.path().join("real/file.txt"), "content").unwrap();
+    let mid = tmp.path().join("mid");
+    std::os::unix::fs::symlink("real/file.txt", &mid).unwrap();
+    let link = tmp.path().join("link");
+    std::os::unix::fs::symlink("mid", &link).unwrap();
+    let dest_dir = tmp.path().join("archive");
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .args(["-L", "--flatten-links"])
+        .arg(&link)
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    for path in [&link, &mid] {
+        assert_eq!(
+            fs::read_link(path).unwrap(),
+            std::path::Path::new("archive/file.txt")
+        );
+    }
+    assert_eq!(fs::read_to_string(&link).unwrap(), "content");
+
+    mvln_cmd()
+        .arg("--flatten-links")
+        .arg(&link)
+        .arg(&dest_dir)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_chmod_applies_at_the_destination() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("project");
+    fs::create_dir_all(src.join("docs")).unwrap();
+    fs::write(src.join("docs/notes.txt"), "notes").unwrap();
+    fs::set_permissions(
+        src.join("docs/notes.txt"),
+        fs::Permissions::from_mode(0o600),
+    )
+    .unwrap();
+    let dest_dir = tmp.path().join("archive");
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .args(["-w", "--chmod", "Dg+rxs,Fg+r"])
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    let mode = |path: &str| {
+        let metadata = fs::metadata(dest_dir.join(path)).unwrap();
+        metadata.permissions().mode() & 0o7777
+    };
+    assert_eq!(mode("project/docs/notes.txt"), 0o640);
+    assert_eq!(mode("project/docs") & 0o2050, 0o2050);
+    assert!(src.is_symlink());
+}
+
+#[test]
+fn test_dedupe_hardlinks_identical_file() {
+    use std::os::unix::fs::MetadataExt;
+
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("copy.bin");
+    let dest_dir = tmp.path().join("archive");
+    fs::create_dir_all(dest_dir.join("2023")).unwrap();
+    fs::write(dest_dir.join("2023/original.bin"), "same bytes").unwrap();
+    fs::write(&src, "same bytes").unwrap();
+
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", tmp.path().join("state"))
+        .arg("--dedupe")
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ln "));
+
+    assert_eq!(
+        fs::metadata(dest_dir.join("copy.bin")).unwrap().ino(),
+        fs::metadata(dest_dir.join("2023/original.bin"))
+            .unwrap()
+            .ino()
+    );
+    assert!(src.is_symlink());
+}
+
+#[test]
+fn test_cas_stores_identical_files_once() {
+    let tmp = TempDir::new().unwrap();
+    let store = tmp.path().join("store");
+    let a = tmp.path().join("a.txt");
+    let b = tmp.path().join("b.txt");
+    fs::write(&a, "same").unwrap();
+    fs::write(&b, "same").unwrap();
+
+    mvln_cmd()
+        .arg("--cas")
+        .arg(&a)
+        .arg(&b)
+        .arg(&store)
+        .assert()
+        .success();
+
+    // sha256("same")
+    let object = store
+        .join("objects/09")
+        .join("0967115f2813a3541eaef77de9d9d5773f1c0c04314b0bbfe4ff3b3b1c55b5d5");
+    assert_eq!(fs::read_to_string(&object).unwrap(), "same");
+    assert_eq!(fs::read_dir(store.join("objects")).unwrap().count(), 1);
+    assert_eq!(fs::read_link(&a).unwrap(), fs::read_link(&b).unwrap());
+    assert_eq!(fs::read_to_string(&b).unwrap(), "same");
+}
+
+#[test]
+fn test_larger_than_selects_big_files_only() {
+    let tmp = TempDir::new().unwrap();
+    let dest_dir = tmp.path().join("dest");
+    fs::create_dir(&dest_dir).unwrap();
+    fs::write(tmp.path().join("big.bin"), vec![0u8; 4096]).unwrap();
+    fs::write(tmp.path().join("small.bin"), vec![0u8; 10]).unwrap();
+
+    mvln_cmd()
+        .current_dir(tmp.path())
+        .args(["--larger-than", "1K", "*.bin"])
+        .arg(&dest_dir)
+        .assert()
+        .success();
+
+    assert!(dest_dir.join("big.bin").is_file());
+    assert!(!dest_dir.join("small.bin").exists());
+    assert!(tmp.path().join("small.bin").is_file());
+    assert!(tmp.path().join("big.bin").is_symlink());
+}
+
+#[test]
+fn test_invalid_size_is_rejected() {
+    mvln_cmd()
+        .args(["--larger-than", "lots", "a", "b"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid size"));
+}
+
+#[test]
+fn test_max_files_defers_remaining_sources() {
+    let tmp = TempDir::new().unwrap();
+    let dest_dir = tmp.path().join("dest");
+    fs::create_dir(&dest_dir).unwrap();
+    for name in ["a.log", "b.log", "c.log"] {
+        fs::write(tmp.path().join(name), name).unwrap();
+    }
+
+    mvln_cmd()
+        .current_dir(tmp.path())
+        .args(["--max-files", "2", "*.log"])
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deferred"))
+        .stdout(predicate::str::contains("c.log"));
+
+    assert!(dest_dir.join("a.log").is_file());
+    assert!(dest_dir.join("b.log").is_file());
+    assert!(tmp.path().join("c.log").is_file());
+    assert!(!tmp.path().join("c.log").is_symlink());
+}
+
+#[test]
+fn test_sort_size_reverse_moves_biggest_first() {
+    let tmp = TempDir::new().unwrap();
+    let dest_dir = tmp.path().join("dest");
+    fs::create_dir(&dest_dir).unwrap();
+    fs::write(tmp.path().join("a.bin"), vec![0u8; 10]).unwrap();
+    fs::write(tmp.path().join("b.bin"), vec![0u8; 300]).unwrap();
+    fs::write(tmp.path().join("c.bin"), vec![0u8; 200]).unwrap();
+
+    let output = mvln_cmd()
+        .current_dir(tmp.path())
+        .args(["--sort", "size", "--reverse", "--max-files", "1", "*.bin"])
+        .arg(&dest_dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    assert!(dest_dir.join("b.bin").is_file());
+    assert!(!dest_dir.join("a.bin").exists());
+    assert!(!dest_dir.join("c.bin").exists());
+}
+
+#[test]
+fn test_completions_scripts() {
+    for shell in ["bash", "zsh", "fish", "powershell"] {
+        mvln_cmd()
+            .args(["completions", shell])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("complete-profiles"))
+            .stdout(predicate::str::contains("convert-links"));
+    }
+}
+
+#[test]
+fn test_complete_profiles_lists_profile_names() {
+    let tmp = TempDir::new().unwrap();
+    let config = tmp.path().join("config.toml");
+    fs::write(
+        &config,
+        "[profile.nas]\ndest = \"/mnt/nas\"\n\n[profile.backup]\ndest = \"/backup\"\n",
+    )
+    .unwrap();
+
+    mvln_cmd()
+        .arg("--config")
+        .arg(&config)
+        .arg("complete-profiles")
+        .assert()
+        .success()
+        .stdout("nas\nbackup\n");
+}
+
+#[test]
+fn test_man_page() {
+    mvln_cmd()
+        .arg("man")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(".TH MVLN 1"))
+        .stdout(predicate::str::contains(".SS \"mvln organize\""));
+}
+
+#[test]
+fn test_shell_powershell_echoes_pasteable_commands() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("it's.txt");
+    let dest_dir = tmp.path().join("dest");
+    fs::write(&src, "x").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .args(["--dry-run", "--shell", "powershell"])
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Move-Item -LiteralPath '"))
+        .stdout(predicate::str::contains("it''s.txt'"))
+        .stdout(predicate::str::contains("New-Item -ItemType SymbolicLink"));
+}
+
+#[test]
+fn test_commands_to_stderr_and_no_commands() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("file.txt");
+    let dest_dir = tmp.path().join("dest");
+    fs::write(&src, "x").unwrap();
+    fs::create_dir(&dest_dir).unwrap();
+
+    mvln_cmd()
+        .args(["--dry-run", "--commands-to", "stderr"])
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("mv ").not())
+        .stderr(predicate::str::contains("mv "))
+        .stderr(predicate::str::contains("ln -s "));
+
+    mvln_cmd()
+        .args(["--dry-run", "--no-commands"])
+        .arg(&src)
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ln -s ").not())
+        .stderr(predicate::str::contains("ln -s ").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_emit_script_replays_batch() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("my file.txt");
+    let dest_dir = tmp.path().join("dest");
+    let script = tmp.path().join("replay.sh");
+    fs::write(&src, "content").unwrap();
+
+    mvln_cmd()
+        .current_dir(tmp.path())
+        .args(["--dry-run", "--emit-script"])
+        .arg(&script)
+        .arg("my file.txt")
+        .arg("dest/new name.txt")
+        .assert()
+        .success();
+
+    // Dry run: nothing changed yet
+    assert!(src.is_file());
+    let text = fs::read_to_string(&script).unwrap();
+    assert!(text.starts_with("#!/bin/sh\n"));
+    assert!(text.contains("set -e\n"));
+    assert!(text.contains("mkdir -p "));
+
+    let status = Command::new("sh").arg(&script).status().unwrap();
+    assert!(status.success());
+    assert!(src.is_symlink());
+    assert_eq!(
+        fs::read_to_string(dest_dir.join("new name.txt")).unwrap(),
+        "content"
+    );
+    assert_eq!(fs::read_to_string(&src).unwrap(), "content");
+}
+
+#[test]
+fn test_lang_flag_and_env_override_locale() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("file.txt");
+    fs::write(&src, "x").unwrap();
+    let dest = tmp.path().join("dest.txt");
+
+    mvln_cmd()
+        .args(["--dry-run", "--lang", "zh-CN"])
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("完成"));
+
+    mvln_cmd()
+        .env("MVLN_LANG", "zh_CN.UTF-8")
+        .arg("--dry-run")
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("完成"));
+
+    // The flag wins over the environment
+    mvln_cmd()
+        .env("MVLN_LANG", "zh-CN")
+        .args(["--dry-run", "--lang", "en"])
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Complete:"));
+}
+
+#[test]
+fn test_user_translation_overrides_embedded_message() {
+    let tmp = TempDir::new().unwrap();
+    let data = tmp.path().join("data");
+    fs::create_dir_all(data.join("i18n/en-US")).unwrap();
+    fs::write(
+        data.join("i18n/en-US/custom.ftl"),
+        "op-complete = All done: { $files } moved\n",
+    )
+    .unwrap();
+    let src = tmp.path().join("file.txt");
+    fs::write(&src, "x").unwrap();
+
+    mvln_cmd()
+        .env("MVLN_DATA_DIR", &data)
+        .args(["--dry-run", "--lang", "en-US"])
+        .arg(&src)
+        .arg(tmp.path().join("dest.txt"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All done:"));
+}
+
+#[test]
+fn test_dry_run_prints_estimate() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    let a = tmp.path().join("a.bin");
+    let b = tmp.path().join("b.bin");
+    fs::write(&a, vec![0u8; 1024]).unwrap();
+    fs::write(&b, vec![0u8; 1024]).unwrap();
+
+    let output = mvln_cmd()
+        .args(["--dry-run", "--lang", "en-US"])
+        .args([&a, &b, &dest])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout).replace(['\u{2068}', '\u{2069}'], "");
+    assert!(stdout.contains("Estimate: 2.0 KiB in 2 renames and 0 cross-device copies"));
+    assert!(stdout.contains("after (+0 B)"));
+    assert!(a.is_file() && !a.is_symlink());
+}
+
+#[test]
+fn test_dry_run_estimate_of_relative_sources() {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir(tmp.path().join("dest")).unwrap();
+    fs::write(tmp.path().join("a.txt"), "a").unwrap();
+
+    let output = mvln_cmd()
+        .current_dir(tmp.path())
+        .args(["--dry-run", "--lang", "en-US", "a.txt", "dest"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout).replace(['\u{2068}', '\u{2069}'], "");
+    assert!(!stdout.contains("free space unknown"), "{stdout}");
+    if cfg!(unix) {
+        assert!(stdout.contains(".: "), "{stdout}");
+    }
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn test_review_requires_a_terminal() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("a.txt");
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    fs::write(&src, "a").unwrap();
+
+    mvln_cmd()
+        .arg("--review")
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--review needs a terminal"));
+    assert!(src.is_file() && !src.is_symlink());
+}
+
+/// Write fake `ssh` and `sftp` commands to `bin`, which act on the local
+/// filesystem as if it were the remote host's.
+#[cfg(feature = "ssh")]
+fn fake_ssh(bin: &std::path::Path) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::create_dir(bin).unwrap();
+    let scripts = [
+        ("ssh", "#!/bin/sh\nshift 3\nexec sh -c \"$1\"\n"),
+        (
+            "sftp",
+            "#!/bin/sh\nwhile read -r line; do\n  eval \"set -- $line\"\n  \
+             for arg; do src=$dest; dest=$arg; done\n  cp -R -p \"$src\" \"$dest\" || exit 1\ndone\n",
+        ),
+    ];
+    for (name, script) in scripts {
+        let path = bin.join(name);
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    format!("{}:{}", bin.display(), std::env::var("PATH").unwrap())
+}
+
+#[cfg(feature = "ssh")]
+#[test]
+fn test_remote_destination_leaves_a_stub() {
+    let tmp = TempDir::new().unwrap();
+    let path = fake_ssh(&tmp.path().join("bin"));
+    let src = tmp.path().join("photos");
+    fs::create_dir_all(src.join("2024")).unwrap();
+    fs::write(src.join("2024/a.jpg"), "jpeg").unwrap();
+    let remote = tmp.path().join("remote/archive");
+
+    // A directory needs -w, as for local moves
+    for dry_run in [true, false] {
+        let mut cmd = mvln_cmd();
+        cmd.env("PATH", &path).args(["--lang", "en-US"]);
+        if dry_run {
+            cmd.arg("--dry-run");
+        }
+        cmd.arg(&src)
+            .arg(format!("me@nas:{}", remote.display()))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("mvln::is_directory"));
+    }
+    assert!(src.join("2024/a.jpg").exists());
+    assert!(!remote.exists());
+
+    mvln_cmd()
+        .env("PATH", &path)
+        .arg("-w")
+        .arg(&src)
+        .arg(format!("me@nas:{}", remote.display()))
+        .assert()
+        .success();
+
+    assert_eq!(fs::read(remote.join("photos/2024/a.jpg")).unwrap(), b"jpeg");
+    assert!(!remote.join("photos.mvln-partial").exists());
+    assert!(!src.exists());
+    let stub = fs::read_to_string(tmp.path().join("photos.url")).unwrap();
+    assert_eq!(
+        stub,
+        format!(
+            "[InternetShortcut]\nURL=sftp://me@nas{}/photos\n",
+            remote.display()
+        )
+    );
+
+    // An existing destination is left alone without --force
+    fs::write(&src, "again").unwrap();
+    mvln_cmd()
+        .env("PATH", &path)
+        .args(["--lang", "en-US"])
+        .arg(&src)
+        .arg(format!("me@nas:{}", remote.display()))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+    assert_eq!(fs::read_to_string(&src).unwrap(), "again");
+}
+
+#[test]
+fn test_background_job_runs_detached_and_shows_in_status() {
+    let tmp = TempDir::new().unwrap();
+    let state = tmp.path().join("state");
+    let src = tmp.path().join("a.txt");
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    fs::write(&src, "a").unwrap();
+
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .args(["--lang", "en-US", "--background"])
+        .arg(&src)
+        .arg(&dest)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Started background job"));
+
+    // Wait for the worker to finish
+    let status = || {
+        let output = mvln_cmd()
+            .env("MVLN_STATE_DIR", &state)
+            .args(["--lang", "en-US", "status"])
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).replace(['\u{2068}', '\u{2069}'], "")
+    };
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    let mut stdout = status();
+    while stdout.contains("running") && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        stdout = status();
+    }
+
+    assert!(stdout.contains("[1] finished, started "), "{stdout}");
+    assert!(stdout.contains("--background"), "{stdout}");
+    assert!(stdout.contains("1/1 moved, 1 B/1 B, 0 failed"), "{stdout}");
+    assert!(src.is_symlink());
+    assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "a");
+
+    // One job in detail, with where its output went
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .args(["--lang", "en-US", "status", "1", "--watch"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Output: "));
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", &state)
+        .args(["--lang", "en-US", "status", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Background job not found"));
+}
+
+#[test]
+fn test_status_without_jobs() {
+    let tmp = TempDir::new().unwrap();
+    mvln_cmd()
+        .env("MVLN_STATE_DIR", tmp.path())
+        .args(["--lang", "en-US", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No background jobs"));
+}
+
+#[test]
+fn test_trailing_slash_and_no_target_directory() {
+    let tmp = TempDir::new().unwrap();
+    let a = tmp.path().join("a.txt");
+    let b = tmp.path().join("b.txt");
+    fs::write(&a, "a").unwrap();
+    fs::write(&b, "b").unwrap();
+
+    // A trailing slash names a directory to create and move into
+    let new = tmp.path().join("new");
+    mvln_cmd()
+        .arg(&a)
+        .arg(&b)
+        .arg(format!("{}/", new.display()))
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(new.join("a.txt")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(new.join("b.txt")).unwrap(), "b");
+
+    // -T moves a directory as DEST rather than into it
+    let photos = tmp.path().join("photos");
+    let archive = tmp.path().join("archive");
+    fs::create_dir(&photos).unwrap();
+    fs::write(photos.join("p.jpg"), "p").unwrap();
+    fs::create_dir(&archive).unwrap();
+    mvln_cmd()
+        .args(["-T", "-w"])
+        .arg(&photos)
+        .arg(&archive)
+        .assert()
+        .failure();
+    assert!(!photos.is_symlink());
+    mvln_cmd()
+        .args(["-T", "-w", "-f"])
+        .arg(&photos)
+        .arg(&archive)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(archive.join("p.jpg")).unwrap(), "p");
+    assert!(!archive.join("photos").exists());
+    assert!(photos.is_symlink());
+}
+
+#[test]
+fn test_wildcards_skip_hidden_files_without_hidden() {
+    let tmp = TempDir::new().unwrap();
+    let src = tmp.path().join("src");
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&src).unwrap();
+    fs::create_dir(&dest).unwrap();
+    fs::write(src.join(".env"), "secret").unwrap();
+    fs::write(src.join("notes.txt"), "notes").unwrap();
+
+    mvln_cmd()
+        .arg("--dry-run")
+        .arg(format!("{}/*", src.display()))
+        .arg(&dest)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("notes.txt"))
+        .stdout(predicate::str::contains(".env").not());
+
+    mvln_cmd()
+        .arg("--hidden")
+        .arg(format!("{}/*", src.display()))
+        .arg(&dest)
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(dest.join(".env")).unwrap(), "secret");
+    assert!(src.join(".env").is_symlink());
+    assert!(src.join("notes.txt").is_symlink());
+}
+
+#[test]
+fn test_wildcards_after_dot_segments() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir_all(tmp.path().join("sub")).unwrap();
+    fs::create_dir(&dest).unwrap();
+    fs::write(tmp.path().join("a.txt"), "a").unwrap();
+    fs::write(tmp.path().join(".b.txt"), "b").unwrap();
+    fs::write(tmp.path().join("sub/c.txt"), "c").unwrap();
+    fs::write(tmp.path().join("sub/.d.txt"), "d").unwrap();
+
+    for pattern in ["./*.txt", "sub/./*.txt"] {
+        mvln_cmd()
+            .current_dir(tmp.path())
+            .arg(pattern)
+            .arg("dest")
+            .assert()
+            .success();
+    }
+    assert!(tmp.path().join("a.txt").is_symlink());
+    assert!(tmp.path().join("sub/c.txt").is_symlink());
+    assert!(!tmp.path().join(".b.txt").is_symlink());
+    assert!(!tmp.path().join("sub/.d.txt").is_symlink());
+    assert!(!dest.join(".b.txt").exists());
+}
+
+#[test]
+fn test_max_matches_refuses_broad_pattern() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(tmp.path().join(name), name).unwrap();
+    }
+    let pattern = format!("{}/*.txt", tmp.path().display());
+
+    mvln_cmd()
+        .args(["--max-matches", "2"])
+        .arg(&pattern)
+        .arg(&dest)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("mvln::too_many_matches"));
+    assert!(!tmp.path().join("a.txt").is_symlink());
+
+    mvln_cmd()
+        .args(["--max-matches", "3"])
+        .arg(&pattern)
+        .arg(&dest)
+        .assert()
+        .success();
+    assert!(dest.join("c.txt").exists());
+}
+
+#[test]
+fn test_every_unmatched_pattern_is_reported() {
+    let tmp = TempDir::new().unwrap();
+    let dest = tmp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    fs::write(tmp.path().join("a.txt"), "a").unwrap();
+
+    mvln_cmd()
+        .current_dir(tmp.path())
+        .args(["*.log", "*.txt", "*.tmp"])
+        .arg(&dest)
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("*.log"))
+        .stderr(predicate::str::contains("*.tmp"));
+    // Nothing is moved when a pattern matched nothing
+    assert!(!tmp.path().join("a.txt").is_symlink());
+}
@@ -13,7 +13,7 @@ use std::path::Path;
 
 use tempfile::TempDir;
 
-use mvln::{move_and_link, MoveOptions, MvlnError};
+use mvln::{link_duplicate, link_only, move_and_link, MoveOptions, MvlnError, SymlinkStyle};
 
 /// Helper to create a test file with content.
 fn create_test_file(path: &Path, content: &str) {
@@ -43,7 +43,7 @@ fn file_never_lost_on_successful_operation() {
     let result = move_and_link(&source, &dest, &options);
 
     // THEN: File is at destination AND symlink exists at source
-    assert!(result.is_ok(), "Operation should succeed: {:?}", result);
+    assert!(result.is_ok(), "Operation should succeed: {result:?}");
 
     // File content is accessible at destination
     assert!(dest.exists(), "Destination should exist");
@@ -136,10 +136,9 @@ fn relative_symlink_computed_correctly() {
     create_test_file(&source, content);
 
     // WHEN: mvln with relative mode (default)
-    let options = MoveOptions {
-        absolute: false,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder()
+        .symlink_style(SymlinkStyle::Relative)
+        .build();
     let result = move_and_link(&source, &dest, &options);
 
     // THEN: Symlink uses relative path
@@ -152,8 +151,7 @@ fn relative_symlink_computed_correctly() {
     // Should be relative (not start with /)
     assert!(
         !raw_target.is_absolute(),
-        "Symlink should be relative, got: {:?}",
-        raw_target
+        "Symlink should be relative, got: {raw_target:?}"
     );
 
     // Should navigate correctly (e.g., ../../x/y/file.txt)
@@ -174,10 +172,9 @@ fn absolute_symlink_uses_absolute_path() {
     create_test_file(&source, "absolute test");
 
     // WHEN: mvln with absolute mode
-    let options = MoveOptions {
-        absolute: true,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder()
+        .symlink_style(SymlinkStyle::Absolute)
+        .build();
     let result = move_and_link(&source, &dest, &options);
 
     // THEN: Symlink target is absolute
@@ -187,8 +184,7 @@ fn absolute_symlink_uses_absolute_path() {
     let raw_target = fs::read_link(&source).expect("Should read symlink");
     assert!(
         raw_target.is_absolute(),
-        "Symlink should be absolute, got: {:?}",
-        raw_target
+        "Symlink should be absolute, got: {raw_target:?}"
     );
 }
 
@@ -211,8 +207,7 @@ fn source_not_found_returns_error() {
     let err = result.unwrap_err();
     assert!(
         matches!(err, MvlnError::SourceNotFound { .. }),
-        "Should be SourceNotFound error, got: {:?}",
-        err
+        "Should be SourceNotFound error, got: {err:?}"
     );
 }
 
@@ -227,10 +222,7 @@ fn destination_exists_without_force_returns_error() {
     create_test_file(&dest, "existing dest");
 
     // WHEN: mvln without force flag
-    let options = MoveOptions {
-        force: false,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder().force(false).build();
     let result = move_and_link(&source, &dest, &options);
 
     // THEN: Returns DestinationExists error
@@ -238,8 +230,7 @@ fn destination_exists_without_force_returns_error() {
     let err = result.unwrap_err();
     assert!(
         matches!(err, MvlnError::DestinationExists { .. }),
-        "Should be DestinationExists error, got: {:?}",
-        err
+        "Should be DestinationExists error, got: {err:?}"
     );
 
     // AND: Source is unchanged (not moved or deleted!)
@@ -259,10 +250,7 @@ fn destination_exists_with_force_overwrites() {
     create_test_file(&dest, "old content");
 
     // WHEN: mvln with force flag
-    let options = MoveOptions {
-        force: true,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder().force(true).build();
     let result = move_and_link(&source, &dest, &options);
 
     // THEN: Operation succeeds, dest has new content
@@ -305,18 +293,11 @@ fn force_on_symlink_to_dir_does_not_delete_target_contents() {
     // WHEN: mvln source.txt to dest_dir/ with force
     // resolve_destination will turn dest_dir/ into dest_dir/source.txt
     // which is the existing symlink pointing to target_dir
-    let options = MoveOptions {
-        force: true,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder().force(true).build();
     let result = move_and_link(&source, &dest_dir, &options);
 
     // THEN: Operation succeeds
-    assert!(
-        result.is_ok(),
-        "Should succeed with force flag: {:?}",
-        result
-    );
+    assert!(result.is_ok(), "Should succeed with force flag: {result:?}");
 
     // AND: The target directory and its contents are PRESERVED (critical!)
     assert!(target_dir.exists(), "Target directory must still exist");
@@ -356,10 +337,7 @@ fn dry_run_does_not_modify_filesystem() {
     create_test_file(&source, content);
 
     // WHEN: mvln with dry-run
-    let options = MoveOptions {
-        dry_run: true,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder().dry_run(true).build();
     let result = move_and_link(&source, &dest, &options);
 
     // THEN: No filesystem changes
@@ -403,8 +381,7 @@ fn dangling_symlink_source_can_be_moved() {
     // THEN: Operation succeeds
     assert!(
         result.is_ok(),
-        "Should succeed moving dangling symlink: {:?}",
-        result
+        "Should succeed moving dangling symlink: {result:?}"
     );
 
     // Source is now a symlink pointing to dest
@@ -437,18 +414,11 @@ fn dangling_symlink_dest_detected_with_force() {
     assert!(!dest.exists(), "Dest should be dangling");
 
     // WHEN: mvln with force flag
-    let options = MoveOptions {
-        force: true,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder().force(true).build();
     let result = move_and_link(&source, &dest, &options);
 
     // THEN: Operation succeeds, dangling symlink is replaced
-    assert!(
-        result.is_ok(),
-        "Should succeed with force flag: {:?}",
-        result
-    );
+    assert!(result.is_ok(), "Should succeed with force flag: {result:?}");
 
     // Dest is now a regular file with new content (dangling symlink replaced)
     assert!(dest.exists(), "Dest should exist");
@@ -474,14 +444,13 @@ fn absolute_mode_works_when_dest_not_exists() {
     create_test_file(&source, "absolute path test");
 
     // WHEN: mvln with absolute mode
-    let options = MoveOptions {
-        absolute: true,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder()
+        .symlink_style(SymlinkStyle::Absolute)
+        .build();
     let result = move_and_link(&source, &dest, &options);
 
     // THEN: Operation succeeds
-    assert!(result.is_ok(), "Operation should succeed: {:?}", result);
+    assert!(result.is_ok(), "Operation should succeed: {result:?}");
 
     // Source is a symlink with absolute target
     assert!(source.is_symlink(), "Source should be a symlink");
@@ -489,8 +458,7 @@ fn absolute_mode_works_when_dest_not_exists() {
     let raw_target = fs::read_link(&source).expect("Should read symlink");
     assert!(
         raw_target.is_absolute(),
-        "Symlink should use absolute path, got: {:?}",
-        raw_target
+        "Symlink should use absolute path, got: {raw_target:?}"
     );
 
     // Symlink resolves correctly to destination
@@ -520,10 +488,7 @@ fn force_with_source_equals_dest_returns_error() {
 
     // WHEN: mvln with force flag, moving file to itself (via current directory)
     // This simulates `mvln -f file.txt .` when already in the file's directory
-    let options = MoveOptions {
-        force: true,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder().force(true).build();
 
     // Move to parent directory (which would resolve to same location)
     let dest = temp.path();
@@ -534,8 +499,7 @@ fn force_with_source_equals_dest_returns_error() {
     let err = result.unwrap_err();
     assert!(
         matches!(err, MvlnError::SameSourceAndDest { .. }),
-        "Should be SameSourceAndDest error, got: {:?}",
-        err
+        "Should be SameSourceAndDest error, got: {err:?}"
     );
 
     // AND: Source file is preserved (not deleted!)
@@ -565,8 +529,7 @@ fn directory_move_to_subdirectory_returns_error() {
     let err = result.unwrap_err();
     assert!(
         matches!(err, MvlnError::DestinationInsideSource { .. }),
-        "Should be DestinationInsideSource error, got: {:?}",
-        err
+        "Should be DestinationInsideSource error, got: {err:?}"
     );
 
     // AND: Source directory is preserved
@@ -598,8 +561,7 @@ fn directory_move_to_nonexistent_subdirectory_returns_error() {
     let err = result.unwrap_err();
     assert!(
         matches!(err, MvlnError::DestinationInsideSource { .. }),
-        "Should be DestinationInsideSource error, got: {:?}",
-        err
+        "Should be DestinationInsideSource error, got: {err:?}"
     );
 
     // AND: Source directory is preserved
@@ -624,10 +586,7 @@ fn symlink_source_self_move_with_force_returns_error() {
     assert!(source_link.is_symlink(), "Source should be a symlink");
 
     // WHEN: Try to move symlink to its parent directory (resolves to same path)
-    let options = MoveOptions {
-        force: true,
-        ..Default::default()
-    };
+    let options = MoveOptions::builder().force(true).build();
     let result = move_and_link(&source_link, temp.path(), &options);
 
     // THEN: Returns SameSourceAndDest error
@@ -635,8 +594,7 @@ fn symlink_source_self_move_with_force_returns_error() {
     let err = result.unwrap_err();
     assert!(
         matches!(err, MvlnError::SameSourceAndDest { .. }),
-        "Should be SameSourceAndDest error, got: {:?}",
-        err
+        "Should be SameSourceAndDest error, got: {err:?}"
     );
 
     // AND: The symlink is preserved (not deleted!)
@@ -644,3 +602,181 @@ fn symlink_source_self_move_with_force_returns_error() {
     // AND: The target file is also preserved
     assert!(target_file.exists(), "Target file must still exist");
 }
+
+#[test]
+fn link_only_never_replaces_regular_file_even_with_force() {
+    // GIVEN: Data already at the destination, and a regular file at the source
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("file.txt");
+    let dest = temp.path().join("archive.txt");
+    create_test_file(&source, "source content");
+    create_test_file(&dest, "dest content");
+
+    // WHEN: link-only is asked to force a link over the regular file
+    let options = MoveOptions::builder().force(true).build();
+    let result = link_only(&source, &dest, &options);
+
+    // THEN: It fails and the source file is untouched
+    assert!(
+        matches!(result, Err(MvlnError::InvalidPath { .. })),
+        "Should refuse to replace a regular file, got: {result:?}"
+    );
+    assert_eq!(fs::read_to_string(&source).unwrap(), "source content");
+}
+
+#[test]
+fn link_only_creates_link_to_existing_data() {
+    // GIVEN: Data moved by another tool, leaving nothing at the source
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("work/file.txt");
+    let dest = temp.path().join("archive/file.txt");
+    fs::create_dir(temp.path().join("archive")).unwrap();
+    create_test_file(&dest, "content");
+
+    // WHEN: link-only repairs the source location
+    let result = link_only(&source, &dest, &MoveOptions::default()).unwrap();
+
+    // THEN: A relative symlink points at the data, which is not moved
+    assert_eq!(result.symlink_target, Path::new("../archive/file.txt"));
+    assert!(source.is_symlink());
+    assert_eq!(fs::read_to_string(&source).unwrap(), "content");
+    assert!(dest.is_file());
+}
+
+#[test]
+fn link_depth_builds_farm_and_keeps_data_at_destination() {
+    // GIVEN: A directory with a file and a subdirectory
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("project");
+    let dest = temp.path().join("archive/project");
+    fs::create_dir_all(source.join("sub")).unwrap();
+    create_test_file(&source.join("a.txt"), "a");
+    create_test_file(&source.join("sub/b.txt"), "b");
+    fs::create_dir(temp.path().join("archive")).unwrap();
+
+    // WHEN: Moving it with link depth 2
+    let options = MoveOptions::builder().link_depth(2).build();
+    let result = move_and_link(&source, &dest, &options).unwrap();
+
+    // THEN: The data lives at the destination
+    assert!(dest.join("sub/b.txt").is_file());
+    // AND: The source is a real directory tree of links
+    assert!(source.is_dir() && !source.is_symlink());
+    assert!(source.join("sub").is_dir() && !source.join("sub").is_symlink());
+    assert!(source.join("a.txt").is_symlink());
+    assert!(source.join("sub/b.txt").is_symlink());
+    assert_eq!(fs::read_to_string(source.join("sub/b.txt")).unwrap(), "b");
+    assert_eq!(result.farm.len(), 2);
+}
+
+#[test]
+fn link_duplicate_hardlinks_existing_copy() {
+    use std::os::unix::fs::MetadataExt;
+
+    // GIVEN: An identical copy already stored at the destination
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("photo.jpg");
+    let existing = temp.path().join("archive/old/photo.jpg");
+    let dest = temp.path().join("archive/photo.jpg");
+    fs::create_dir_all(existing.parent().unwrap()).unwrap();
+    create_test_file(&source, "pixels");
+    create_test_file(&existing, "pixels");
+
+    // WHEN: Moving with the duplicate
+    let result = link_duplicate(&source, &dest, &existing, &MoveOptions::default()).unwrap();
+
+    // THEN: The destination shares the existing copy's inode
+    assert_eq!(result.hardlinked_from.as_deref(), Some(existing.as_path()));
+    assert_eq!(
+        fs::metadata(&dest).unwrap().ino(),
+        fs::metadata(&existing).unwrap().ino()
+    );
+    // AND: The source is a symlink to the destination
+    assert!(source.is_symlink());
+    assert_eq!(fs::read_to_string(&source).unwrap(), "pixels");
+}
+
+#[test]
+fn io_failures_keep_the_underlying_error() {
+    use std::error::Error;
+
+    // GIVEN: A destination whose parent "directory" is a regular file
+    let temp = TempDir::new().unwrap();
+    let source = temp.path().join("a.txt");
+    let blocker = temp.path().join("blocker");
+    create_test_file(&source, "content");
+    create_test_file(&blocker, "not a directory");
+
+    // WHEN: Moving below it
+    let err =
+        move_and_link(&source, blocker.join("sub/a.txt"), &MoveOptions::default()).unwrap_err();
+
+    // THEN: The io::Error is preserved, with its kind and OS error code
+    assert!(matches!(err, MvlnError::CreateDirFailed { .. }), "{err:?}");
+    let io = err.io_error().expect("should carry an io::Error");
+    assert_eq!(io.kind(), std::io::ErrorKind::NotADirectory);
+    assert_eq!(err.raw_os_error(), Some(20)); // ENOTDIR
+    assert!(err.source().is_some());
+    // AND: The source file is untouched
+    assert_eq!(fs::read_to_string(&source).unwrap(), "content");
+}
+
+#[test]
+fn conflict_handler_resolves_existing_destinations() {
+    use mvln::conflict::Resolution;
+    use mvln::plan::{ErrorPolicy, Layout, MovePlan};
+
+    let temp = TempDir::new().unwrap();
+    let dest = temp.path().join("dest");
+    fs::create_dir(&dest).unwrap();
+    for (name, resolution) in [
+        ("backup.txt", Resolution::Backup),
+        ("rename.txt", Resolution::Rename),
+        ("skip.txt", Resolution::Skip),
+    ] {
+        create_test_file(&temp.path().join(name), "new");
+        create_test_file(&dest.join(name), "old");
+        let options = MoveOptions::builder()
+            .on_conflict(move |conflict| {
+                assert!(!conflict.source_is_dir && !conflict.dest_is_dir);
+                resolution
+            })
+            .build();
+        let _ = move_and_link(temp.path().join(name), &dest, &options);
+    }
+
+    // Backup: the old file is moved aside, the new one takes its place
+    assert_eq!(fs::read_to_string(dest.join("backup.txt")).unwrap(), "new");
+    assert_eq!(fs::read_to_string(dest.join("backup.txt~")).unwrap(), "old");
+    // Rename: both are kept
+    assert_eq!(fs::read_to_string(dest.join("rename.txt")).unwrap(), "old");
+    assert_eq!(
+        fs::read_to_string(dest.join("rename (1).txt")).unwrap(),
+        "new"
+    );
+    assert!(temp.path().join("rename.txt").is_symlink());
+    // Skip: nothing is touched
+    assert_eq!(fs::read_to_string(dest.join("skip.txt")).unwrap(), "old");
+    assert!(!temp.path().join("skip.txt").is_symlink());
+
+    // Abort stops a batch even when it would keep going
+    let sources: Vec<_> = ["a.txt", "b.txt"]
+        .iter()
+        .map(|name| {
+            let path = temp.path().join(name);
+            create_test_file(&path, "new");
+            create_test_file(&dest.join(name), "old");
+            path
+        })
+        .collect();
+    let plan = MovePlan::build(&sources, &dest, &Layout::Flat);
+    let options = MoveOptions::builder()
+        .on_conflict(|_| Resolution::Abort)
+        .build();
+    let outcome = plan.execute(&options, ErrorPolicy::KeepGoing);
+    assert!(matches!(
+        outcome.failed[..],
+        [(_, MvlnError::ConflictAborted { .. })]
+    ));
+    assert_eq!(outcome.skipped.len(), 1);
+}